@@ -3,6 +3,8 @@
 //! The `ast` module defines the data structures that represent the code in a structured way.
 //! The parser will produce these structures, and the compiler will consume them.
 
+use crate::mangle;
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum AstType {
     I8,
@@ -32,6 +34,17 @@ pub enum AstType {
         param_types: Vec<AstType>,
         return_type: Box<AstType>,
     },
+    /// A function value written `(i32) i32`: a closure or a named function,
+    /// called with the environment it captured.
+    Closure {
+        param_types: Vec<AstType>,
+        return_type: Box<AstType>,
+    },
+    /// `(i32, string)`: an anonymous product of two or more types.
+    Tuple(Vec<AstType>),
+    /// `dyn Shape`: a value of any type implementing the behavior, held as
+    /// a pointer to the value and one to its type's vtable for the behavior.
+    Dyn(String),
     Struct {
         name: String,
         fields: Vec<(String, AstType)>,
@@ -57,6 +70,55 @@ pub enum AstType {
         name: String,
         type_args: Vec<AstType>,
     },
+    // Placeholder for a type still being inferred; only the type checker
+    // creates these and they never reach later passes
+    Var(usize),
+}
+
+impl std::fmt::Display for AstType {
+    /// Formats the type using Zen surface syntax, e.g. `*i32` or `Option<string>`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        fn join(types: &[AstType]) -> String {
+            types.iter().map(|t| t.to_string()).collect::<Vec<_>>().join(", ")
+        }
+
+        match self {
+            AstType::I8 => write!(f, "i8"),
+            AstType::I16 => write!(f, "i16"),
+            AstType::I32 => write!(f, "i32"),
+            AstType::I64 => write!(f, "i64"),
+            AstType::U8 => write!(f, "u8"),
+            AstType::U16 => write!(f, "u16"),
+            AstType::U32 => write!(f, "u32"),
+            AstType::U64 => write!(f, "u64"),
+            AstType::F32 => write!(f, "f32"),
+            AstType::F64 => write!(f, "f64"),
+            AstType::Bool => write!(f, "bool"),
+            AstType::String => write!(f, "string"),
+            AstType::Void => write!(f, "void"),
+            AstType::Pointer(inner) => write!(f, "*{}", inner),
+            AstType::Array(inner) => write!(f, "[{}]", inner),
+            AstType::FixedArray { element_type, size } => write!(f, "[{}; {}]", element_type, size),
+            AstType::Function { args, return_type } => write!(f, "({}) {}", join(args), return_type),
+            AstType::FunctionPointer { param_types, return_type } => {
+                write!(f, "*({}) {}", join(param_types), return_type)
+            }
+            AstType::Closure { param_types, return_type } => write!(f, "({}) {}", join(param_types), return_type),
+            AstType::Tuple(elements) => write!(f, "({})", join(elements)),
+            AstType::Dyn(behavior) => write!(f, "dyn {}", behavior),
+            AstType::Struct { name, .. } | AstType::Enum { name, .. } => write!(f, "{}", name),
+            AstType::Ref(inner) => write!(f, "&{}", inner),
+            AstType::Option(inner) => write!(f, "Option<{}>", inner),
+            AstType::Result { ok_type, err_type } => write!(f, "Result<{}, {}>", ok_type, err_type),
+            AstType::Range { start_type, end_type, inclusive } => {
+                let op = if *inclusive { "..=" } else { ".." };
+                write!(f, "Range<{}{}{}>", start_type, op, end_type)
+            }
+            AstType::Generic { name, type_args } if type_args.is_empty() => write!(f, "{}", name),
+            AstType::Generic { name, type_args } => write!(f, "{}<{}>", name, join(type_args)),
+            AstType::Var(_) => write!(f, "_"),
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -68,7 +130,7 @@ pub struct EnumVariant {
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct TypeParameter {
     pub name: String,
-    pub constraints: Vec<String>, // For future trait bounds
+    pub constraints: Vec<String>, // Behaviors the type must implement: `T: Ord + Display`
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -87,6 +149,19 @@ pub enum BinaryOperator {
     StringConcat,
     And,
     Or,
+    BitAnd,
+    BitOr,
+    BitXor,
+    ShiftLeft,
+    /// Arithmetic for signed operands, logical for unsigned ones.
+    ShiftRight,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnaryOperator {
+    Negate,
+    Not,
+    BitNot,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -109,10 +184,23 @@ pub enum Expression {
         op: BinaryOperator,
         right: Box<Expression>,
     },
+    UnaryOp {
+        op: UnaryOperator,
+        operand: Box<Expression>,
+    },
     FunctionCall {
         name: String,
         args: Vec<Expression>,
     },
+    /// `receiver.method(args)`. The type checker decides what the call
+    /// refers to: a method of the receiver's type, a function taking the
+    /// receiver as its first argument, or, when the receiver is a bare type
+    /// or module name, an associated or module function.
+    MethodCall {
+        object: Box<Expression>,
+        method: String,
+        args: Vec<Expression>,
+    },
     // Enhanced conditional expression for pattern matching with unified ? syntax
     Conditional {
         scrutinee: Box<Expression>,
@@ -132,6 +220,13 @@ pub enum Expression {
         struct_: Box<Expression>,
         field: String,
     },
+    /// `(a, b)`, with at least two elements.
+    TupleLiteral(Vec<Expression>),
+    /// `pair.0`: an element of a tuple, by position.
+    TupleIndex {
+        tuple: Box<Expression>,
+        index: usize,
+    },
     // New expressions for enhanced features
     ArrayLiteral(Vec<Expression>),
     ArrayIndex {
@@ -178,6 +273,26 @@ pub enum Expression {
         expr: Box<Expression>,
         target_type: AstType,
     },
+    /// A lambda: `x -> x + 1` or `(a: i32, b: i32) -> { a + b }`. Untyped
+    /// parameters take their types from where the closure is used.
+    Closure {
+        params: Vec<(String, Option<AstType>)>,
+        body: Box<Expression>,
+    },
+}
+
+impl Expression {
+    /// For `name.method(...)`, the qualified name `name.method` the call
+    /// refers to when `name` is a module, type or enum rather than a value.
+    pub fn qualified_call_name(&self) -> Option<String> {
+        match self {
+            Expression::MethodCall { object, method, .. } => match &**object {
+                Expression::Identifier(name) => Some(format!("{}.{}", name, method)),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -204,10 +319,14 @@ pub struct PatternArm {
 pub enum Pattern {
     Literal(Expression),
     Identifier(String),
+    /// `Point { x: px, y }`. Destructuring declarations write `{ x, y }`
+    /// with an empty name, taking the struct from the initializer's type.
     Struct {
         name: String,
         fields: Vec<(String, Pattern)>,
     },
+    /// `(q, r)`: the elements of a tuple, in order.
+    Tuple(Vec<Pattern>),
     EnumVariant {
         enum_name: String,
         variant: String,
@@ -238,6 +357,13 @@ pub enum Statement {
         is_mutable: bool, // true for ::= and :: T =, false for := and : T =
         declaration_type: VariableDeclarationType,
     },
+    /// `(q, r) := divmod(a, b)` or `{ x, y } ::= point`: binds each name
+    /// the pattern mentions to the matching part of the initializer.
+    DestructuringDeclaration {
+        pattern: Pattern,
+        initializer: Expression,
+        is_mutable: bool,
+    },
     VariableAssignment {
         name: String,
         value: Expression,
@@ -246,6 +372,13 @@ pub enum Statement {
         pointer: Expression,
         value: Expression,
     },
+    /// `a[i] += value` or `p.count |= value`: applies `op` to the field or
+    /// element `target` names and `value`, evaluating `target` once.
+    CompoundAssignment {
+        target: Expression,
+        op: BinaryOperator,
+        value: Expression,
+    },
     // Loop construct supporting all Zen loop variations
     Loop {
         kind: LoopKind,
@@ -264,6 +397,9 @@ pub enum Statement {
         alias: String,
         module_path: String,
     },
+    /// A statement that failed to parse. The parser reports the error
+    /// separately and skips to the next statement.
+    Error(crate::error::Span),
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -346,6 +482,12 @@ pub struct BehaviorDefinition {
     pub name: String,
     pub type_params: Vec<TypeParameter>,
     pub methods: Vec<BehaviorMethod>,
+    /// Types each implementation names, e.g. `Item: type`, referred to as
+    /// `Self.Item` in the behavior's signatures.
+    pub associated_types: Vec<String>,
+    /// Constants each implementation gives, e.g. `LIMIT: i32`, with an
+    /// optional default value.
+    pub constants: Vec<AssociatedConstant>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -353,14 +495,139 @@ pub struct BehaviorMethod {
     pub name: String,
     pub params: Vec<Parameter>,
     pub return_type: AstType,
+    /// The default implementation, inherited by impls that don't give
+    /// their own.
+    pub body: Option<Vec<Statement>>,
+}
+
+/// `LIMIT: i32 = 10`, accessed as `Type.LIMIT` or `Self.LIMIT`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AssociatedConstant {
+    pub name: String,
+    pub type_: AstType,
+    pub value: Option<Expression>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct ImplBlock {
     pub type_name: String,
     pub behavior_name: Option<String>, // None for inherent impls
+    /// Type parameters of a generic impl: `T` in `Box<T>.impl<T>`, or in
+    /// `T.impl<T: Show>` for a blanket implementation.
     pub type_params: Vec<TypeParameter>,
+    /// Type arguments of the implementing type, e.g. `T` in `Box<T>`.
+    pub type_args: Vec<AstType>,
     pub methods: Vec<Function>,
+    /// The behavior's associated types, e.g. `Item: type = i32`.
+    pub associated_types: Vec<(String, AstType)>,
+    pub constants: Vec<AssociatedConstant>,
+}
+
+impl ImplBlock {
+    /// The symbol `method` of this block is compiled to.
+    pub fn method_symbol(&self, method: &str) -> String {
+        method_symbol(&self.type_name, self.behavior_name.as_deref(), method)
+    }
+
+    /// Whether the block is a template, instantiated for each type it's
+    /// used with.
+    pub fn is_generic(&self) -> bool {
+        !self.type_params.is_empty()
+    }
+
+    /// The implementing type, in terms of the block's type parameters.
+    pub fn target(&self) -> AstType {
+        AstType::Generic { name: self.type_name.clone(), type_args: self.type_args.clone() }
+    }
+
+    /// `type_` with `Self` replaced by the block's type and `Self.Item` by
+    /// the associated type the block gives.
+    pub fn resolve_self(&self, type_: &AstType) -> AstType {
+        match type_ {
+            AstType::Generic { name, type_args } if type_args.is_empty() => {
+                if name == "Self" {
+                    return self.target();
+                }
+                let associated = name
+                    .strip_prefix("Self.")
+                    .and_then(|item| self.associated_types.iter().find(|(name, _)| name == item));
+                match associated {
+                    Some((_, associated)) => associated.clone(),
+                    None => type_.clone(),
+                }
+            }
+            AstType::Generic { name, type_args } => AstType::Generic {
+                name: name.clone(),
+                type_args: type_args.iter().map(|arg| self.resolve_self(arg)).collect(),
+            },
+            AstType::Pointer(inner) => AstType::Pointer(Box::new(self.resolve_self(inner))),
+            AstType::Array(inner) => AstType::Array(Box::new(self.resolve_self(inner))),
+            AstType::Ref(inner) => AstType::Ref(Box::new(self.resolve_self(inner))),
+            AstType::Option(inner) => AstType::Option(Box::new(self.resolve_self(inner))),
+            AstType::FixedArray { element_type, size } => AstType::FixedArray {
+                element_type: Box::new(self.resolve_self(element_type)),
+                size: *size,
+            },
+            AstType::Result { ok_type, err_type } => AstType::Result {
+                ok_type: Box::new(self.resolve_self(ok_type)),
+                err_type: Box::new(self.resolve_self(err_type)),
+            },
+            AstType::Tuple(elements) => AstType::Tuple(elements.iter().map(|element| self.resolve_self(element)).collect()),
+            AstType::Closure { param_types, return_type } => AstType::Closure {
+                param_types: param_types.iter().map(|param| self.resolve_self(param)).collect(),
+                return_type: Box::new(self.resolve_self(return_type)),
+            },
+            AstType::FunctionPointer { param_types, return_type } => AstType::FunctionPointer {
+                param_types: param_types.iter().map(|param| self.resolve_self(param)).collect(),
+                return_type: Box::new(self.resolve_self(return_type)),
+            },
+            other => other.clone(),
+        }
+    }
+
+    /// The methods the block defines: its own, then the default methods of
+    /// `behavior` it doesn't override. `Self` is resolved in their
+    /// signatures, and the methods of a generic block are generic over its
+    /// type parameters.
+    pub fn all_methods(&self, behavior: Option<&BehaviorDefinition>) -> Vec<Function> {
+        let resolve = |function: Function| Function {
+            type_params: self.type_params.iter().chain(&function.type_params).cloned().collect(),
+            args: function.args.iter().map(|(name, type_)| (name.clone(), self.resolve_self(type_))).collect(),
+            return_type: self.resolve_self(&function.return_type),
+            ..function
+        };
+        let mut methods: Vec<Function> = self.methods.iter().cloned().map(resolve).collect();
+        let inherited = behavior
+            .into_iter()
+            .flat_map(|behavior| &behavior.methods)
+            .filter(|method| !self.methods.iter().any(|own| own.name == method.name));
+        for method in inherited {
+            let Some(body) = &method.body else {
+                continue;
+            };
+            methods.push(resolve(Function {
+                name: method.name.clone(),
+                type_params: vec![],
+                args: method.params.iter().map(|param| (param.name.clone(), param.type_.clone())).collect(),
+                return_type: method.return_type.clone(),
+                body: body.clone(),
+                is_async: false,
+            }));
+        }
+        methods
+    }
+}
+
+/// The symbol a method is compiled to, which demangles to `Point.area`
+/// for an inherent method and `Point.Shape.area` for one implementing
+/// behavior `Shape`.
+pub fn method_symbol(type_name: &str, behavior_name: Option<&str>, method: &str) -> String {
+    mangle::Symbol::method(mangle::named_type(type_name), behavior_name, method).mangle()
+}
+
+/// The symbol of the vtable a `dyn` behavior object of a type points to.
+pub fn vtable_symbol(type_name: &str, behavior_name: &str) -> String {
+    mangle::Symbol::vtable(mangle::named_type(type_name), behavior_name).mangle()
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -377,6 +644,9 @@ pub enum Declaration {
         module_path: String,
     },
     TypeAlias(TypeAlias),
+    /// A declaration that failed to parse. The parser reports the error
+    /// separately and skips to the next declaration.
+    Error(crate::error::Span),
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -0,0 +1,171 @@
+//! Native code emission for `zen build`.
+//! Turns an LLVM module into object files, assembly, IR or bitcode and
+//! links executables with the system C toolchain.
+
+use crate::error::{CompileError, Result};
+use inkwell::module::Module;
+use inkwell::targets::{
+    CodeModel, FileType, InitializationConfig, RelocMode, Target, TargetMachine,
+};
+use inkwell::OptimizationLevel;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::str::FromStr;
+
+/// What `zen build` should write to disk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmitKind {
+    /// A linked native executable (the default).
+    Executable,
+    /// A relocatable object file.
+    Object,
+    /// Target assembly.
+    Assembly,
+    /// Textual LLVM IR.
+    LlvmIr,
+    /// LLVM bitcode.
+    LlvmBitcode,
+}
+
+impl EmitKind {
+    /// The file extension used when no `-o` path is given.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            EmitKind::Executable => std::env::consts::EXE_EXTENSION,
+            EmitKind::Object => "o",
+            EmitKind::Assembly => "s",
+            EmitKind::LlvmIr => "ll",
+            EmitKind::LlvmBitcode => "bc",
+        }
+    }
+
+    /// Default output path for an input file, e.g. `hello.zen` -> `hello.o`.
+    pub fn default_output(&self, input: &Path) -> PathBuf {
+        let stem = input.file_stem().unwrap_or_else(|| "a".as_ref());
+        let mut path = PathBuf::from(stem);
+        if !self.extension().is_empty() {
+            path.set_extension(self.extension());
+        }
+        path
+    }
+}
+
+impl FromStr for EmitKind {
+    type Err = CompileError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "exe" | "bin" => Ok(EmitKind::Executable),
+            "obj" => Ok(EmitKind::Object),
+            "asm" => Ok(EmitKind::Assembly),
+            "llvm-ir" => Ok(EmitKind::LlvmIr),
+            "llvm-bc" => Ok(EmitKind::LlvmBitcode),
+            _ => Err(CompileError::UnsupportedFeature(
+                format!("Unknown emit kind '{}' (expected exe, bin, obj, asm, llvm-ir or llvm-bc)", s),
+                None,
+            )),
+        }
+    }
+}
+
+/// Options controlling a single `zen build` invocation.
+#[derive(Debug, Clone)]
+pub struct BuildOptions {
+    pub emit: EmitKind,
+    pub output: PathBuf,
+    pub opt_level: OptimizationLevel,
+}
+
+/// Create a target machine for the host.
+pub fn host_target_machine(opt_level: OptimizationLevel) -> Result<TargetMachine> {
+    Target::initialize_native(&InitializationConfig::default())
+        .map_err(|e| CompileError::InternalError(format!("LLVM initialization failed: {}", e), None))?;
+
+    let triple = TargetMachine::get_default_triple();
+    let target = Target::from_triple(&triple)
+        .map_err(|e| CompileError::InternalError(format!("Unsupported target {}: {}", triple, e), None))?;
+
+    let cpu = TargetMachine::get_host_cpu_name();
+    let features = TargetMachine::get_host_cpu_features();
+    target
+        .create_target_machine(
+            &triple,
+            cpu.to_str().unwrap_or("generic"),
+            features.to_str().unwrap_or(""),
+            opt_level,
+            RelocMode::PIC,
+            CodeModel::Default,
+        )
+        .ok_or_else(|| CompileError::InternalError(
+            format!("Could not create a target machine for {}", triple),
+            None,
+        ))
+}
+
+/// Write `module` to `options.output` in the requested format,
+/// linking an executable when `EmitKind::Executable` is requested.
+pub fn build_module(module: &Module, options: &BuildOptions) -> Result<()> {
+    match options.emit {
+        EmitKind::LlvmIr => module
+            .print_to_file(&options.output)
+            .map_err(|e| CompileError::InternalError(format!("Failed to write IR: {}", e), None)),
+        EmitKind::LlvmBitcode => {
+            if module.write_bitcode_to_path(&options.output) {
+                Ok(())
+            } else {
+                Err(CompileError::InternalError(
+                    format!("Failed to write bitcode to {}", options.output.display()),
+                    None,
+                ))
+            }
+        }
+        EmitKind::Object | EmitKind::Assembly => {
+            let file_type = if options.emit == EmitKind::Object {
+                FileType::Object
+            } else {
+                FileType::Assembly
+            };
+            emit_native(module, options.opt_level, file_type, &options.output)
+        }
+        EmitKind::Executable => {
+            // The object is only an intermediate, so keep it out of the way
+            // of the user's files; the directory is removed when dropped
+            let dir = tempfile::tempdir()
+                .map_err(|e| CompileError::InternalError(format!("Failed to create a temporary directory: {}", e), None))?;
+            let object = dir.path().join("main.o");
+            emit_native(module, options.opt_level, FileType::Object, &object)?;
+            link_executable(&[object.as_path()], &options.output)
+        }
+    }
+}
+
+fn emit_native(module: &Module, opt_level: OptimizationLevel, file_type: FileType, path: &Path) -> Result<()> {
+    let machine = host_target_machine(opt_level)?;
+    module.set_triple(&machine.get_triple());
+    module.set_data_layout(&machine.get_target_data().get_data_layout());
+    machine
+        .write_to_file(module, file_type, path)
+        .map_err(|e| CompileError::InternalError(format!("Failed to emit {}: {}", path.display(), e), None))
+}
+
+/// Link object files into an executable using the system C compiler.
+/// Honours the `CC` environment variable and falls back to `cc`.
+pub fn link_executable(objects: &[&Path], output: &Path) -> Result<()> {
+    let linker = std::env::var("CC").unwrap_or_else(|_| "cc".to_string());
+    let status = Command::new(&linker)
+        .args(objects)
+        .arg("-o")
+        .arg(output)
+        .arg("-lm")
+        .status()
+        .map_err(|e| CompileError::InternalError(format!("Failed to run linker '{}': {}", linker, e), None))?;
+
+    if status.success() {
+        Ok(())
+    } else {
+        Err(CompileError::InternalError(
+            format!("Linker '{}' exited with {}", linker, status),
+            None,
+        ))
+    }
+}
@@ -1,8 +1,10 @@
-use crate::ast::{ImplBlock, Expression};
+use crate::ast::{self, BehaviorDefinition, Expression, Function, ImplBlock};
 use crate::error::CompileError;
+use crate::typechecker::{DynCoercion, DynMethodCall};
 use super::LLVMCompiler;
-use inkwell::values::{BasicValueEnum, FunctionValue, PointerValue};
-use inkwell::types::BasicMetadataTypeEnum;
+use inkwell::types::StructType;
+use inkwell::values::{BasicMetadataValueEnum, BasicValueEnum, FunctionValue, PointerValue};
+use inkwell::AddressSpace;
 use std::collections::HashMap;
 
 /// Manages behavior/trait implementations and method dispatch in LLVM
@@ -11,6 +13,9 @@ pub struct BehaviorCodegen<'ctx> {
     vtables: HashMap<(String, String), PointerValue<'ctx>>,
     /// Maps (type_name, method_name) -> function
     method_impls: HashMap<(String, String), FunctionValue<'ctx>>,
+    /// Maps behavior_name -> its definition, whose method order is the
+    /// order of the slots of its vtables
+    behaviors: HashMap<String, BehaviorDefinition>,
 }
 
 impl<'ctx> BehaviorCodegen<'ctx> {
@@ -18,9 +23,20 @@ impl<'ctx> BehaviorCodegen<'ctx> {
         Self {
             vtables: HashMap::new(),
             method_impls: HashMap::new(),
+            behaviors: HashMap::new(),
         }
     }
 
+    /// Register a behavior, whose method order its vtables follow
+    pub fn register_behavior(&mut self, behavior: &BehaviorDefinition) {
+        self.behaviors.insert(behavior.name.clone(), behavior.clone());
+    }
+
+    /// The definition of a registered behavior
+    pub fn behavior(&self, name: &str) -> Option<&BehaviorDefinition> {
+        self.behaviors.get(name)
+    }
+
     /// Generate a vtable for a behavior implementation
     pub fn generate_vtable(
         &mut self,
@@ -30,6 +46,24 @@ impl<'ctx> BehaviorCodegen<'ctx> {
         behavior_name: &str,
         methods: &[(&str, FunctionValue<'ctx>)],
     ) -> Result<PointerValue<'ctx>, CompileError> {
+        // One slot per behavior method, in the order the behavior declares them
+        let methods: Vec<_> = match self.behaviors.get(behavior_name) {
+            Some(behavior) => behavior
+                .methods
+                .iter()
+                .map(|declared| &declared.name)
+                .map(|name| {
+                    methods.iter().find(|(method, _)| method == name).copied().ok_or_else(|| {
+                        CompileError::InternalError(
+                            format!("Implementation of '{}' for '{}' has no method '{}'", behavior_name, type_name, name),
+                            None,
+                        )
+                    })
+                })
+                .collect::<Result<_, _>>()?,
+            None => methods.to_vec(),
+        };
+
         // Create vtable type: array of function pointers
         let fn_ptr_type = context.ptr_type(inkwell::AddressSpace::default());
         let field_types: Vec<_> = (0..methods.len())
@@ -38,12 +72,12 @@ impl<'ctx> BehaviorCodegen<'ctx> {
         let vtable_type = context.struct_type(&field_types, false);
 
         // Create global vtable
-        let vtable_name = format!("vtable_{}_{}", type_name, behavior_name);
+        let vtable_name = ast::vtable_symbol(type_name, behavior_name);
         let vtable_global = module.add_global(vtable_type, None, &vtable_name);
         
         // Initialize vtable with method pointers
         let mut method_ptrs = Vec::new();
-        for (_, func) in methods {
+        for (_, func) in &methods {
             let ptr = func.as_global_value().as_pointer_value();
             method_ptrs.push(ptr.const_cast(fn_ptr_type));
         }
@@ -85,180 +119,128 @@ impl<'ctx> BehaviorCodegen<'ctx> {
 }
 
 impl<'ctx> LLVMCompiler<'ctx> {
-    /// Compile an impl block
+    /// Register the methods of an impl block, including the default methods
+    /// it inherits, which are declared and compiled along with the
+    /// program's functions, and build the vtable of a behavior
+    /// implementation.
     pub fn compile_impl_block(&mut self, impl_block: &ImplBlock) -> Result<(), CompileError> {
         let type_name = &impl_block.type_name;
-        
-        // Process each method in the impl block
-        for method in &impl_block.methods {
-            // Generate a mangled name for the method
-            let mangled_name = if let Some(behavior) = &impl_block.behavior_name {
-                format!("{}_{}_{}", type_name, behavior, method.name)
-            } else {
-                format!("{}_{}", type_name, method.name)
-            };
-
-            // Create LLVM function for the method
-            let llvm_return_type = self.to_llvm_type(&method.return_type)?;
-            
-            let mut param_types = Vec::new();
-            for (_, param_type) in &method.args {
-                let llvm_param_type = self.to_llvm_type(param_type)?;
-                if let Ok(basic_type) = llvm_param_type.into_basic_type() {
-                    param_types.push(BasicMetadataTypeEnum::from(basic_type));
-                }
+        let all_methods = self.impl_methods(impl_block);
+        let mut methods = Vec::new();
+        for method in &all_methods {
+            if let Some(function) = self.module.get_function(&impl_block.method_symbol(&method.name)) {
+                methods.push((method.name.as_str(), function));
             }
+        }
 
-            let fn_type = if let super::Type::Void = llvm_return_type {
-                self.context.void_type().fn_type(&param_types, false)
-            } else if let super::Type::Basic(basic_type) = llvm_return_type {
-                match basic_type {
-                    inkwell::types::BasicTypeEnum::IntType(int_type) => int_type.fn_type(&param_types, false),
-                    inkwell::types::BasicTypeEnum::FloatType(float_type) => float_type.fn_type(&param_types, false),
-                    inkwell::types::BasicTypeEnum::PointerType(ptr_type) => ptr_type.fn_type(&param_types, false),
-                    inkwell::types::BasicTypeEnum::StructType(struct_type) => struct_type.fn_type(&param_types, false),
-                    _ => {
-                        return Err(CompileError::UnsupportedFeature(
-                            format!("Unsupported method return type: {:?}", basic_type),
-                            None,
-                        ))
-                    }
-                }
-            } else {
-                return Err(CompileError::UnsupportedFeature(
-                    format!("Method return type not yet supported: {:?}", llvm_return_type),
-                    None,
-                ))
-            };
-
-            let function = self.module.add_function(&mangled_name, fn_type, None);
-            
-            // Set up the function body
-            let entry = self.context.append_basic_block(function, "entry");
-            self.builder.position_at_end(entry);
-            
-            // Store the current function
-            let prev_function = self.current_function;
-            self.current_function = Some(function);
-            
-            // Add parameters to symbol table
-            self.symbols.enter_scope();
-            for (i, (param_name, _)) in method.args.iter().enumerate() {
-                if i < function.count_params() as usize {
-                    let param_value = function.get_nth_param(i as u32).unwrap();
-                    let alloca = self.builder.build_alloca(param_value.get_type(), param_name)?;
-                    self.builder.build_store(alloca, param_value)?;
-                    self.symbols.insert(param_name.clone(), super::symbols::Symbol::Variable(alloca));
-                }
-            }
-            
-            // Compile method body
-            for stmt in &method.body {
-                self.compile_statement(stmt)?;
-            }
-            
-            // Add implicit return if needed
-            if matches!(llvm_return_type, super::Type::Void) {
-                if self.builder.get_insert_block().unwrap().get_terminator().is_none() {
-                    self.builder.build_return(None)?;
-                }
-            }
-            
-            // Clean up
-            self.symbols.exit_scope();
-            self.current_function = prev_function;
-            
-            // Register the method in our behavior codegen
-            if !self.behavior_codegen.is_some() {
-                self.behavior_codegen = Some(BehaviorCodegen::new());
-            }
-            
-            if let Some(ref mut behavior_codegen) = self.behavior_codegen {
-                behavior_codegen.register_method(type_name, &method.name, function);
-            }
+        let behavior_codegen = self.behavior_codegen.get_or_insert_with(BehaviorCodegen::new);
+        for (name, function) in &methods {
+            behavior_codegen.register_method(type_name, name, *function);
         }
-        
-        // If this implements a behavior, generate vtable
         if let Some(behavior_name) = &impl_block.behavior_name {
-            let mut methods = Vec::new();
-            
-            for method in &impl_block.methods {
-                let mangled_name = format!("{}_{}_{}", type_name, behavior_name, method.name);
-                if let Some(func) = self.module.get_function(&mangled_name) {
-                    methods.push((method.name.as_str(), func));
-                }
-            }
-            
-            if let Some(ref mut behavior_codegen) = self.behavior_codegen {
-                behavior_codegen.generate_vtable(self.context, &self.module, type_name, behavior_name, &methods)?;
-            }
+            behavior_codegen.generate_vtable(self.context, &self.module, type_name, behavior_name, &methods)?;
         }
-        
+
         Ok(())
     }
 
-    /// Compile a method call (e.g., obj.method(args))
+    /// The methods an impl block defines, with the default methods of its
+    /// behavior it doesn't override.
+    pub fn impl_methods(&self, impl_block: &ImplBlock) -> Vec<Function> {
+        let behavior = impl_block
+            .behavior_name
+            .as_ref()
+            .and_then(|name| self.behavior_codegen.as_ref()?.behavior(name));
+        impl_block.all_methods(behavior)
+    }
+
+    /// Compile `receiver.method(args)` as the function call the type
+    /// checker resolved it to. Unresolved calls on a bare name are calls
+    /// qualified by a module or enum, such as `io.print(...)`.
     pub fn compile_method_call(
         &mut self,
-        object: &Expression,
-        method_name: &str,
+        call: &Expression,
+        method: &str,
         args: &[Expression],
     ) -> Result<BasicValueEnum<'ctx>, CompileError> {
-        // For now, we'll implement static dispatch only
-        // Dynamic dispatch would require trait objects
-        
-        // Get the type of the object
-        // This is simplified - in a real implementation we'd need proper type tracking
-        let type_name = self.infer_type_name(object)?;
-        
-        // Look up the method
-        if let Some(ref behavior_codegen) = self.behavior_codegen {
-            if let Some(function) = behavior_codegen.resolve_method(&type_name, method_name) {
-                // Compile arguments
-                let mut compiled_args = Vec::new();
-                
-                // First argument is 'self' - the object
-                let self_value = self.compile_expression(object)?;
-                compiled_args.push(self_value);
-                
-                // Compile remaining arguments
-                for arg in args {
-                    compiled_args.push(self.compile_expression(arg)?);
-                }
-                
-                // Make the call
-                let args_metadata: Vec<inkwell::values::BasicMetadataValueEnum> = compiled_args
-                    .iter()
-                    .map(|arg| inkwell::values::BasicMetadataValueEnum::try_from(*arg).unwrap())
-                    .collect();
-                
-                let call_site = self.builder.build_call(function, &args_metadata, "method_call")?;
-                
-                return call_site.try_as_basic_value()
-                    .left()
-                    .ok_or_else(|| CompileError::TypeError(
-                        "Method call returned void where value expected".to_string(),
-                        None,
-                    ));
-            }
+        let Some(id) = self.span_cursor.current() else {
+            return self.compile_qualified_call(call, method, args);
+        };
+        if let Some(dyn_call) = self.types.dyn_method_call(&id).cloned() {
+            return self.compile_dyn_method_call(&dyn_call);
         }
-        
-        Err(CompileError::UndeclaredFunction(
-            format!("{}.{}", type_name, method_name),
-            None,
-        ))
+        let Some(Expression::FunctionCall { name, args: resolved_args }) = self.types.method_call(&id).cloned() else {
+            return self.compile_qualified_call(call, method, args);
+        };
+        // The resolved arguments are the call's children, less the
+        // receiver of a call through a type
+        let first_arg = (args.len() + 1).saturating_sub(resolved_args.len());
+        self.compile_function_call(&name, &resolved_args, first_arg)
     }
 
-    /// Helper to infer type name from an expression (simplified)
-    fn infer_type_name(&self, expr: &Expression) -> Result<String, CompileError> {
-        match expr {
-            Expression::Identifier(_name) => {
-                // Look up the variable's type in our type tracking
-                // This is simplified - real implementation would track types properly
-                Ok("UnknownType".to_string())
-            }
-            Expression::StructLiteral { name, .. } => Ok(name.clone()),
-            _ => Ok("UnknownType".to_string()),
+    fn compile_qualified_call(
+        &mut self,
+        call: &Expression,
+        method: &str,
+        args: &[Expression],
+    ) -> Result<BasicValueEnum<'ctx>, CompileError> {
+        match call.qualified_call_name() {
+            Some(name) => self.compile_function_call(&name, args, 1),
+            None => Err(CompileError::UndeclaredFunction(method.to_string(), None)),
+        }
+    }
+
+    /// Behavior objects are a pair of a pointer to the value and a pointer
+    /// to its type's vtable for the behavior.
+    pub fn dyn_struct_type(&self) -> StructType<'ctx> {
+        let ptr_type = self.context.ptr_type(AddressSpace::default());
+        self.context.struct_type(&[ptr_type.into(), ptr_type.into()], false)
+    }
+
+    /// Build the behavior object value the expression under the cursor
+    /// converts to. The coercion's data is the expression or its address.
+    pub fn compile_dyn_coercion(&mut self, coercion: &DynCoercion) -> Result<BasicValueEnum<'ctx>, CompileError> {
+        let data = self.compile_expression(&coercion.data)?;
+        let vtable_name = ast::vtable_symbol(&coercion.type_name, &coercion.behavior);
+        let Some(vtable) = self.module.get_global(&vtable_name) else {
+            return Err(CompileError::InternalError(
+                format!("No vtable for '{}' as a dyn {}", coercion.type_name, coercion.behavior),
+                None,
+            ));
+        };
+        let object = self.dyn_struct_type().get_undef();
+        let object = self.builder.build_insert_value(object, data, 0, "dyn_data")?;
+        let object = self.builder.build_insert_value(object, vtable.as_pointer_value(), 1, "dyn_vtable")?;
+        Ok(object.into_struct_value().into())
+    }
+
+    /// Make the method call under the cursor on a behavior object through
+    /// its vtable, passing the object's data pointer as `self`.
+    fn compile_dyn_method_call(&mut self, call: &DynMethodCall) -> Result<BasicValueEnum<'ctx>, CompileError> {
+        let BasicValueEnum::StructValue(object) = self.compile_child(0, &call.receiver)? else {
+            return Err(CompileError::TypeError(
+                format!("Cannot call '{}' on a value that isn't a dyn {}", call.method, call.behavior),
+                None,
+            ));
+        };
+        let data = self.builder.build_extract_value(object, 0, "dyn_data")?;
+        let vtable = self.builder.build_extract_value(object, 1, "dyn_vtable")?.into_pointer_value();
+        let ptr_type = self.context.ptr_type(AddressSpace::default());
+        let slot = self.context.i32_type().const_int(call.slot as u64, false);
+        let slot = unsafe { self.builder.build_gep(ptr_type, vtable, &[slot], "dyn_slot")? };
+        let function = self.builder.build_load(ptr_type, slot, "dyn_method")?.into_pointer_value();
+
+        let mut args: Vec<BasicMetadataValueEnum> = vec![data.into()];
+        for (index, (arg, type_)) in call.args.iter().zip(&call.param_types).enumerate() {
+            let value = self.compile_child_as(index + 1, arg, Some(type_))?;
+            let llvm_type = self.to_llvm_type(type_)?;
+            args.push(self.cast_value_to_type(value, self.expect_basic_type(llvm_type)?)?.into());
         }
+        let function_type = self.closure_function_type(&call.param_types, &call.return_type)?;
+        let result = self.builder.build_indirect_call(function_type, function, &args, "dyn_call")?;
+        Ok(result
+            .try_as_basic_value()
+            .left()
+            .unwrap_or_else(|| self.context.i32_type().const_int(0, false).into()))
     }
-}
\ No newline at end of file
+}
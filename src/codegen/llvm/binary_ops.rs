@@ -1,5 +1,5 @@
 use super::LLVMCompiler;
-use crate::ast::{Expression, BinaryOperator};
+use crate::ast::{AstType, Expression, BinaryOperator, UnaryOperator};
 use crate::error::CompileError;
 use inkwell::values::BasicValueEnum;
 use inkwell::{IntPredicate, FloatPredicate};
@@ -12,9 +12,21 @@ impl<'ctx> LLVMCompiler<'ctx> {
         left: &Expression,
         right: &Expression,
     ) -> Result<BasicValueEnum<'ctx>, CompileError> {
-        let left_val = self.compile_expression(left)?;
-        let right_val = self.compile_expression(right)?;
+        let left_val = self.compile_child(0, left)?;
+        let right_val = self.compile_child(1, right)?;
+        self.compile_operation(op, left, right, left_val, right_val)
+    }
 
+    /// Apply `op` to `left_val` and `right_val`, the values of `left` and
+    /// `right`, which are children 0 and 1 of the node under the cursor.
+    pub fn compile_operation(
+        &mut self,
+        op: &BinaryOperator,
+        left: &Expression,
+        right: &Expression,
+        left_val: BasicValueEnum<'ctx>,
+        right_val: BasicValueEnum<'ctx>,
+    ) -> Result<BasicValueEnum<'ctx>, CompileError> {
         match op {
             BinaryOperator::Add => self.compile_add(left_val, right_val),
             BinaryOperator::Subtract => self.compile_subtract(left_val, right_val),
@@ -30,9 +42,85 @@ impl<'ctx> LLVMCompiler<'ctx> {
             BinaryOperator::Modulo => self.compile_modulo(left_val, right_val),
             BinaryOperator::And => self.compile_and(left_val, right_val),
             BinaryOperator::Or => self.compile_or(left_val, right_val),
+            BinaryOperator::BitAnd
+            | BinaryOperator::BitOr
+            | BinaryOperator::BitXor
+            | BinaryOperator::ShiftLeft
+            | BinaryOperator::ShiftRight => {
+                let signed = self.operands_signed(left, right);
+                self.compile_bitwise(op, left_val, right_val, signed)
+            }
         }
     }
 
+    /// Whether the left and right operands of the binary operation under
+    /// the cursor are signed, by the types the checker solved for them.
+    /// Operations it never saw, in instantiated generic bodies, fall back
+    /// to the operands' apparent types.
+    fn operands_signed(&self, left: &Expression, right: &Expression) -> (bool, bool) {
+        let (left_type, right_type) = match self.span_cursor.current().and_then(|id| self.types.operand_types(&id).cloned()) {
+            Some((left_type, right_type)) => (Some(left_type), Some(right_type)),
+            None => (self.expression_type(left, self.child_id(0)), self.expression_type(right, self.child_id(1))),
+        };
+        let signed = |type_: Option<AstType>| !type_.is_some_and(|type_| type_.is_unsigned_integer());
+        (signed(left_type), signed(right_type))
+    }
+
+    pub fn compile_unary_operation(
+        &mut self,
+        op: UnaryOperator,
+        operand: &Expression,
+    ) -> Result<BasicValueEnum<'ctx>, CompileError> {
+        let value = self.compile_child(0, operand)?;
+        match op {
+            UnaryOperator::Negate if value.is_int_value() => {
+                Ok(self.builder.build_int_neg(value.into_int_value(), "negtmp")?.into())
+            }
+            UnaryOperator::Negate if value.is_float_value() => {
+                Ok(self.builder.build_float_neg(value.into_float_value(), "negtmp")?.into())
+            }
+            // `!` on an i1 and `~` on any integer both flip every bit
+            UnaryOperator::Not | UnaryOperator::BitNot if value.is_int_value() => {
+                Ok(self.builder.build_not(value.into_int_value(), "nottmp")?.into())
+            }
+            _ => Err(CompileError::TypeMismatch {
+                expected: "int or float".to_string(),
+                found: format!("{:?}", value.get_type()),
+                span: None,
+            }),
+        }
+    }
+
+    /// Bitwise operations and shifts. The right operand is converted to
+    /// the left one's width, by its own signedness, so shift amounts can be
+    /// any integer type.
+    fn compile_bitwise(
+        &mut self,
+        op: &BinaryOperator,
+        left_val: BasicValueEnum<'ctx>,
+        right_val: BasicValueEnum<'ctx>,
+        (signed, right_signed): (bool, bool),
+    ) -> Result<BasicValueEnum<'ctx>, CompileError> {
+        if !left_val.is_int_value() || !right_val.is_int_value() {
+            return Err(CompileError::TypeMismatch {
+                expected: "int".to_string(),
+                found: "mixed types".to_string(),
+                span: None,
+            });
+        }
+        let left = left_val.into_int_value();
+        let right = self.builder.build_int_cast_sign_flag(right_val.into_int_value(), left.get_type(), right_signed, "widthtmp")?;
+        let result = match op {
+            BinaryOperator::BitAnd => self.builder.build_and(left, right, "bitandtmp")?,
+            BinaryOperator::BitOr => self.builder.build_or(left, right, "bitortmp")?,
+            BinaryOperator::BitXor => self.builder.build_xor(left, right, "xortmp")?,
+            BinaryOperator::ShiftLeft => self.builder.build_left_shift(left, right, "shltmp")?,
+            // Arithmetic for signed values, logical for unsigned ones
+            _ => self.builder.build_right_shift(left, right, signed, "shrtmp")?,
+        };
+        Ok(result.into())
+    }
+
     fn compile_add(
         &mut self,
         left_val: BasicValueEnum<'ctx>,
@@ -0,0 +1,374 @@
+use super::{LLVMCompiler, Type};
+use crate::ast::{AstType, Expression, Statement};
+use crate::error::CompileError;
+use crate::typechecker::captures::{self, Capture, CaptureMode};
+use inkwell::{
+    module::Linkage,
+    types::{BasicMetadataTypeEnum, BasicType, BasicTypeEnum, FunctionType, StructType},
+    values::{BasicMetadataValueEnum, BasicValueEnum, FunctionValue, PointerValue},
+    AddressSpace,
+};
+
+impl<'ctx> LLVMCompiler<'ctx> {
+    /// Function values are a pair of a function pointer and an environment
+    /// pointer. The function takes the environment as a hidden first
+    /// argument; it is null when nothing was captured.
+    pub fn closure_struct_type(&self) -> StructType<'ctx> {
+        let ptr_type = self.context.ptr_type(AddressSpace::default());
+        self.context.struct_type(&[ptr_type.into(), ptr_type.into()], false)
+    }
+
+    /// The LLVM type of the function behind a function value. Methods
+    /// called through a vtable have the same shape, taking the object's
+    /// data pointer first.
+    pub(super) fn closure_function_type(&mut self, param_types: &[AstType], return_type: &AstType) -> Result<FunctionType<'ctx>, CompileError> {
+        let mut params: Vec<BasicMetadataTypeEnum> = vec![self.context.ptr_type(AddressSpace::default()).into()];
+        for type_ in param_types {
+            let llvm_type = self.to_llvm_type(type_)?;
+            params.push(self.expect_basic_type(llvm_type)?.into());
+        }
+        match self.to_llvm_type(return_type)? {
+            Type::Void => Ok(self.context.void_type().fn_type(&params, false)),
+            other => Ok(self.expect_basic_type(other)?.fn_type(&params, false)),
+        }
+    }
+
+    /// Compile `expr` where a value of type `expected` is wanted. Closures
+    /// take their parameter and result types from it, and named functions
+    /// used as function values are wrapped into closures.
+    pub fn compile_expression_as(&mut self, expr: &Expression, expected: Option<&AstType>) -> Result<BasicValueEnum<'ctx>, CompileError> {
+        match (expr, expected) {
+            (Expression::Closure { params, body }, _) => self.compile_closure(params, body, expected),
+            (Expression::Identifier(name), Some(AstType::Closure { param_types, return_type }))
+                if !self.variables.contains_key(name) =>
+            {
+                match self.module.get_function(name) {
+                    Some(function) => self.wrap_function(function, param_types, return_type),
+                    None => self.compile_expression(expr),
+                }
+            }
+            (Expression::TupleLiteral(elements), Some(AstType::Tuple(element_types))) => {
+                self.compile_tuple_literal(elements, Some(element_types))
+            }
+            (Expression::ArrayLiteral(elements), Some(AstType::Array(element_type))) => {
+                self.compile_array_literal(elements, Some(element_type))
+            }
+            (expr, Some(AstType::Dyn(_))) => {
+                let coercion = self.span_cursor.current().and_then(|id| self.types.dyn_coercion(&id).cloned());
+                match coercion {
+                    Some(coercion) => self.compile_dyn_coercion(&coercion),
+                    None => self.compile_expression(expr),
+                }
+            }
+            _ => self.compile_expression(expr),
+        }
+    }
+
+    fn compile_closure(
+        &mut self,
+        params: &[(String, Option<AstType>)],
+        body: &Expression,
+        expected: Option<&AstType>,
+    ) -> Result<BasicValueEnum<'ctx>, CompileError> {
+        let Some(AstType::Closure { param_types: expected_params, return_type }) = expected else {
+            return Err(CompileError::UnsupportedFeature(
+                "The type of this closure isn't known here; assign it to a variable or pass it as an argument".to_string(),
+                None,
+            ));
+        };
+        let param_types: Vec<AstType> = params
+            .iter()
+            .zip(expected_params)
+            .map(|((_, annotation), expected)| annotation.clone().unwrap_or_else(|| expected.clone()))
+            .collect();
+
+        let captures = captures::closure_captures(params, body, |name| self.variables.contains_key(name));
+        let mut captured = Vec::with_capacity(captures.len());
+        let mut fields: Vec<BasicTypeEnum> = Vec::with_capacity(captures.len());
+        for capture in captures {
+            let (variable, type_) = self.get_variable(&capture.name)?;
+            fields.push(match capture.mode {
+                CaptureMode::Value => {
+                    let llvm_type = self.to_llvm_type(&type_)?;
+                    self.expect_basic_type(llvm_type)?
+                }
+                CaptureMode::Pointer => self.context.ptr_type(AddressSpace::default()).into(),
+            });
+            captured.push((capture, variable, type_));
+        }
+        let env_type = self.context.struct_type(&fields, false);
+        let local = self.span_cursor.current().is_some_and(|id| self.types.is_local_closure(&id));
+        let env = self.build_closure_env(env_type, &captured, local)?;
+
+        let function_type = self.closure_function_type(&param_types, return_type)?;
+        let name = match self.current_function {
+            Some(enclosing) => format!("{}.closure", enclosing.get_name().to_string_lossy()),
+            None => "closure".to_string(),
+        };
+        let function = self.module.add_function(&name, function_type, Some(Linkage::Internal));
+        let name = function.get_name().to_string_lossy().into_owned();
+        self.function_params.insert(name.clone(), param_types.clone());
+        self.function_types.insert(name, (**return_type).clone());
+
+        // Compile the body as a function of its own, then come back
+        let saved_block = self.builder.get_insert_block();
+        let saved_function = self.current_function.replace(function);
+        let saved_variables = std::mem::take(&mut self.variables);
+        let saved_loops = std::mem::take(&mut self.loop_stack);
+        let entry = self.context.append_basic_block(function, "entry");
+        self.builder.position_at_end(entry);
+        self.symbols.enter_scope();
+
+        let result = self.in_child(0, |this| {
+            this.compile_closure_body(function, env_type, &captured, params, &param_types, body, return_type)
+        });
+
+        self.symbols.exit_scope();
+        self.loop_stack = saved_loops;
+        self.variables = saved_variables;
+        self.current_function = saved_function;
+        if let Some(block) = saved_block {
+            self.builder.position_at_end(block);
+        }
+        result?;
+
+        self.build_closure_value(function, env)
+    }
+
+    /// Allocate the environment and fill it with the captured values and
+    /// pointers.
+    ///
+    /// A `local` closure can't outlive the function creating it, so its
+    /// environment is a slot in that function's frame, reused each time the
+    /// closure is created. Other closures may, so their environment lives
+    /// on the heap. It belongs to the closure value, which is copied freely
+    /// with nothing tracking the last copy, so it is never freed. Only
+    /// local closures capture by pointer, which the type checker ensures,
+    /// so the variables they point to outlive them.
+    fn build_closure_env(
+        &mut self,
+        env_type: StructType<'ctx>,
+        captured: &[(Capture, PointerValue<'ctx>, AstType)],
+        local: bool,
+    ) -> Result<PointerValue<'ctx>, CompileError> {
+        if captured.is_empty() {
+            return Ok(self.context.ptr_type(AddressSpace::default()).const_null());
+        }
+        let env = if local {
+            self.build_entry_alloca(env_type, "closure_env")?
+        } else {
+            self.builder.build_malloc(env_type, "closure_env")?
+        };
+        for (index, (capture, variable, _)) in captured.iter().enumerate() {
+            let field = self.builder.build_struct_gep(env_type, env, index as u32, &capture.name)?;
+            let value: BasicValueEnum = match capture.mode {
+                CaptureMode::Value => {
+                    let field_type = env_type.get_field_type_at_index(index as u32).ok_or_else(|| {
+                        CompileError::InternalError("Closure environment is missing a field".to_string(), None)
+                    })?;
+                    self.builder.build_load(field_type, *variable, &capture.name)?
+                }
+                CaptureMode::Pointer => (*variable).into(),
+            };
+            self.builder.build_store(field, value)?;
+        }
+        Ok(env)
+    }
+
+    /// Allocate a slot in the entry block of the current function, so one
+    /// made inside a loop doesn't grow the frame on each iteration.
+    fn build_entry_alloca(&self, type_: StructType<'ctx>, name: &str) -> Result<PointerValue<'ctx>, CompileError> {
+        let entry = self
+            .current_function
+            .and_then(|function| function.get_first_basic_block())
+            .ok_or_else(|| CompileError::InternalError("Closure created outside a function".to_string(), None))?;
+        let builder = self.context.create_builder();
+        match entry.get_first_instruction() {
+            Some(instruction) => builder.position_before(&instruction),
+            None => builder.position_at_end(entry),
+        }
+        Ok(builder.build_alloca(type_, name)?)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn compile_closure_body(
+        &mut self,
+        function: FunctionValue<'ctx>,
+        env_type: StructType<'ctx>,
+        captured: &[(Capture, PointerValue<'ctx>, AstType)],
+        params: &[(String, Option<AstType>)],
+        param_types: &[AstType],
+        body: &Expression,
+        return_type: &AstType,
+    ) -> Result<(), CompileError> {
+        let ptr_type = self.context.ptr_type(AddressSpace::default());
+        let env = function
+            .get_nth_param(0)
+            .ok_or_else(|| CompileError::InternalError("Closure is missing its environment".to_string(), None))?
+            .into_pointer_value();
+        env.set_name("env");
+
+        // Captured values get a local copy; captured pointers are used as
+        // the variable itself
+        for (index, (capture, _, type_)) in captured.iter().enumerate() {
+            let field = self.builder.build_struct_gep(env_type, env, index as u32, &capture.name)?;
+            let variable = match capture.mode {
+                CaptureMode::Value => {
+                    let field_type = env_type.get_field_type_at_index(index as u32).ok_or_else(|| {
+                        CompileError::InternalError("Closure environment is missing a field".to_string(), None)
+                    })?;
+                    let value = self.builder.build_load(field_type, field, &capture.name)?;
+                    let alloca = self.builder.build_alloca(field_type, &capture.name)?;
+                    self.builder.build_store(alloca, value)?;
+                    alloca
+                }
+                CaptureMode::Pointer => self.builder.build_load(ptr_type, field, &capture.name)?.into_pointer_value(),
+            };
+            self.variables.insert(capture.name.clone(), (variable, type_.clone()));
+        }
+
+        for (index, ((name, _), type_)) in params.iter().zip(param_types).enumerate() {
+            let param = function
+                .get_nth_param(index as u32 + 1)
+                .ok_or_else(|| CompileError::InternalError(format!("Closure is missing parameter '{}'", name), None))?;
+            param.set_name(name);
+            let alloca = self.builder.build_alloca(param.get_type(), name)?;
+            self.builder.build_store(alloca, param)?;
+            self.variables.insert(name.clone(), (alloca, type_.clone()));
+        }
+
+        // A block's value is its trailing expression, if it has one
+        let (statements, result) = match body {
+            Expression::Block(statements) => match statements.split_last() {
+                Some((Statement::Expression(value), rest)) => (rest, Some((true, value))),
+                _ => (statements.as_slice(), None),
+            },
+            body => (&[][..], Some((false, body))),
+        };
+        for (index, statement) in statements.iter().enumerate() {
+            self.compile_block_statement(index, statement)?;
+        }
+        let value = match result {
+            // A trailing expression is the value of the block's last statement
+            Some((true, result)) if !self.block_terminated() => Some(self.in_child(statements.len(), |this| {
+                this.compile_child_as(0, result, Some(return_type))
+                    .map_err(|error| this.spans.attach(&this.span_cursor, error))
+            })?),
+            Some((false, result)) if !self.block_terminated() => Some(self.compile_expression_as(result, Some(return_type))?),
+            _ => None,
+        };
+        if self.block_terminated() {
+            return Ok(());
+        }
+
+        match (value, return_type) {
+            (_, AstType::Void) => {
+                self.builder.build_return(None)?;
+            }
+            (Some(value), _) => {
+                let llvm_type = self.to_llvm_type(return_type)?;
+                let value = self.cast_value_to_type(value, self.expect_basic_type(llvm_type)?)?;
+                self.builder.build_return(Some(&value))?;
+            }
+            (None, _) => return Err(CompileError::MissingReturnStatement("closure".to_string(), None)),
+        }
+        Ok(())
+    }
+
+    fn block_terminated(&self) -> bool {
+        self.builder
+            .get_insert_block()
+            .is_some_and(|block| block.get_terminator().is_some())
+    }
+
+    fn build_closure_value(&mut self, function: FunctionValue<'ctx>, env: PointerValue<'ctx>) -> Result<BasicValueEnum<'ctx>, CompileError> {
+        let closure = self.closure_struct_type().get_undef();
+        let closure = self
+            .builder
+            .build_insert_value(closure, function.as_global_value().as_pointer_value(), 0, "closure")?;
+        let closure = self.builder.build_insert_value(closure, env, 1, "closure")?;
+        Ok(closure.into_struct_value().into())
+    }
+
+    /// A named function as a function value: a closure with no environment
+    /// whose function forwards to it.
+    fn wrap_function(
+        &mut self,
+        target: FunctionValue<'ctx>,
+        param_types: &[AstType],
+        return_type: &AstType,
+    ) -> Result<BasicValueEnum<'ctx>, CompileError> {
+        let name = format!("{}.closure", target.get_name().to_string_lossy());
+        let wrapper = match self.module.get_function(&name) {
+            Some(wrapper) => wrapper,
+            None => {
+                let function_type = self.closure_function_type(param_types, return_type)?;
+                let wrapper = self.module.add_function(&name, function_type, Some(Linkage::Internal));
+                let saved_block = self.builder.get_insert_block();
+                let entry = self.context.append_basic_block(wrapper, "entry");
+                self.builder.position_at_end(entry);
+
+                let result = self.forward_call(wrapper, target);
+                if let Some(block) = saved_block {
+                    self.builder.position_at_end(block);
+                }
+                result?;
+                wrapper
+            }
+        };
+        let null = self.context.ptr_type(AddressSpace::default()).const_null();
+        self.build_closure_value(wrapper, null)
+    }
+
+    fn forward_call(&mut self, wrapper: FunctionValue<'ctx>, target: FunctionValue<'ctx>) -> Result<(), CompileError> {
+        let mut args: Vec<BasicMetadataValueEnum> = Vec::new();
+        for (param, target_param) in wrapper.get_params().into_iter().skip(1).zip(target.get_params()) {
+            args.push(self.cast_value_to_type(param, target_param.get_type())?.into());
+        }
+        let call = self.builder.build_call(target, &args, "forward")?;
+        match (call.try_as_basic_value().left(), wrapper.get_type().get_return_type()) {
+            (Some(value), Some(return_type)) => {
+                let value = self.cast_value_to_type(value, return_type)?;
+                self.builder.build_return(Some(&value))?;
+            }
+            _ => {
+                self.builder.build_return(None)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Call the function value stored at `closure`, passing its
+    /// environment along with `args`, the children of the call under the
+    /// cursor from `first_arg` on.
+    pub fn compile_closure_call(
+        &mut self,
+        closure: PointerValue<'ctx>,
+        param_types: &[AstType],
+        return_type: &AstType,
+        args: &[Expression],
+        first_arg: usize,
+    ) -> Result<BasicValueEnum<'ctx>, CompileError> {
+        let closure = self
+            .builder
+            .build_load(self.closure_struct_type(), closure, "closure")?
+            .into_struct_value();
+        let function = self.builder.build_extract_value(closure, 0, "closure_fn")?.into_pointer_value();
+        let env = self.builder.build_extract_value(closure, 1, "closure_env")?;
+
+        let mut call_args: Vec<BasicMetadataValueEnum> = vec![env.into()];
+        for (index, (arg, type_)) in args.iter().zip(param_types).enumerate() {
+            let value = self.compile_child_as(first_arg + index, arg, Some(type_))?;
+            let llvm_type = self.to_llvm_type(type_)?;
+            call_args.push(self.cast_value_to_type(value, self.expect_basic_type(llvm_type)?)?.into());
+        }
+        let function_type = self.closure_function_type(param_types, return_type)?;
+        let call = self.builder.build_indirect_call(function_type, function, &call_args, "closure_call")?;
+        // Calls to void closures are used as statements; give them the
+        // placeholder value blocks have
+        Ok(call
+            .try_as_basic_value()
+            .left()
+            .unwrap_or_else(|| self.context.i32_type().const_int(0, false).into()))
+    }
+}
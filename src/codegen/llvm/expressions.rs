@@ -1,9 +1,33 @@
 use super::{LLVMCompiler, symbols};
-use crate::ast::Expression;
+use crate::ast::{AssociatedConstant, AstType, Expression};
 use crate::error::CompileError;
+use inkwell::types::{BasicType, BasicTypeEnum};
 use inkwell::values::{BasicValueEnum, BasicValue, PointerValue};
 
 impl<'ctx> LLVMCompiler<'ctx> {
+    /// Run `compile` with the cursor on child `index` of the node being
+    /// compiled.
+    pub fn in_child<T>(&mut self, index: usize, compile: impl FnOnce(&mut Self) -> T) -> T {
+        self.span_cursor.push(index);
+        let result = compile(self);
+        self.span_cursor.pop();
+        result
+    }
+
+    /// Compile `expr`, child `index` of the node being compiled.
+    pub fn compile_child(&mut self, index: usize, expr: &Expression) -> Result<BasicValueEnum<'ctx>, CompileError> {
+        self.in_child(index, |this| this.compile_expression(expr))
+    }
+
+    pub fn compile_child_as(
+        &mut self,
+        index: usize,
+        expr: &Expression,
+        expected: Option<&AstType>,
+    ) -> Result<BasicValueEnum<'ctx>, CompileError> {
+        self.in_child(index, |this| this.compile_expression_as(expr, expected))
+    }
+
     pub fn compile_expression(&mut self, expr: &Expression) -> Result<BasicValueEnum<'ctx>, CompileError> {
         match expr {
             Expression::Integer8(value) => {
@@ -45,11 +69,20 @@ impl<'ctx> LLVMCompiler<'ctx> {
             Expression::Identifier(name) => {
                 self.compile_identifier(name)
             }
+            Expression::Closure { .. } => {
+                self.compile_expression_as(expr, None)
+            }
             Expression::BinaryOp { left, op, right } => {
                 self.compile_binary_operation(op, left, right)
             }
+            Expression::UnaryOp { op, operand } => {
+                self.compile_unary_operation(*op, operand)
+            }
             Expression::FunctionCall { name, args } => {
-                self.compile_function_call(name, args)
+                self.compile_function_call(name, args, 0)
+            }
+            Expression::MethodCall { method, args, .. } => {
+                self.compile_method_call(expr, method, args)
             }
             Expression::Conditional { scrutinee, arms } => {
                 self.compile_conditional_expression(scrutinee, arms)
@@ -69,8 +102,14 @@ impl<'ctx> LLVMCompiler<'ctx> {
             Expression::StructField { struct_, field } => {
                 self.compile_struct_field(struct_, field)
             }
+            Expression::TupleLiteral(elements) => {
+                self.compile_tuple_literal(elements, None)
+            }
+            Expression::TupleIndex { tuple, index } => {
+                self.compile_tuple_index(tuple, *index)
+            }
             Expression::ArrayLiteral(elements) => {
-                self.compile_array_literal(elements)
+                self.compile_array_literal(elements, None)
             }
             Expression::ArrayIndex { array, index } => {
                 self.compile_array_index(array, index)
@@ -79,7 +118,14 @@ impl<'ctx> LLVMCompiler<'ctx> {
                 self.compile_enum_variant(enum_name, variant, payload)
             }
             Expression::MemberAccess { object, member } => {
-                self.compile_member_access(object, member)
+                let constant = self
+                    .span_cursor
+                    .current()
+                    .and_then(|id| self.types.associated_constant(&id).cloned());
+                match constant {
+                    Some(constant) => self.compile_associated_constant(&constant),
+                    None => self.compile_member_access(object, member),
+                }
             }
             Expression::StringLength(expr) => {
                 self.compile_string_length(expr)
@@ -108,17 +154,18 @@ impl<'ctx> LLVMCompiler<'ctx> {
             }
             Expression::Block(statements) => {
                 // Compile block expression - evaluates to last expression or void
-                for stmt in statements {
-                    // Note: compile_statement returns (), we need to handle this differently
-                    self.compile_statement(stmt)?;
-                    // For now, blocks evaluate to void (i32 0)
-                }
+                let result = statements
+                    .iter()
+                    .enumerate()
+                    .try_for_each(|(index, stmt)| self.compile_block_statement(index, stmt));
+                // For now, blocks evaluate to void (i32 0)
+                result?;
                 // For now, blocks always return void
                 Ok(self.context.i32_type().const_int(0, false).into())
             }
             Expression::Return(expr) => {
                 // Compile return expression 
-                let return_val = self.compile_expression(expr)?;
+                let return_val = self.compile_child(0, expr)?;
                 // Generate return instruction
                 self.builder.build_return(Some(&return_val))
                     .map_err(|e| CompileError::InternalError(format!("Failed to build return: {:?}", e), None))?;
@@ -137,7 +184,7 @@ impl<'ctx> LLVMCompiler<'ctx> {
             .ok_or_else(|| CompileError::InternalError("No current function for conditional".to_string(), None))?;
         
         // Compile the scrutinee expression
-        let scrutinee_val = self.compile_expression(scrutinee)?;
+        let scrutinee_val = self.compile_child(0, scrutinee)?;
         
         // Create the merge block where all arms will jump to
         let merge_bb = self.context.append_basic_block(parent_function, "match_merge");
@@ -160,7 +207,7 @@ impl<'ctx> LLVMCompiler<'ctx> {
                 let saved_vars = self.apply_pattern_bindings(&bindings);
                 
                 // Compile the guard expression
-                let guard_val = self.compile_expression(guard_expr)?;
+                let guard_val = self.compile_child(2 * i + 1, guard_expr)?;
                 
                 // Restore variables
                 self.restore_variables(saved_vars);
@@ -203,7 +250,7 @@ impl<'ctx> LLVMCompiler<'ctx> {
             let saved_vars = self.apply_pattern_bindings(&bindings);
             
             // Compile the arm body
-            let arm_val = self.compile_expression(&arm.body)?;
+            let arm_val = self.compile_child(2 * i + 2, &arm.body)?;
             
             // Restore variables
             self.restore_variables(saved_vars);
@@ -241,25 +288,32 @@ impl<'ctx> LLVMCompiler<'ctx> {
         Ok(phi.as_basic_value())
     }
 
-    fn compile_array_literal(&mut self, elements: &[Expression]) -> Result<BasicValueEnum<'ctx>, CompileError> {
-        // Infer type from first element or default to i32
-        let element_type = if !elements.is_empty() {
-            // Compile first element to get its type
-            let first_val = self.compile_expression(&elements[0])?;
-            match first_val.get_type() {
-                inkwell::types::BasicTypeEnum::IntType(_) => self.context.i32_type(),
-                _ => self.context.i32_type() // Default to i32 for other types too
+    /// Build an array on the heap. Elements are converted to the element
+    /// type when the array's type is known here, and are otherwise taken to
+    /// be i32s.
+    pub(super) fn compile_array_literal(&mut self, elements: &[Expression], expected: Option<&AstType>) -> Result<BasicValueEnum<'ctx>, CompileError> {
+        let element_type: BasicTypeEnum<'ctx> = match expected {
+            Some(expected) => {
+                let llvm_type = self.to_llvm_type(expected)?;
+                self.expect_basic_type(llvm_type)?
+            }
+            // Infer type from first element or default to i32
+            None if !elements.is_empty() => {
+                // Compile first element to get its type
+                let first_val = self.compile_child(0, &elements[0])?;
+                match first_val.get_type() {
+                    inkwell::types::BasicTypeEnum::IntType(_) => self.context.i32_type().into(),
+                    _ => self.context.i32_type().into() // Default to i32 for other types too
+                }
             }
-        } else {
-            self.context.i32_type()
+            None => self.context.i32_type().into(),
         };
         
         let array_len = elements.len() as u32;
-        let _array_type = element_type.array_type(array_len);
 
         // Allocate the array on the heap (malloc)
-        let elem_size = element_type.size_of();
-        let total_size = element_type.const_int(array_len as u64, false);
+        let elem_size = element_type.size_of().ok_or_else(|| CompileError::InternalError("Array element type has no size".to_string(), None))?;
+        let total_size = elem_size.get_type().const_int(array_len as u64, false);
         let malloc_fn = self.module.get_function("malloc").ok_or_else(|| CompileError::InternalError("No malloc function declared".to_string(), None))?;
         let size = self.builder.build_int_mul(elem_size, total_size, "arraysize");
         let raw_ptr = self.builder.build_call(malloc_fn, &[size?.into()], "arraymalloc")?.try_as_basic_value().left().unwrap().into_pointer_value();
@@ -267,31 +321,38 @@ impl<'ctx> LLVMCompiler<'ctx> {
 
         // Store each element
         for (i, expr) in elements.iter().enumerate() {
-            let value = self.compile_expression(expr)?;
+            let value = match expected {
+                Some(expected) => {
+                    let value = self.compile_child_as(i, expr, Some(expected))?;
+                    self.cast_value_to_type(value, element_type)?
+                }
+                None => self.compile_child(i, expr)?,
+            };
             let gep = unsafe {
-                self.builder.build_gep(element_type, array_ptr, &[element_type.const_int(i as u64, false)], &format!("arrayidx{}", i))?
+                self.builder.build_gep(element_type, array_ptr, &[self.context.i32_type().const_int(i as u64, false)], &format!("arrayidx{}", i))?
             };
             self.builder.build_store(gep, value)?;
         }
         Ok(array_ptr.as_basic_value_enum())
     }
 
+    /// Load `array[index]`, the index expression under the cursor.
     fn compile_array_index(&mut self, array: &Expression, index: &Expression) -> Result<BasicValueEnum<'ctx>, CompileError> {
         // Get the address of the indexed element
         let gep = self.compile_array_index_address(array, index)?;
         
-        // Try to infer element type from context. Default to i32 for compatibility with tests
-        // TODO: Proper type inference for array elements from declaration
-        let element_type = self.context.i32_type();
+        let element_type = self.array_element_type(array)?;
         
         // Load the value from the address
         let loaded = self.builder.build_load(element_type, gep, "arrayload")?;
         Ok(loaded)
     }
     
+    /// The address of `array[index]`, the index expression under the
+    /// cursor.
     pub fn compile_array_index_address(&mut self, array: &Expression, index: &Expression) -> Result<PointerValue<'ctx>, CompileError> {
         // Compile array expression - should be a pointer
-        let array_val = self.compile_expression(array)?;
+        let array_val = self.compile_child(0, array)?;
         
         // Get the actual pointer value
         let array_ptr = if array_val.is_pointer_value() {
@@ -303,17 +364,28 @@ impl<'ctx> LLVMCompiler<'ctx> {
             ));
         };
         
-        // Try to infer element type from context. Default to i32 for compatibility with tests
-        // TODO: Proper type inference for array elements from declaration
-        let element_type = self.context.i32_type();
+        let element_type = self.array_element_type(array)?;
         
-        let index_val = self.compile_expression(index)?;
+        let index_val = self.compile_child(1, index)?;
         let gep = unsafe {
             self.builder.build_gep(element_type, array_ptr, &[index_val.into_int_value()], "arrayidx")?
         };
         Ok(gep)
     }
 
+    /// The element type of `array`, the array indexed by the expression
+    /// under the cursor, from its declared or inferred type. Arrays whose
+    /// type isn't known here are taken to hold i32s.
+    pub fn array_element_type(&mut self, array: &Expression) -> Result<BasicTypeEnum<'ctx>, CompileError> {
+        match self.expression_type(array, self.child_id(0)) {
+            Some(AstType::Array(element_type)) => {
+                let llvm_type = self.to_llvm_type(&element_type)?;
+                self.expect_basic_type(llvm_type)
+            }
+            _ => Ok(self.context.i32_type().into()),
+        }
+    }
+
     fn compile_enum_variant(&mut self, enum_name: &str, variant: &str, payload: &Option<Box<Expression>>) -> Result<BasicValueEnum<'ctx>, CompileError> {
         // Look up the enum info from the symbol table
         let enum_info = match self.symbols.lookup(enum_name) {
@@ -324,7 +396,7 @@ impl<'ctx> LLVMCompiler<'ctx> {
                 let tag = 0;
                 let tag_val = self.context.i64_type().const_int(tag, false);
                 let payload_val = if let Some(expr) = payload {
-                    self.compile_expression(expr)?
+                    self.compile_child(0, expr)?
                 } else {
                     self.context.i64_type().const_int(0, false).into()
                 };
@@ -352,7 +424,7 @@ impl<'ctx> LLVMCompiler<'ctx> {
         
         let tag_val = self.context.i64_type().const_int(tag, false);
         let payload_val = if let Some(expr) = payload {
-            self.compile_expression(expr)?
+            self.compile_child(0, expr)?
         } else {
             self.context.i64_type().const_int(0, false).into()
         };
@@ -395,7 +467,7 @@ impl<'ctx> LLVMCompiler<'ctx> {
             .ok_or_else(|| CompileError::InternalError("No current function for pattern match".to_string(), None))?;
         
         // Compile the scrutinee expression
-        let scrutinee_val = self.compile_expression(scrutinee)?;
+        let scrutinee_val = self.compile_child(0, scrutinee)?;
         
         // Create the merge block where all arms will jump to
         let merge_bb = self.context.append_basic_block(parent_function, "match_merge");
@@ -418,7 +490,7 @@ impl<'ctx> LLVMCompiler<'ctx> {
                 let saved_vars = self.apply_pattern_bindings(&bindings);
                 
                 // Compile the guard expression
-                let guard_val = self.compile_expression(guard_expr)?;
+                let guard_val = self.compile_child(2 * i + 1, guard_expr)?;
                 
                 // Restore variables
                 self.restore_variables(saved_vars);
@@ -461,7 +533,7 @@ impl<'ctx> LLVMCompiler<'ctx> {
             let saved_vars = self.apply_pattern_bindings(&bindings);
             
             // Compile the arm body
-            let arm_val = self.compile_expression(&arm.body)?;
+            let arm_val = self.compile_child(2 * i + 2, &arm.body)?;
             
             // Restore variables
             self.restore_variables(saved_vars);
@@ -501,8 +573,8 @@ impl<'ctx> LLVMCompiler<'ctx> {
 
     fn compile_range_expression(&mut self, start: &Expression, end: &Expression, inclusive: bool) -> Result<BasicValueEnum<'ctx>, CompileError> {
         // For now, represent ranges as a simple struct { start: i64, end: i64, inclusive: bool }
-        let start_val = self.compile_expression(start)?;
-        let end_val = self.compile_expression(end)?;
+        let start_val = self.compile_child(0, start)?;
+        let end_val = self.compile_child(1, end)?;
         
         // Create a simple struct type for the range
         let _range_struct_type = self.context.struct_type(&[
@@ -521,11 +593,27 @@ impl<'ctx> LLVMCompiler<'ctx> {
         Ok(range_struct.as_basic_value_enum())
     }
     
+    /// The value of an associated constant, converted to its declared type.
+    /// The value isn't part of the body being compiled, so numbering is
+    /// suspended while it is.
+    fn compile_associated_constant(&mut self, constant: &AssociatedConstant) -> Result<BasicValueEnum<'ctx>, CompileError> {
+        let Some(value) = &constant.value else {
+            return Err(CompileError::InternalError(format!("Constant '{}' has no value", constant.name), None));
+        };
+        self.span_cursor.suspend();
+        let value = self.compile_expression_as(value, Some(&constant.type_));
+        self.span_cursor.resume();
+        let value = value?;
+        let llvm_type = self.to_llvm_type(&constant.type_)?;
+        let llvm_type = self.expect_basic_type(llvm_type)?;
+        self.cast_value_to_type(value, llvm_type)
+    }
+
     fn compile_type_cast(&mut self, expr: &Expression, target_type: &crate::ast::AstType) -> Result<BasicValueEnum<'ctx>, CompileError> {
         use inkwell::values::{IntValue, FloatValue, PointerValue};
         use crate::ast::AstType;
         
-        let value = self.compile_expression(expr)?;
+        let value = self.compile_child(0, expr)?;
         let target_llvm_type = self.to_llvm_type(target_type)?;
         
         // Handle pointer casts
@@ -98,13 +98,13 @@ impl<'ctx> LLVMCompiler<'ctx> {
         let mut format_string = String::new();
         let mut values: Vec<BasicMetadataValueEnum> = Vec::new();
         
-        for part in parts {
+        for (index, part) in parts.iter().enumerate() {
             match part {
                 StringPart::Literal(s) => {
                     format_string.push_str(s);
                 }
                 StringPart::Interpolation(expr) => {
-                    let val = self.compile_expression(expr)?;
+                    let val = self.compile_child(index, expr)?;
                     
                     // Determine the format specifier based on the value type
                     let format_spec = if val.is_int_value() {
@@ -1,6 +1,8 @@
 use crate::ast::{self, AstType};
 use crate::comptime;
 use crate::error::CompileError;
+use crate::spans::{self, SpanCursor, SpanTable};
+use crate::typechecker::TypeTable;
 use inkwell::{
     basic_block::BasicBlock,
     builder::Builder,
@@ -13,6 +15,7 @@ use std::collections::HashMap;
 
 mod behaviors;
 mod binary_ops;
+mod closures;
 mod control_flow;
 mod expressions;
 mod functions;
@@ -60,12 +63,16 @@ pub struct LLVMCompiler<'ctx> {
     pub variables: HashMap<String, (PointerValue<'ctx>, AstType)>,
     pub functions: HashMap<String, FunctionValue<'ctx>>,
     pub function_types: HashMap<String, AstType>,  // Track function return types
+    pub function_params: HashMap<String, Vec<AstType>>,
     pub current_function: Option<FunctionValue<'ctx>>,
     pub symbols: symbols::SymbolTable<'ctx>,
     pub struct_types: HashMap<String, StructTypeInfo<'ctx>>,
     pub loop_stack: Vec<(BasicBlock<'ctx>, BasicBlock<'ctx>)>, // (continue_target, break_target)
     pub comptime_evaluator: comptime::ComptimeInterpreter,
     pub behavior_codegen: Option<behaviors::BehaviorCodegen<'ctx>>,
+    pub spans: SpanTable,
+    pub span_cursor: SpanCursor,
+    pub types: TypeTable,
 }
 
 impl<'ctx> LLVMCompiler<'ctx> {
@@ -92,15 +99,32 @@ impl<'ctx> LLVMCompiler<'ctx> {
             variables: HashMap::new(),
             functions: HashMap::new(),
             function_types: HashMap::new(),
+            function_params: HashMap::new(),
             current_function: None,
             symbols,
             struct_types: HashMap::new(),
             loop_stack: Vec::new(),
             comptime_evaluator,
             behavior_codegen: Some(behaviors::BehaviorCodegen::new()),
+            spans: SpanTable::new(),
+            span_cursor: SpanCursor::new(),
+            types: TypeTable::new(),
         }
     }
 
+    /// Use the parser's span table so codegen errors point at source.
+    pub fn with_spans(mut self, spans: SpanTable) -> Self {
+        self.spans = spans;
+        self
+    }
+
+    /// Use the types resolved by the type checker instead of inferring
+    /// them from generated values.
+    pub fn with_type_table(mut self, types: TypeTable) -> Self {
+        self.types = types;
+        self
+    }
+
     pub fn get_type(&self, name: &str) -> Result<BasicTypeEnum<'ctx>, CompileError> {
         self.symbols.lookup(name)
             .and_then(|sym| match sym {
@@ -134,68 +158,146 @@ impl<'ctx> LLVMCompiler<'ctx> {
         Err(CompileError::UndeclaredVariable(name.to_string(), None))
     }
 
+    /// The functions `declaration` defines, with the owner their spans are
+    /// recorded under. Methods are renamed to the symbols they compile to.
+    fn declared_functions(&self, declaration: &ast::Declaration) -> Vec<(String, ast::Function)> {
+        let methods = |type_name: &str, behavior_name: Option<&str>, methods: &[ast::Function]| {
+            methods
+                .iter()
+                .map(|method| {
+                    let symbol = ast::method_symbol(type_name, behavior_name, &method.name);
+                    (spans::method_owner(type_name, &method.name), ast::Function { name: symbol, ..method.clone() })
+                })
+                .collect()
+        };
+        match declaration {
+            ast::Declaration::Function(func) => vec![(func.name.clone(), func.clone())],
+            ast::Declaration::Impl(block) => {
+                methods(&block.type_name, block.behavior_name.as_deref(), &self.impl_methods(block))
+            }
+            ast::Declaration::Struct(struct_def) => methods(&struct_def.name, None, &struct_def.methods),
+            ast::Declaration::Enum(enum_def) => methods(&enum_def.name, None, &enum_def.methods),
+            _ => Vec::new(),
+        }
+    }
+
     pub fn compile_program(&mut self, program: &ast::Program) -> Result<(), CompileError> {
+        match self.compile_program_collecting(program).into_iter().next() {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+
+    /// Compile a program, continuing past functions that fail to compile so
+    /// every codegen error is reported. Failed function bodies are removed,
+    /// leaving only their declarations in the module.
+    pub fn compile_program_collecting(&mut self, program: &ast::Program) -> Vec<CompileError> {
+        let mut errors = Vec::new();
         
         // First pass: register struct types
         for declaration in &program.declarations {
             if let ast::Declaration::Struct(struct_def) = declaration {
-                self.register_struct_type(struct_def)?;
+                if let Err(e) = self.register_struct_type(struct_def) {
+                    errors.push(e);
+                }
             }
         }
         
         // Register enum types
         for declaration in &program.declarations {
             if let ast::Declaration::Enum(enum_def) = declaration {
-                self.register_enum_type(enum_def)?;
+                if let Err(e) = self.register_enum_type(enum_def) {
+                    errors.push(e);
+                }
             }
         }
         
         for declaration in &program.declarations {
-            match declaration {
+            let result = match declaration {
                 ast::Declaration::ExternalFunction(ext_func) => {
-                    self.declare_external_function(ext_func)?;
+                    self.declare_external_function(ext_func).map(|_| ())
                 }
-                ast::Declaration::Function(_) => {}
-                ast::Declaration::Struct(_) => {} // Already handled above
-                ast::Declaration::Enum(_) => {} // Already handled above
-                ast::Declaration::ModuleImport { .. } => {}
-                ast::Declaration::Behavior(_) => {} // Behaviors are interface definitions, no codegen needed
-                ast::Declaration::Impl(impl_block) => {
-                    self.compile_impl_block(impl_block)?;
+                ast::Declaration::Function(_) => Ok(()),
+                ast::Declaration::Struct(_) => Ok(()), // Already handled above
+                ast::Declaration::Enum(_) => Ok(()), // Already handled above
+                ast::Declaration::ModuleImport { .. } => Ok(()),
+                ast::Declaration::Behavior(behavior) => {
+                    // Behaviors only fix the slot order of their vtables
+                    self.behavior_codegen.get_or_insert_with(behaviors::BehaviorCodegen::new).register_behavior(behavior);
+                    Ok(())
                 }
+                // Methods are declared and compiled with the functions below
+                ast::Declaration::Impl(_) => Ok(()),
                 ast::Declaration::ComptimeBlock(statements) => {
                     // Evaluate comptime blocks and generate constants
-                    for stmt in statements {
-                        if let Err(e) = self.comptime_evaluator.execute_statement(stmt) {
-                            return Err(CompileError::InternalError(
+                    statements.iter().try_for_each(|stmt| {
+                        self.comptime_evaluator.execute_statement(stmt).map(|_| ()).map_err(|e| {
+                            CompileError::InternalError(
                                 format!("Comptime evaluation error: {}", e),
                                 None
-                            ));
-                        }
-                    }
+                            )
+                        })
+                    })
                 }
                 ast::Declaration::TypeAlias(_) => {
                     // Type aliases are resolved at compile time, no codegen needed
+                    Ok(())
                 }
+                ast::Declaration::Error(span) => Err(CompileError::InternalError(
+                    "Cannot compile a declaration that failed to parse".to_string(),
+                    Some(span.clone()),
+                )),
+            };
+            if let Err(e) = result {
+                errors.push(self.locate_in_declaration(declaration, e));
             }
         }
         
-        // First pass: Declare all functions
+        // First pass: Declare all functions, and methods under their symbols
+        let mut declared = Vec::new();
         for declaration in &program.declarations {
-            if let ast::Declaration::Function(func) = declaration {
-                self.declare_function(func)?;
+            for (owner, func) in self.declared_functions(declaration) {
+                match self.declare_function(&func) {
+                    Ok(_) => declared.push((owner, func)),
+                    Err(e) => errors.push(self.locate_in_declaration(declaration, e)),
+                }
             }
         }
-        
-        // Second pass: Define and compile all functions
+
         for declaration in &program.declarations {
-            if let ast::Declaration::Function(func) = declaration {
-                self.compile_function_body(func)?;
+            if let ast::Declaration::Impl(impl_block) = declaration {
+                if let Err(e) = self.compile_impl_block(impl_block) {
+                    errors.push(self.locate_in_declaration(declaration, e));
+                }
             }
         }
         
+        // Second pass: Define and compile all functions
+        for (owner, func) in &declared {
+            self.span_cursor.enter_owner(owner.clone());
+            let result = self.compile_function_body(func)
+                .map_err(|e| self.spans.attach(&self.span_cursor, e));
+            self.span_cursor.leave_owner();
+            if let Err(e) = result {
+                // Drop the partial body so the remaining functions still verify
+                if let Some(function) = self.module.get_function(&func.name) {
+                    for block in function.get_basic_blocks() {
+                        let _ = unsafe { block.delete() };
+                    }
+                }
+                self.builder.clear_insertion_position();
+                errors.push(e);
+            }
+        }
         
-        Ok(())
+        errors
+    }
+
+    fn locate_in_declaration(&self, declaration: &ast::Declaration, error: CompileError) -> CompileError {
+        match spans::declaration_key(declaration).and_then(|key| self.spans.declaration(&key)) {
+            Some(span) if error.span().is_none() => error.with_span(span.clone()),
+            _ => error,
+        }
     }
 
     pub fn register_struct_type(&mut self, struct_def: &ast::StructDefinition) -> Result<(), CompileError> {
@@ -174,6 +174,24 @@ impl<'ctx> LLVMCompiler<'ctx> {
                 ));
             }
             
+            Pattern::Tuple(elements) => {
+                let BasicValueEnum::StructValue(tuple) = scrutinee_val else {
+                    return Err(CompileError::TypeMismatch {
+                        expected: "tuple for tuple pattern".to_string(),
+                        found: format!("{:?}", scrutinee_val.get_type()),
+                        span: None,
+                    });
+                };
+                let mut result = self.context.bool_type().const_int(1, false);
+                for (index, element) in elements.iter().enumerate() {
+                    let element_val = self.builder.build_extract_value(*tuple, index as u32, "element")?;
+                    let (element_match, mut element_bindings) = self.compile_pattern_test(&element_val, element)?;
+                    bindings.append(&mut element_bindings);
+                    result = self.builder.build_and(result, element_match, "tuple_match")?;
+                }
+                result
+            }
+            
             Pattern::EnumVariant { enum_name, variant, payload } => {
                 // Get the enum type info
                 let enum_info = match self.symbols.lookup(enum_name) {
@@ -89,8 +89,8 @@ impl<'ctx> LLVMCompiler<'ctx> {
     }
 
     pub fn compile_pointer_offset(&mut self, pointer: &Expression, offset: &Expression) -> Result<BasicValueEnum<'ctx>, CompileError> {
-        let base_val = self.compile_expression(pointer)?;
-        let offset_val = self.compile_expression(offset)?;
+        let base_val = self.compile_child(0, pointer)?;
+        let offset_val = self.compile_child(1, offset)?;
         if !base_val.is_pointer_value() {
             return Err(CompileError::TypeMismatch {
                 expected: "pointer for pointer offset base".to_string(),
@@ -7,25 +7,94 @@ use inkwell::{
 };
 
 impl<'ctx> LLVMCompiler<'ctx> {
+    /// Compile the statements of a function or loop body, giving errors the
+    /// span of the statement they came from.
+    pub fn compile_body(&mut self, body: &[Statement]) -> Result<(), CompileError> {
+        for (index, statement) in body.iter().enumerate() {
+            self.span_cursor.push(index);
+            let result = self.compile_statement(statement)
+                .map_err(|error| self.spans.attach(&self.span_cursor, error));
+            self.span_cursor.pop();
+            result?;
+        }
+        Ok(())
+    }
+
+    /// Compile `statement`, statement `index` of a block expression,
+    /// giving errors its span.
+    pub fn compile_block_statement(&mut self, index: usize, statement: &Statement) -> Result<(), CompileError> {
+        self.in_child(index, |this| {
+            this.compile_statement(statement)
+                .map_err(|error| this.spans.attach(&this.span_cursor, error))
+        })
+    }
+
+    /// The type the checker resolved for the variable declared by the
+    /// current statement, if its LLVM representation matches `value`.
+    fn checked_binding_type(&mut self, value: &BasicValueEnum<'ctx>) -> Option<AstType> {
+        let id = self.span_cursor.current()?;
+        let checked_type = self.types.binding(&id)?.clone();
+        let llvm_type = match self.to_llvm_type(&checked_type).ok()? {
+            Type::Basic(basic) => basic,
+            Type::Struct(struct_type) => struct_type.as_basic_type_enum(),
+            _ => return None,
+        };
+        (llvm_type == value.get_type()).then_some(checked_type)
+    }
+
+    /// Store `val` at the address held in the pointer variable `ptr_val`.
+    fn store_through(&mut self, ptr_val: BasicValueEnum<'ctx>, val: BasicValueEnum<'ctx>) -> Result<(), CompileError> {
+        if ptr_val.is_pointer_value() {
+            let ptr = ptr_val.into_pointer_value();
+            // Load the address stored in the pointer variable
+            let address = self.builder.build_load(self.context.ptr_type(inkwell::AddressSpace::default()), ptr, "deref_ptr")?;
+            // Store the value at that address
+            let address_ptr = address.into_pointer_value();
+            self.builder.build_store(address_ptr, val)?;
+            Ok(())
+        } else {
+            Err(CompileError::TypeMismatch {
+                expected: "pointer".to_string(),
+                found: format!("{:?}", ptr_val.get_type()),
+                span: None,
+            })
+        }
+    }
+
     pub fn compile_statement(&mut self, statement: &Statement) -> Result<(), CompileError> {
         match statement {
             Statement::Expression(expr) => {
-                self.compile_expression(expr)?;
+                self.compile_child(0, expr)?;
                 Ok(())
             }
             Statement::Return(expr) => {
-                let value = self.compile_expression(expr)?;
+                let expected = self.current_function
+                    .and_then(|function| self.function_types.get(function.get_name().to_str().ok()?).cloned());
+                let value = self.compile_child_as(0, expr, expected.as_ref())?;
                 self.builder.build_return(Some(&value))?;
                 Ok(())
             }
             Statement::VariableDeclaration { name, type_, initializer, is_mutable: _, declaration_type: _ } => {
+                // Function values need their type before the initializer is
+                // compiled: closures take their parameter types from it
+                let expected = type_.clone().or_else(|| {
+                    let id = self.span_cursor.current()?;
+                    self.types.binding(&id).cloned()
+                });
+                if let (Some(closure_type @ AstType::Closure { .. }), Some(init_expr)) = (&expected, initializer) {
+                    let value = self.compile_child_as(0, init_expr, Some(closure_type))?;
+                    let alloca = self.builder.build_alloca(self.closure_struct_type(), name)?;
+                    self.builder.build_store(alloca, value)?;
+                    self.variables.insert(name.clone(), (alloca, closure_type.clone()));
+                    return Ok(());
+                }
                 // Handle type inference or explicit type
                 let llvm_type = match type_ {
                     Some(type_) => self.to_llvm_type(type_)?,
                     None => {
                         // Type inference - try to infer from initializer
                         if let Some(init_expr) = initializer {
-                            let init_value = self.compile_expression(init_expr)?;
+                            let init_value = self.compile_child(0, init_expr)?;
                             match init_value {
                                 BasicValueEnum::IntValue(int_val) => {
                                     if int_val.get_type().get_bit_width() <= 32 {
@@ -42,6 +111,7 @@ impl<'ctx> LLVMCompiler<'ctx> {
                                     // For pointers (including strings), use ptr type
                                     Type::Basic(self.context.ptr_type(inkwell::AddressSpace::default()).into())
                                 }
+                                BasicValueEnum::StructValue(struct_val) => Type::Struct(struct_val.get_type()),
                                 _ => Type::Basic(self.context.i64_type().into()), // Default to i64
                             }
                         } else {
@@ -61,7 +131,7 @@ impl<'ctx> LLVMCompiler<'ctx> {
                 let alloca = self.builder.build_alloca(basic_type, name).map_err(|e| CompileError::from(e))?;
 
                 if let Some(init_expr) = initializer {
-                    let value = self.compile_expression(init_expr)?;
+                    let value = self.compile_child_as(0, init_expr, type_.as_ref())?;
                     
                     // Handle function pointers specially
                     if let Some(type_) = type_ {
@@ -130,27 +200,9 @@ impl<'ctx> LLVMCompiler<'ctx> {
                     } else {
                         // Type inference case
                         self.builder.build_store(alloca, value).map_err(|e| CompileError::from(e))?;
-                        // For inferred types, we need to determine the type from the value and the expression
-                        let inferred_type = if let Expression::StructLiteral { name: struct_name, .. } = init_expr {
-                            // If initializer is a struct literal, use the struct type
-                            // We need to get the field types from the registered struct
-                            if let Some(struct_info) = self.struct_types.get(struct_name) {
-                                // Reconstruct the AstType::Struct with field information
-                                let mut fields = vec![];
-                                for (field_name, (_, field_type)) in &struct_info.fields {
-                                    fields.push((field_name.clone(), field_type.clone()));
-                                }
-                                AstType::Struct {
-                                    name: struct_name.clone(),
-                                    fields,
-                                }
-                            } else {
-                                // Fallback if struct not found - shouldn't happen in practice
-                                AstType::Struct {
-                                    name: struct_name.clone(),
-                                    fields: vec![],
-                                }
-                            }
+                        // Prefer the type the checker resolved; otherwise determine it from the value
+                        let inferred_type = if let Some(checked_type) = self.checked_binding_type(&value) {
+                            checked_type
                         } else {
                             match value {
                                 BasicValueEnum::IntValue(int_val) => {
@@ -212,7 +264,7 @@ impl<'ctx> LLVMCompiler<'ctx> {
                     // Get the struct variable
                     let (struct_alloca, struct_type) = self.get_variable(struct_name)?;
                     // Compile the value to assign
-                    let value = self.compile_expression(value)?;
+                    let value = self.compile_child(0, value)?;
                     // Handle type conversion if needed
                     let value = match (&value, &struct_type) {
                         (BasicValueEnum::IntValue(int_val), AstType::Struct { .. }) => {
@@ -230,7 +282,7 @@ impl<'ctx> LLVMCompiler<'ctx> {
                 }
                 // Regular variable assignment
                 let (alloca, var_type) = self.get_variable(name)?;
-                let value = self.compile_expression(value)?;
+                let value = self.compile_child(0, value)?;
                 let value = match (&value, &var_type) {
                     (BasicValueEnum::IntValue(int_val), AstType::I32) => {
                         if int_val.get_type().get_bit_width() != 32 {
@@ -269,32 +321,36 @@ impl<'ctx> LLVMCompiler<'ctx> {
                 // Special case for array indexing on left side
                 if let Expression::ArrayIndex { array, index } = pointer {
                     // Get the address of the array element
-                    let element_ptr = self.compile_array_index_address(array, index)?;
-                    let val = self.compile_expression(&value)?;
+                    let element_ptr = self.in_child(0, |this| this.compile_array_index_address(array, index))?;
+                    let val = self.compile_child(1, value)?;
                     self.builder.build_store(element_ptr, val)?;
                     Ok(())
                 } else {
-                    let ptr_val = self.compile_expression(&pointer)?;
-                    let val = self.compile_expression(&value)?;
-                    
-                    // For pointer variables, we need to load the address first, then store to that address
-                    if ptr_val.is_pointer_value() {
-                        let ptr = ptr_val.into_pointer_value();
-                        // Load the address stored in the pointer variable
-                        let address = self.builder.build_load(self.context.ptr_type(inkwell::AddressSpace::default()), ptr, "deref_ptr")?;
-                        // Store the value at that address
-                        let address_ptr = address.into_pointer_value();
-                        self.builder.build_store(address_ptr, val)?;
-                        Ok(())
-                    } else {
-                        Err(CompileError::TypeMismatch {
-                            expected: "pointer".to_string(),
-                            found: format!("{:?}", ptr_val.get_type()),
-                            span: None,
-                        })
-                    }
+                    let ptr_val = self.compile_child(0, pointer)?;
+                    let val = self.compile_child(1, value)?;
+                    self.store_through(ptr_val, val)
                 }
             }
+            Statement::CompoundAssignment { target, op, value } => {
+                let Expression::ArrayIndex { array, index } = target else {
+                    // Fields are reached through their binding, so reading
+                    // the target again has no side effects
+                    let current = self.compile_child(0, target)?;
+                    let operand = self.compile_child(1, value)?;
+                    let result = self.compile_operation(op, target, value, current, operand)?;
+                    let ptr_val = self.compile_child(0, target)?;
+                    return self.store_through(ptr_val, result);
+                };
+                // The element's address is computed once, so the index's
+                // side effects happen once
+                let element_ptr = self.in_child(0, |this| this.compile_array_index_address(array, index))?;
+                let element_type = self.in_child(0, |this| this.array_element_type(array))?;
+                let current = self.builder.build_load(element_type, element_ptr, "compoundload")?;
+                let operand = self.compile_child(1, value)?;
+                let result = self.compile_operation(op, target, value, current, operand)?;
+                self.builder.build_store(element_ptr, result)?;
+                Ok(())
+            }
             Statement::Loop { kind, body, label: _ } => {
                 use crate::ast::LoopKind;
                 
@@ -312,9 +368,7 @@ impl<'ctx> LLVMCompiler<'ctx> {
                         self.builder.position_at_end(loop_body);
                         
                         // Compile body
-                        for stmt in body {
-                            self.compile_statement(stmt)?;
-                        }
+                        self.compile_body(body)?;
                         
                         // Loop back if no terminator
                         let current_block = self.builder.get_insert_block().unwrap();
@@ -339,7 +393,7 @@ impl<'ctx> LLVMCompiler<'ctx> {
                         self.builder.position_at_end(loop_header);
                         
                         // Evaluate condition
-                        let cond_value = self.compile_expression(cond_expr)?;
+                        let cond_value = self.compile_child(body.len(), cond_expr)?;
                         if let BasicValueEnum::IntValue(int_val) = cond_value {
                             if int_val.get_type().get_bit_width() == 1 {
                                 self.builder.build_conditional_branch(int_val, loop_body, after_loop_block).map_err(|e| CompileError::from(e))?;
@@ -359,9 +413,7 @@ impl<'ctx> LLVMCompiler<'ctx> {
                         
                         // Compile body
                         self.builder.position_at_end(loop_body);
-                        for stmt in body {
-                            self.compile_statement(stmt)?;
-                        }
+                        self.compile_body(body)?;
                         
                         // Loop back to header
                         let current_block = self.builder.get_insert_block().unwrap();
@@ -411,10 +463,23 @@ impl<'ctx> LLVMCompiler<'ctx> {
                 }
                 Ok(())
             },
+            Statement::DestructuringDeclaration { pattern, initializer, .. } => {
+                let type_ = self
+                    .span_cursor
+                    .current()
+                    .and_then(|id| self.types.binding(&id).cloned())
+                    .ok_or_else(|| CompileError::TypeError("The type of a destructured value isn't known".to_string(), None))?;
+                let value = self.compile_child_as(0, initializer, Some(&type_))?;
+                self.destructure(pattern, value, &type_)
+            }
             Statement::ModuleImport { .. } => {
                 // Module imports are handled during parsing, not codegen
                 Ok(())
             },
+            Statement::Error(span) => Err(CompileError::InternalError(
+                "Cannot compile a statement that failed to parse".to_string(),
+                Some(span.clone()),
+            )),
         }
     }
 } 
\ No newline at end of file
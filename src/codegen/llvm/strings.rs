@@ -5,7 +5,7 @@ use inkwell::values::BasicValueEnum;
 
 impl<'ctx> LLVMCompiler<'ctx> {
     pub fn compile_string_length(&mut self, expr: &Expression) -> Result<BasicValueEnum<'ctx>, CompileError> {
-        let str_val = self.compile_expression(expr)?;
+        let str_val = self.compile_child(0, expr)?;
         if !str_val.is_pointer_value() {
             return Err(CompileError::TypeMismatch {
                 expected: "string (i8*) for length operation".to_string(),
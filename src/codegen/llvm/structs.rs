@@ -1,9 +1,10 @@
 use inkwell::types::BasicType;
 use super::{LLVMCompiler, Type};
-use crate::ast::{AstType, Expression};
+use crate::ast::{AstType, BinaryOperator, Expression, Pattern};
 use crate::error::CompileError;
+use crate::spans::NodeId;
 use inkwell::{
-    types::StructType,
+    types::{BasicTypeEnum, StructType},
     values::BasicValueEnum,
 };
 use std::collections::HashMap;
@@ -26,7 +27,7 @@ impl<'ctx> LLVMCompiler<'ctx> {
                     None
                 ))?;
             let mut fields_with_info = Vec::new();
-            for (field_name, field_expr) in fields {
+            for (position, (field_name, field_expr)) in fields.iter().enumerate() {
                 let (field_index, field_type) = struct_info.fields.get(field_name)
                     .ok_or_else(|| CompileError::TypeError(
                         format!("No field '{}' in struct '{}'", field_name, name),
@@ -36,18 +37,19 @@ impl<'ctx> LLVMCompiler<'ctx> {
                     field_name.clone(),
                     *field_index,
                     field_type.clone(),
-                    field_expr.clone()
+                    position,
+                    field_expr
                 ));
             }
-            fields_with_info.sort_by_key(|&(_, idx, _, _)| idx);
+            fields_with_info.sort_by_key(|&(_, idx, _, _, _)| idx);
             (struct_info.llvm_type, fields_with_info)
         };
         let alloca = self.builder.build_alloca(
             llvm_type, 
             &format!("{}_tmp", name)
         )?;
-        for (field_name, field_index, _field_type, field_expr) in fields_with_info {
-            let field_val = self.compile_expression(&field_expr)?;
+        for (field_name, field_index, _field_type, position, field_expr) in fields_with_info {
+            let field_val = self.compile_child(position, field_expr)?;
             let field_ptr = self.builder.build_struct_gep(
                 llvm_type,
                 alloca,
@@ -66,6 +68,8 @@ impl<'ctx> LLVMCompiler<'ctx> {
         }
     }
 
+    /// Load `field` of `struct_`, the object of the field access under the
+    /// cursor.
     pub fn compile_struct_field(&mut self, struct_: &Expression, field: &str) -> Result<BasicValueEnum<'ctx>, CompileError> {
         // Special handling for identifiers - we need the pointer, not the loaded value
         if let Expression::Identifier(name) = struct_ {
@@ -127,7 +131,7 @@ impl<'ctx> LLVMCompiler<'ctx> {
         // Handle dereference case - when accessing field of a dereferenced pointer
         if let Expression::Dereference(inner) = struct_ {
             // Compile the inner expression to get the pointer
-            let ptr_val = self.compile_expression(inner)?;
+            let ptr_val = self.compile_child(0, inner)?;
             
             if let BasicValueEnum::PointerValue(ptr) = ptr_val {
                 // Load the pointer value to get the actual struct pointer
@@ -202,10 +206,10 @@ impl<'ctx> LLVMCompiler<'ctx> {
         // 3. Access the field from there
         
         // First compile the struct expression
-        let struct_val = self.compile_expression(struct_)?;
+        let struct_val = self.compile_child(0, struct_)?;
         
-        // Infer the struct type from the value
-        let struct_name = self.infer_struct_type_from_value(&struct_val, struct_)?;
+        // Look up the struct type the checker resolved
+        let struct_name = self.struct_name_of(struct_, self.child_id(0))?;
         
         // Get struct type info (clone to avoid borrow checker issues)
         let (llvm_type, field_index, field_type) = {
@@ -280,86 +284,216 @@ impl<'ctx> LLVMCompiler<'ctx> {
         ))
     }
 
-    /// Infer struct type name from a compiled value and the original expression
-    pub fn infer_struct_type_from_value(&mut self, _val: &BasicValueEnum<'ctx>, expr: &Expression) -> Result<String, CompileError> {
+    /// The struct `expr`, the expression `id`, evaluates to or points at,
+    /// taken from the types resolved by the type checker.
+    pub fn struct_name_of(&self, expr: &Expression, id: Option<NodeId>) -> Result<String, CompileError> {
+        // Named types the parser couldn't resolve yet appear as `Generic`
+        let is_struct = |name: &String| self.struct_types.contains_key(name);
+        match self.expression_type(expr, id) {
+            Some(AstType::Struct { name, .. }) => Ok(name),
+            Some(AstType::Generic { name, .. }) if is_struct(&name) => Ok(name),
+            Some(AstType::Pointer(inner)) => match *inner {
+                AstType::Struct { name, .. } => Ok(name),
+                AstType::Generic { name, .. } if is_struct(&name) => Ok(name),
+                other => Err(CompileError::TypeError(
+                    format!("Expected a struct or pointer to struct, got *{}", other),
+                    None
+                )),
+            },
+            Some(other) => Err(CompileError::TypeError(
+                format!("Expected a struct or pointer to struct, got {}", other),
+                None
+            )),
+            None => Err(CompileError::TypeError(
+                format!("Cannot determine struct type of expression: {:?}", expr),
+                None
+            )),
+        }
+    }
+
+    /// The id of child `index` of the node being compiled.
+    pub(super) fn child_id(&self, index: usize) -> Option<NodeId> {
+        self.span_cursor.current().map(|id| id.child(index))
+    }
+
+    /// The type of `expr`, the expression `id`, from variable bindings and
+    /// the type table.
+    pub(super) fn expression_type(&self, expr: &Expression, id: Option<NodeId>) -> Option<AstType> {
+        let child = |index: usize| id.as_ref().map(|id| id.child(index));
         match expr {
-            Expression::Identifier(name) => {
-                // Look up the variable type
-                if let Some((_, var_type)) = self.variables.get(name) {
-                    match var_type {
-                        AstType::Struct { name: struct_name, .. } => Ok(struct_name.clone()),
-                        AstType::Pointer(inner) => {
-                            // Handle pointer to struct
-                            if let AstType::Struct { name: struct_name, .. } = &**inner {
-                                Ok(struct_name.clone())
-                            } else {
-                                Err(CompileError::TypeError(
-                                    format!("Variable '{}' is not a struct or pointer to struct", name),
-                                    None
-                                ))
-                            }
-                        },
-                        _ => Err(CompileError::TypeError(
-                            format!("Variable '{}' is not a struct type, got {:?}", name, var_type),
-                            None
-                        ))
-                    }
-                } else {
-                    Err(CompileError::TypeError(
-                        format!("Unknown variable '{}'", name),
-                        None
-                    ))
-                }
+            Expression::Identifier(name) => self.variables.get(name).map(|(_, type_)| type_.clone()),
+            Expression::StructLiteral { name, .. } => Some(AstType::Struct {
+                name: name.clone(),
+                fields: vec![],
+            }),
+            Expression::FunctionCall { name, .. } => self.types.function(name)
+                .map(|signature| signature.return_type.clone())
+                .or_else(|| self.function_types.get(name).cloned()),
+            // The resolved call's arguments aren't looked into, so its id
+            // doesn't matter
+            Expression::MethodCall { .. } => self.expression_type(self.types.method_call(id.as_ref()?)?, None),
+            Expression::StructField { struct_, field } => {
+                let parent = self.struct_name_of(struct_, child(0)).ok()?;
+                // Instantiated generic structs only exist after monomorphization
+                self.types.field_type(&parent, field).cloned().or_else(|| {
+                    self.struct_types.get(&parent)?.fields.get(field).map(|(_, type_)| type_.clone())
+                })
             }
-            Expression::StructLiteral { name, .. } => Ok(name.clone()),
-            Expression::FunctionCall { name, .. } => {
-                // Look up function return type
-                if let Some(func_type) = self.function_types.get(name) {
-                    if let AstType::Struct { name: struct_name, .. } = func_type {
-                        Ok(struct_name.clone())
-                    } else {
-                        Err(CompileError::TypeError(
-                            format!("Function '{}' does not return a struct", name),
-                            None
-                        ))
+            Expression::Integer8(_) => Some(AstType::I8),
+            Expression::Integer16(_) => Some(AstType::I16),
+            Expression::Integer32(_) => Some(AstType::I32),
+            Expression::Integer64(_) => Some(AstType::I64),
+            Expression::Unsigned8(_) => Some(AstType::U8),
+            Expression::Unsigned16(_) => Some(AstType::U16),
+            Expression::Unsigned32(_) => Some(AstType::U32),
+            Expression::Unsigned64(_) => Some(AstType::U64),
+            Expression::TypeCast { target_type, .. } => Some(target_type.clone()),
+            Expression::UnaryOp { operand, .. } => self.expression_type(operand, child(0)),
+            // Arithmetic, bitwise operations and shifts have the type of their left operand
+            Expression::BinaryOp { left, op, .. } if !matches!(
+                op,
+                BinaryOperator::Equals
+                    | BinaryOperator::NotEquals
+                    | BinaryOperator::LessThan
+                    | BinaryOperator::GreaterThan
+                    | BinaryOperator::LessThanEquals
+                    | BinaryOperator::GreaterThanEquals
+                    | BinaryOperator::And
+                    | BinaryOperator::Or
+            ) => self.expression_type(left, child(0)),
+            Expression::Dereference(inner) => match self.expression_type(inner, id)? {
+                AstType::Pointer(pointee) => Some(*pointee),
+                _ => None,
+            },
+            Expression::TupleLiteral(elements) => {
+                let elements = elements
+                    .iter()
+                    .enumerate()
+                    .map(|(index, element)| self.expression_type(element, child(index)))
+                    .collect::<Option<_>>()?;
+                Some(AstType::Tuple(elements))
+            }
+            Expression::TupleIndex { tuple, index } => match self.expression_type(tuple, child(0))? {
+                AstType::Tuple(mut elements) if *index < elements.len() => Some(elements.swap_remove(*index)),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// The anonymous struct a tuple is lowered to: one field per element,
+    /// in order.
+    pub fn tuple_struct_type(&mut self, elements: &[AstType]) -> Result<StructType<'ctx>, CompileError> {
+        let mut field_types = Vec::with_capacity(elements.len());
+        for element in elements {
+            let element_type = self.to_llvm_type(element)?;
+            field_types.push(self.expect_basic_type(element_type)?);
+        }
+        Ok(self.context.struct_type(&field_types, false))
+    }
+
+    /// Build a tuple value. Numeric elements are converted to the element
+    /// types in `expected`, e.g. for `pair: (i64, f32) = (1, 2.5)`.
+    pub fn compile_tuple_literal(&mut self, elements: &[Expression], expected: Option<&[AstType]>) -> Result<BasicValueEnum<'ctx>, CompileError> {
+        let mut values = Vec::with_capacity(elements.len());
+        for (index, element) in elements.iter().enumerate() {
+            let expected_element = expected.and_then(|types| types.get(index));
+            let value = self.compile_child_as(index, element, expected_element)?;
+            let value = match expected_element {
+                Some(element_type) => {
+                    let element_type = self.to_llvm_type(element_type)?;
+                    let element_type = self.expect_basic_type(element_type)?;
+                    self.cast_value_to_type(value, element_type)?
+                }
+                None => value,
+            };
+            values.push(value);
+        }
+        let field_types: Vec<BasicTypeEnum<'ctx>> = values.iter().map(|value| value.get_type()).collect();
+        let mut tuple = self.context.struct_type(&field_types, false).get_undef();
+        for (index, value) in values.into_iter().enumerate() {
+            tuple = self
+                .builder
+                .build_insert_value(tuple, value, index as u32, "tuple")?
+                .into_struct_value();
+        }
+        Ok(tuple.into())
+    }
+
+    pub fn compile_tuple_index(&mut self, tuple: &Expression, index: usize) -> Result<BasicValueEnum<'ctx>, CompileError> {
+        let value = self.compile_child(0, tuple)?;
+        let BasicValueEnum::StructValue(struct_value) = value else {
+            return Err(CompileError::TypeError(format!("Cannot take element {} of a non-tuple value", index), None));
+        };
+        if index >= struct_value.get_type().count_fields() as usize {
+            return Err(CompileError::TypeError(
+                format!("Tuple index {} is out of range for a tuple of {} elements", index, struct_value.get_type().count_fields()),
+                None,
+            ));
+        }
+        Ok(self.builder.build_extract_value(struct_value, index as u32, "element")?)
+    }
+
+    /// Declare the variables `pattern` names, each holding the matching
+    /// element or field of `value`, a value of type `type_`.
+    pub fn destructure(&mut self, pattern: &Pattern, value: BasicValueEnum<'ctx>, type_: &AstType) -> Result<(), CompileError> {
+        match pattern {
+            Pattern::Wildcard => Ok(()),
+            Pattern::Identifier(name) => {
+                let type_ = match type_ {
+                    AstType::Generic { name, type_args } if type_args.is_empty() && self.struct_types.contains_key(name) => {
+                        AstType::Struct { name: name.clone(), fields: vec![] }
                     }
-                } else {
-                    Err(CompileError::TypeError(
-                        format!("Unknown function '{}'", name),
-                        None
-                    ))
+                    other => other.clone(),
+                };
+                let alloca = self.builder.build_alloca(value.get_type(), name)?;
+                self.builder.build_store(alloca, value)?;
+                self.variables.insert(name.clone(), (alloca, type_));
+                Ok(())
+            }
+            Pattern::Tuple(elements) => {
+                let (AstType::Tuple(element_types), BasicValueEnum::StructValue(tuple)) = (type_, value) else {
+                    return Err(CompileError::TypeError(format!("Cannot destructure a value of type {} as a tuple", type_), None));
+                };
+                for (index, (element, element_type)) in elements.iter().zip(element_types).enumerate() {
+                    let element_value = self.builder.build_extract_value(tuple, index as u32, "element")?;
+                    self.destructure(element, element_value, element_type)?;
                 }
+                Ok(())
             }
-            Expression::StructField { struct_, field } => {
-                // For nested field access, recursively get the field type
-                let parent_struct_name = self.infer_struct_type_from_value(_val, struct_)?;
-                if let Some(struct_info) = self.struct_types.get(&parent_struct_name) {
-                    if let Some((_, field_type)) = struct_info.fields.get(field) {
-                        if let AstType::Struct { name: field_struct_name, .. } = field_type {
-                            Ok(field_struct_name.clone())
-                        } else {
-                            Err(CompileError::TypeError(
-                                format!("Field '{}' is not a struct type", field),
-                                None
-                            ))
-                        }
-                    } else {
-                        Err(CompileError::TypeError(
-                            format!("Field '{}' not found in struct '{}'", field, parent_struct_name),
-                            None
-                        ))
+            Pattern::Struct { fields, .. } => {
+                let struct_name = match type_ {
+                    AstType::Struct { name, .. } | AstType::Generic { name, .. } => name,
+                    other => {
+                        return Err(CompileError::TypeError(format!("Cannot destructure a value of type {} as a struct", other), None));
                     }
-                } else {
-                    Err(CompileError::TypeError(
-                        format!("Struct type '{}' not found", parent_struct_name),
-                        None
-                    ))
+                };
+                let struct_info = self.struct_types.get(struct_name).cloned().ok_or_else(|| {
+                    CompileError::TypeError(format!("Undefined struct type: {}", struct_name), None)
+                })?;
+                let struct_value = match value {
+                    BasicValueEnum::StructValue(struct_value) => struct_value,
+                    BasicValueEnum::PointerValue(pointer) => self
+                        .builder
+                        .build_load(struct_info.llvm_type, pointer, "load_struct")?
+                        .into_struct_value(),
+                    _ => {
+                        return Err(CompileError::TypeError(format!("Expected a value of struct type {}", struct_name), None));
+                    }
+                };
+                for (field, field_pattern) in fields {
+                    let (index, field_type) = struct_info.fields.get(field).ok_or_else(|| {
+                        CompileError::TypeError(format!("No field '{}' in struct '{}'", field, struct_name), None)
+                    })?;
+                    let field_value = self.builder.build_extract_value(struct_value, *index as u32, field)?;
+                    self.destructure(field_pattern, field_value, field_type)?;
                 }
+                Ok(())
             }
-            _ => Err(CompileError::TypeError(
-                format!("Cannot infer struct type from expression: {:?}", expr),
-                None
-            ))
+            _ => Err(CompileError::UnsupportedFeature(
+                "Only names, `_`, tuples and struct fields can be destructured in a declaration".to_string(),
+                None,
+            )),
         }
     }
     
@@ -88,6 +88,9 @@ impl<'ctx> LLVMCompiler<'ctx> {
                 };
                 Ok(Type::Function(function_type))
             },
+            AstType::Closure { .. } => Ok(Type::Struct(self.closure_struct_type())),
+            AstType::Tuple(elements) => Ok(Type::Struct(self.tuple_struct_type(elements)?)),
+            AstType::Dyn(_) => Ok(Type::Struct(self.dyn_struct_type())),
             AstType::FunctionPointer { param_types, return_type } => {
                 // Function pointers are represented as pointers to functions
                 let return_llvm_type = self.to_llvm_type(return_type)?;
@@ -167,6 +170,10 @@ impl<'ctx> LLVMCompiler<'ctx> {
                     None
                 ))
             },
+            AstType::Var(_) => Err(CompileError::InternalError(
+                "Uninferred type variable found during code generation. This is a compiler bug.".to_string(),
+                None
+            )),
         };
         result
     }
@@ -6,66 +6,107 @@ use crate::codegen::llvm::LLVMCompiler;
 use crate::comptime::ComptimeInterpreter;
 use crate::error::{CompileError, Result};
 use crate::module_system::{ModuleSystem, resolver::ModuleResolver};
+use crate::spans::SpanTable;
+use crate::diagnostics::Diagnostic;
 use crate::type_system::Monomorphizer;
+use crate::typechecker::{TypeChecker, TypeTable};
 use inkwell::context::Context;
 use inkwell::module::Module;
 
+/// A program that passed type checking, with the types resolved for it.
+pub struct CheckedProgram {
+    pub program: Program,
+    pub types: TypeTable,
+    /// Warnings the type checker raised, such as unreachable match arms.
+    pub warnings: Vec<Diagnostic>,
+}
+
 /// The main compiler structure.
 pub struct Compiler<'ctx> {
     context: &'ctx Context,
+    spans: SpanTable,
 }
 
 impl<'ctx> Compiler<'ctx> {
     pub fn new(context: &'ctx Context) -> Self {
-        Self { context }
+        Self { context, spans: SpanTable::new() }
+    }
+
+    /// Attach the parser's span table so errors from every stage point at
+    /// the source they came from.
+    pub fn with_spans(mut self, spans: SpanTable) -> Self {
+        self.spans = spans;
+        self
     }
 
     /// Compiles a program using the LLVM backend.
     /// In the future, this could take a `target` enum.
     pub fn compile_llvm(&self, program: &Program) -> Result<String> {
-        // Process module imports
-        let processed_program = self.process_imports(program)?;
-        
-        // Execute comptime blocks and expressions
-        let processed_program = self.execute_comptime(processed_program)?;
-        
-        // Monomorphize the program to resolve all generic types
-        let mut monomorphizer = Monomorphizer::new();
-        let monomorphized_program = monomorphizer.monomorphize_program(&processed_program)?;
-        
-        let mut llvm_compiler = LLVMCompiler::new(self.context);
-        llvm_compiler.compile_program(&monomorphized_program)?;
-
-        if let Err(e) = llvm_compiler.module.verify() {
-            return Err(CompileError::InternalError(
-                format!("LLVM verification error: {}", e.to_string()),
-                None,
-            ));
-        }
-
-        Ok(llvm_compiler.module.print_to_string().to_string())
+        let module = self.get_module(program)?;
+        Ok(module.print_to_string().to_string())
     }
 
     /// Gets the LLVM module after compilation for execution engine creation.
     pub fn get_module(&self, program: &Program) -> Result<Module<'ctx>> {
+        self.get_module_collecting(program)
+            .map_err(|mut errors| errors.remove(0))
+    }
+
+    /// Compiles a program to an LLVM module, reporting every type and
+    /// codegen error found instead of stopping at the first one.
+    /// The returned error list is never empty.
+    pub fn get_module_collecting(&self, program: &Program) -> std::result::Result<Module<'ctx>, Vec<CompileError>> {
+        let checked = self.check(program)?;
+        self.generate_module(checked)
+    }
+
+    /// Runs the front end: import resolution, comptime evaluation and type
+    /// checking. `zen check` stops here; every type error is reported.
+    pub fn check(&self, program: &Program) -> std::result::Result<CheckedProgram, Vec<CompileError>> {
         // Process module imports
-        let processed_program = self.process_imports(program)?;
+        let processed_program = self.process_imports(program).map_err(|e| vec![e])?;
         
         // Execute comptime blocks and expressions
-        let processed_program = self.execute_comptime(processed_program)?;
+        let processed_program = self.execute_comptime(processed_program).map_err(|e| vec![e])?;
         
+        let mut type_checker = TypeChecker::new().with_spans(self.spans.clone());
+        let type_errors = type_checker.check_program_collecting(&processed_program);
+        if !type_errors.is_empty() {
+            return Err(type_errors);
+        }
+
+        let warnings = type_checker.warnings().to_vec();
+        Ok(CheckedProgram {
+            program: processed_program,
+            types: type_checker.into_type_table(),
+            warnings,
+        })
+    }
+
+    /// Runs the back end on a type checked program: monomorphization, code
+    /// generation and LLVM verification.
+    pub fn generate_module(&self, checked: CheckedProgram) -> std::result::Result<Module<'ctx>, Vec<CompileError>> {
         // Monomorphize the program to resolve all generic types
-        let mut monomorphizer = Monomorphizer::new();
-        let monomorphized_program = monomorphizer.monomorphize_program(&processed_program)?;
+        let mut monomorphizer = Monomorphizer::new()
+            .with_spans(self.spans.clone())
+            .with_type_table(checked.types.clone());
+        let monomorphized_program = monomorphizer
+            .monomorphize_program(&checked.program)
+            .map_err(|e| vec![e])?;
         
-        let mut llvm_compiler = LLVMCompiler::new(self.context);
-        llvm_compiler.compile_program(&monomorphized_program)?;
+        let mut llvm_compiler = LLVMCompiler::new(self.context)
+            .with_spans(monomorphizer.spans().clone())
+            .with_type_table(checked.types);
+        let codegen_errors = llvm_compiler.compile_program_collecting(&monomorphized_program);
+        if !codegen_errors.is_empty() {
+            return Err(codegen_errors);
+        }
 
         if let Err(e) = llvm_compiler.module.verify() {
-            return Err(CompileError::InternalError(
+            return Err(vec![CompileError::InternalError(
                 format!("LLVM verification error: {}", e.to_string()),
                 None,
-            ));
+            )]);
         }
 
         Ok(llvm_compiler.module)
@@ -94,7 +135,7 @@ impl<'ctx> Compiler<'ctx> {
         }
         
         // Merge all modules into a single program
-        let mut merged_program = module_system.merge_programs(program.clone());
+        let mut merged_program = module_system.merge_programs(program.clone())?;
         
         // Resolve module references
         resolver.resolve_program(&mut merged_program)
@@ -183,6 +224,13 @@ impl<'ctx> Compiler<'ctx> {
                     declaration_type,
                 })
             }
+            Statement::DestructuringDeclaration { pattern, initializer, is_mutable } => {
+                Ok(Statement::DestructuringDeclaration {
+                    pattern,
+                    initializer: self.process_expression_comptime(initializer, interpreter)?,
+                    is_mutable,
+                })
+            }
             Statement::VariableAssignment { name, value } => {
                 Ok(Statement::VariableAssignment {
                     name,
@@ -195,6 +243,13 @@ impl<'ctx> Compiler<'ctx> {
                     value: self.process_expression_comptime(value, interpreter)?,
                 })
             }
+            Statement::CompoundAssignment { target, op, value } => {
+                Ok(Statement::CompoundAssignment {
+                    target: self.process_expression_comptime(target, interpreter)?,
+                    op,
+                    value: self.process_expression_comptime(value, interpreter)?,
+                })
+            }
             Statement::Return(expr) => {
                 Ok(Statement::Return(self.process_expression_comptime(expr, interpreter)?))
             }
@@ -236,6 +291,25 @@ impl<'ctx> Compiler<'ctx> {
                     right: Box::new(self.process_expression_comptime(*right, interpreter)?),
                 })
             }
+            Expression::UnaryOp { op, operand } => {
+                Ok(Expression::UnaryOp {
+                    op,
+                    operand: Box::new(self.process_expression_comptime(*operand, interpreter)?),
+                })
+            }
+            Expression::TupleLiteral(elements) => {
+                let mut processed_elements = Vec::new();
+                for element in elements {
+                    processed_elements.push(self.process_expression_comptime(element, interpreter)?);
+                }
+                Ok(Expression::TupleLiteral(processed_elements))
+            }
+            Expression::TupleIndex { tuple, index } => {
+                Ok(Expression::TupleIndex {
+                    tuple: Box::new(self.process_expression_comptime(*tuple, interpreter)?),
+                    index,
+                })
+            }
             Expression::FunctionCall { name, args } => {
                 let mut processed_args = Vec::new();
                 for arg in args {
@@ -246,6 +320,17 @@ impl<'ctx> Compiler<'ctx> {
                     args: processed_args,
                 })
             }
+            Expression::MethodCall { object, method, args } => {
+                let mut processed_args = Vec::new();
+                for arg in args {
+                    processed_args.push(self.process_expression_comptime(arg, interpreter)?);
+                }
+                Ok(Expression::MethodCall {
+                    object: Box::new(self.process_expression_comptime(*object, interpreter)?),
+                    method,
+                    args: processed_args,
+                })
+            }
             Expression::ArrayLiteral(elements) => {
                 let mut processed = Vec::new();
                 for elem in elements {
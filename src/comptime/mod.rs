@@ -308,9 +308,23 @@ impl ComptimeInterpreter {
                 self.evaluate_binary_op(left_val, op, right_val)
             }
             
+            Expression::UnaryOp { op, operand } => {
+                let value = self.evaluate_expression(operand)?;
+                self.evaluate_unary_op(*op, value)
+            }
+            
             Expression::FunctionCall { name, args } => {
                 self.evaluate_function_call(name, args)
             }
+
+            Expression::MethodCall { method, args, .. } => {
+                match expr.qualified_call_name() {
+                    Some(name) => self.evaluate_function_call(&name, args),
+                    None => Err(CompileError::ComptimeError(
+                        format!("Method call '{}' is not supported at compile time", method)
+                    )),
+                }
+            }
             
             Expression::ArrayLiteral(elements) => {
                 let values: Result<Vec<_>> = elements.iter()
@@ -384,6 +398,19 @@ impl ComptimeInterpreter {
                     BinaryOperator::LessThanEquals => Ok(ComptimeValue::Bool(l <= r)),
                     BinaryOperator::GreaterThan => Ok(ComptimeValue::Bool(l > r)),
                     BinaryOperator::GreaterThanEquals => Ok(ComptimeValue::Bool(l >= r)),
+                    BinaryOperator::BitAnd => Ok(ComptimeValue::I32(l & r)),
+                    BinaryOperator::BitOr => Ok(ComptimeValue::I32(l | r)),
+                    BinaryOperator::BitXor => Ok(ComptimeValue::I32(l ^ r)),
+                    BinaryOperator::ShiftLeft | BinaryOperator::ShiftRight => {
+                        let shifted = if matches!(op, BinaryOperator::ShiftLeft) {
+                            l.checked_shl(r as u32)
+                        } else {
+                            l.checked_shr(r as u32)
+                        };
+                        shifted.map(ComptimeValue::I32).ok_or_else(|| CompileError::ComptimeError(
+                            format!("Shift amount {} is out of range for I32", r)
+                        ))
+                    }
                     _ => Err(CompileError::ComptimeError(
                         format!("Unsupported operation {:?} for I32", op)
                     ))
@@ -418,6 +445,24 @@ impl ComptimeInterpreter {
         }
     }
     
+    /// Evaluate unary operations
+    fn evaluate_unary_op(&self, op: ast::UnaryOperator, value: ComptimeValue) -> Result<ComptimeValue> {
+        use ast::UnaryOperator;
+        
+        match (op, value) {
+            (UnaryOperator::Negate, ComptimeValue::I32(v)) => Ok(ComptimeValue::I32(v.wrapping_neg())),
+            (UnaryOperator::Negate, ComptimeValue::I64(v)) => Ok(ComptimeValue::I64(v.wrapping_neg())),
+            (UnaryOperator::Negate, ComptimeValue::F32(v)) => Ok(ComptimeValue::F32(-v)),
+            (UnaryOperator::Negate, ComptimeValue::F64(v)) => Ok(ComptimeValue::F64(-v)),
+            (UnaryOperator::Not, ComptimeValue::Bool(v)) => Ok(ComptimeValue::Bool(!v)),
+            (UnaryOperator::BitNot, ComptimeValue::I32(v)) => Ok(ComptimeValue::I32(!v)),
+            (UnaryOperator::BitNot, ComptimeValue::I64(v)) => Ok(ComptimeValue::I64(!v)),
+            (op, value) => Err(CompileError::ComptimeError(
+                format!("Unsupported operation {:?} for {:?}", op, value.get_type())
+            ))
+        }
+    }
+    
     /// Evaluate function calls
     fn evaluate_function_call(&mut self, name: &str, args: &[Expression]) -> Result<ComptimeValue> {
         // Check for built-in compile-time functions
@@ -0,0 +1,361 @@
+//! Lossless concrete syntax tree.
+//!
+//! The `Parser` builds an `ast::Program` and drops everything that doesn't
+//! affect meaning. Tools that rewrite source (the formatter, doc
+//! generation, LSP refactorings) instead need every byte back, so this
+//! module keeps whitespace and comments as trivia attached to the tokens
+//! around them. Printing a tree or token stream reproduces the input
+//! exactly.
+//!
+//! Trivia on the same line after a token belongs to that token; the rest,
+//! from the first line break on, leads the next token. Doc comments
+//! therefore end up in the leading trivia of the item they document.
+//!
+//! The tree only groups tokens into top-level items and bracketed groups.
+//! It is built without the `Parser`, so it exists even for source that
+//! doesn't parse.
+
+use crate::error::Span;
+use crate::lexer::{Lexer, Token};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TriviaKind {
+    /// Spaces, tabs and line breaks.
+    Whitespace,
+    /// `// ...`, without the line break.
+    LineComment,
+    /// `/* ... */`
+    BlockComment,
+    /// `/// ...` or `/** ... */`, documenting the item that follows.
+    DocComment,
+    /// `//! ...`, documenting the enclosing module.
+    InnerDocComment,
+    /// A character the lexer skipped because no token starts with it.
+    Skipped,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trivia {
+    pub kind: TriviaKind,
+    pub text: String,
+    /// Byte offset of the trivia in the source.
+    pub start: usize,
+}
+
+/// A token with its exact source text and surrounding trivia.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CstToken {
+    pub token: Token,
+    pub span: Span,
+    pub text: String,
+    pub leading: Vec<Trivia>,
+    pub trailing: Vec<Trivia>,
+}
+
+impl CstToken {
+    /// Whether the token is the first on its line.
+    pub fn starts_line(&self) -> bool {
+        self.span.start == 0 || self.leading.iter().any(|trivia| trivia.text.contains('\n'))
+    }
+
+    /// The text of the doc comments in the leading trivia, without their
+    /// `///` or `/** */` markers.
+    pub fn doc_comments(&self) -> Vec<String> {
+        self.leading
+            .iter()
+            .filter(|trivia| trivia.kind == TriviaKind::DocComment)
+            .map(|trivia| match trivia.text.strip_prefix("///") {
+                Some(line) => line.strip_prefix(' ').unwrap_or(line).to_string(),
+                None => trivia.text.trim_start_matches("/**").trim_end_matches("*/").trim().to_string(),
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for CstToken {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for trivia in &self.leading {
+            f.write_str(&trivia.text)?;
+        }
+        f.write_str(&self.text)?;
+        for trivia in &self.trailing {
+            f.write_str(&trivia.text)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeKind {
+    /// The whole file: items, then the end-of-file token.
+    Root,
+    /// A top-level declaration or statement.
+    Item,
+    /// `( ... )`
+    Parens,
+    /// `{ ... }`
+    Braces,
+    /// `[ ... ]`
+    Brackets,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SyntaxElement {
+    Node(SyntaxNode),
+    Token(CstToken),
+}
+
+impl fmt::Display for SyntaxElement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SyntaxElement::Node(node) => node.fmt(f),
+            SyntaxElement::Token(token) => token.fmt(f),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxNode {
+    pub kind: NodeKind,
+    pub children: Vec<SyntaxElement>,
+}
+
+impl SyntaxNode {
+    fn new(kind: NodeKind) -> Self {
+        Self { kind, children: Vec::new() }
+    }
+
+    /// The node's tokens in source order, including those of nested nodes.
+    pub fn tokens(&self) -> Vec<&CstToken> {
+        let mut tokens = Vec::new();
+        self.collect_tokens(&mut tokens);
+        tokens
+    }
+
+    fn collect_tokens<'a>(&'a self, tokens: &mut Vec<&'a CstToken>) {
+        for child in &self.children {
+            match child {
+                SyntaxElement::Node(node) => node.collect_tokens(tokens),
+                SyntaxElement::Token(token) => tokens.push(token),
+            }
+        }
+    }
+
+    /// The direct children that are nodes of `kind`.
+    pub fn nodes(&self, kind: NodeKind) -> impl Iterator<Item = &SyntaxNode> {
+        self.children.iter().filter_map(move |child| match child {
+            SyntaxElement::Node(node) if node.kind == kind => Some(node),
+            _ => None,
+        })
+    }
+
+    /// The top-level items of a root node.
+    pub fn items(&self) -> impl Iterator<Item = &SyntaxNode> {
+        self.nodes(NodeKind::Item)
+    }
+
+    pub fn first_token(&self) -> Option<&CstToken> {
+        self.children.iter().find_map(|child| match child {
+            SyntaxElement::Node(node) => node.first_token(),
+            SyntaxElement::Token(token) => Some(token),
+        })
+    }
+
+    /// The doc comments written directly before the node.
+    pub fn doc_comments(&self) -> Vec<String> {
+        self.first_token().map(CstToken::doc_comments).unwrap_or_default()
+    }
+
+    /// The node's source text without its leading trivia or the trailing
+    /// trivia of its last token.
+    pub fn source_text(&self) -> String {
+        let tokens = self.tokens();
+        let mut text = String::new();
+        for (i, token) in tokens.iter().enumerate() {
+            if i > 0 {
+                token.leading.iter().for_each(|trivia| text.push_str(&trivia.text));
+            }
+            text.push_str(&token.text);
+            if i + 1 < tokens.len() {
+                token.trailing.iter().for_each(|trivia| text.push_str(&trivia.text));
+            }
+        }
+        text
+    }
+}
+
+impl fmt::Display for SyntaxNode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.children.iter().try_for_each(|child| child.fmt(f))
+    }
+}
+
+/// Lex `source` into tokens that keep their trivia. The last token is
+/// always `Token::Eof`, carrying whatever trivia ends the file.
+pub fn tokenize(source: &str) -> Vec<CstToken> {
+    let mut lexer = Lexer::new(source);
+    let mut tokens: Vec<CstToken> = Vec::new();
+    let mut previous_end = 0;
+
+    loop {
+        let next = lexer.next_token_with_span();
+        let start = next.span.start.clamp(previous_end, source.len());
+        let end = next.span.end.clamp(start, source.len());
+
+        let mut trivia = split_trivia(&source[previous_end..start], previous_end);
+        if let Some(previous) = tokens.last_mut() {
+            let leading_from = trivia
+                .iter()
+                .position(|piece| piece.text.contains('\n'))
+                .unwrap_or(trivia.len());
+            let mut leading = trivia.split_off(leading_from);
+            // Keep the rest of the line ahead of the break as trailing trivia
+            if let Some(first) = leading.first_mut().filter(|piece| piece.kind == TriviaKind::Whitespace) {
+                let newline = first.text.find('\n').unwrap_or(0);
+                if newline > 0 {
+                    trivia.push(Trivia {
+                        kind: TriviaKind::Whitespace,
+                        text: first.text[..newline].to_string(),
+                        start: first.start,
+                    });
+                    first.text.drain(..newline);
+                    first.start += newline;
+                }
+            }
+            previous.trailing = trivia;
+            trivia = leading;
+        }
+
+        let is_eof = next.token == Token::Eof;
+        tokens.push(CstToken {
+            token: next.token,
+            span: next.span,
+            text: source[start..end].to_string(),
+            leading: trivia,
+            trailing: Vec::new(),
+        });
+        previous_end = end;
+        if is_eof {
+            return tokens;
+        }
+    }
+}
+
+/// Split the text between two tokens into trivia pieces.
+fn split_trivia(gap: &str, offset: usize) -> Vec<Trivia> {
+    let mut pieces = Vec::new();
+    let mut position = 0;
+    while position < gap.len() {
+        let rest = &gap[position..];
+        let (kind, length) = if rest.starts_with("//") {
+            let length = rest.find('\n').unwrap_or(rest.len());
+            let kind = if rest.starts_with("///") && !rest.starts_with("////") {
+                TriviaKind::DocComment
+            } else if rest.starts_with("//!") {
+                TriviaKind::InnerDocComment
+            } else {
+                TriviaKind::LineComment
+            };
+            (kind, length)
+        } else if let Some(body) = rest.strip_prefix("/*") {
+            let length = body.find("*/").map_or(rest.len(), |end| end + 4);
+            let kind = if rest.starts_with("/**") && !rest.starts_with("/**/") {
+                TriviaKind::DocComment
+            } else {
+                TriviaKind::BlockComment
+            };
+            (kind, length)
+        } else {
+            let whitespace = rest.len() - rest.trim_start().len();
+            if whitespace > 0 {
+                (TriviaKind::Whitespace, whitespace)
+            } else {
+                let skipped = rest.chars().next().map_or(1, char::len_utf8);
+                (TriviaKind::Skipped, skipped)
+            }
+        };
+        pieces.push(Trivia {
+            kind,
+            text: rest[..length].to_string(),
+            start: offset + position,
+        });
+        position += length;
+    }
+    pieces
+}
+
+/// Build the lossless tree for `source`: tokens grouped by brackets, and
+/// the top level split into items.
+pub fn parse(source: &str) -> SyntaxNode {
+    let mut root = SyntaxNode::new(NodeKind::Root);
+    let mut item = SyntaxNode::new(NodeKind::Item);
+    // Groups opened but not yet closed, innermost last
+    let mut groups: Vec<SyntaxNode> = Vec::new();
+    let mut previous: Option<Token> = None;
+
+    for token in tokenize(source) {
+        if token.token == Token::Eof {
+            while let Some(group) = groups.pop() {
+                push_child(&mut groups, &mut item, SyntaxElement::Node(group));
+            }
+            if !item.children.is_empty() {
+                root.children.push(SyntaxElement::Node(item));
+            }
+            root.children.push(SyntaxElement::Token(token));
+            return root;
+        }
+
+        if groups.is_empty() && !item.children.is_empty() && starts_item(&token, previous.as_ref()) {
+            root.children.push(SyntaxElement::Node(std::mem::replace(&mut item, SyntaxNode::new(NodeKind::Item))));
+        }
+        previous = Some(token.token.clone());
+
+        match &token.token {
+            Token::Symbol(open @ ('(' | '{' | '[')) => {
+                let kind = match open {
+                    '(' => NodeKind::Parens,
+                    '{' => NodeKind::Braces,
+                    _ => NodeKind::Brackets,
+                };
+                let mut group = SyntaxNode::new(kind);
+                group.children.push(SyntaxElement::Token(token));
+                groups.push(group);
+            }
+            Token::Symbol(close @ (')' | '}' | ']'))
+                if groups.last().is_some_and(|group| group.kind == closing_kind(*close)) =>
+            {
+                let mut group = groups.pop().expect("checked above");
+                group.children.push(SyntaxElement::Token(token));
+                push_child(&mut groups, &mut item, SyntaxElement::Node(group));
+            }
+            _ => push_child(&mut groups, &mut item, SyntaxElement::Token(token)),
+        }
+    }
+    unreachable!("tokenize always ends with Eof")
+}
+
+fn push_child(groups: &mut [SyntaxNode], item: &mut SyntaxNode, child: SyntaxElement) {
+    match groups.last_mut() {
+        Some(group) => group.children.push(child),
+        None => item.children.push(child),
+    }
+}
+
+fn closing_kind(close: char) -> NodeKind {
+    match close {
+        ')' => NodeKind::Parens,
+        '}' => NodeKind::Braces,
+        _ => NodeKind::Brackets,
+    }
+}
+
+/// Whether a top-level token begins a new item: it starts a line with a
+/// name or keyword, and the line before didn't end mid-expression.
+fn starts_item(token: &CstToken, previous: Option<&Token>) -> bool {
+    let continues = matches!(
+        previous,
+        Some(Token::Operator(_) | Token::Symbol(',' | '|' | '.' | ':' | '&' | '?'))
+    );
+    token.starts_line() && !continues && matches!(token.token, Token::Identifier(_) | Token::Keyword(_))
+}
@@ -0,0 +1,307 @@
+//! Source diagnostics rendering.
+//!
+//! Converts `CompileError`s into `Diagnostic`s with labeled spans and notes,
+//! and renders them as annotated source snippets using ariadne.
+
+use crate::error::{CompileError, ImmutableTarget, Span};
+use ariadne::{Color, Config, IndexType, Label as AriadneLabel, Report, ReportKind, Source};
+use serde::{Deserialize, Serialize};
+use std::io::IsTerminal;
+use std::ops::Range;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+}
+
+/// A span of source text with an attached message.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+}
+
+/// A single diagnostic. The first label is the primary location; any
+/// further labels are rendered as secondary annotations.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// Stable error code such as `E0200`, see `CompileError::code`.
+    pub code: Option<String>,
+    pub message: String,
+    pub labels: Vec<Label>,
+    pub notes: Vec<String>,
+    pub help: Option<String>,
+}
+
+impl Diagnostic {
+    pub fn new(severity: Severity, message: impl Into<String>) -> Self {
+        Self {
+            severity,
+            code: None,
+            message: message.into(),
+            labels: Vec::new(),
+            notes: Vec::new(),
+            help: None,
+        }
+    }
+
+    pub fn error(message: impl Into<String>) -> Self {
+        Self::new(Severity::Error, message)
+    }
+
+    pub fn warning(message: impl Into<String>) -> Self {
+        Self::new(Severity::Warning, message)
+    }
+
+    pub fn with_code(mut self, code: impl Into<String>) -> Self {
+        self.code = Some(code.into());
+        self
+    }
+
+    pub fn with_label(mut self, span: Span, message: impl Into<String>) -> Self {
+        self.labels.push(Label { span, message: message.into() });
+        self
+    }
+
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+
+    pub fn with_help(mut self, help: impl Into<String>) -> Self {
+        self.help = Some(help.into());
+        self
+    }
+
+    pub fn primary_span(&self) -> Option<&Span> {
+        self.labels.first().map(|label| &label.span)
+    }
+}
+
+impl From<&CompileError> for Diagnostic {
+    fn from(error: &CompileError) -> Self {
+        let diagnostic = Diagnostic::error(error.message()).with_code(error.code());
+        let diagnostic = match error.span() {
+            Some(span) => {
+                let label = match error {
+                    CompileError::TypeMismatch { expected, .. } => format!("expected {}", expected),
+                    CompileError::UndeclaredVariable(..) => "not found in this scope".to_string(),
+                    CompileError::UndeclaredFunction(..) => "no function with this name".to_string(),
+                    CompileError::NonExhaustiveMatch { missing, .. } => {
+                        format!("{} not covered", if missing.len() == 1 { "pattern" } else { "patterns" })
+                    }
+                    CompileError::AssignToImmutable { .. } => "cannot assign".to_string(),
+                    _ => "here".to_string(),
+                };
+                diagnostic.with_label(span.clone(), label)
+            }
+            None => diagnostic,
+        };
+        match error {
+            CompileError::InternalError(..) => {
+                diagnostic.with_note("this is a bug in the Zen compiler")
+            }
+            CompileError::NonExhaustiveMatch { .. } => {
+                diagnostic.with_help("add arms for the missing patterns, or a `_` arm")
+            }
+            CompileError::AssignToImmutable { name, target, declared, .. } => {
+                let (label, help) = match target {
+                    ImmutableTarget::Variable => (
+                        format!("'{}' is declared immutable here", name),
+                        format!("declare it with `::=` to allow assignment: `{} ::= ...`", name),
+                    ),
+                    ImmutableTarget::Parameter => (
+                        format!("'{}' is a parameter of this function", name),
+                        format!("parameters are immutable; copy it into a mutable binding first: `{} ::= {}`", name, name),
+                    ),
+                    ImmutableTarget::Field { .. } => (
+                        format!("field '{}' is declared without `::` here", name),
+                        "mark the field mutable with `::` after its type, as you would use `::=` for a binding".to_string(),
+                    ),
+                };
+                let diagnostic = match declared {
+                    Some(span) if error.span().is_some() => diagnostic.with_label(span.clone(), label),
+                    _ => diagnostic.with_note(label),
+                };
+                diagnostic.with_help(help)
+            }
+            _ => diagnostic,
+        }
+    }
+}
+
+impl From<CompileError> for Diagnostic {
+    fn from(error: CompileError) -> Self {
+        Diagnostic::from(&error)
+    }
+}
+
+/// A diagnostic paired with the file it belongs to, as emitted by
+/// `zen check --message-format=json` (one JSON object per line).
+#[derive(Debug, Clone, Serialize)]
+pub struct FileDiagnostic<'a> {
+    pub file: &'a str,
+    #[serde(flatten)]
+    pub diagnostic: &'a Diagnostic,
+}
+
+impl<'a> FileDiagnostic<'a> {
+    pub fn new(file: &'a str, diagnostic: &'a Diagnostic) -> Self {
+        Self { file, diagnostic }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("diagnostics always serialize")
+    }
+}
+
+/// When to emit ANSI colors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorChoice {
+    /// Color when stderr is a terminal and `NO_COLOR` is unset.
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    pub fn enabled(self) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => std::env::var_os("NO_COLOR").is_none() && std::io::stderr().is_terminal(),
+        }
+    }
+}
+
+impl std::str::FromStr for ColorChoice {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(ColorChoice::Auto),
+            "always" => Ok(ColorChoice::Always),
+            "never" => Ok(ColorChoice::Never),
+            _ => Err(format!("Unknown color choice '{}' (expected auto, always or never)", s)),
+        }
+    }
+}
+
+/// Renders diagnostics against a single source file.
+pub struct DiagnosticRenderer<'a> {
+    path: &'a str,
+    source: &'a str,
+    color: bool,
+}
+
+impl<'a> DiagnosticRenderer<'a> {
+    pub fn new(path: &'a str, source: &'a str, color: ColorChoice) -> Self {
+        Self { path, source, color: color.enabled() }
+    }
+
+    /// Render one diagnostic to a string.
+    pub fn render(&self, diagnostic: &Diagnostic) -> String {
+        let Some(primary) = diagnostic.primary_span() else {
+            return self.render_without_source(diagnostic);
+        };
+
+        let kind = match diagnostic.severity {
+            Severity::Error => ReportKind::Error,
+            Severity::Warning => ReportKind::Warning,
+            Severity::Note => ReportKind::Advice,
+        };
+
+        let path = self.path.to_string();
+        let mut report = Report::build(kind, path.clone(), self.clamp(primary).start)
+            .with_config(
+                Config::default()
+                    .with_color(self.color)
+                    .with_index_type(IndexType::Byte),
+            )
+            .with_message(&diagnostic.message);
+        if let Some(code) = &diagnostic.code {
+            report = report.with_code(code);
+        }
+
+        for (i, label) in diagnostic.labels.iter().enumerate() {
+            let color = if i == 0 { Color::Red } else { Color::Blue };
+            let mut ariadne_label = AriadneLabel::new((path.clone(), self.clamp(&label.span)))
+                .with_message(&label.message)
+                .with_order(i as i32);
+            if self.color {
+                ariadne_label = ariadne_label.with_color(color);
+            }
+            report = report.with_label(ariadne_label);
+        }
+
+        if !diagnostic.notes.is_empty() {
+            report = report.with_note(diagnostic.notes.join("\n"));
+        }
+        if let Some(help) = &diagnostic.help {
+            report = report.with_help(help);
+        }
+
+        let mut out = Vec::new();
+        if report
+            .finish()
+            .write((path, Source::from(self.source)), &mut out)
+            .is_err()
+        {
+            return self.render_without_source(diagnostic);
+        }
+        String::from_utf8_lossy(&out).into_owned()
+    }
+
+    /// Render every diagnostic, followed by a summary line.
+    pub fn render_all(&self, diagnostics: &[Diagnostic]) -> String {
+        let mut out = String::new();
+        for diagnostic in diagnostics {
+            out.push_str(&self.render(diagnostic));
+        }
+
+        let errors = diagnostics.iter().filter(|d| d.severity == Severity::Error).count();
+        if errors > 0 {
+            let plural = if errors == 1 { "" } else { "s" };
+            out.push_str(&format!("{}: could not compile due to {} error{}\n", self.path, errors, plural));
+        }
+        out
+    }
+
+    fn render_without_source(&self, diagnostic: &Diagnostic) -> String {
+        let header = match diagnostic.severity {
+            Severity::Error => "Error",
+            Severity::Warning => "Warning",
+            Severity::Note => "Advice",
+        };
+        let code = diagnostic.code.as_ref().map(|c| format!("[{}] ", c)).unwrap_or_default();
+        let mut out = format!("{}{}: {}\n   ╭─[{}]\n", code, header, diagnostic.message, self.path);
+        for note in &diagnostic.notes {
+            out.push_str(&format!("   │ Note: {}\n", note));
+        }
+        if let Some(help) = &diagnostic.help {
+            out.push_str(&format!("   │ Help: {}\n", help));
+        }
+        out.push_str("───╯\n");
+        out
+    }
+
+    /// Keep spans inside the source and at least one byte wide so the
+    /// caret has something to point at.
+    fn clamp(&self, span: &Span) -> Range<usize> {
+        let len = self.source.len();
+        let start = span.start.min(len);
+        let end = span.end.clamp(start, len);
+        if start == end && end < len {
+            // Widen an empty span to cover the whole character it sits on
+            let width = self.source.get(end..).and_then(|rest| rest.chars().next()).map_or(1, char::len_utf8);
+            start..end + width
+        } else {
+            start..end
+        }
+    }
+}
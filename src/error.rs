@@ -1,13 +1,19 @@
 use inkwell::builder::BuilderError;
 use inkwell::support::LLVMString;
+use serde::{Deserialize, Serialize};
 use std::fmt;
 
-#[derive(Debug, Clone, PartialEq)]
+/// A region of source. `start` and `end` are byte offsets; `line` and
+/// the columns are 1-based and describe where it starts.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Span {
     pub start: usize,
     pub end: usize,
     pub line: usize,
+    /// Column in characters.
     pub column: usize,
+    /// Column in UTF-16 code units, as used by the LSP.
+    pub utf16_column: usize,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -20,6 +26,17 @@ pub enum CompileError {
         found: String,
         span: Option<Span>,
     },
+    NonExhaustiveMatch {
+        missing: Vec<String>,
+        span: Option<Span>,
+    },
+    AssignToImmutable {
+        name: String,
+        target: ImmutableTarget,
+        /// Where the binding or field was declared.
+        declared: Option<Span>,
+        span: Option<Span>,
+    },
     InvalidLoopCondition(String, Option<Span>),
     MissingReturnStatement(String, Option<Span>),
     InternalError(String, Option<Span>),
@@ -30,6 +47,109 @@ pub enum CompileError {
     ComptimeError(String),
 }
 
+/// What an assignment to something immutable was aimed at.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ImmutableTarget {
+    /// A binding declared with `:=` or `: T =`.
+    Variable,
+    /// A function parameter.
+    Parameter,
+    /// A struct field declared without `::`.
+    Field { struct_name: String },
+}
+
+impl CompileError {
+    /// The source location of the error, if known.
+    pub fn span(&self) -> Option<&Span> {
+        match self {
+            CompileError::SyntaxError(_, span)
+            | CompileError::UndeclaredVariable(_, span)
+            | CompileError::UndeclaredFunction(_, span)
+            | CompileError::TypeMismatch { span, .. }
+            | CompileError::NonExhaustiveMatch { span, .. }
+            | CompileError::AssignToImmutable { span, .. }
+            | CompileError::InvalidLoopCondition(_, span)
+            | CompileError::MissingReturnStatement(_, span)
+            | CompileError::InternalError(_, span)
+            | CompileError::UnsupportedFeature(_, span)
+            | CompileError::TypeError(_, span)
+            | CompileError::ParseError(_, span) => span.as_ref(),
+            CompileError::FileNotFound(..) | CompileError::ComptimeError(_) => None,
+        }
+    }
+
+    /// Replace the error's source location. Errors that can't carry a
+    /// span are returned unchanged.
+    pub fn with_span(mut self, new_span: Span) -> Self {
+        match &mut self {
+            CompileError::SyntaxError(_, span)
+            | CompileError::UndeclaredVariable(_, span)
+            | CompileError::UndeclaredFunction(_, span)
+            | CompileError::TypeMismatch { span, .. }
+            | CompileError::NonExhaustiveMatch { span, .. }
+            | CompileError::AssignToImmutable { span, .. }
+            | CompileError::InvalidLoopCondition(_, span)
+            | CompileError::MissingReturnStatement(_, span)
+            | CompileError::InternalError(_, span)
+            | CompileError::UnsupportedFeature(_, span)
+            | CompileError::TypeError(_, span)
+            | CompileError::ParseError(_, span) => *span = Some(new_span),
+            CompileError::FileNotFound(..) | CompileError::ComptimeError(_) => {}
+        }
+        self
+    }
+
+    /// A stable code identifying the kind of error, for tooling and docs.
+    /// Codes are never reused once assigned.
+    pub fn code(&self) -> &'static str {
+        match self {
+            CompileError::SyntaxError(..) => "E0001",
+            CompileError::ParseError(..) => "E0002",
+            CompileError::UndeclaredVariable(..) => "E0100",
+            CompileError::UndeclaredFunction(..) => "E0101",
+            CompileError::TypeMismatch { .. } => "E0200",
+            CompileError::TypeError(..) => "E0201",
+            CompileError::InvalidLoopCondition(..) => "E0202",
+            CompileError::MissingReturnStatement(..) => "E0203",
+            CompileError::NonExhaustiveMatch { .. } => "E0204",
+            CompileError::AssignToImmutable { .. } => "E0205",
+            CompileError::UnsupportedFeature(..) => "E0300",
+            CompileError::FileNotFound(..) => "E0400",
+            CompileError::ComptimeError(_) => "E0500",
+            CompileError::InternalError(..) => "E9999",
+        }
+    }
+
+    /// The error message without the trailing location.
+    pub fn message(&self) -> String {
+        match self {
+            CompileError::SyntaxError(msg, _) => format!("Syntax Error: {}", msg),
+            CompileError::UndeclaredVariable(name, _) => format!("Undeclared variable: '{}'", name),
+            CompileError::UndeclaredFunction(name, _) => format!("Undeclared function: '{}'", name),
+            CompileError::TypeMismatch { expected, found, .. } => format!("Type mismatch: Expected {}, found {}", expected, found),
+            CompileError::NonExhaustiveMatch { missing, .. } => {
+                let missing: Vec<String> = missing.iter().map(|pattern| format!("`{}`", pattern)).collect();
+                format!("Non-exhaustive match: {} not covered", missing.join(", "))
+            }
+            CompileError::AssignToImmutable { name, target, .. } => match target {
+                ImmutableTarget::Variable => format!("Cannot assign to immutable variable '{}'", name),
+                ImmutableTarget::Parameter => format!("Cannot assign to parameter '{}'", name),
+                ImmutableTarget::Field { struct_name } => {
+                    format!("Cannot assign to immutable field '{}' of '{}'", name, struct_name)
+                }
+            },
+            CompileError::InvalidLoopCondition(msg, _) => format!("Invalid loop condition: {}", msg),
+            CompileError::MissingReturnStatement(func_name, _) => format!("Missing return statement in function '{}'", func_name),
+            CompileError::InternalError(msg, _) => format!("Internal Compiler Error: {}", msg),
+            CompileError::UnsupportedFeature(msg, _) => format!("Unsupported feature: {}", msg),
+            CompileError::TypeError(msg, _) => format!("Type error: {}", msg),
+            CompileError::FileNotFound(path, detail) => format!("File not found: {}{}", path, detail.as_ref().map(|d| format!(" ({})", d)).unwrap_or_default()),
+            CompileError::ParseError(msg, _) => format!("Parse error: {}", msg),
+            CompileError::ComptimeError(msg) => format!("Compile-time error: {}", msg),
+        }
+    }
+}
+
 impl From<BuilderError> for CompileError {
     fn from(err: BuilderError) -> Self {
         CompileError::InternalError(err.to_string(), None)
@@ -50,20 +170,11 @@ impl From<LLVMString> for CompileError {
 
 impl fmt::Display for CompileError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self {
-            CompileError::SyntaxError(msg, span) => write!(f, "Syntax Error: {}{}", msg, span.as_ref().map(|s| format!(" at line {} column {}", s.line, s.column)).unwrap_or_default()),
-            CompileError::UndeclaredVariable(name, span) => write!(f, "Undeclared variable: '{}'{}", name, span.as_ref().map(|s| format!(" at line {} column {}", s.line, s.column)).unwrap_or_default()),
-            CompileError::UndeclaredFunction(name, span) => write!(f, "Undeclared function: '{}'{}", name, span.as_ref().map(|s| format!(" at line {} column {}", s.line, s.column)).unwrap_or_default()),
-            CompileError::TypeMismatch { expected, found, span } => write!(f, "Type mismatch: Expected {}, found {}{}", expected, found, span.as_ref().map(|s| format!(" at line {} column {}", s.line, s.column)).unwrap_or_default()),
-            CompileError::InvalidLoopCondition(msg, span) => write!(f, "Invalid loop condition: {}{}", msg, span.as_ref().map(|s| format!(" at line {} column {}", s.line, s.column)).unwrap_or_default()),
-            CompileError::MissingReturnStatement(func_name, span) => write!(f, "Missing return statement in function '{}'{}", func_name, span.as_ref().map(|s| format!(" at line {} column {}", s.line, s.column)).unwrap_or_default()),
-            CompileError::InternalError(msg, span) => write!(f, "Internal Compiler Error: {}{}", msg, span.as_ref().map(|s| format!(" at line {} column {}", s.line, s.column)).unwrap_or_default()),
-            CompileError::UnsupportedFeature(msg, _) => write!(f, "Unsupported feature: {}", msg),
-            CompileError::TypeError(msg, span) => write!(f, "Type error: {}{}", msg, span.as_ref().map(|s| format!(" at line {} column {}", s.line, s.column)).unwrap_or_default()),
-            CompileError::FileNotFound(path, detail) => write!(f, "File not found: {}{}", path, detail.as_ref().map(|d| format!(" ({})", d)).unwrap_or_default()),
-            CompileError::ParseError(msg, span) => write!(f, "Parse error: {}{}", msg, span.as_ref().map(|s| format!(" at line {} column {}", s.line, s.column)).unwrap_or_default()),
-            CompileError::ComptimeError(msg) => write!(f, "Compile-time error: {}", msg),
+        write!(f, "{}", self.message())?;
+        if let Some(span) = self.span() {
+            write!(f, " at line {} column {}", span.line, span.column)?;
         }
+        Ok(())
     }
 }
 
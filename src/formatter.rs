@@ -0,0 +1,273 @@
+//! Canonical source formatting for `zen fmt`.
+//!
+//! The formatter works on the lossless token stream from `cst`, so
+//! comments survive. It keeps the author's line breaks (collapsing runs
+//! of blank lines) and rewrites everything within a line: indentation
+//! follows bracket nesting, and spacing between tokens follows the rules
+//! in docs/STYLE_GUIDE.md. Where a token's role can't be told from the
+//! tokens alone, such as `<` opening generics or comparing, the parser's
+//! reading of it, recorded in the span table, decides.
+//!
+//! Whitespace never affects how Zen parses, so reprinting tokens this way
+//! can't change the program unless two tokens run together. Every pair
+//! printed without a space is checked to lex back as the same two tokens,
+//! and as a last guard the output is parsed again and its AST compared
+//! with the original's.
+
+use crate::cst::{self, TriviaKind};
+use crate::error::{CompileError, Result, Span};
+use crate::lexer::{Lexer, Token};
+use crate::parser::Parser;
+use crate::spans::{SpanTable, TokenRole};
+
+const INDENT: &str = "    ";
+
+/// Format Zen source in the canonical style. Fails if the source doesn't
+/// parse, so only programs whose meaning is known are rewritten.
+pub fn format_source(source: &str) -> Result<String> {
+    let mut parser = Parser::new(Lexer::new(source));
+    let original = parser.parse_program()?;
+    let formatted = Printer::new(elements(source)?, &parser.take_spans()).print();
+
+    match parse(&formatted) {
+        Ok(program) if program == original => Ok(formatted),
+        _ => Err(CompileError::InternalError(
+            "Formatting would change the meaning of the program; the file was left unchanged".to_string(),
+            None,
+        )),
+    }
+}
+
+fn parse(source: &str) -> Result<crate::ast::Program> {
+    Parser::new(Lexer::new(source)).parse_program()
+}
+
+/// A token or comment, with the whitespace that preceded it.
+struct Element {
+    text: String,
+    /// `None` for comments.
+    token: Option<Token>,
+    /// Byte offset in the source.
+    start: usize,
+    /// Line breaks between the previous element and this one.
+    newlines: usize,
+    /// Whether any whitespace separated it from the previous element.
+    spaced: bool,
+}
+
+/// Flatten the token stream into tokens and comments, turning whitespace
+/// into the `newlines` and `spaced` of the element after it.
+fn elements(source: &str) -> Result<Vec<Element>> {
+    let mut elements = Vec::new();
+    let mut newlines = 0;
+    let mut spaced = false;
+
+    for token in cst::tokenize(source) {
+        let trivia_then_token = token.leading.iter().map(Some).chain(std::iter::once(None));
+        for piece in trivia_then_token.chain(token.trailing.iter().map(Some)) {
+            let (text, token, start) = match piece {
+                None if token.token == Token::Eof => return Ok(elements),
+                None => (token.text.clone(), Some(token.token.clone()), token.span.start),
+                Some(trivia) => match trivia.kind {
+                    TriviaKind::Whitespace => {
+                        newlines += trivia.text.matches('\n').count();
+                        spaced = true;
+                        continue;
+                    }
+                    TriviaKind::Skipped => {
+                        let c = trivia.text.chars().next().unwrap_or_default();
+                        return Err(CompileError::SyntaxError(
+                            format!("Unexpected character '{}'", c),
+                            Some(span_at(source, trivia.start, trivia.text.len())),
+                        ));
+                    }
+                    TriviaKind::BlockComment => (trivia.text.clone(), None, trivia.start),
+                    _ => (trivia.text.trim_end().to_string(), None, trivia.start),
+                },
+            };
+            elements.push(Element { text, token, start, newlines, spaced });
+            newlines = 0;
+            spaced = false;
+        }
+    }
+    unreachable!("tokenize always ends with Eof")
+}
+
+fn span_at(source: &str, start: usize, len: usize) -> Span {
+    let line_start = source[..start].rfind('\n').map_or(0, |i| i + 1);
+    let before = &source[line_start..start];
+    Span {
+        start,
+        end: start + len,
+        line: source[..start].matches('\n').count() + 1,
+        column: before.chars().count() + 1,
+        utf16_column: before.encode_utf16().count() + 1,
+    }
+}
+
+/// How two tokens on the same line are separated.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Spacing {
+    None,
+    Space,
+}
+
+fn spacing(prev: &Token, prev_role: Option<TokenRole>, next: &Token, next_role: Option<TokenRole>) -> Spacing {
+    use TokenRole::{Path, Postfix, Prefix, ShiftRight, TypeArguments};
+    match (prev, next) {
+        (_, Token::Symbol(',' | ';' | ':')) => Spacing::None,
+        (Token::Symbol('(' | '['), _) | (_, Token::Symbol(')' | ']')) => Spacing::None,
+        (Token::Symbol('{'), Token::Symbol('}')) => Spacing::None,
+        (Token::Symbol('{'), _) | (_, Token::Symbol('}')) => Spacing::Space,
+        (Token::Symbol('.'), _) => Spacing::None,
+        _ if matches!(prev_role, Some(Prefix | ShiftRight)) => Spacing::None,
+        _ if prev_role == Some(Path) || next_role == Some(Path) => Spacing::None,
+        (Token::Operator(op), _) | (_, Token::Operator(op)) if op == ".." || op == "..=" => Spacing::None,
+        _ if next_role == Some(TypeArguments) => Spacing::None,
+        (Token::Operator(op), _) if prev_role == Some(TypeArguments) && op == "<" => Spacing::None,
+        (_, Token::Symbol('(' | '[' | '.')) if prev_role == Some(TypeArguments) => Spacing::None,
+        // A call's arguments or an index
+        _ if next_role == Some(Postfix) => Spacing::None,
+        (prev, Token::Symbol('(' | '[')) if ends_operand(prev) => Spacing::None,
+        (prev, Token::Symbol('.')) if ends_operand(prev) || matches!(prev, Token::Symbol(')' | ']' | '}')) => Spacing::None,
+        _ => Spacing::Space,
+    }
+}
+
+fn ends_operand(token: &Token) -> bool {
+    matches!(
+        token,
+        Token::Identifier(_)
+            | Token::Integer(_)
+            | Token::Float(_)
+            | Token::SuffixedNumber(..)
+            | Token::Char(_)
+            | Token::Byte(_)
+            | Token::ByteString(_)
+            | Token::RawString(_)
+            | Token::StringLiteral(_)
+            | Token::InterpolatedString(_)
+    )
+}
+
+/// Whether a line ending in `token` continues on the next line.
+fn expects_more(token: &Token) -> bool {
+    matches!(token, Token::Operator(_) | Token::Symbol('?' | '|' | '.' | ':' | '&' | '!'))
+}
+
+/// Whether a line starting with `token` continues the line before.
+fn continues_line(token: &Token) -> bool {
+    match token {
+        Token::Symbol('|' | '.' | '?') => true,
+        Token::Operator(op) => !matches!(op.as_str(), "-" | "~" | "*" | "::" | ".." | "..=" | "..."),
+        _ => false,
+    }
+}
+
+fn closes(open: char, close: &Token) -> bool {
+    matches!((open, close), ('(', Token::Symbol(')')) | ('[', Token::Symbol(']')) | ('{', Token::Symbol('}')))
+}
+
+/// Whether `first` and `second` printed with nothing between them still lex
+/// as the same two tokens.
+fn can_join(first: &Element, second: &Element) -> bool {
+    let joined = format!("{}{}", first.text, second.text);
+    let mut lexer = Lexer::new(&joined);
+    let tokens = [lexer.next_token(), lexer.next_token(), lexer.next_token()];
+    Some(&tokens[0]) == first.token.as_ref() && Some(&tokens[1]) == second.token.as_ref() && tokens[2] == Token::Eof
+}
+
+struct Printer {
+    elements: Vec<Element>,
+    output: String,
+    /// Open brackets, with the indent of the line each was opened on.
+    open: Vec<(char, usize)>,
+    line_indent: usize,
+    previous_token: Option<usize>,
+    /// The parser's reading of each element's token, where it recorded one.
+    roles: Vec<Option<TokenRole>>,
+}
+
+impl Printer {
+    fn new(elements: Vec<Element>, spans: &SpanTable) -> Self {
+        Self {
+            roles: elements
+                .iter()
+                .map(|element| element.token.as_ref().and_then(|_| spans.token_role(element.start)))
+                .collect(),
+            elements,
+            output: String::new(),
+            open: Vec::new(),
+            line_indent: 0,
+            previous_token: None,
+        }
+    }
+
+    fn print(mut self) -> String {
+        for i in 0..self.elements.len() {
+            let element = &self.elements[i];
+            if i == 0 || element.newlines > 0 {
+                if i > 0 {
+                    let after_open = matches!(self.elements[i - 1].token, Some(Token::Symbol('(' | '[' | '{')));
+                    let before_close = matches!(element.token, Some(Token::Symbol(')' | ']' | '}')));
+                    let max_newlines = if after_open || before_close { 1 } else { 2 };
+                    self.output.push_str(&"\n".repeat(element.newlines.min(max_newlines)));
+                }
+                self.line_indent = self.indent_for(i);
+                self.output.push_str(&INDENT.repeat(self.line_indent));
+            } else if self.space_before(i) {
+                self.output.push(' ');
+            }
+            self.output.push_str(&self.elements[i].text);
+
+            if let Some(token) = &self.elements[i].token {
+                match token {
+                    Token::Symbol(c @ ('(' | '[' | '{')) => self.open.push((*c, self.line_indent)),
+                    close if self.open.last().is_some_and(|(open, _)| closes(*open, close)) => {
+                        self.open.pop();
+                    }
+                    _ => {}
+                }
+                self.previous_token = Some(i);
+            }
+        }
+        if !self.output.is_empty() {
+            self.output.push('\n');
+        }
+        self.output
+    }
+
+    /// The indent of a line starting with element `i`.
+    fn indent_for(&self, i: usize) -> usize {
+        let base = self.open.last().map_or(0, |(_, indent)| indent + 1);
+        // A comment is indented like the code after it
+        let Some(next) = self.elements[i..].iter().find_map(|element| element.token.as_ref()) else {
+            return base;
+        };
+        if let Some((open, indent)) = self.open.last() {
+            if closes(*open, next) {
+                return if self.elements[i].token.is_some() { *indent } else { base };
+            }
+        }
+        let previous = self.previous_token.and_then(|p| self.elements[p].token.as_ref());
+        let continuation = match previous {
+            None | Some(Token::Symbol('(' | '[' | '{')) => false,
+            Some(previous) => expects_more(previous) || continues_line(next),
+        };
+        base + usize::from(continuation)
+    }
+
+    fn space_before(&self, i: usize) -> bool {
+        let element = &self.elements[i];
+        let previous = &self.elements[i - 1];
+        match (&previous.token, &element.token) {
+            (Some(prev), Some(next)) => match spacing(prev, self.roles[i - 1], next, self.roles[i]) {
+                Spacing::Space => true,
+                Spacing::None => !can_join(previous, element),
+            },
+            // Line comments after code always get a space; block comments
+            // keep whatever they had
+            _ => element.spaced || element.token.is_none() && !element.text.starts_with("/*"),
+        }
+    }
+}
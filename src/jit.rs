@@ -0,0 +1,155 @@
+//! JIT execution for `zen run`.
+//! Runs a module's `main` in-process through an LLVM execution engine.
+
+use crate::error::{CompileError, Result};
+use inkwell::module::Module;
+use inkwell::types::BasicTypeEnum;
+use inkwell::OptimizationLevel;
+use std::ffi::{c_char, c_void, CString};
+
+extern "C" {
+    fn fflush(stream: *mut c_void) -> i32;
+}
+
+/// The shapes of `main` the JIT knows how to call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MainSignature {
+    /// `main = () T`
+    NoArgs,
+    /// `main = (argc: i32, argv: **i8) T`
+    ArgcArgv32,
+    /// `main = (argc: i64, argv: **i8) T`
+    ArgcArgv64,
+}
+
+/// The return types of `main` the JIT knows how to call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MainReturn {
+    Void,
+    I1,
+    I8,
+    I16,
+    I32,
+    I64,
+}
+
+/// JIT-compile `module` and call its `main` with `args` as argv
+/// (`args[0]` is the program name). Returns `main`'s result as an exit code;
+/// `void` mains exit with 0.
+pub fn run_main(module: &Module, args: &[String], opt_level: OptimizationLevel) -> Result<i32> {
+    let main_fn = module.get_function("main").ok_or_else(|| {
+        CompileError::UndeclaredFunction("main".to_string(), None)
+    })?;
+
+    let fn_type = main_fn.get_type();
+    let params = fn_type.get_param_types();
+    let signature = match params.as_slice() {
+        [] => MainSignature::NoArgs,
+        [argc, argv] if argv.is_pointer_type() && argc.is_int_type() => {
+            match argc.into_int_type().get_bit_width() {
+                32 => MainSignature::ArgcArgv32,
+                64 => MainSignature::ArgcArgv64,
+                width => return Err(CompileError::TypeError(
+                    format!("Unsupported argc width for main: i{}", width),
+                    None,
+                )),
+            }
+        }
+        _ => return Err(CompileError::TypeError(
+            "main must take no arguments or (argc, argv)".to_string(),
+            None,
+        )),
+    };
+
+    let return_type = match fn_type.get_return_type() {
+        None => MainReturn::Void,
+        Some(ty) => match ty {
+            BasicTypeEnum::IntType(int_type) => match int_type.get_bit_width() {
+                1 => MainReturn::I1,
+                8 => MainReturn::I8,
+                16 => MainReturn::I16,
+                32 => MainReturn::I32,
+                64 => MainReturn::I64,
+                width => return Err(CompileError::TypeError(
+                    format!("Unsupported return width for main: i{}", width),
+                    None,
+                )),
+            },
+            other => return Err(CompileError::TypeError(
+                format!("main must return an integer or void, found {:?}", other),
+                None,
+            )),
+        },
+    };
+
+    inkwell::support::load_visible_symbols();
+    let engine = module
+        .create_jit_execution_engine(opt_level)
+        .map_err(|e| CompileError::InternalError(format!("Failed to create execution engine: {}", e), None))?;
+
+    let address = engine
+        .get_function_address("main")
+        .map_err(|e| CompileError::InternalError(format!("Failed to get main function: {}", e), None))?;
+
+    let c_args = args
+        .iter()
+        .map(|arg| CString::new(arg.as_str()))
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(|e| CompileError::InternalError(format!("Invalid program argument: {}", e), None))?;
+    let mut argv: Vec<*const c_char> = c_args.iter().map(|arg| arg.as_ptr()).collect();
+    argv.push(std::ptr::null());
+    let argc = c_args.len();
+
+    engine.run_static_constructors();
+
+    // SAFETY: the address comes from the execution engine for a function whose
+    // LLVM signature was checked above, each call uses the matching return
+    // type, and `argv` outlives the call. Integer results are sign-extended
+    // from their own width. An `i1` result only defines its low bit, so it
+    // is read as a byte and masked rather than trusted to be a valid `bool`.
+    let argv = argv.as_ptr();
+    let status = unsafe {
+        match return_type {
+            MainReturn::Void => {
+                call_main::<()>(address, signature, argc, argv);
+                0
+            }
+            MainReturn::I1 => (call_main::<u8>(address, signature, argc, argv) & 1) as i64,
+            MainReturn::I8 => call_main::<i8>(address, signature, argc, argv) as i64,
+            MainReturn::I16 => call_main::<i16>(address, signature, argc, argv) as i64,
+            MainReturn::I32 => call_main::<i32>(address, signature, argc, argv) as i64,
+            MainReturn::I64 => call_main::<i64>(address, signature, argc, argv),
+        }
+    };
+
+    engine.run_static_destructors();
+
+    // Programs write through C stdio; flush it before the host process exits.
+    unsafe {
+        fflush(std::ptr::null_mut());
+    }
+
+    Ok(status as i32)
+}
+
+/// Call the `main` at `address` as a function of `signature` returning `R`.
+///
+/// # Safety
+/// `address` must be a function with that signature and return type, and
+/// `argv` must point to `argc` C strings followed by a null pointer.
+unsafe fn call_main<R>(address: usize, signature: MainSignature, argc: usize, argv: *const *const c_char) -> R {
+    match signature {
+        MainSignature::NoArgs => {
+            let f: unsafe extern "C" fn() -> R = std::mem::transmute(address);
+            f()
+        }
+        MainSignature::ArgcArgv32 => {
+            let f: unsafe extern "C" fn(i32, *const *const c_char) -> R = std::mem::transmute(address);
+            f(argc as i32, argv)
+        }
+        MainSignature::ArgcArgv64 => {
+            let f: unsafe extern "C" fn(i64, *const *const c_char) -> R = std::mem::transmute(address);
+            f(argc as i64, argv)
+        }
+    }
+}
@@ -1,4 +1,5 @@
 use crate::error::Span;
+use unicode_ident::{is_xid_continue, is_xid_start};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Keyword {
@@ -16,18 +17,102 @@ pub enum Keyword {
     Type,
 }
 
+/// The type suffix of a numeric literal such as `42u8` or `3.0f32`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NumberSuffix {
+    I8,
+    I16,
+    I32,
+    I64,
+    U8,
+    U16,
+    U32,
+    U64,
+    F32,
+    F64,
+}
+
+impl NumberSuffix {
+    fn from_str(suffix: &str) -> Option<Self> {
+        match suffix {
+            "i8" => Some(NumberSuffix::I8),
+            "i16" => Some(NumberSuffix::I16),
+            "i32" => Some(NumberSuffix::I32),
+            "i64" => Some(NumberSuffix::I64),
+            "u8" => Some(NumberSuffix::U8),
+            "u16" => Some(NumberSuffix::U16),
+            "u32" => Some(NumberSuffix::U32),
+            "u64" => Some(NumberSuffix::U64),
+            "f32" => Some(NumberSuffix::F32),
+            "f64" => Some(NumberSuffix::F64),
+            _ => None,
+        }
+    }
+
+    pub fn is_float(self) -> bool {
+        matches!(self, NumberSuffix::F32 | NumberSuffix::F64)
+    }
+}
+
+impl std::fmt::Display for NumberSuffix {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let name = match self {
+            NumberSuffix::I8 => "i8",
+            NumberSuffix::I16 => "i16",
+            NumberSuffix::I32 => "i32",
+            NumberSuffix::I64 => "i64",
+            NumberSuffix::U8 => "u8",
+            NumberSuffix::U16 => "u16",
+            NumberSuffix::U32 => "u32",
+            NumberSuffix::U64 => "u64",
+            NumberSuffix::F32 => "f32",
+            NumberSuffix::F64 => "f64",
+        };
+        write!(f, "{}", name)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Token {
     Identifier(String),
+    /// An unsuffixed integer literal, as decimal digits. A literal that
+    /// isn't valid keeps its source text for the parser to report.
     Integer(String),
+    /// An unsuffixed float literal, in a form `str::parse::<f64>` accepts.
     Float(String),
+    /// A literal with a type suffix: its value as for `Integer` or `Float`,
+    /// and the suffix.
+    SuffixedNumber(String, NumberSuffix),
+    /// A `'c'` character literal.
+    Char(char),
+    /// A `b'c'` byte literal.
+    Byte(u8),
+    /// A `b"..."` byte string.
+    ByteString(Vec<u8>),
+    /// A `r"..."` or `r#"..."#` string, taken verbatim: no escapes and no
+    /// interpolation.
+    RawString(String),
     StringLiteral(String),
+    /// A string with `$(...)` interpolations, split into its text and the
+    /// source of each interpolated expression. An escaped `\$(` is text.
+    InterpolatedString(Vec<StringSegment>),
     Keyword(Keyword),
     Symbol(char),
     Operator(String),
+    /// A malformed literal, with a message for the parser to report.
+    Error(String),
     Eof,
 }
 
+/// A piece of an interpolated string.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StringSegment {
+    /// Literal text, with its escapes processed.
+    Text(String),
+    /// The source of an interpolated expression, without the `$(` and `)`.
+    Code(String),
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct TokenWithSpan {
     pub token: Token,
@@ -41,7 +126,11 @@ pub struct Lexer<'a> {
     pub read_position: usize,
     pub current_char: Option<char>,
     pub line: usize,
+    /// Column of `current_char` in characters, starting at 1.
     pub column: usize,
+    /// Column of `current_char` in UTF-16 code units, starting at 1, as
+    /// editors speaking LSP count them.
+    pub utf16_column: usize,
 }
 
 impl<'a> Lexer<'a> {
@@ -53,61 +142,89 @@ impl<'a> Lexer<'a> {
             current_char: None,
             line: 1,
             column: 1,
+            utf16_column: 1,
         };
         lexer.read_char();
         lexer
     }
 
     fn read_char(&mut self) {
-        if self.read_position >= self.input.len() {
-            self.current_char = None;
-        } else {
-            self.current_char = self.input[self.read_position..].chars().next();
-        }
-        self.position = self.read_position;
-        self.read_position += 1;
-        
-        // Update line and column tracking
-        if let Some(c) = self.current_char {
-            if c == '\n' {
+        // Advance the line and column past the character being left behind
+        match self.current_char {
+            Some('\n') => {
                 self.line += 1;
                 self.column = 1;
-            } else {
+                self.utf16_column = 1;
+            }
+            Some(c) => {
                 self.column += 1;
+                self.utf16_column += c.len_utf16();
             }
+            None => {}
+        }
+
+        // Stay at the end of input rather than stepping past it
+        self.position = self.read_position.min(self.input.len());
+        self.current_char = self.input.get(self.read_position..).and_then(|rest| rest.chars().next());
+        // Positions are byte offsets, so step over the whole UTF-8 sequence
+        self.read_position += self.current_char.map_or(1, char::len_utf8);
+    }
+
+    /// An empty span at the current character.
+    fn mark(&self) -> Span {
+        Span {
+            start: self.position,
+            end: self.position,
+            line: self.line,
+            column: self.column,
+            utf16_column: self.utf16_column,
         }
     }
 
+    /// The span from `start` up to the current character.
+    fn span_from(&self, start: &Span) -> Span {
+        Span { end: self.position, ..start.clone() }
+    }
+
     pub fn next_token(&mut self) -> Token {
         self.next_token_with_span().token
     }
 
     pub fn next_token_with_span(&mut self) -> TokenWithSpan {
-        let start_pos = self.position;
-        let start_line = self.line;
-        let start_column = self.column;
-        
         self.skip_whitespace_and_comments();
         
+        let start = self.mark();
+        
         let token = match self.current_char {
-            Some(c) if c.is_ascii_alphabetic() || c == '_' || c == '@' => {
+            Some('r') if self.raw_string_follows() => self.read_raw_string(),
+            Some('b') if self.peek_char() == Some('"') => {
+                self.read_char(); // consume 'b'
+                self.read_byte_string()
+            }
+            Some('b') if self.peek_char() == Some('\'') => {
+                self.read_char(); // consume 'b'
+                match self.read_char_literal() {
+                    Ok(c) if c.is_ascii() => Token::Byte(c as u8),
+                    Ok(_) => Token::Error("Byte literals may only contain ASCII characters".to_string()),
+                    Err(message) => Token::Error(message),
+                }
+            }
+            Some('\'') => match self.read_char_literal() {
+                Ok(c) => Token::Char(c),
+                Err(message) => Token::Error(message),
+            },
+            Some('"') if self.input[self.position..].starts_with("\"\"\"") => self.read_multiline_string(),
+            Some(c) if is_xid_start(c) || c == '_' || c == '@' => {
                 let ident = self.read_identifier();
                 match self.str_to_keyword(&ident) {
                     Some(keyword) => Token::Keyword(keyword),
                     None => Token::Identifier(ident),
                 }
             }
-            Some(c) if c.is_ascii_digit() => {
-                let number = self.read_number();
-                if number.contains('.') {
-                    Token::Float(number)
-                } else {
-                    Token::Integer(number)
-                }
-            }
+            Some(c) if c.is_ascii_digit() => self.read_number(),
             Some('"') => {
-                let string = self.read_string();
-                Token::StringLiteral(string)
+                let raw = self.read_quoted();
+                string_token(raw)
             }
             Some(':') => {
                 // Check for multi-character operators (:=, ::, ::=)
@@ -117,7 +234,7 @@ impl<'a> Lexer<'a> {
                         self.read_char(); // consume '='
                         return TokenWithSpan {
                             token: Token::Operator(":=".to_string()),
-                            span: Span { start: start_pos, end: self.position, line: start_line, column: start_column },
+                            span: self.span_from(&start),
                         };
                     } else if next == ':' {
                         self.read_char(); // consume ':'
@@ -127,14 +244,14 @@ impl<'a> Lexer<'a> {
                                 self.read_char(); // consume '='
                                 return TokenWithSpan {
                                     token: Token::Operator("::=".to_string()),
-                                    span: Span { start: start_pos, end: self.position, line: start_line, column: start_column },
+                                    span: self.span_from(&start),
                                 };
                             }
                         }
                         self.read_char(); // consume second ':'
                         return TokenWithSpan {
                             token: Token::Operator("::".to_string()),
-                            span: Span { start: start_pos, end: self.position, line: start_line, column: start_column },
+                            span: self.span_from(&start),
                         };
                     }
                 }
@@ -152,21 +269,21 @@ impl<'a> Lexer<'a> {
                                 self.read_char(); // consume third '.'
                                 return TokenWithSpan {
                                     token: Token::Operator("...".to_string()),
-                                    span: Span { start: start_pos, end: self.position, line: start_line, column: start_column },
+                                    span: self.span_from(&start),
                                 };
                             } else if next2 == '=' {
                                 self.read_char(); // consume second '.'
                                 self.read_char(); // consume '='
                                 return TokenWithSpan {
                                     token: Token::Operator("..=".to_string()),
-                                    span: Span { start: start_pos, end: self.position, line: start_line, column: start_column },
+                                    span: self.span_from(&start),
                                 };
                             }
                         }
                         self.read_char(); // consume second '.'
                         return TokenWithSpan {
                             token: Token::Operator("..".to_string()),
-                            span: Span { start: start_pos, end: self.position, line: start_line, column: start_column },
+                            span: self.span_from(&start),
                         };
                     }
                 }
@@ -178,14 +295,14 @@ impl<'a> Lexer<'a> {
                 Token::Symbol('?')
             }
             Some('|') => {
-                // Only treat as operator if part of '||'
+                // Only treat as operator if part of '||' or '|='
                 if let Some(next) = self.peek_char() {
-                    if next == '|' {
+                    if next == '|' || next == '=' {
                         self.read_char(); // consume '|'
-                        self.read_char(); // consume second '|'
+                        self.read_char(); // consume second '|' or '='
                         return TokenWithSpan {
-                            token: Token::Operator("||".to_string()),
-                            span: Span { start: start_pos, end: self.position, line: start_line, column: start_column },
+                            token: Token::Operator(format!("|{}", next)),
+                            span: self.span_from(&start),
                         };
                     }
                 }
@@ -193,14 +310,14 @@ impl<'a> Lexer<'a> {
                 Token::Symbol('|')
             }
             Some('&') => {
-                // Check for '&&' operator
+                // Check for '&&' and '&=' operators
                 if let Some(next) = self.peek_char() {
-                    if next == '&' {
+                    if next == '&' || next == '=' {
                         self.read_char(); // consume '&'
-                        self.read_char(); // consume second '&'
+                        self.read_char(); // consume second '&' or '='
                         return TokenWithSpan {
-                            token: Token::Operator("&&".to_string()),
-                            span: Span { start: start_pos, end: self.position, line: start_line, column: start_column },
+                            token: Token::Operator(format!("&{}", next)),
+                            span: self.span_from(&start),
                         };
                     }
                 }
@@ -212,11 +329,21 @@ impl<'a> Lexer<'a> {
                 if self.current_char == Some('=') {
                     self.read_char();
                     Token::Operator("<=".to_string())
+                } else if self.current_char == Some('<') {
+                    self.read_char();
+                    if self.current_char == Some('=') {
+                        self.read_char();
+                        Token::Operator("<<=".to_string())
+                    } else {
+                        Token::Operator("<<".to_string())
+                    }
                 } else {
                     Token::Operator("<".to_string())
                 }
             }
             Some('>') => {
+                // `>>` stays two tokens so nested type arguments can close;
+                // the parser joins adjacent ones into a shift
                 self.read_char();
                 if self.current_char == Some('=') {
                     self.read_char();
@@ -251,7 +378,7 @@ impl<'a> Lexer<'a> {
         
         TokenWithSpan {
             token,
-            span: Span { start: start_pos, end: self.position, line: start_line, column: start_column },
+            span: self.span_from(&start),
         }
     }
 
@@ -302,7 +429,7 @@ impl<'a> Lexer<'a> {
     fn read_identifier(&mut self) -> String {
         let start = self.position;
         while let Some(c) = self.current_char {
-            if c.is_ascii_alphanumeric() || c == '_' || c == '@' {
+            if is_xid_continue(c) || c == '@' {
                 self.read_char();
             } else {
                 break;
@@ -311,32 +438,87 @@ impl<'a> Lexer<'a> {
         self.input[start..self.position].to_string()
     }
 
-    fn read_number(&mut self) -> String {
+    /// Read an integer or float literal: decimal, or `0x`, `0o` and `0b`
+    /// integers, with `_` separators, exponents and a type suffix.
+    fn read_number(&mut self) -> Token {
         let start = self.position;
-        let mut has_dot = false;
-        
-        while let Some(c) = self.current_char {
-            if c.is_ascii_digit() {
+        let radix = match (self.current_char, self.peek_char()) {
+            (Some('0'), Some('x' | 'X')) => 16,
+            (Some('0'), Some('o' | 'O')) => 8,
+            (Some('0'), Some('b' | 'B')) => 2,
+            _ => 10,
+        };
+        if radix != 10 {
+            self.read_char();
+            self.read_char();
+        }
+
+        let digits_start = self.position;
+        let mut is_float = false;
+        self.read_digits(radix);
+        if radix == 10 {
+            // Only consume '.' if it's followed by a digit (for floats like 3.14)
+            // This prevents consuming '..' as part of a number
+            if self.current_char == Some('.') && self.peek_char().is_some_and(|c| c.is_ascii_digit()) {
+                is_float = true;
                 self.read_char();
-            } else if c == '.' && !has_dot {
-                // Only consume '.' if it's followed by a digit (for floats like 3.14)
-                // This prevents consuming '..' as part of a number
-                if let Some(next_c) = self.peek_char() {
-                    if next_c.is_ascii_digit() {
-                        has_dot = true;
-                        self.read_char();
-                    } else {
-                        // Don't consume '.' if not followed by digit
-                        break;
-                    }
-                } else {
-                    break;
+                self.read_digits(10);
+            }
+            if matches!(self.current_char, Some('e' | 'E')) && self.exponent_follows() {
+                is_float = true;
+                self.read_char();
+                if matches!(self.current_char, Some('+' | '-')) {
+                    self.read_char();
                 }
-            } else {
-                break;
+                self.read_digits(10);
             }
         }
-        self.input[start..self.position].to_string()
+        let digits_end = self.position;
+
+        // Whatever identifier characters follow form the suffix
+        while self.current_char.is_some_and(|c| c.is_ascii_alphanumeric() || c == '_') {
+            self.read_char();
+        }
+
+        let digits = self.input[digits_start..digits_end].replace('_', "");
+        let suffix = &self.input[digits_end..self.position];
+        let raw = || Token::Integer(self.input[start..self.position].to_string());
+
+        let value = if is_float {
+            digits
+        } else {
+            match u128::from_str_radix(&digits, radix) {
+                Ok(value) => value.to_string(),
+                // Too large, or no digits at all
+                Err(_) if radix == 10 && !digits.is_empty() => digits,
+                Err(_) => return raw(),
+            }
+        };
+
+        match (suffix, NumberSuffix::from_str(suffix)) {
+            ("", _) if is_float => Token::Float(value),
+            ("", _) => Token::Integer(value),
+            // `1.5u8` and `0xFFf32` aren't meaningful
+            (_, Some(suffix)) if (is_float && !suffix.is_float()) || (radix != 10 && suffix.is_float()) => raw(),
+            (_, Some(suffix)) => Token::SuffixedNumber(value, suffix),
+            (_, None) => raw(),
+        }
+    }
+
+    fn read_digits(&mut self, radix: u32) {
+        while self.current_char.is_some_and(|c| c.is_digit(radix) || c == '_') {
+            self.read_char();
+        }
+    }
+
+    /// Whether the `e` at the current character starts an exponent rather
+    /// than a suffix.
+    fn exponent_follows(&self) -> bool {
+        let mut rest = self.input[self.read_position..].chars();
+        match rest.next() {
+            Some('+' | '-') => rest.next().is_some_and(|c| c.is_ascii_digit()),
+            next => next.is_some_and(|c| c.is_ascii_digit()),
+        }
     }
 
     fn str_to_keyword(&self, ident: &str) -> Option<Keyword> {
@@ -362,43 +544,129 @@ impl<'a> Lexer<'a> {
     }
 
     fn is_operator_start(&self, c: char) -> bool {
-        matches!(c, '+' | '-' | '*' | '/' | '=' | '!' | '<' | '>' | '&' | '|' | ':')
+        matches!(c, '+' | '-' | '*' | '/' | '%' | '^' | '~' | '=' | '!' | '<' | '>' | '&' | '|' | ':')
     }
 
     fn read_string(&mut self) -> String {
+        unescape(self.read_quoted())
+    }
+
+    /// Read a `"` string, returning its text with the escapes still in it.
+    fn read_quoted(&mut self) -> &'a str {
         self.read_char(); // consume opening quote
-        let mut result = String::new();
-        let mut escape_next = false;
-        
+        let start = self.position;
+        self.skip_quoted('"');
+        let raw = &self.input[start..self.position];
+        self.read_char(); // consume closing quote
+        raw
+    }
+
+    /// Read a `"""` string. The text is dedented before escapes are
+    /// processed, so `\n` escapes don't count as line breaks.
+    fn read_multiline_string(&mut self) -> Token {
+        self.advance_to(self.position + 3);
+        let start = self.position;
+        loop {
+            match self.current_char {
+                None => return Token::Error("Unterminated multi-line string literal".to_string()),
+                Some('"') if self.input[self.position..].starts_with("\"\"\"") => break,
+                Some('\\') => {
+                    self.read_char();
+                    self.read_char();
+                }
+                Some(_) => self.read_char(),
+            }
+        }
+        let raw = &self.input[start..self.position];
+        self.advance_to(self.position + 3);
+        string_token(&dedent(raw))
+    }
+
+    /// Whether the `r` at the current character opens a raw string.
+    fn raw_string_follows(&self) -> bool {
+        self.input[self.read_position..].trim_start_matches('#').starts_with('"')
+    }
+
+    fn read_raw_string(&mut self) -> Token {
+        self.read_char(); // consume 'r'
+        let mut hashes = 0;
+        while self.current_char == Some('#') {
+            hashes += 1;
+            self.read_char();
+        }
+        self.read_char(); // consume opening quote
+
+        let start = self.position;
+        let closing = format!("\"{}", "#".repeat(hashes));
+        match self.input[start..].find(&closing) {
+            Some(length) => {
+                self.advance_to(start + length + closing.len());
+                Token::RawString(self.input[start..start + length].to_string())
+            }
+            None => {
+                self.advance_to(self.input.len());
+                Token::Error("Unterminated raw string literal".to_string())
+            }
+        }
+    }
+
+    fn read_byte_string(&mut self) -> Token {
+        let string = self.read_string();
+        if string.is_ascii() {
+            Token::ByteString(string.into_bytes())
+        } else {
+            Token::Error("Byte string literals may only contain ASCII characters".to_string())
+        }
+    }
+
+    /// Read a `'c'` literal, with `current_char` on the opening quote.
+    fn read_char_literal(&mut self) -> std::result::Result<char, String> {
+        self.read_char(); // consume opening quote
+        let start = self.position;
+        // A stray quote shouldn't swallow the rest of the file
         while let Some(c) = self.current_char {
-            if escape_next {
-                // Handle escape sequences
-                match c {
-                    'n' => result.push('\n'),
-                    't' => result.push('\t'),
-                    'r' => result.push('\r'),
-                    '\\' => result.push('\\'),
-                    '"' => result.push('"'),
-                    '$' => result.push('$'), // Allow escaping $ for literal $
-                    _ => {
-                        result.push('\\');
-                        result.push(c);
-                    }
+            match c {
+                '\'' | '\n' => break,
+                '\\' => {
+                    self.read_char();
+                    self.read_char();
                 }
-                escape_next = false;
-                self.read_char();
-            } else if c == '\\' {
-                escape_next = true;
-                self.read_char();
-            } else if c == '"' {
+                _ => self.read_char(),
+            }
+        }
+        if self.current_char != Some('\'') {
+            return Err("Unterminated character literal".to_string());
+        }
+        let raw = &self.input[start..self.position];
+        self.read_char(); // consume closing quote
+
+        let text = unescape(raw);
+        let mut chars = text.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Ok(c),
+            (None, _) => Err("Empty character literal".to_string()),
+            _ => Err(format!("Character literal '{}' must contain exactly one character", raw)),
+        }
+    }
+
+    /// Move to the closing `quote`, stepping over escaped characters.
+    fn skip_quoted(&mut self, quote: char) {
+        while let Some(c) = self.current_char {
+            if c == quote {
                 break;
-            } else {
-                result.push(c);
+            }
+            if c == '\\' {
                 self.read_char();
             }
+            self.read_char();
+        }
+    }
+
+    /// Read characters until the byte offset `end`.
+    fn advance_to(&mut self, end: usize) {
+        while self.current_char.is_some() && self.position < end {
+            self.read_char();
         }
-        self.read_char(); // consume closing quote
-        result
     }
 
     fn read_operator(&mut self) -> String {
@@ -421,7 +689,11 @@ impl<'a> Lexer<'a> {
         // Handle two-character operators
         if let Some(second_char) = self.current_char {
             let two_char_op = format!("{}{}", first_char, second_char);
-            if matches!(two_char_op.as_str(), "==" | "!=" | "<=" | ">=" | "&&" | "||" | "->" | "=>" | ":=" | "::" | ".." | "..=") {
+            if matches!(
+                two_char_op.as_str(),
+                "==" | "!=" | "<=" | ">=" | "&&" | "||" | "->" | "=>" | ":=" | "::" | ".." | "..="
+                    | "+=" | "-=" | "*=" | "/=" | "%=" | "^="
+            ) {
                 self.read_char();
                 return two_char_op;
             }
@@ -438,4 +710,124 @@ impl<'a> Lexer<'a> {
             self.input[self.read_position..].chars().next()
         }
     }
-} 
\ No newline at end of file
+}
+
+/// Process the escape sequences in the text of a string or character
+/// literal. Unknown or malformed escapes are kept as written.
+/// The token for a string whose text, escapes and all, is `raw`: a plain
+/// string literal, or one split at its interpolations. Interpolations are
+/// found before escapes are processed, so `\$(` stays literal.
+fn string_token(raw: &str) -> Token {
+    let mut segments = Vec::new();
+    let mut text = String::new();
+    let mut chars = raw.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                text.push(c);
+                text.extend(chars.next());
+            }
+            '$' if chars.as_str().starts_with('(') => {
+                chars.next();
+                let mut code = String::new();
+                let mut depth = 1;
+                loop {
+                    let Some(c) = chars.next() else {
+                        return Token::Error("Unmatched parentheses in string interpolation".to_string());
+                    };
+                    match c {
+                        '(' => depth += 1,
+                        ')' if depth == 1 => break,
+                        ')' => depth -= 1,
+                        _ => {}
+                    }
+                    code.push(c);
+                }
+                segments.push(StringSegment::Text(unescape(&std::mem::take(&mut text))));
+                segments.push(StringSegment::Code(unescape(&code)));
+            }
+            c => text.push(c),
+        }
+    }
+    if segments.is_empty() {
+        return Token::StringLiteral(unescape(&text));
+    }
+    segments.push(StringSegment::Text(unescape(&text)));
+    segments.retain(|segment| !matches!(segment, StringSegment::Text(text) if text.is_empty()));
+    Token::InterpolatedString(segments)
+}
+
+fn unescape(raw: &str) -> String {
+    let mut result = String::with_capacity(raw.len());
+    let mut chars = raw.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => result.push('\n'),
+            Some('t') => result.push('\t'),
+            Some('r') => result.push('\r'),
+            Some('0') => result.push('\0'),
+            Some('\\') => result.push('\\'),
+            Some('"') => result.push('"'),
+            Some('\'') => result.push('\''),
+            Some('$') => result.push('$'), // Allow escaping $ for literal $
+            Some('u') => match unicode_escape(chars.as_str()) {
+                Some((escaped, length)) => {
+                    result.push(escaped);
+                    chars = chars.as_str()[length..].chars();
+                }
+                None => result.push_str("\\u"),
+            },
+            Some(other) => {
+                result.push('\\');
+                result.push(other);
+            }
+            None => result.push('\\'),
+        }
+    }
+    result
+}
+
+/// Decode the `{XXXX}` that follows `\u`, returning the character and the
+/// length of the braced part.
+fn unicode_escape(rest: &str) -> Option<(char, usize)> {
+    let braced = rest.strip_prefix('{')?;
+    let digits = &braced[..braced.find('}')?];
+    if digits.is_empty() || digits.len() > 6 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let escaped = char::from_u32(u32::from_str_radix(digits, 16).ok()?)?;
+    Some((escaped, digits.len() + 2))
+}
+
+/// Strip the indentation of a `"""` string. A line break right after the
+/// opening quotes is dropped, as is the last line when it holds only the
+/// closing quotes' indentation; the indentation common to the remaining
+/// non-blank lines and the closing line is removed from every line.
+fn dedent(raw: &str) -> String {
+    let raw = raw.strip_prefix("\r\n").or_else(|| raw.strip_prefix('\n')).unwrap_or(raw);
+    let mut lines: Vec<&str> = raw.split('\n').map(|line| line.strip_suffix('\r').unwrap_or(line)).collect();
+    let closing_indent = match lines.last() {
+        Some(last) if lines.len() > 1 && last.trim().is_empty() => lines.pop(),
+        _ => None,
+    };
+
+    let indent_of = |line: &str| line.len() - line.trim_start_matches([' ', '\t']).len();
+    let indent = lines
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .copied()
+        .chain(closing_indent)
+        .map(indent_of)
+        .min()
+        .unwrap_or(0);
+
+    lines
+        .iter()
+        .map(|line| if line.trim().is_empty() { "" } else { &line[indent..] })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
@@ -5,13 +5,21 @@ pub fn add(left: u64, right: u64) -> u64 {
 }
 
 pub mod ast;
+pub mod build;
 pub mod codegen;
 pub mod compiler;
+pub mod cst;
+pub mod diagnostics;
 pub mod comptime;
 pub mod error;
+pub mod formatter;
+pub mod jit;
 pub mod lexer;
+pub mod mangle;
 pub mod module_system;
 pub mod parser;
+pub mod repl;
+pub mod spans;
 pub mod lsp;
 pub mod stdlib;
 pub mod typechecker;
@@ -5,9 +5,12 @@ use tower_lsp::lsp_types::*;
 use tower_lsp::{Client, LanguageServer, LspService, Server};
 use tokio::sync::RwLock;
 
+use crate::diagnostics as zen_diagnostics;
+use crate::error::Span;
 use crate::lexer::Lexer;
 use crate::parser::Parser;
 use crate::ast::Program;
+use crate::typechecker::TypeChecker;
 
 #[derive(Debug)]
 pub struct ZenServer {
@@ -36,30 +39,116 @@ impl ZenServer {
     }
 
     async fn get_diagnostics(&self, uri: &str) -> Vec<Diagnostic> {
-        let mut diagnostics = Vec::new();
-        
-        match self.parse_document(uri).await {
-            Ok(_) => {
-                // No errors, document is valid
-            }
-            Err(_) => {
-                // Add a generic error diagnostic
-                diagnostics.push(Diagnostic {
-                    range: Range::new(Position::new(0, 0), Position::new(0, 0)),
-                    severity: Some(DiagnosticSeverity::ERROR),
-                    code: None,
-                    code_description: None,
-                    source: Some("zen".to_string()),
-                    message: "Failed to parse document".to_string(),
-                    related_information: None,
-                    tags: None,
-                    data: None,
-                });
-            }
-        }
-        
-        diagnostics
+        let documents = self.documents.read().await;
+        let Some(content) = documents.get(uri) else {
+            return Vec::new();
+        };
+
+        let lexer = Lexer::new(content);
+        let mut parser = Parser::new(lexer);
+        let (program, syntax_errors) = parser.parse_program_recovering();
+        let (errors, warnings) = if syntax_errors.is_empty() {
+            let mut checker = TypeChecker::new().with_spans(parser.take_spans());
+            let errors = checker.check_program_collecting(&program);
+            (errors, checker.warnings().to_vec())
+        } else {
+            // Type errors in a partial program would mostly be noise
+            (syntax_errors, Vec::new())
+        };
+
+        let uri = uri.parse().ok();
+        errors
+            .iter()
+            .map(zen_diagnostics::Diagnostic::from)
+            .chain(warnings)
+            .map(|diagnostic| lsp_diagnostic(uri.as_ref(), &diagnostic, |span| source_range(content, span)))
+            .collect()
+    }
+}
+
+/// Convert a compiler diagnostic to its LSP form. Secondary labels become
+/// related information when the document URI is known.
+pub fn to_lsp_diagnostic(uri: Option<&Url>, diagnostic: &zen_diagnostics::Diagnostic) -> Diagnostic {
+    lsp_diagnostic(uri, diagnostic, span_to_range)
+}
+
+fn lsp_diagnostic(
+    uri: Option<&Url>,
+    diagnostic: &zen_diagnostics::Diagnostic,
+    span_to_range: impl Fn(&Span) -> Range,
+) -> Diagnostic {
+    let severity = match diagnostic.severity {
+        zen_diagnostics::Severity::Error => DiagnosticSeverity::ERROR,
+        zen_diagnostics::Severity::Warning => DiagnosticSeverity::WARNING,
+        zen_diagnostics::Severity::Note => DiagnosticSeverity::INFORMATION,
+    };
+
+    let range = diagnostic
+        .primary_span()
+        .map(&span_to_range)
+        .unwrap_or_else(|| Range::new(Position::new(0, 0), Position::new(0, 0)));
+
+    let related_information = uri.and_then(|uri| {
+        let related: Vec<_> = diagnostic
+            .labels
+            .iter()
+            .skip(1)
+            .map(|label| DiagnosticRelatedInformation {
+                location: Location::new(uri.clone(), span_to_range(&label.span)),
+                message: label.message.clone(),
+            })
+            .collect();
+        if related.is_empty() { None } else { Some(related) }
+    });
+
+    let mut message = diagnostic.message.clone();
+    for note in &diagnostic.notes {
+        message.push_str(&format!("\nnote: {}", note));
+    }
+    if let Some(help) = &diagnostic.help {
+        message.push_str(&format!("\nhelp: {}", help));
+    }
+
+    Diagnostic {
+        range,
+        severity: Some(severity),
+        code: diagnostic.code.clone().map(NumberOrString::String),
+        code_description: None,
+        source: Some("zen".to_string()),
+        message,
+        related_information,
+        tags: None,
+        data: None,
+    }
+}
+
+/// The range of a span from its recorded line and UTF-16 column. Spans
+/// carry 1-based lines and columns; LSP positions are 0-based. Without the
+/// source text the end is estimated from the span's length in bytes.
+fn span_to_range(span: &Span) -> Range {
+    let line = span.line.saturating_sub(1) as u32;
+    let column = span.utf16_column.saturating_sub(1) as u32;
+    let width = span.end.saturating_sub(span.start).max(1) as u32;
+    Range::new(Position::new(line, column), Position::new(line, column + width))
+}
+
+/// The exact range of a span within `source`, in UTF-16 code units.
+fn source_range(source: &str, span: &Span) -> Range {
+    let start = source_position(source, span.start);
+    let end = source_position(source, span.end.max(span.start + 1));
+    Range::new(start, end)
+}
+
+fn source_position(source: &str, offset: usize) -> Position {
+    let mut offset = offset.min(source.len());
+    while !source.is_char_boundary(offset) {
+        offset += 1;
     }
+    let before = &source[..offset];
+    let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+    let line = before.matches('\n').count() as u32;
+    let character = before[line_start..].encode_utf16().count() as u32;
+    Position::new(line, character)
 }
 
 #[tower_lsp::async_trait]
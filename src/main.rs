@@ -6,16 +6,22 @@ mod ast;
 mod codegen;
 mod compiler;
 mod comptime;
+mod diagnostics;
 mod error;
 mod lexer;
 mod lsp;
+mod mangle;
 mod module_system;
 mod parser;
+mod spans;
 mod stdlib;
 mod typechecker;
 mod type_system;
 
+use zen::build::{self, BuildOptions, EmitKind};
 use zen::compiler::Compiler;
+use zen::diagnostics::{ColorChoice, Diagnostic, DiagnosticRenderer, FileDiagnostic};
+use zen::repl::{self, ReplOutput, ReplSession};
 use zen::lexer::Lexer;
 use zen::parser::Parser;
 use zen::error::{Result, CompileError};
@@ -25,13 +31,28 @@ fn main() -> std::io::Result<()> {
     inkwell::targets::Target::initialize_native(&inkwell::targets::InitializationConfig::default())
         .map_err(|e| io::Error::new(io::ErrorKind::Other, format!("LLVM initialization failed: {}", e)))?;
     
-    let args: Vec<String> = env::args().collect();
+    let (args, color) = take_color_flag(env::args().collect());
     
     match args.len() {
         1 => {
             // No arguments - start REPL
             run_repl()?;
         }
+        _ if args[1] == "build" => {
+            run_build(&args[2..], color)?;
+        }
+        _ if args[1] == "check" => {
+            run_check(&args[2..], color)?;
+        }
+        _ if args[1] == "run" => {
+            run_program(&args[2..], color)?;
+        }
+        _ if args[1] == "fmt" => {
+            run_fmt(&args[2..], color)?;
+        }
+        _ if args[1] == "demangle" => {
+            run_demangle(&args[2..])?;
+        }
         2 => {
             // One argument - treat as file path
             let file_path = &args[1];
@@ -39,7 +60,7 @@ fn main() -> std::io::Result<()> {
                 print_usage();
                 return Ok(());
             }
-            run_file(file_path)?;
+            run_file(file_path, color)?;
         }
         _ => {
             print_usage();
@@ -56,11 +77,37 @@ fn print_usage() {
     println!("Usage:");
     println!("  zen                    Start interactive REPL");
     println!("  zen <file.zen>         Compile and run a Zen file");
+    println!("  zen run <file.zen> [-- args...]");
+    println!("                         JIT-run main and exit with its return value");
+    println!("  zen build <file.zen>   Build a native executable");
+    println!("  zen check <file.zen>   Type check a file without generating code");
+    println!("  zen fmt [paths...]     Format files in place, or stdin to stdout if none are given");
+    println!("  zen demangle [symbols...]");
+    println!("                         Demangle symbols, or the symbols in stdin if none are given");
     println!("  zen --help             Show this help message");
     println!();
+    println!("Build options:");
+    println!("  -o <path>              Output path (defaults to the input file stem)");
+    println!("  --emit=<kind>          One of obj, asm, llvm-ir, llvm-bc (default: executable)");
+    println!("  -O0 .. -O3             Optimization level (default: -O2)");
+    println!();
+    println!("Check options:");
+    println!("  --message-format=<fmt> human (default) or json, one object per line");
+    println!();
+    println!("Format options:");
+    println!("  --check                Report files that aren't formatted instead of rewriting them");
+    println!();
+    println!("Global options:");
+    println!("  --color=<when>         Colorize diagnostics: auto, always or never");
+    println!();
     println!("Examples:");
     println!("  zen                    # Start REPL");
     println!("  zen hello.zen          # Run hello.zen file");
+    println!("  zen run hello.zen -- a b");
+    println!("  zen build hello.zen -o hello");
+    println!("  zen build hello.zen --emit=llvm-ir");
+    println!("  zen fmt --check src/");
+    println!("  nm hello | zen demangle");
 }
 
 fn run_repl() -> std::io::Result<()> {
@@ -71,7 +118,7 @@ fn run_repl() -> std::io::Result<()> {
     println!();
     
     let context = Context::create();
-    let mut compiler = Compiler::new(&context);
+    let mut session = ReplSession::new();
     
     let stdin = io::stdin();
     let mut stdin = stdin.lock();
@@ -90,14 +137,23 @@ fn run_repl() -> std::io::Result<()> {
             break;
         }
         
+        // Keep reading while braces, parens or brackets are unbalanced
+        while repl::is_incomplete(&input) {
+            print!("...  ");
+            stdout.flush()?;
+            if stdin.read_line(&mut input)? == 0 {
+                break;
+            }
+        }
+        
         let input = input.trim();
         
         match input {
-            "exit" | "quit" => {
+            "exit" | "quit" | ":quit" | ":q" => {
                 println!("Goodbye! 👋");
                 break;
             }
-            "help" => {
+            "help" | ":help" => {
                 print_repl_help();
                 continue;
             }
@@ -107,76 +163,418 @@ fn run_repl() -> std::io::Result<()> {
                 stdout.flush()?;
                 continue;
             }
+            ":reset" => {
+                session.reset();
+                println!("Session cleared.");
+            }
+            ":ir" => match session.ir(&context) {
+                Ok(ir) => println!("{}", ir),
+                Err(e) => println!("❌ Error: {}", e),
+            },
             "" => continue,
-            _ => {
-                // Try to parse and execute the input
-                match execute_zen_code(&mut compiler, input) {
-                    Ok(result) => {
-                        if let Some(value) = result {
-                            println!("=> {}", value);
-                        }
-                    }
-                    Err(e) => {
-                        println!("❌ Error: {}", e);
-                    }
+            _ if input.starts_with(":type ") => {
+                match session.type_of(input[":type ".len()..].trim()) {
+                    Ok(type_) => println!("{}", type_),
+                    Err(e) => println!("❌ Error: {}", e),
                 }
             }
+            _ if input.starts_with(":load ") => {
+                let path = input[":load ".len()..].trim();
+                let result = std::fs::read_to_string(path)
+                    .map_err(|e| CompileError::FileNotFound(path.to_string(), Some(e.to_string())))
+                    .and_then(|source| session.load(&context, &source));
+                print_repl_result(result);
+            }
+            _ if input.starts_with(':') => {
+                println!("Unknown command: {} (type :help for a list)", input);
+            }
+            _ => {
+                print_repl_result(session.eval(&context, input));
+            }
         }
     }
     
     Ok(())
 }
 
-fn run_file(file_path: &str) -> std::io::Result<()> {
-    // Read the file
+fn print_repl_result(result: Result<ReplOutput>) {
+    match result {
+        Ok(ReplOutput::Value { value, type_ }) => println!("=> {} : {}", value, type_),
+        Ok(ReplOutput::Bound { name, type_ }) => println!("{} : {}", name, type_),
+        Ok(ReplOutput::Defined(names)) => {
+            for name in names {
+                println!("defined {}", name);
+            }
+        }
+        Ok(ReplOutput::Unit) => {}
+        Err(e) => println!("❌ Error: {}", e),
+    }
+}
+
+fn run_file(file_path: &str, color: ColorChoice) -> std::io::Result<()> {
+    run_program(&[file_path.to_string()], color)
+}
+
+fn run_program(args: &[String], color: ColorChoice) -> std::io::Result<()> {
+    let Some(file_path) = args.first() else {
+        eprintln!("zen run: missing input file");
+        print_usage();
+        std::process::exit(2);
+    };
+
+    // Everything after `--` is passed through to the program's argv
+    let program_args: Vec<String> = match args[1..].split_first() {
+        None => vec![],
+        Some((sep, rest)) if sep == "--" => rest.to_vec(),
+        Some((other, _)) => {
+            eprintln!("Unexpected argument: {} (use -- to pass arguments to the program)", other);
+            std::process::exit(2);
+        }
+    };
+
     let source = std::fs::read_to_string(file_path)
         .map_err(|e| io::Error::new(io::ErrorKind::NotFound, format!("Failed to read file: {}", e)))?;
-    
+
     let context = Context::create();
-    let mut compiler = Compiler::new(&context);
-    
-    match execute_zen_code(&mut compiler, &source) {
-        Ok(result) => {
-            if let Some(value) = result {
-                println!("{}", value);
-            }
+
+    let mut argv = vec![file_path.clone()];
+    argv.extend(program_args);
+
+    let Some(module) = compile_with_diagnostics(&context, file_path, &source, color) else {
+        std::process::exit(1);
+    };
+
+    match zen::jit::run_main(&module, &argv, inkwell::OptimizationLevel::None) {
+        Ok(code) => {
+            io::stdout().flush()?;
+            std::process::exit(code);
         }
         Err(e) => {
-            eprintln!("Compilation error: {}", e);
+            report_errors(file_path, &source, &[e], color);
             std::process::exit(1);
         }
     }
-    
+}
+
+fn run_build(args: &[String], color: ColorChoice) -> std::io::Result<()> {
+    let mut input = None;
+    let mut output = None;
+    let mut emit = EmitKind::Executable;
+    let mut opt_level = inkwell::OptimizationLevel::Default;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let emit_arg = if arg == "--emit" {
+            iter.next().map(String::as_str)
+        } else {
+            arg.strip_prefix("--emit=")
+        };
+
+        if let Some(kind) = emit_arg {
+            emit = kind.parse().unwrap_or_else(|e: CompileError| {
+                eprintln!("{}", e);
+                std::process::exit(2);
+            });
+        } else if arg == "-o" {
+            output = iter.next().map(std::path::PathBuf::from);
+        } else if let Some(level) = arg.strip_prefix("-O") {
+            opt_level = match level {
+                "0" => inkwell::OptimizationLevel::None,
+                "1" => inkwell::OptimizationLevel::Less,
+                "2" => inkwell::OptimizationLevel::Default,
+                "3" => inkwell::OptimizationLevel::Aggressive,
+                _ => {
+                    eprintln!("Unknown optimization level: {}", arg);
+                    std::process::exit(2);
+                }
+            };
+        } else if input.is_none() && !arg.starts_with('-') {
+            input = Some(std::path::PathBuf::from(arg));
+        } else {
+            eprintln!("Unexpected argument: {}", arg);
+            print_usage();
+            std::process::exit(2);
+        }
+    }
+
+    let Some(input) = input else {
+        eprintln!("zen build: missing input file");
+        print_usage();
+        std::process::exit(2);
+    };
+
+    let source = std::fs::read_to_string(&input)
+        .map_err(|e| io::Error::new(io::ErrorKind::NotFound, format!("Failed to read file: {}", e)))?;
+
+    let options = BuildOptions {
+        emit,
+        output: output.unwrap_or_else(|| emit.default_output(&input)),
+        opt_level,
+    };
+
+    let context = Context::create();
+
+    let input_name = input.display().to_string();
+    let Some(module) = compile_with_diagnostics(&context, &input_name, &source, color) else {
+        std::process::exit(1);
+    };
+
+    if let Err(e) = build::build_module(&module, &options) {
+        report_errors(&input_name, &source, &[e], color);
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
+
+fn run_check(args: &[String], color: ColorChoice) -> std::io::Result<()> {
+    let mut input = None;
+    let mut json = false;
+
+    for arg in args {
+        match arg.strip_prefix("--message-format=") {
+            Some("json") => json = true,
+            Some("human") => json = false,
+            Some(other) => {
+                eprintln!("Unknown message format: {} (expected human or json)", other);
+                std::process::exit(2);
+            }
+            None if input.is_none() && !arg.starts_with('-') => input = Some(arg.clone()),
+            None => {
+                eprintln!("Unexpected argument: {}", arg);
+                print_usage();
+                std::process::exit(2);
+            }
+        }
+    }
+
+    let Some(input) = input else {
+        eprintln!("zen check: missing input file");
+        print_usage();
+        std::process::exit(2);
+    };
+
+    let source = std::fs::read_to_string(&input)
+        .map_err(|e| io::Error::new(io::ErrorKind::NotFound, format!("Failed to read file: {}", e)))?;
+
+    let context = Context::create();
+    let (errors, warnings) = match parse_source(&source) {
+        // Stop after type checking; no code is generated
+        Ok((program, spans)) => match Compiler::new(&context).with_spans(spans).check(&program) {
+            Ok(checked) => (Vec::new(), checked.warnings),
+            Err(errors) => (errors, Vec::new()),
+        },
+        Err(errors) => (errors, Vec::new()),
+    };
+
+    if json {
+        let mut stdout = io::stdout().lock();
+        let diagnostics = errors.iter().map(Diagnostic::from).chain(warnings);
+        for diagnostic in diagnostics {
+            writeln!(stdout, "{}", FileDiagnostic::new(&input, &diagnostic).to_json())?;
+        }
+    } else if !errors.is_empty() {
+        report_errors(&input, &source, &errors, color);
+    } else if !warnings.is_empty() {
+        report_warnings(&input, &source, &warnings, color);
+    }
+
+    if !errors.is_empty() {
+        std::process::exit(1);
+    }
     Ok(())
 }
 
-fn execute_zen_code(compiler: &mut Compiler, source: &str) -> Result<Option<String>> {
-    // Parse the source
+fn run_demangle(symbols: &[String]) -> std::io::Result<()> {
+    // With no symbols, filter stdin like c++filt
+    if symbols.is_empty() {
+        for line in io::stdin().lock().lines() {
+            println!("{}", zen::mangle::demangle_text(&line?));
+        }
+        return Ok(());
+    }
+    for symbol in symbols {
+        match zen::mangle::demangle(symbol) {
+            Some(demangled) => println!("{}", demangled),
+            None => println!("{}", symbol),
+        }
+    }
+    Ok(())
+}
+
+fn run_fmt(args: &[String], color: ColorChoice) -> std::io::Result<()> {
+    let mut check = false;
+    let mut paths = Vec::new();
+    for arg in args {
+        match arg.as_str() {
+            "--check" => check = true,
+            "-" => paths.push(std::path::PathBuf::from("-")),
+            _ if arg.starts_with('-') => {
+                eprintln!("Unexpected argument: {}", arg);
+                print_usage();
+                std::process::exit(2);
+            }
+            _ => paths.push(std::path::PathBuf::from(arg)),
+        }
+    }
+
+    // With no paths, format stdin to stdout
+    if paths.is_empty() || paths.iter().any(|path| path.as_os_str() == "-") {
+        if paths.len() > 1 {
+            eprintln!("zen fmt: stdin can't be formatted together with files");
+            std::process::exit(2);
+        }
+        let mut source = String::new();
+        io::Read::read_to_string(&mut io::stdin(), &mut source)?;
+        match zen::formatter::format_source(&source) {
+            Ok(formatted) if check => {
+                if formatted != source {
+                    eprintln!("<stdin> is not formatted");
+                    std::process::exit(1);
+                }
+            }
+            Ok(formatted) => io::stdout().write_all(formatted.as_bytes())?,
+            Err(e) => {
+                report_errors("<stdin>", &source, &[e], color);
+                std::process::exit(1);
+            }
+        }
+        return Ok(());
+    }
+
+    let mut files = Vec::new();
+    for path in &paths {
+        collect_zen_files(path, &mut files)?;
+    }
+
+    let mut failed = false;
+    for file in files {
+        let name = file.display().to_string();
+        let source = std::fs::read_to_string(&file)
+            .map_err(|e| io::Error::new(io::ErrorKind::NotFound, format!("Failed to read {}: {}", name, e)))?;
+        match zen::formatter::format_source(&source) {
+            Ok(formatted) if formatted == source => {}
+            Ok(_) if check => {
+                eprintln!("{} is not formatted", name);
+                failed = true;
+            }
+            Ok(formatted) => std::fs::write(&file, formatted)?,
+            Err(e) => {
+                report_errors(&name, &source, &[e], color);
+                failed = true;
+            }
+        }
+    }
+
+    if failed {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// The `.zen` files at `path`: the file itself, or every one under a
+/// directory, in a stable order.
+fn collect_zen_files(path: &std::path::Path, files: &mut Vec<std::path::PathBuf>) -> std::io::Result<()> {
+    if !path.is_dir() {
+        files.push(path.to_path_buf());
+        return Ok(());
+    }
+    let mut entries: Vec<_> = std::fs::read_dir(path)?.map(|entry| entry.map(|e| e.path())).collect::<std::io::Result<_>>()?;
+    entries.sort();
+    for entry in entries {
+        if entry.is_dir() || entry.extension().is_some_and(|ext| ext == "zen") {
+            collect_zen_files(&entry, files)?;
+        }
+    }
+    Ok(())
+}
+
+/// Parse and compile a file, rendering every error found to stderr.
+fn compile_with_diagnostics<'ctx>(
+    context: &'ctx Context,
+    path: &str,
+    source: &str,
+    color: ColorChoice,
+) -> Option<inkwell::module::Module<'ctx>> {
+    let result = parse_source(source)
+        .and_then(|(program, spans)| {
+            let compiler = Compiler::new(context).with_spans(spans);
+            let checked = compiler.check(&program)?;
+            report_warnings(path, source, &checked.warnings, color);
+            compiler.generate_module(checked)
+        });
+
+    match result {
+        Ok(module) => Some(module),
+        Err(errors) => {
+            report_errors(path, source, &errors, color);
+            None
+        }
+    }
+}
+
+fn report_errors(path: &str, source: &str, errors: &[CompileError], color: ColorChoice) {
+    let renderer = DiagnosticRenderer::new(path, source, color);
+    let diagnostics: Vec<Diagnostic> = errors.iter().map(Diagnostic::from).collect();
+    eprint!("{}", renderer.render_all(&diagnostics));
+}
+
+fn report_warnings(path: &str, source: &str, warnings: &[Diagnostic], color: ColorChoice) {
+    if !warnings.is_empty() {
+        let renderer = DiagnosticRenderer::new(path, source, color);
+        eprint!("{}", renderer.render_all(warnings));
+    }
+}
+
+/// Remove a `--color=<when>` flag from anywhere in the arguments.
+fn take_color_flag(args: Vec<String>) -> (Vec<String>, ColorChoice) {
+    let mut color = ColorChoice::Auto;
+    let mut rest = Vec::with_capacity(args.len());
+    let mut passthrough = false;
+    for arg in args {
+        if arg == "--" {
+            passthrough = true;
+        }
+        match arg.strip_prefix("--color=") {
+            Some(when) if !passthrough => {
+                color = when.parse().unwrap_or_else(|e: String| {
+                    eprintln!("{}", e);
+                    std::process::exit(2);
+                });
+            }
+            _ => rest.push(arg),
+        }
+    }
+    (rest, color)
+}
+
+/// Parse a source file into the program and its source spans, collecting
+/// every syntax error rather than stopping at the first.
+fn parse_source(source: &str) -> std::result::Result<(zen::ast::Program, zen::spans::SpanTable), Vec<CompileError>> {
     let lexer = Lexer::new(source);
     let mut parser = Parser::new(lexer);
-    let program = parser.parse_program()
-        .map_err(|e| CompileError::InternalError(format!("Parse error: {}", e), None))?;
-    
-    if program.declarations.is_empty() {
-        return Ok(None);
+    let (program, errors) = parser.parse_program_recovering();
+    if !errors.is_empty() {
+        return Err(errors);
     }
-    
-    // Compile the program using LLVM backend
-    let llvm_ir = compiler.compile_llvm(&program)?;
-    
-    // Return just the LLVM IR
-    Ok(Some(llvm_ir))
+    Ok((program, parser.take_spans()))
 }
 
 fn print_repl_help() {
     println!("Available commands:");
-    println!("  help                    Show this help");
+    println!("  help, :help             Show this help");
     println!("  clear                   Clear the screen");
-    println!("  exit, quit              Exit the REPL");
+    println!("  :type <expr>            Show the type of an expression");
+    println!("  :ir                     Show LLVM IR for the current definitions");
+    println!("  :load <file.zen>        Load the declarations in a file");
+    println!("  :reset                  Forget all definitions and bindings");
+    println!("  exit, quit, :quit       Exit the REPL");
+    println!();
+    println!("Unclosed braces, parens or brackets continue the input on the next line.");
     println!();
     println!("Zen code examples:");
-    println!("  main = () i32 {{ 42 }}");
     println!("  add = (a: i32, b: i32) i32 {{ a + b }}");
-    println!("  x := 10; y := 20; x + y");
+    println!("  x := 10");
+    println!("  add(x, 20)");
     println!();
 } 
\ No newline at end of file
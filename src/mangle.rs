@@ -0,0 +1,455 @@
+//! Symbol names for generated code.
+//!
+//! Everything the compiler names on its own — instances of generic
+//! functions and types, methods, vtables and functions imported from
+//! modules — gets a mangled symbol, built from a structured `Symbol` and
+//! parsed back by `demangle`. Top-level functions and types without type
+//! arguments keep their source names, so `main` and functions called from
+//! C link as written. Mangled symbols start with `$Z`, and `$` can't
+//! appear in an identifier, so they never clash with those names or with
+//! each other:
+//!
+//! ```text
+//! symbol   := "$Z" module* item generics?
+//! module   := "M" ident                     a segment of the module path
+//! item     := "N" ident                     a function or type
+//!           | "F" type ident                an inherent method
+//!           | "B" type ident ident          a method implementing a behavior
+//!           | "V" type ident                the vtable of a type as a behavior
+//! generics := "I" type+ "E"
+//! ident    := <decimal length> <name>
+//! type     := "a" i8 | "s" i16 | "i" i32 | "l" i64
+//!           | "h" u8 | "t" u16 | "j" u32 | "m" u64
+//!           | "f" f32 | "d" f64 | "b" bool | "z" string | "v" void
+//!           | "P" type                      pointer
+//!           | "A" type                      array
+//!           | "X" <decimal size> "_" type   fixed-size array
+//!           | "R" type                      reference
+//!           | "O" type                      Option
+//!           | "Q" type type                 Result
+//!           | "G" ("0" | "1") type type     range, "1" when inclusive
+//!           | "K" type* "E" type            function
+//!           | "U" type* "E" type            function pointer
+//!           | "C" type* "E" type            closure
+//!           | "T" type* "E"                 tuple
+//!           | "D" ident                     dyn behavior
+//!           | "N" ident generics?           named type
+//!           | "W"                           uninferred type
+//! ```
+//!
+//! So `identity<i32>` is `$ZN8identityIiE`, `Point.Shape.area` is
+//! `$ZBN5Point5Shape4area` and `pair<Box<i32>, *u8>` is
+//! `$ZN4pairIN3BoxIiEPhE`. `Symbol`'s `Display` gives those readable forms.
+
+use crate::ast::AstType;
+use std::fmt;
+
+const PREFIX: &str = "$Z";
+
+/// A generated symbol: an item, the module path it was imported from and
+/// the type arguments it was instantiated with.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Symbol {
+    pub module: Vec<String>,
+    pub item: Item,
+    pub type_args: Vec<AstType>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Item {
+    /// A function or a type.
+    Name(String),
+    /// A method of `type_`, implementing `behavior` if it has one.
+    Method {
+        type_: AstType,
+        behavior: Option<String>,
+        name: String,
+    },
+    /// The vtable of `type_` as a `dyn behavior`.
+    Vtable { type_: AstType, behavior: String },
+}
+
+impl Symbol {
+    pub fn name(name: &str) -> Self {
+        Self::item(Item::Name(name.to_string()))
+    }
+
+    pub fn method(type_: AstType, behavior: Option<&str>, name: &str) -> Self {
+        Self::item(Item::Method {
+            type_,
+            behavior: behavior.map(str::to_string),
+            name: name.to_string(),
+        })
+    }
+
+    pub fn vtable(type_: AstType, behavior: &str) -> Self {
+        Self::item(Item::Vtable { type_, behavior: behavior.to_string() })
+    }
+
+    fn item(item: Item) -> Self {
+        Self { module: Vec::new(), item, type_args: Vec::new() }
+    }
+
+    /// The symbol inside module `path`, e.g. `std.io`.
+    pub fn in_module(mut self, path: &str) -> Self {
+        self.module = path.split('.').map(str::to_string).collect();
+        self
+    }
+
+    pub fn with_type_args(mut self, type_args: Vec<AstType>) -> Self {
+        self.type_args = type_args;
+        self
+    }
+
+    /// The name this symbol is compiled to.
+    pub fn mangle(&self) -> String {
+        if let Item::Name(name) = &self.item {
+            if self.module.is_empty() && self.type_args.is_empty() {
+                return name.clone();
+            }
+        }
+        let mut out = PREFIX.to_string();
+        for segment in &self.module {
+            out.push('M');
+            push_ident(&mut out, segment);
+        }
+        match &self.item {
+            Item::Name(name) => {
+                out.push('N');
+                push_ident(&mut out, name);
+            }
+            Item::Method { type_, behavior: None, name } => {
+                out.push('F');
+                push_type(&mut out, type_);
+                push_ident(&mut out, name);
+            }
+            Item::Method { type_, behavior: Some(behavior), name } => {
+                out.push('B');
+                push_type(&mut out, type_);
+                push_ident(&mut out, behavior);
+                push_ident(&mut out, name);
+            }
+            Item::Vtable { type_, behavior } => {
+                out.push('V');
+                push_type(&mut out, type_);
+                push_ident(&mut out, behavior);
+            }
+        }
+        push_generics(&mut out, &self.type_args);
+        out
+    }
+}
+
+impl fmt::Display for Symbol {
+    /// Formats the symbol using Zen surface syntax, e.g. `io.println` or
+    /// `Point.Shape.area`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for segment in &self.module {
+            write!(f, "{}.", segment)?;
+        }
+        match &self.item {
+            Item::Name(name) => write!(f, "{}", name)?,
+            Item::Method { type_, behavior: None, name } => write!(f, "{}.{}", type_, name)?,
+            Item::Method { type_, behavior: Some(behavior), name } => write!(f, "{}.{}.{}", type_, behavior, name)?,
+            Item::Vtable { type_, behavior } => write!(f, "vtable for {} as dyn {}", type_, behavior)?,
+        }
+        if !self.type_args.is_empty() {
+            let args: Vec<String> = self.type_args.iter().map(|arg| arg.to_string()).collect();
+            write!(f, "<{}>", args.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// The name of generic function or type `base` instantiated with
+/// `type_args`. `base` may itself be a mangled method symbol.
+pub fn instantiate(base: &str, type_args: &[AstType]) -> String {
+    if type_args.is_empty() {
+        return base.to_string();
+    }
+    demangle(base)
+        .unwrap_or_else(|| Symbol::name(base))
+        .with_type_args(type_args.to_vec())
+        .mangle()
+}
+
+/// The type a type name in an impl block or method symbol stands for:
+/// a primitive, or a named type.
+pub fn named_type(name: &str) -> AstType {
+    match name {
+        "i8" => AstType::I8,
+        "i16" => AstType::I16,
+        "i32" => AstType::I32,
+        "i64" => AstType::I64,
+        "u8" => AstType::U8,
+        "u16" => AstType::U16,
+        "u32" => AstType::U32,
+        "u64" => AstType::U64,
+        "f32" => AstType::F32,
+        "f64" => AstType::F64,
+        "bool" => AstType::Bool,
+        "string" => AstType::String,
+        "void" => AstType::Void,
+        _ => AstType::Generic { name: name.to_string(), type_args: vec![] },
+    }
+}
+
+/// Parse a mangled symbol. Names that aren't mangled give `None`.
+pub fn demangle(symbol: &str) -> Option<Symbol> {
+    let mut decoder = Decoder { input: symbol.strip_prefix(PREFIX)?.as_bytes(), pos: 0 };
+    let symbol = decoder.symbol()?;
+    (decoder.pos == decoder.input.len()).then_some(symbol)
+}
+
+/// `text` with every mangled symbol in it replaced by its readable form,
+/// for filtering linker output and backtraces.
+pub fn demangle_text(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(PREFIX) {
+        out.push_str(&rest[..start]);
+        let candidate = &rest[start..];
+        let end = candidate[PREFIX.len()..]
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .map_or(candidate.len(), |end| end + PREFIX.len());
+        // Take the longest prefix that parses, since a symbol may be
+        // followed directly by more word characters
+        match (PREFIX.len() + 1..=end).rev().find_map(|len| Some((len, demangle(&candidate[..len])?))) {
+            Some((len, symbol)) => {
+                out.push_str(&symbol.to_string());
+                rest = &candidate[len..];
+            }
+            None => {
+                out.push_str(PREFIX);
+                rest = &candidate[PREFIX.len()..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn push_ident(out: &mut String, ident: &str) {
+    out.push_str(&ident.len().to_string());
+    out.push_str(ident);
+}
+
+fn push_generics(out: &mut String, type_args: &[AstType]) {
+    if type_args.is_empty() {
+        return;
+    }
+    out.push('I');
+    for arg in type_args {
+        push_type(out, arg);
+    }
+    out.push('E');
+}
+
+fn push_types(out: &mut String, types: &[AstType]) {
+    for type_ in types {
+        push_type(out, type_);
+    }
+    out.push('E');
+}
+
+fn push_type(out: &mut String, type_: &AstType) {
+    match type_ {
+        AstType::I8 => out.push('a'),
+        AstType::I16 => out.push('s'),
+        AstType::I32 => out.push('i'),
+        AstType::I64 => out.push('l'),
+        AstType::U8 => out.push('h'),
+        AstType::U16 => out.push('t'),
+        AstType::U32 => out.push('j'),
+        AstType::U64 => out.push('m'),
+        AstType::F32 => out.push('f'),
+        AstType::F64 => out.push('d'),
+        AstType::Bool => out.push('b'),
+        AstType::String => out.push('z'),
+        AstType::Void => out.push('v'),
+        AstType::Pointer(inner) => {
+            out.push('P');
+            push_type(out, inner);
+        }
+        AstType::Array(inner) => {
+            out.push('A');
+            push_type(out, inner);
+        }
+        AstType::FixedArray { element_type, size } => {
+            out.push('X');
+            out.push_str(&size.to_string());
+            out.push('_');
+            push_type(out, element_type);
+        }
+        AstType::Ref(inner) => {
+            out.push('R');
+            push_type(out, inner);
+        }
+        AstType::Option(inner) => {
+            out.push('O');
+            push_type(out, inner);
+        }
+        AstType::Result { ok_type, err_type } => {
+            out.push('Q');
+            push_type(out, ok_type);
+            push_type(out, err_type);
+        }
+        AstType::Range { start_type, end_type, inclusive } => {
+            out.push('G');
+            out.push(if *inclusive { '1' } else { '0' });
+            push_type(out, start_type);
+            push_type(out, end_type);
+        }
+        AstType::Function { args, return_type } => {
+            out.push('K');
+            push_types(out, args);
+            push_type(out, return_type);
+        }
+        AstType::FunctionPointer { param_types, return_type } => {
+            out.push('U');
+            push_types(out, param_types);
+            push_type(out, return_type);
+        }
+        AstType::Closure { param_types, return_type } => {
+            out.push('C');
+            push_types(out, param_types);
+            push_type(out, return_type);
+        }
+        AstType::Tuple(elements) => {
+            out.push('T');
+            push_types(out, elements);
+        }
+        AstType::Dyn(behavior) => {
+            out.push('D');
+            push_ident(out, behavior);
+        }
+        AstType::Struct { name, .. } | AstType::Enum { name, .. } => {
+            out.push('N');
+            push_ident(out, name);
+        }
+        AstType::Generic { name, type_args } => {
+            out.push('N');
+            push_ident(out, name);
+            push_generics(out, type_args);
+        }
+        AstType::Var(_) => out.push('W'),
+    }
+}
+
+struct Decoder<'a> {
+    input: &'a [u8],
+    pos: usize,
+}
+
+impl Decoder<'_> {
+    fn next(&mut self) -> Option<u8> {
+        let byte = *self.input.get(self.pos)?;
+        self.pos += 1;
+        Some(byte)
+    }
+
+    fn eat(&mut self, byte: u8) -> bool {
+        let found = self.input.get(self.pos) == Some(&byte);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn symbol(&mut self) -> Option<Symbol> {
+        let mut module = Vec::new();
+        while self.eat(b'M') {
+            module.push(self.ident()?);
+        }
+        let item = match self.next()? {
+            b'N' => Item::Name(self.ident()?),
+            b'F' => Item::Method { type_: self.type_()?, behavior: None, name: self.ident()? },
+            b'B' => {
+                let type_ = self.type_()?;
+                Item::Method { type_, behavior: Some(self.ident()?), name: self.ident()? }
+            }
+            b'V' => Item::Vtable { type_: self.type_()?, behavior: self.ident()? },
+            _ => return None,
+        };
+        Some(Symbol { module, item, type_args: self.generics()? })
+    }
+
+    fn number(&mut self) -> Option<usize> {
+        let start = self.pos;
+        while self.input.get(self.pos).is_some_and(u8::is_ascii_digit) {
+            self.pos += 1;
+        }
+        std::str::from_utf8(&self.input[start..self.pos]).ok()?.parse().ok()
+    }
+
+    fn ident(&mut self) -> Option<String> {
+        let len = self.number()?;
+        let bytes = self.input.get(self.pos..self.pos.checked_add(len)?)?;
+        self.pos += len;
+        String::from_utf8(bytes.to_vec()).ok()
+    }
+
+    fn generics(&mut self) -> Option<Vec<AstType>> {
+        if !self.eat(b'I') {
+            return Some(Vec::new());
+        }
+        let types = self.types()?;
+        (!types.is_empty()).then_some(types)
+    }
+
+    /// Types up to and including an `E`.
+    fn types(&mut self) -> Option<Vec<AstType>> {
+        let mut types = Vec::new();
+        while !self.eat(b'E') {
+            types.push(self.type_()?);
+        }
+        Some(types)
+    }
+
+    fn type_(&mut self) -> Option<AstType> {
+        let boxed = |decoder: &mut Self| decoder.type_().map(Box::new);
+        Some(match self.next()? {
+            b'a' => AstType::I8,
+            b's' => AstType::I16,
+            b'i' => AstType::I32,
+            b'l' => AstType::I64,
+            b'h' => AstType::U8,
+            b't' => AstType::U16,
+            b'j' => AstType::U32,
+            b'm' => AstType::U64,
+            b'f' => AstType::F32,
+            b'd' => AstType::F64,
+            b'b' => AstType::Bool,
+            b'z' => AstType::String,
+            b'v' => AstType::Void,
+            b'P' => AstType::Pointer(boxed(self)?),
+            b'A' => AstType::Array(boxed(self)?),
+            b'X' => {
+                let size = self.number()?;
+                if !self.eat(b'_') {
+                    return None;
+                }
+                AstType::FixedArray { element_type: boxed(self)?, size }
+            }
+            b'R' => AstType::Ref(boxed(self)?),
+            b'O' => AstType::Option(boxed(self)?),
+            b'Q' => AstType::Result { ok_type: boxed(self)?, err_type: boxed(self)? },
+            b'G' => {
+                let inclusive = match self.next()? {
+                    b'0' => false,
+                    b'1' => true,
+                    _ => return None,
+                };
+                AstType::Range { start_type: boxed(self)?, end_type: boxed(self)?, inclusive }
+            }
+            b'K' => AstType::Function { args: self.types()?, return_type: boxed(self)? },
+            b'U' => AstType::FunctionPointer { param_types: self.types()?, return_type: boxed(self)? },
+            b'C' => AstType::Closure { param_types: self.types()?, return_type: boxed(self)? },
+            b'T' => AstType::Tuple(self.types()?),
+            b'D' => AstType::Dyn(self.ident()?),
+            b'N' => AstType::Generic { name: self.ident()?, type_args: self.generics()? },
+            b'W' => AstType::Var(0),
+            _ => return None,
+        })
+    }
+}
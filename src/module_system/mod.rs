@@ -107,21 +107,25 @@ impl ModuleSystem {
         &self.modules
     }
     
-    /// Merge all loaded modules into a single program
-    pub fn merge_programs(&self, main_program: Program) -> Program {
+    /// Merge all loaded modules into a single program, with the functions
+    /// they declare under their qualified names
+    pub fn merge_programs(&self, main_program: Program) -> Result<Program, CompileError> {
         let mut merged = main_program;
         
         // Add all declarations from imported modules
-        for (_path, module) in &self.modules {
-            for decl in &module.declarations {
+        for (path, module) in &self.modules {
+            let mut module = module.clone();
+            resolver::ModuleResolver::qualify_module(path, &mut module)
+                .map_err(|e| CompileError::InternalError(e, None))?;
+            for decl in module.declarations {
                 // Skip duplicate imports
                 if !matches!(decl, Declaration::ModuleImport { .. }) {
-                    merged.declarations.push(decl.clone());
+                    merged.declarations.push(decl);
                 }
             }
         }
         
-        merged
+        Ok(merged)
     }
 }
 
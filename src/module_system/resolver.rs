@@ -1,5 +1,6 @@
 use std::collections::{HashMap, HashSet};
 use crate::ast::{Program, Declaration, Function, Statement, Expression};
+use crate::mangle::Symbol;
 
 /// Resolves module imports and manages symbol visibility
 pub struct ModuleResolver {
@@ -7,6 +8,8 @@ pub struct ModuleResolver {
     imports: HashMap<String, String>,
     /// Exported symbols from each module
     exports: HashMap<String, HashSet<String>>,
+    /// Functions of the module being resolved -> their qualified names
+    locals: HashMap<String, String>,
 }
 
 impl ModuleResolver {
@@ -14,6 +17,7 @@ impl ModuleResolver {
         ModuleResolver {
             imports: HashMap::new(),
             exports: HashMap::new(),
+            locals: HashMap::new(),
         }
     }
     
@@ -74,6 +78,25 @@ impl ModuleResolver {
         exports
     }
     
+    /// The name function `symbol` of module `module_path` is compiled to.
+    pub fn qualified_name(module_path: &str, symbol: &str) -> String {
+        Symbol::name(symbol).in_module(module_path).mangle()
+    }
+
+    /// Give the functions module `module_path` declares their qualified
+    /// names, and point the calls between them at those names, so they
+    /// can't clash with the importing program's.
+    pub fn qualify_module(module_path: &str, program: &mut Program) -> Result<(), String> {
+        let mut resolver = ModuleResolver::new();
+        for decl in &mut program.declarations {
+            if let Declaration::Function(func) = decl {
+                let qualified = Self::qualified_name(module_path, &func.name);
+                resolver.locals.insert(std::mem::replace(&mut func.name, qualified.clone()), qualified);
+            }
+        }
+        resolver.resolve_program(program)
+    }
+    
     /// Rewrite a program to resolve module references
     pub fn resolve_program(&self, program: &mut Program) -> Result<(), String> {
         // Process each declaration
@@ -103,6 +126,7 @@ impl ModuleResolver {
                 }
                 Ok(())
             }
+            Statement::DestructuringDeclaration { initializer, .. } => self.resolve_expression(initializer),
             Statement::VariableAssignment { value, .. } => {
                 self.resolve_expression(value)
             }
@@ -119,6 +143,8 @@ impl ModuleResolver {
     
     /// Resolve module references in an expression
     fn resolve_expression(&self, expr: &mut Expression) -> Result<(), String> {
+        // `alias.function(...)` on an imported module is a qualified call
+        let module_call = expr.qualified_call_name().and_then(|name| self.resolve_qualified_name(&name));
         match expr {
             Expression::FunctionCall { name, args } => {
                 // Check if this is a qualified module call
@@ -127,7 +153,9 @@ impl ModuleResolver {
                         return Err(format!("Symbol '{}' is not exported from module '{}'", symbol, module_path));
                     }
                     // Rewrite to fully qualified name for codegen
-                    *name = format!("{}_{}", module_path.replace('.', "_"), symbol);
+                    *name = Self::qualified_name(&module_path, &symbol);
+                } else if let Some(qualified) = self.locals.get(name) {
+                    *name = qualified.clone();
                 }
                 
                 // Resolve arguments
@@ -136,11 +164,36 @@ impl ModuleResolver {
                 }
                 Ok(())
             }
+            Expression::MethodCall { object, args, .. } => {
+                if let Some((module_path, symbol)) = module_call {
+                    if !self.is_exported(&module_path, &symbol) {
+                        return Err(format!("Symbol '{}' is not exported from module '{}'", symbol, module_path));
+                    }
+                    *expr = Expression::FunctionCall {
+                        name: Self::qualified_name(&module_path, &symbol),
+                        args: std::mem::take(args),
+                    };
+                    return self.resolve_expression(expr);
+                }
+                self.resolve_expression(object)?;
+                for arg in args {
+                    self.resolve_expression(arg)?;
+                }
+                Ok(())
+            }
             Expression::BinaryOp { left, right, .. } => {
                 self.resolve_expression(left)?;
                 self.resolve_expression(right)?;
                 Ok(())
             }
+            Expression::UnaryOp { operand, .. } => self.resolve_expression(operand),
+            Expression::TupleIndex { tuple, .. } => self.resolve_expression(tuple),
+            Expression::TupleLiteral(elements) => {
+                for element in elements {
+                    self.resolve_expression(element)?;
+                }
+                Ok(())
+            }
             _ => Ok(())
         }
     }
@@ -174,4 +227,20 @@ mod tests {
         assert!(!resolver.is_exported("std.io", "private_func"));
         assert!(!resolver.is_exported("unknown.module", "anything"));
     }
+    
+    #[test]
+    fn test_qualify_module() {
+        let source = "double = (x: i32) i32 {\n    return x * 2\n}\n\nquadruple = (x: i32) i32 {\n    return double(double(x))\n}\n";
+        let mut program = crate::parser::Parser::new(crate::lexer::Lexer::new(source)).parse_program().unwrap();
+        ModuleResolver::qualify_module("math", &mut program).unwrap();
+        
+        let double = ModuleResolver::qualified_name("math", "double");
+        let names: Vec<_> = program.functions().map(|func| func.name.clone()).collect();
+        assert_eq!(names, vec![double.clone(), ModuleResolver::qualified_name("math", "quadruple")]);
+        let Statement::Return(Expression::FunctionCall { name, args }) = &program.functions().nth(1).unwrap().body[0] else {
+            panic!("expected the call");
+        };
+        assert_eq!(name, &double);
+        assert!(matches!(&args[0], Expression::FunctionCall { name, .. } if *name == double));
+    }
 }
\ No newline at end of file
@@ -1,7 +1,8 @@
-use crate::ast::{BehaviorDefinition, BehaviorMethod, ImplBlock, Parameter, TypeParameter};
+use crate::ast::{AssociatedConstant, AstType, BehaviorDefinition, BehaviorMethod, ImplBlock, Parameter, TypeParameter};
 use crate::lexer::{Keyword, Token};
 use crate::parser::core::Parser;
 use crate::error::{CompileError, Result};
+use crate::spans::{self, TokenRole};
 
 impl<'a> Parser<'a> {
     pub fn parse_behavior(&mut self) -> Result<BehaviorDefinition> {
@@ -50,12 +51,22 @@ impl<'a> Parser<'a> {
         }
         self.next_token();
         
-        // Parse behavior methods
+        // Parse behavior methods, associated types and constants
         let mut methods = Vec::new();
+        let mut associated_types = Vec::new();
+        let mut constants = Vec::new();
         
         while self.current_token != Token::Symbol('}') && self.current_token != Token::Eof {
-            let method = self.parse_behavior_method()?;
-            methods.push(method);
+            if self.peek_token == Token::Symbol(':') {
+                match self.parse_associated_item(false)? {
+                    AssociatedItem::Type(item) => associated_types.push(item),
+                    AssociatedItem::TypeDefinition(..) => unreachable!("behaviors don't define associated types"),
+                    AssociatedItem::Constant(constant) => constants.push(constant),
+                }
+            } else {
+                let method = self.parse_behavior_method(&name)?;
+                methods.push(method);
+            }
             
             // Handle comma separator
             if self.current_token == Token::Symbol(',') {
@@ -76,10 +87,13 @@ impl<'a> Parser<'a> {
             name,
             type_params,
             methods,
+            associated_types,
+            constants,
         })
     }
     
-    fn parse_behavior_method(&mut self) -> Result<BehaviorMethod> {
+    /// A method signature, optionally followed by a default body.
+    fn parse_behavior_method(&mut self, behavior: &str) -> Result<BehaviorMethod> {
         // Parse method name
         let name = if let Token::Identifier(n) = &self.current_token {
             n.clone()
@@ -136,16 +150,92 @@ impl<'a> Parser<'a> {
         
         // Parse return type
         let return_type = self.parse_type()?;
+
+        // Parse the default body
+        let body = if self.current_token == Token::Symbol('{') {
+            self.next_token();
+            let body = self.parse_function_body(spans::method_owner(behavior, &name))?;
+            if self.current_token != Token::Symbol('}') {
+                return Err(CompileError::SyntaxError(
+                    "Expected '}' to close method body".to_string(),
+                    Some(self.current_span.clone()),
+                ));
+            }
+            self.next_token();
+            Some(body)
+        } else {
+            None
+        };
         
         Ok(BehaviorMethod {
             name,
             params,
             return_type,
+            body,
         })
     }
+
+    /// `Item: type` or `LIMIT: i32 = 10` in a behavior, where the value is
+    /// optional, and `Item: type = i32` or `LIMIT: i32 = 10` in an
+    /// implementation of one, where it is required.
+    fn parse_associated_item(&mut self, in_impl: bool) -> Result<AssociatedItem> {
+        let Token::Identifier(name) = &self.current_token else {
+            return Err(CompileError::SyntaxError(
+                format!("Expected associated item name, got {:?}", self.current_token),
+                Some(self.current_span.clone()),
+            ));
+        };
+        let name = name.clone();
+        self.next_token(); // consume name
+        self.next_token(); // consume ':'
+
+        if self.current_token == Token::Keyword(Keyword::Type) {
+            self.next_token();
+            if !in_impl {
+                return Ok(AssociatedItem::Type(name));
+            }
+            if self.current_token != Token::Operator("=".to_string()) {
+                return Err(CompileError::SyntaxError(
+                    format!("Expected '=' and the type '{}' stands for, got {:?}", name, self.current_token),
+                    Some(self.current_span.clone()),
+                ));
+            }
+            self.next_token();
+            let type_ = self.parse_type()?;
+            return Ok(AssociatedItem::TypeDefinition(name, type_));
+        }
+
+        let type_ = self.parse_type()?;
+        let value = if self.current_token == Token::Operator("=".to_string()) {
+            self.next_token();
+            Some(self.parse_expression()?)
+        } else if in_impl {
+            return Err(CompileError::SyntaxError(
+                format!("Expected '=' and the value of constant '{}', got {:?}", name, self.current_token),
+                Some(self.current_span.clone()),
+            ));
+        } else {
+            None
+        };
+        Ok(AssociatedItem::Constant(AssociatedConstant { name, type_, value }))
+    }
     
-    pub fn parse_impl_block_from_type(&mut self, type_name: String) -> Result<ImplBlock> {
-        // 'impl' keyword already consumed, expect '='
+    /// An impl block for `type_name<type_args>`, after `.impl`.
+    pub fn parse_impl_block_from_type(&mut self, type_name: String, type_args: Vec<AstType>) -> Result<ImplBlock> {
+        let previous_impl = self.current_impl.replace(type_name.clone());
+        let result = self.parse_impl_block_body(type_name, type_args);
+        self.current_impl = previous_impl;
+        result
+    }
+
+    fn parse_impl_block_body(&mut self, type_name: String, type_args: Vec<AstType>) -> Result<ImplBlock> {
+        // 'impl' keyword already consumed; parse optional type parameters
+        let type_params = if self.current_token == Token::Operator("<".to_string()) {
+            self.parse_type_parameters()?
+        } else {
+            Vec::new()
+        };
+
         if self.current_token != Token::Operator("=".to_string()) {
             return Err(CompileError::SyntaxError(
                 format!("Expected '=' after 'impl', got {:?}", self.current_token),
@@ -154,9 +244,6 @@ impl<'a> Parser<'a> {
         }
         self.next_token();
         
-        // Parse optional type parameters  
-        let type_params = Vec::new(); // TODO: support generics on impl blocks
-        
         // Expect '{'
         if self.current_token != Token::Symbol('{') {
             return Err(CompileError::SyntaxError(
@@ -168,15 +255,13 @@ impl<'a> Parser<'a> {
         
         let mut behavior_name = None;
         let mut methods = Vec::new();
+        let mut associated_types = Vec::new();
+        let mut constants = Vec::new();
         
         // Check if this is a behavior implementation
         if let Token::Identifier(name) = &self.current_token {
             let saved_name = name.clone();
-            let saved_position = self.lexer.position;
-            let saved_read_position = self.lexer.read_position;
-            let saved_current_char = self.lexer.current_char;
-            let saved_current_token = self.current_token.clone();
-            let saved_peek_token = self.peek_token.clone();
+            let checkpoint = self.checkpoint();
             
             self.next_token();
             
@@ -194,8 +279,19 @@ impl<'a> Parser<'a> {
                 }
                 self.next_token();
                 
-                // Parse methods for this behavior
+                // Parse methods, associated types and constants for this behavior
                 while self.current_token != Token::Symbol('}') && self.current_token != Token::Eof {
+                    if self.peek_token == Token::Symbol(':') {
+                        match self.parse_associated_item(true)? {
+                            AssociatedItem::TypeDefinition(item, type_) => associated_types.push((item, type_)),
+                            AssociatedItem::Type(_) => unreachable!("implementations define their associated types"),
+                            AssociatedItem::Constant(constant) => constants.push(constant),
+                        }
+                        if self.current_token == Token::Symbol(',') {
+                            self.next_token();
+                        }
+                        continue;
+                    }
                     let method = self.parse_impl_function()?;
                     methods.push(method);
                     
@@ -215,11 +311,7 @@ impl<'a> Parser<'a> {
                 self.next_token();
             } else {
                 // This was actually a method, rewind
-                self.lexer.position = saved_position;
-                self.lexer.read_position = saved_read_position;
-                self.lexer.current_char = saved_current_char;
-                self.current_token = saved_current_token;
-                self.peek_token = saved_peek_token;
+                self.restore(checkpoint);
                 
                 // Parse inherent methods
                 while self.current_token != Token::Symbol('}') && self.current_token != Token::Eof {
@@ -258,7 +350,10 @@ impl<'a> Parser<'a> {
             type_name,
             behavior_name,
             type_params,
+            type_args,
             methods,
+            associated_types,
+            constants,
         })
     }
     
@@ -307,6 +402,7 @@ impl<'a> Parser<'a> {
                 Some(self.current_span.clone()),
             ));
         }
+        self.mark_token(TokenRole::TypeArguments);
         self.next_token();
         
         let mut type_params = Vec::new();
@@ -322,8 +418,7 @@ impl<'a> Parser<'a> {
             };
             self.next_token();
             
-            // TODO: Parse constraints (: BehaviorName)
-            let constraints = Vec::new();
+            let constraints = self.parse_type_parameter_bounds()?;
             
             type_params.push(TypeParameter { name, constraints });
             
@@ -339,11 +434,41 @@ impl<'a> Parser<'a> {
                 Some(self.current_span.clone()),
             ));
         }
+        self.mark_token(TokenRole::TypeArguments);
         self.next_token();
         
         Ok(type_params)
     }
     
+    /// The behaviors a type parameter is bounded by, written after its
+    /// name: `T: Ord` or `T: Ord + Display`. No bounds is an empty list.
+    pub(crate) fn parse_type_parameter_bounds(&mut self) -> Result<Vec<String>> {
+        let mut bounds = Vec::new();
+        if self.current_token != Token::Symbol(':') {
+            return Ok(bounds);
+        }
+        
+        loop {
+            self.next_token(); // consume ':' or '+'
+            
+            if let Token::Identifier(behavior) = &self.current_token {
+                bounds.push(behavior.clone());
+            } else {
+                return Err(CompileError::SyntaxError(
+                    format!("Expected behavior name in type parameter bound, got {:?}", self.current_token),
+                    Some(self.current_span.clone()),
+                ));
+            }
+            self.next_token();
+            
+            if self.current_token != Token::Operator("+".to_string()) {
+                break;
+            }
+        }
+        
+        Ok(bounds)
+    }
+    
     /// Parse a function within an impl block context
     /// This is different from parse_function() because the function name and '=' have already been consumed
     pub fn parse_impl_function(&mut self) -> Result<crate::ast::Function> {
@@ -363,16 +488,17 @@ impl<'a> Parser<'a> {
         // Parse generic type parameters if present: <T, U, ...>
         let mut type_params = Vec::new();
         if self.current_token == Token::Operator("<".to_string()) {
+            self.mark_token(TokenRole::TypeArguments);
             self.next_token();
             loop {
                 if let Token::Identifier(gen) = &self.current_token {
-                    type_params.push(TypeParameter {
-                        name: gen.clone(),
-                        constraints: Vec::new(),
-                    });
+                    let name = gen.clone();
                     self.next_token();
+                    let constraints = self.parse_type_parameter_bounds()?;
+                    type_params.push(TypeParameter { name, constraints });
                     
                     if self.current_token == Token::Operator(">".to_string()) {
+                        self.mark_token(TokenRole::TypeArguments);
                         self.next_token();
                         break;
                     } else if self.current_token == Token::Symbol(',') {
@@ -468,10 +594,11 @@ impl<'a> Parser<'a> {
         }
         self.next_token();
         
-        let mut body = vec![];
-        while self.current_token != Token::Symbol('}') && self.current_token != Token::Eof {
-            body.push(self.parse_statement()?);
-        }
+        let owner = match &self.current_impl {
+            Some(type_name) => spans::method_owner(type_name, &name),
+            None => name.clone(),
+        };
+        let body = self.parse_function_body(owner)?;
         
         if self.current_token != Token::Symbol('}') {
             return Err(CompileError::SyntaxError(
@@ -490,4 +617,13 @@ impl<'a> Parser<'a> {
             is_async: false, // TODO: Support async functions
         })
     }
-}
\ No newline at end of file
+}
+
+/// An item of a behavior or implementation other than a method.
+enum AssociatedItem {
+    /// `Item: type`, declared by a behavior
+    Type(String),
+    /// `Item: type = i32`, given by an implementation
+    TypeDefinition(String, AstType),
+    Constant(AssociatedConstant),
+}
@@ -21,7 +21,7 @@ impl<'a> Parser<'a> {
         let mut statements = Vec::new();
         
         while self.current_token != Token::Symbol('}') && self.current_token != Token::Eof {
-            let stmt = self.parse_statement()?;
+            let stmt = self.parse_body_statement(statements.len());
             statements.push(stmt);
             
             // Skip optional semicolons
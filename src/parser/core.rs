@@ -1,5 +1,8 @@
 use super::super::lexer::{Lexer, Token};
-use crate::error::Span;
+use crate::ast::Statement;
+use crate::error::{CompileError, Result, Span};
+use crate::spans::{self, Node, NodeId, SpanCursor, SpanTable, TokenRole};
+use std::collections::BTreeMap;
 
 pub struct Parser<'a> {
     pub(crate) lexer: Lexer<'a>,
@@ -7,27 +10,101 @@ pub struct Parser<'a> {
     pub(crate) peek_token: Token,
     pub(crate) current_span: Span,
     pub(crate) peek_span: Span,
+    // Span of the last consumed token, used to close node spans
+    pub(crate) previous_span: Span,
+    pub(crate) spans: SpanTable,
+    pub(crate) span_cursor: SpanCursor,
+    // Type whose impl block is being parsed, for method span owners
+    pub(crate) current_impl: Option<String>,
+    // Brackets opened by the tokens consumed so far and not yet closed
+    pub(crate) open_brackets: Vec<char>,
+    // Syntax errors recovered from so far
+    pub(crate) errors: Vec<CompileError>,
+    // Bracket depth of the match arm being parsed, where `|` ends the arm
+    pub(crate) match_arm_depth: Option<usize>,
+    // Spans of the block expression statements parsed in the current body
+    // statement, by start offset, until the statement is numbered
+    pub(crate) block_statements: BTreeMap<usize, Span>,
+}
+
+/// Lexer and lookahead state saved before speculative parsing.
+pub(crate) struct Checkpoint {
+    position: usize,
+    read_position: usize,
+    current_char: Option<char>,
+    line: usize,
+    column: usize,
+    utf16_column: usize,
+    current_token: Token,
+    peek_token: Token,
+    current_span: Span,
+    peek_span: Span,
+    previous_span: Span,
+    open_brackets: Vec<char>,
 }
 
 impl<'a> Parser<'a> {
     pub fn new(mut lexer: Lexer<'a>) -> Self {
         let current_token_with_span = lexer.next_token_with_span();
         let peek_token_with_span = lexer.next_token_with_span();
-        Parser {
+        let mut parser = Parser {
             lexer,
             current_token: current_token_with_span.token,
             peek_token: peek_token_with_span.token,
+            previous_span: Span { start: 0, end: 0, line: 1, column: 1, utf16_column: 1 },
             current_span: current_token_with_span.span,
             peek_span: peek_token_with_span.span,
-        }
+            spans: SpanTable::new(),
+            span_cursor: SpanCursor::new(),
+            current_impl: None,
+            open_brackets: Vec::new(),
+            errors: Vec::new(),
+            match_arm_depth: None,
+            block_statements: BTreeMap::new(),
+        };
+        parser.record_identifier();
+        parser
     }
 
     pub fn next_token(&mut self) {
+        match self.current_token {
+            Token::Symbol(open @ ('{' | '(' | '[')) => self.open_brackets.push(open),
+            Token::Symbol(close @ ('}' | ')' | ']')) => {
+                // A stray closing bracket doesn't close anything
+                let open = match close {
+                    '}' => '{',
+                    ')' => '(',
+                    _ => '[',
+                };
+                if self.open_brackets.last() == Some(&open) {
+                    self.open_brackets.pop();
+                }
+            }
+            _ => {}
+        }
         let token_with_span = self.lexer.next_token_with_span();
+        self.previous_span = std::mem::replace(&mut self.current_span, self.peek_span.clone());
         self.current_token = self.peek_token.clone();
         self.peek_token = token_with_span.token;
-        self.current_span = self.peek_span.clone();
         self.peek_span = token_with_span.span;
+        self.record_identifier();
+    }
+
+    /// Source spans of the declarations, statements and identifiers parsed
+    /// so far.
+    pub fn spans(&self) -> &SpanTable {
+        &self.spans
+    }
+
+    pub fn take_spans(&mut self) -> SpanTable {
+        std::mem::take(&mut self.spans)
+    }
+
+    /// The syntax errors recovered from so far, in source order. Statements
+    /// in a body that fail to parse become `Statement::Error` rather than
+    /// failing the whole body, so callers of `parse_statement` check here.
+    pub fn take_errors(&mut self) -> Vec<CompileError> {
+        std::mem::take(&mut self.errors)
     }
 
     pub fn debug_current_token(&self) -> &Token {
@@ -37,4 +114,182 @@ impl<'a> Parser<'a> {
     pub fn debug_peek_token(&self) -> &Token {
         &self.peek_token
     }
+
+    pub(crate) fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            position: self.lexer.position,
+            read_position: self.lexer.read_position,
+            current_char: self.lexer.current_char,
+            line: self.lexer.line,
+            column: self.lexer.column,
+            utf16_column: self.lexer.utf16_column,
+            current_token: self.current_token.clone(),
+            peek_token: self.peek_token.clone(),
+            current_span: self.current_span.clone(),
+            peek_span: self.peek_span.clone(),
+            previous_span: self.previous_span.clone(),
+            open_brackets: self.open_brackets.clone(),
+        }
+    }
+
+    pub(crate) fn restore(&mut self, checkpoint: Checkpoint) {
+        self.lexer.position = checkpoint.position;
+        self.lexer.read_position = checkpoint.read_position;
+        self.lexer.current_char = checkpoint.current_char;
+        self.lexer.line = checkpoint.line;
+        self.lexer.column = checkpoint.column;
+        self.lexer.utf16_column = checkpoint.utf16_column;
+        self.current_token = checkpoint.current_token;
+        self.peek_token = checkpoint.peek_token;
+        self.current_span = checkpoint.current_span;
+        self.peek_span = checkpoint.peek_span;
+        self.previous_span = checkpoint.previous_span;
+        self.open_brackets = checkpoint.open_brackets;
+        self.spans.forget_token_roles_from(self.current_span.start);
+        self.block_statements.split_off(&self.current_span.start);
+    }
+
+    /// Record how the current token is read, for tools such as the
+    /// formatter that can't tell from the tokens alone.
+    pub(crate) fn mark_token(&mut self, role: TokenRole) {
+        self.spans.record_token_role(self.current_span.start, role);
+    }
+
+    /// The span from `start` to the end of the last consumed token.
+    pub(crate) fn span_from(&self, start: &Span) -> Span {
+        Span {
+            end: self.previous_span.end.max(start.start),
+            ..start.clone()
+        }
+    }
+
+    /// Parse statement `index` of a function, loop or comptime body and
+    /// record its span. A statement that fails to parse becomes
+    /// `Statement::Error`, with the error kept for `parse_program_recovering`.
+    pub(crate) fn parse_body_statement(&mut self, index: usize) -> Statement {
+        let start = self.current_span.clone();
+        let depth = self.open_brackets.len();
+        self.span_cursor.push(index);
+        let statement = self.parse_statement().unwrap_or_else(|error| {
+            self.errors.push(error);
+            self.synchronize_statement(&start, depth);
+            Statement::Error(self.span_from(&start))
+        });
+        let span = self.span_from(&start);
+        match self.span_cursor.current() {
+            Some(id) => self.spans.record_statement(id, span),
+            None => {
+                self.block_statements.insert(start.start, span);
+            }
+        }
+        self.span_cursor.pop();
+        statement
+    }
+
+    /// Record the spans of the block expression statements nested in
+    /// `statement`, statement `index` of the body starting at offset
+    /// `start`, now that their paths are known. Source order is the order
+    /// their paths are visited in.
+    fn record_block_statements(&mut self, index: usize, start: usize, statement: &Statement) {
+        let recorded = self.block_statements.split_off(&start);
+        self.block_statements.clear();
+        let Some(owner) = self.span_cursor.owner() else {
+            return;
+        };
+        let mut paths = vec![];
+        spans::visit_statement_children(statement, &mut vec![index], &mut |node, path| {
+            if let Node::BlockStatement(_) = node {
+                paths.push(path.to_vec());
+            }
+        });
+        // A statement that failed to parse leaves spans nothing matches
+        if paths.len() != recorded.len() {
+            return;
+        }
+        let owner = owner.to_string();
+        for (path, span) in paths.into_iter().zip(recorded.into_values()) {
+            self.spans.record_statement(NodeId::new(owner.clone(), path), span);
+        }
+    }
+
+    /// Skip the rest of a statement that failed to parse, stopping at the
+    /// next line, a `;` or the `}` closing the body, back at the body's
+    /// bracket depth.
+    fn synchronize_statement(&mut self, start: &Span, depth: usize) {
+        loop {
+            if self.current_token == Token::Eof || self.open_brackets.len() < depth {
+                return;
+            }
+            if self.open_brackets.len() == depth {
+                match self.current_token {
+                    Token::Symbol('}') => return,
+                    Token::Symbol(';') => {
+                        self.next_token();
+                        return;
+                    }
+                    _ if self.current_span.line > start.line && self.starts_line() => return,
+                    _ => {}
+                }
+            }
+            self.next_token();
+        }
+    }
+
+    /// Skip the rest of a declaration that failed to parse, up to the next
+    /// top-level line that starts a declaration.
+    pub(crate) fn synchronize_declaration(&mut self, start: &Span) {
+        loop {
+            if self.current_token == Token::Eof {
+                return;
+            }
+            let moved = self.current_span.start > start.start;
+            if moved && self.open_brackets.is_empty() && self.starts_line() && self.starts_declaration() {
+                return;
+            }
+            self.next_token();
+        }
+    }
+
+    /// Whether the current token is the first on its line.
+    pub(crate) fn starts_line(&self) -> bool {
+        self.current_span.line > self.previous_span.line
+    }
+
+    /// Whether the current tokens look like the start of a top-level
+    /// declaration: `name =`, `name<T>`, `name ::`, `Type.impl` or a
+    /// declaration keyword.
+    fn starts_declaration(&self) -> bool {
+        match &self.current_token {
+            Token::Identifier(_) => match &self.peek_token {
+                Token::Operator(op) => matches!(op.as_str(), "=" | "<" | "::"),
+                Token::Symbol('.') => true,
+                _ => false,
+            },
+            Token::Keyword(keyword) => matches!(
+                keyword,
+                crate::lexer::Keyword::Type | crate::lexer::Keyword::Comptime | crate::lexer::Keyword::Extern
+            ),
+            _ => false,
+        }
+    }
+
+    /// Parse the statements of `owner`'s body up to its closing brace.
+    pub(crate) fn parse_function_body(&mut self, owner: String) -> Result<Vec<Statement>> {
+        self.span_cursor.enter_owner(owner);
+        let mut body = vec![];
+        while self.current_token != Token::Symbol('}') && self.current_token != Token::Eof {
+            let start = self.current_span.start;
+            let statement = self.parse_body_statement(body.len());
+            self.record_block_statements(body.len(), start, &statement);
+            body.push(statement);
+        }
+        self.span_cursor.leave_owner();
+        Ok(body)
+    }
+
+    fn record_identifier(&mut self) {
+        if let Token::Identifier(name) = &self.current_token {
+            self.spans.record_identifier(name, self.current_span.clone());
+        }
+    }
 }
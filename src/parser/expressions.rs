@@ -1,7 +1,8 @@
 use super::core::Parser;
-use crate::ast::{Expression, BinaryOperator, Pattern};
+use crate::ast::{AstType, Expression, BinaryOperator, Pattern, StringPart, UnaryOperator};
 use crate::error::{CompileError, Result};
-use crate::lexer::Token;
+use crate::lexer::{Lexer, NumberSuffix, StringSegment, Token};
+use crate::spans::TokenRole;
 
 impl<'a> Parser<'a> {
     pub fn parse_expression(&mut self) -> Result<Expression> {
@@ -21,11 +22,15 @@ impl<'a> Parser<'a> {
                     expr: Box::new(left),
                     target_type,
                 };
-            } else if let Token::Operator(op) = &self.current_token {
-                let op_clone = op.clone();
+            } else if let Some((op_clone, tokens)) = self.binary_operator() {
                 let next_prec = self.get_precedence(&op_clone);
                 if next_prec > precedence {
-                    self.next_token(); // advance past the operator
+                    if tokens == 2 {
+                        self.mark_token(TokenRole::ShiftRight);
+                    }
+                    for _ in 0..tokens {
+                        self.next_token(); // advance past the operator
+                    }
                     
                     // Handle range expressions specially
                     if op_clone == ".." || op_clone == "..=" {
@@ -53,29 +58,126 @@ impl<'a> Parser<'a> {
         Ok(left)
     }
 
-    fn parse_unary_expression(&mut self) -> Result<Expression> {
+    /// The binary operator at the current token, and how many tokens it
+    /// spans. `>>` is lexed as two `>` so it's only a shift when they touch,
+    /// and a `|` directly in a match arm starts the next arm.
+    fn binary_operator(&self) -> Option<(String, usize)> {
         match &self.current_token {
-            Token::Operator(op) if op == "-" => {
-                self.next_token();
-                let expr = self.parse_unary_expression()?;
-                // For now, represent unary minus as BinaryOp with 0 - expr
-                Ok(Expression::BinaryOp {
-                    left: Box::new(Expression::Integer32(0)),
-                    op: BinaryOperator::Subtract,
-                    right: Box::new(expr),
-                })
+            Token::Operator(op) if op == ">" && self.current_span.end == self.peek_span.start => match &self.peek_token {
+                Token::Operator(next) if next == ">" => Some((">>".to_string(), 2)),
+                // `>>=` is an assignment
+                Token::Operator(next) if next == ">=" => None,
+                _ => Some((op.clone(), 1)),
+            },
+            Token::Operator(op) => Some((op.clone(), 1)),
+            Token::Symbol('&') => Some(("&".to_string(), 1)),
+            Token::Symbol('|') if self.match_arm_depth != Some(self.open_brackets.len()) => Some(("|".to_string(), 1)),
+            _ => None,
+        }
+    }
+
+    /// The operator of a compound assignment such as `+=` at the current
+    /// token, and how many tokens it spans.
+    pub(crate) fn compound_assignment(&self) -> Option<(BinaryOperator, usize)> {
+        let Token::Operator(op) = &self.current_token else {
+            return None;
+        };
+        let op = match op.as_str() {
+            "+=" => BinaryOperator::Add,
+            "-=" => BinaryOperator::Subtract,
+            "*=" => BinaryOperator::Multiply,
+            "/=" => BinaryOperator::Divide,
+            "%=" => BinaryOperator::Modulo,
+            "&=" => BinaryOperator::BitAnd,
+            "|=" => BinaryOperator::BitOr,
+            "^=" => BinaryOperator::BitXor,
+            "<<=" => BinaryOperator::ShiftLeft,
+            ">" if self.current_span.end == self.peek_span.start && self.peek_token == Token::Operator(">=".to_string()) => {
+                return Some((BinaryOperator::ShiftRight, 2));
             }
-            Token::Operator(op) if op == "!" => {
-                self.next_token();
-                let expr = self.parse_unary_expression()?;
-                // For now, represent logical not as a function call
-                Ok(Expression::FunctionCall {
-                    name: "not".to_string(),
-                    args: vec![expr],
-                })
+            _ => return None,
+        };
+        Some((op, 1))
+    }
+
+    fn parse_unary_expression(&mut self) -> Result<Expression> {
+        let op = match &self.current_token {
+            Token::Operator(op) if op == "-" => UnaryOperator::Negate,
+            Token::Operator(op) if op == "~" => UnaryOperator::BitNot,
+            Token::Symbol('!') => UnaryOperator::Not,
+            _ => return self.parse_postfix_expression(),
+        };
+        self.mark_token(TokenRole::Prefix);
+        self.next_token();
+        // A suffixed literal takes its sign with it, so `-128i8` is in
+        // range and `-1i8` stays an i8. A method called on the literal sees
+        // it unsigned, and the sign applies to the result: `-1i32.abs()`
+        if op == UnaryOperator::Negate && self.peek_token != Token::Symbol('.') {
+            match &self.current_token {
+                Token::SuffixedNumber(value, suffix) => {
+                    let literal = self.suffixed_literal(value, *suffix, true)?;
+                    self.next_token();
+                    return Ok(literal);
+                }
+                // Only the magnitude of `i64::MIN` is out of range unsigned
+                Token::Integer(digits) if digits.parse::<i64>().is_err() => {
+                    if let Ok(value) = format!("-{}", digits).parse::<i64>() {
+                        self.next_token();
+                        return Ok(Expression::Integer64(value));
+                    }
+                }
+                _ => {}
             }
-            _ => self.parse_postfix_expression(),
         }
+        let operand = self.parse_unary_expression()?;
+        Ok(Expression::UnaryOp {
+            op,
+            operand: Box::new(operand),
+        })
+    }
+
+    /// The literal for a suffixed number token, checked against the range
+    /// of its type.
+    fn suffixed_literal(&self, value: &str, suffix: NumberSuffix, negative: bool) -> Result<Expression> {
+        let span = Some(self.current_span.clone());
+        let sign = if negative { "-" } else { "" };
+        let out_of_range = || {
+            CompileError::TypeError(
+                format!("Literal {}{}{} is out of range for {}", sign, value, suffix, suffix),
+                span.clone(),
+            )
+        };
+
+        if suffix.is_float() {
+            let magnitude = value.parse::<f64>().map_err(|_| {
+                CompileError::SyntaxError(format!("Invalid float: {}", value), span.clone())
+            })?;
+            let value = if negative { -magnitude } else { magnitude };
+            return match suffix {
+                NumberSuffix::F32 if (value as f32).is_finite() => Ok(Expression::Float32(value as f32)),
+                NumberSuffix::F64 if value.is_finite() => Ok(Expression::Float64(value)),
+                _ => Err(out_of_range()),
+            };
+        }
+
+        let magnitude = value
+            .parse::<u128>()
+            .ok()
+            .and_then(|magnitude| i128::try_from(magnitude).ok())
+            .ok_or_else(out_of_range)?;
+        let value = if negative { -magnitude } else { magnitude };
+        let literal = match suffix {
+            NumberSuffix::I8 => i8::try_from(value).map(Expression::Integer8).ok(),
+            NumberSuffix::I16 => i16::try_from(value).map(Expression::Integer16).ok(),
+            NumberSuffix::I32 => i32::try_from(value).map(Expression::Integer32).ok(),
+            NumberSuffix::I64 => i64::try_from(value).map(Expression::Integer64).ok(),
+            NumberSuffix::U8 => u8::try_from(value).map(Expression::Unsigned8).ok(),
+            NumberSuffix::U16 => u16::try_from(value).map(Expression::Unsigned16).ok(),
+            NumberSuffix::U32 => u32::try_from(value).map(Expression::Unsigned32).ok(),
+            NumberSuffix::U64 => u64::try_from(value).map(Expression::Unsigned64).ok(),
+            NumberSuffix::F32 | NumberSuffix::F64 => unreachable!("float suffixes are handled above"),
+        };
+        literal.ok_or_else(out_of_range)
     }
 
     fn parse_postfix_expression(&mut self) -> Result<Expression> {
@@ -91,6 +193,9 @@ impl<'a> Parser<'a> {
     }
 
     fn parse_primary_expression(&mut self) -> Result<Expression> {
+        if self.current_token == Token::Symbol('(') && self.looks_like_closure() {
+            return self.parse_closure();
+        }
         match &self.current_token {
             Token::Keyword(crate::lexer::Keyword::Return) => {
                 self.next_token(); // consume 'return'
@@ -104,18 +209,26 @@ impl<'a> Parser<'a> {
             }
             Token::Integer(value_str) => {
                 let value = value_str.parse::<i64>().map_err(|_| {
-                    CompileError::SyntaxError(
-                        format!("Invalid integer: {}", value_str),
-                        Some(self.current_span.clone()),
-                    )
+                    if value_str.bytes().all(|b| b.is_ascii_digit()) {
+                        CompileError::TypeError(
+                            format!("Integer literal {} is out of range for i64; add a `u64` suffix", value_str),
+                            Some(self.current_span.clone()),
+                        )
+                    } else {
+                        CompileError::SyntaxError(
+                            format!("Invalid numeric literal: {}", value_str),
+                            Some(self.current_span.clone()),
+                        )
+                    }
                 })?;
                 self.next_token();
                 // Default to Integer32 unless out of range
-                if value <= i32::MAX as i64 && value >= i32::MIN as i64 {
-                    Ok(Expression::Integer32(value as i32))
+                let literal = if value <= i32::MAX as i64 && value >= i32::MIN as i64 {
+                    Expression::Integer32(value as i32)
                 } else {
-                    Ok(Expression::Integer64(value))
-                }
+                    Expression::Integer64(value)
+                };
+                self.parse_literal_suffixes(literal)
             }
             Token::Float(value_str) => {
                 let value = value_str.parse::<f64>().map_err(|_| {
@@ -125,18 +238,50 @@ impl<'a> Parser<'a> {
                     )
                 })?;
                 self.next_token();
-                Ok(Expression::Float64(value))
+                self.parse_literal_suffixes(Expression::Float64(value))
             }
+            Token::SuffixedNumber(value, suffix) => {
+                let literal = self.suffixed_literal(value, *suffix, false)?;
+                self.next_token();
+                self.parse_literal_suffixes(literal)
+            }
+            Token::Char(c) => {
+                // Characters are Unicode code points
+                let value = *c as u32;
+                self.next_token();
+                Ok(Expression::Unsigned32(value))
+            }
+            Token::Byte(byte) => {
+                let byte = *byte;
+                self.next_token();
+                Ok(Expression::Unsigned8(byte))
+            }
+            Token::ByteString(bytes) => {
+                let bytes = bytes.iter().map(|byte| Expression::Unsigned8(*byte)).collect();
+                self.next_token();
+                Ok(Expression::ArrayLiteral(bytes))
+            }
+            Token::RawString(value) => {
+                let value = value.clone();
+                self.next_token();
+                Ok(Expression::String(value))
+            }
+            Token::Error(message) => Err(CompileError::SyntaxError(
+                message.clone(),
+                Some(self.current_span.clone()),
+            )),
             Token::StringLiteral(value) => {
                 let value = value.clone();
                 self.next_token();
-                
-                // Check if the string contains interpolation syntax
-                if value.contains("$(") {
-                    self.parse_interpolated_string(value)
-                } else {
-                    Ok(Expression::String(value))
-                }
+                Ok(Expression::String(value))
+            }
+            Token::InterpolatedString(segments) => {
+                let segments = segments.clone();
+                self.next_token();
+                self.parse_interpolated_string(segments)
+            }
+            Token::Identifier(_) if self.peek_token == Token::Operator("->".to_string()) => {
+                self.parse_closure()
             }
             Token::Identifier(name) => {
                 let name = name.clone();
@@ -151,6 +296,7 @@ impl<'a> Parser<'a> {
                 
                 // Check for enum variant syntax: EnumName::VariantName
                 if self.current_token == Token::Operator("::".to_string()) {
+                    self.mark_token(TokenRole::Path);
                     self.next_token(); // consume '::'
                     
                     let variant = match &self.current_token {
@@ -201,18 +347,28 @@ impl<'a> Parser<'a> {
                     if self.looks_like_generic_type_args() {
                         // Consume the generic type arguments
                         let mut depth = 1;
+                        self.mark_token(TokenRole::TypeArguments);
                         self.next_token(); // consume '<'
                         while depth > 0 && self.current_token != Token::Eof {
                             match &self.current_token {
-                                Token::Operator(op) if op == "<" => depth += 1,
-                                Token::Operator(op) if op == ">" => depth -= 1,
+                                Token::Operator(op) if op == "<" => {
+                                    depth += 1;
+                                    self.mark_token(TokenRole::TypeArguments);
+                                }
+                                Token::Operator(op) if op == ">" => {
+                                    depth -= 1;
+                                    self.mark_token(TokenRole::TypeArguments);
+                                }
+                                // Pointer and reference types
+                                Token::Operator(op) if op == "*" => self.mark_token(TokenRole::Prefix),
+                                Token::Symbol('*' | '&') => self.mark_token(TokenRole::Prefix),
                                 _ => {}
                             }
                             self.next_token();
                         }
                         // For now, we just track that we consumed generics
                         // In the future, we should preserve the actual type arguments
-                        (format!("{}<>", name), true)
+                        (name.clone(), true)
                     } else {
                         // Not generic type args, probably a comparison
                         (name.clone(), false)
@@ -237,29 +393,12 @@ impl<'a> Parser<'a> {
                 loop {
                     match &self.current_token {
                         Token::Symbol('.') => {
-                            self.next_token(); // consume '.'
-                            
-                            let member = match &self.current_token {
-                                Token::Identifier(name) => name.clone(),
-                                Token::Keyword(kw) => {
-                                    // Allow keywords as member names (e.g., .loop, .await, etc.)
-                                    format!("{:?}", kw).to_lowercase()
-                                }
-                                _ => {
-                                    return Err(CompileError::SyntaxError(
-                                        "Expected identifier after '.'".to_string(),
-                                        Some(self.current_span.clone()),
-                                    ));
-                                }
-                            };
-                            self.next_token();
-                            expr = Expression::MemberAccess {
-                                object: Box::new(expr),
-                                member,
-                            };
+                            expr = self.parse_member_suffix(expr)?;
                         }
-                        Token::Symbol('[') => {
+                        // A `[` after a call starts the next statement
+                        Token::Symbol('[') if !matches!(expr, Expression::FunctionCall { .. } | Expression::MethodCall { .. }) => {
                             // Array indexing
+                            self.mark_token(TokenRole::Postfix);
                             self.next_token(); // consume '['
                             let index = self.parse_expression()?;
                             if self.current_token != Token::Symbol(']') {
@@ -275,17 +414,20 @@ impl<'a> Parser<'a> {
                             };
                         }
                         Token::Symbol('(') => {
-                            // Function call
-                            if let Expression::MemberAccess { object, member } = expr {
-                                return self.parse_call_expression_with_object(*object, member);
-                            } else if let Expression::Identifier(name) = expr {
-                                return self.parse_call_expression(name);
-                            } else {
-                                return Err(CompileError::SyntaxError(
-                                    "Unexpected expression type for function call".to_string(),
-                                    Some(self.current_span.clone()),
-                                ));
-                            }
+                            // Function call; method calls can be chained
+                            expr = match expr {
+                                Expression::MemberAccess { object, member } => {
+                                    self.parse_call_expression_with_object(*object, member)?
+                                }
+                                Expression::Identifier(name) => self.parse_call_expression(name)?,
+                                Expression::FunctionCall { .. } | Expression::MethodCall { .. } => break,
+                                _ => {
+                                    return Err(CompileError::SyntaxError(
+                                        "Unexpected expression type for function call".to_string(),
+                                        Some(self.current_span.clone()),
+                                    ));
+                                }
+                            };
                         }
                         _ => break,
                     }
@@ -296,6 +438,18 @@ impl<'a> Parser<'a> {
             Token::Symbol('(') => {
                 self.next_token();
                 let mut expr = self.parse_expression()?;
+                if self.current_token == Token::Symbol(',') {
+                    // Tuple literal: (a, b, ...)
+                    let mut elements = vec![expr];
+                    while self.current_token == Token::Symbol(',') {
+                        self.next_token();
+                        if self.current_token == Token::Symbol(')') {
+                            break;
+                        }
+                        elements.push(self.parse_expression()?);
+                    }
+                    expr = Expression::TupleLiteral(elements);
+                }
                 if self.current_token != Token::Symbol(')') {
                     return Err(CompileError::SyntaxError(
                         "Expected closing parenthesis".to_string(),
@@ -308,29 +462,12 @@ impl<'a> Parser<'a> {
                 loop {
                     match &self.current_token {
                         Token::Symbol('.') => {
-                            self.next_token(); // consume '.'
-                            
-                            let member = match &self.current_token {
-                                Token::Identifier(name) => name.clone(),
-                                Token::Keyword(kw) => {
-                                    // Allow keywords as member names (e.g., .loop, .await, etc.)
-                                    format!("{:?}", kw).to_lowercase()
-                                }
-                                _ => {
-                                    return Err(CompileError::SyntaxError(
-                                        "Expected identifier after '.'".to_string(),
-                                        Some(self.current_span.clone()),
-                                    ));
-                                }
-                            };
-                            self.next_token();
-                            expr = Expression::MemberAccess {
-                                object: Box::new(expr),
-                                member,
-                            };
+                            expr = self.parse_member_suffix(expr)?;
                         }
-                        Token::Symbol('[') => {
+                        // A `[` after a call starts the next statement
+                        Token::Symbol('[') if !matches!(expr, Expression::FunctionCall { .. } | Expression::MethodCall { .. }) => {
                             // Array indexing
+                            self.mark_token(TokenRole::Postfix);
                             self.next_token(); // consume '['
                             let index = self.parse_expression()?;
                             if self.current_token != Token::Symbol(']') {
@@ -346,15 +483,19 @@ impl<'a> Parser<'a> {
                             };
                         }
                         Token::Symbol('(') => {
-                            // Function call
-                            if let Expression::MemberAccess { object, member } = expr {
-                                return self.parse_call_expression_with_object(*object, member);
-                            } else {
-                                return Err(CompileError::SyntaxError(
-                                    "Cannot call non-identifier expression".to_string(),
-                                    Some(self.current_span.clone()),
-                                ));
-                            }
+                            // Method call; method calls can be chained
+                            expr = match expr {
+                                Expression::MemberAccess { object, member } => {
+                                    self.parse_call_expression_with_object(*object, member)?
+                                }
+                                Expression::MethodCall { .. } => break,
+                                _ => {
+                                    return Err(CompileError::SyntaxError(
+                                        "Cannot call non-identifier expression".to_string(),
+                                        Some(self.current_span.clone()),
+                                    ));
+                                }
+                            };
                         }
                         _ => break,
                     }
@@ -377,7 +518,131 @@ impl<'a> Parser<'a> {
         }
     }
 
+    /// The member accesses and method calls after a number literal, as in
+    /// `1i32.abs()`.
+    fn parse_literal_suffixes(&mut self, mut expr: Expression) -> Result<Expression> {
+        while self.current_token == Token::Symbol('.') {
+            expr = match self.parse_member_suffix(expr)? {
+                Expression::MemberAccess { object, member } if self.current_token == Token::Symbol('(') => {
+                    self.parse_call_expression_with_object(*object, member)?
+                }
+                expr => expr,
+            };
+        }
+        Ok(expr)
+    }
+
+    /// The `.member` or `.0` after `object`. A chained index like `t.0.1`
+    /// arrives as the float `0.1` and is split back into two accesses.
+    fn parse_member_suffix(&mut self, object: Expression) -> Result<Expression> {
+        self.next_token(); // consume '.'
+
+        let expr = match &self.current_token {
+            Token::Identifier(name) => Expression::MemberAccess {
+                object: Box::new(object),
+                member: name.clone(),
+            },
+            Token::Keyword(kw) => Expression::MemberAccess {
+                object: Box::new(object),
+                // Allow keywords as member names (e.g., .loop, .await, etc.)
+                member: format!("{:?}", kw).to_lowercase(),
+            },
+            Token::Integer(digits) | Token::Float(digits) => {
+                let mut expr = object;
+                for index in digits.split('.') {
+                    let index = index.parse::<usize>().map_err(|_| {
+                        CompileError::SyntaxError(
+                            format!("Invalid tuple index: {}", digits),
+                            Some(self.current_span.clone()),
+                        )
+                    })?;
+                    expr = Expression::TupleIndex { tuple: Box::new(expr), index };
+                }
+                expr
+            }
+            _ => {
+                return Err(CompileError::SyntaxError(
+                    "Expected identifier after '.'".to_string(),
+                    Some(self.current_span.clone()),
+                ));
+            }
+        };
+        self.next_token();
+        Ok(expr)
+    }
+
+    /// Whether the parenthesized list at the current token is the parameter
+    /// list of a closure, i.e. names with optional types followed by `->`.
+    fn looks_like_closure(&mut self) -> bool {
+        let checkpoint = self.checkpoint();
+        let is_closure = self.parse_closure_params().is_ok()
+            && self.current_token == Token::Operator("->".to_string());
+        self.restore(checkpoint);
+        is_closure
+    }
+
+    /// Parse a closure: `x -> body`, or `(a: i32, b) -> body` where each
+    /// parameter's type is optional.
+    fn parse_closure(&mut self) -> Result<Expression> {
+        let params = if let Token::Identifier(name) = &self.current_token {
+            let param = (name.clone(), None);
+            self.next_token();
+            vec![param]
+        } else {
+            self.parse_closure_params()?
+        };
+
+        if self.current_token != Token::Operator("->".to_string()) {
+            return Err(CompileError::SyntaxError(
+                "Expected '->' after closure parameters".to_string(),
+                Some(self.current_span.clone()),
+            ));
+        }
+        self.next_token(); // consume '->'
+
+        let body = self.parse_expression()?;
+        Ok(Expression::Closure {
+            params,
+            body: Box::new(body),
+        })
+    }
+
+    fn parse_closure_params(&mut self) -> Result<Vec<(String, Option<AstType>)>> {
+        self.next_token(); // consume '('
+        let mut params = Vec::new();
+        while self.current_token != Token::Symbol(')') {
+            let Token::Identifier(name) = &self.current_token else {
+                return Err(CompileError::SyntaxError(
+                    "Expected parameter name in closure".to_string(),
+                    Some(self.current_span.clone()),
+                ));
+            };
+            let name = name.clone();
+            self.next_token();
+
+            let type_ = if self.current_token == Token::Symbol(':') {
+                self.next_token();
+                Some(self.parse_type()?)
+            } else {
+                None
+            };
+            params.push((name, type_));
+
+            if self.current_token == Token::Symbol(',') {
+                self.next_token();
+            } else if self.current_token != Token::Symbol(')') {
+                return Err(CompileError::SyntaxError(
+                    "Expected ',' or ')' in closure parameters".to_string(),
+                    Some(self.current_span.clone()),
+                ));
+            }
+        }
+        self.next_token(); // consume ')'
+        Ok(params)
+    }
+
     fn parse_call_expression(&mut self, function_name: String) -> Result<Expression> {
+        self.mark_token(TokenRole::Postfix);
         self.next_token(); // consume '('
         let mut arguments = vec![];
         if self.current_token != Token::Symbol(')') {
@@ -403,6 +668,7 @@ impl<'a> Parser<'a> {
     }
 
     fn parse_call_expression_with_object(&mut self, object: Expression, method_name: String) -> Result<Expression> {
+        self.mark_token(TokenRole::Postfix);
         self.next_token(); // consume '('
         let mut arguments = vec![];
         if self.current_token != Token::Symbol(')') {
@@ -421,14 +687,9 @@ impl<'a> Parser<'a> {
             }
         }
         self.next_token(); // consume ')'
-        Ok(Expression::FunctionCall {
-            name: format!("{}.{}", match &object {
-                Expression::Identifier(name) => name.clone(),
-                _ => return Err(CompileError::SyntaxError(
-                    "Expected identifier for object in method call".to_string(),
-                    Some(self.current_span.clone()),
-                )),
-            }, method_name),
+        Ok(Expression::MethodCall {
+            object: Box::new(object),
+            method: method_name,
             args: arguments,
         })
     }
@@ -514,27 +775,7 @@ impl<'a> Parser<'a> {
                 Pattern::Or(patterns)
             };
             
-            // Check for destructuring/guard with ->
-            let guard = if self.current_token == Token::Operator("->".to_string()) {
-                self.next_token();
-                // TODO: Properly handle destructuring vs guards
-                // For now, treat it as a guard
-                Some(self.parse_expression()?)
-            } else {
-                None
-            };
-            
-            // Expect =>
-            if self.current_token != Token::Operator("=>".to_string()) {
-                return Err(CompileError::SyntaxError(
-                    "Expected '=>' after pattern in match arm".to_string(),
-                    Some(self.current_span.clone()),
-                ));
-            }
-            self.next_token(); // consume '=>'
-            
-            // Parse the result expression
-            let body = self.parse_expression()?;
+            let (guard, body) = self.parse_arm_guard_and_body()?;
             
             arms.push(crate::ast::ConditionalArm {
                 pattern,
@@ -553,6 +794,41 @@ impl<'a> Parser<'a> {
         })
     }
 
+    /// The optional `-> guard` and the `=> body` of a match arm.
+    fn parse_arm_guard_and_body(&mut self) -> Result<(Option<Expression>, Expression)> {
+        // Check for destructuring/guard with ->
+        let guard = if self.current_token == Token::Operator("->".to_string()) {
+            self.next_token();
+            // TODO: Properly handle destructuring vs guards
+            // For now, treat it as a guard
+            Some(self.parse_expression_in_arm()?)
+        } else {
+            None
+        };
+        
+        // Expect =>
+        if self.current_token != Token::Operator("=>".to_string()) {
+            return Err(CompileError::SyntaxError(
+                "Expected '=>' after pattern in match arm".to_string(),
+                Some(self.current_span.clone()),
+            ));
+        }
+        self.next_token(); // consume '=>'
+        
+        // Parse the result expression
+        let body = self.parse_expression_in_arm()?;
+        Ok((guard, body))
+    }
+
+    /// Parse an expression in a match arm, where a `|` outside any brackets
+    /// starts the next pattern or arm rather than a bitwise OR.
+    pub(crate) fn parse_expression_in_arm(&mut self) -> Result<Expression> {
+        let outer = self.match_arm_depth.replace(self.open_brackets.len());
+        let expr = self.parse_expression();
+        self.match_arm_depth = outer;
+        expr
+    }
+
     fn looks_like_generic_type_args(&self) -> bool {
         // Try to determine if this is generic type args Vec<T> or vec_new<i32> vs comparison x < y
         // Heuristics:
@@ -618,9 +894,24 @@ impl<'a> Parser<'a> {
         self.next_token(); // consume '{'
         let mut statements = vec![];
         
+        // Block statements are numbered once the body statement holding
+        // them is complete
+        self.span_cursor.suspend();
         while self.current_token != Token::Symbol('}') && self.current_token != Token::Eof {
-            statements.push(self.parse_statement()?);
+            let start = self.current_span.clone();
+            match self.parse_statement() {
+                Ok(statement) => {
+                    let span = self.span_from(&start);
+                    self.block_statements.insert(start.start, span);
+                    statements.push(statement);
+                }
+                Err(error) => {
+                    self.span_cursor.resume();
+                    return Err(error);
+                }
+            }
         }
+        self.span_cursor.resume();
         
         if self.current_token != Token::Symbol('}') {
             return Err(CompileError::SyntaxError(
@@ -648,6 +939,11 @@ impl<'a> Parser<'a> {
             ">=" => Ok(BinaryOperator::GreaterThanEquals),
             "&&" => Ok(BinaryOperator::And),
             "||" => Ok(BinaryOperator::Or),
+            "&" => Ok(BinaryOperator::BitAnd),
+            "|" => Ok(BinaryOperator::BitOr),
+            "^" => Ok(BinaryOperator::BitXor),
+            "<<" => Ok(BinaryOperator::ShiftLeft),
+            ">>" => Ok(BinaryOperator::ShiftRight),
             _ => Err(CompileError::SyntaxError(
                 format!("Unknown binary operator: {}", op),
                 Some(self.current_span.clone()),
@@ -662,77 +958,29 @@ impl<'a> Parser<'a> {
             "&&" => 3,          // Logical AND
             "==" | "!=" => 4,   // Equality
             "<" | "<=" | ">" | ">=" => 5,  // Comparison
-            "+" | "-" => 6,     // Addition/Subtraction
-            "*" | "/" | "%" => 7,  // Multiplication/Division/Modulo
+            "|" => 6,           // Bitwise OR
+            "^" => 7,           // Bitwise XOR
+            "&" => 8,           // Bitwise AND
+            "<<" | ">>" => 9,   // Shifts
+            "+" | "-" => 10,    // Addition/Subtraction
+            "*" | "/" | "%" => 11,  // Multiplication/Division/Modulo
             _ => 0,
         }
     }
 
-    fn parse_interpolated_string(&mut self, input: String) -> Result<Expression> {
-        use crate::ast::StringPart;
-        let mut parts = Vec::new();
-        let mut current = String::new();
-        let mut chars = input.chars().peekable();
-        
-        while let Some(ch) = chars.next() {
-            if ch == '$' && chars.peek() == Some(&'(') {
-                // Found interpolation start
-                chars.next(); // consume '('
-                
-                // Save any literal part before this interpolation
-                if !current.is_empty() {
-                    parts.push(StringPart::Literal(current.clone()));
-                    current.clear();
-                }
-                
-                // Parse the interpolated expression
-                let mut expr_str = String::new();
-                let mut paren_count = 1;
-                
-                while let Some(ch) = chars.next() {
-                    if ch == '(' {
-                        paren_count += 1;
-                        expr_str.push(ch);
-                    } else if ch == ')' {
-                        paren_count -= 1;
-                        if paren_count == 0 {
-                            break;
-                        }
-                        expr_str.push(ch);
-                    } else {
-                        expr_str.push(ch);
-                    }
-                }
-                
-                if paren_count != 0 {
-                    return Err(CompileError::SyntaxError(
-                        "Unmatched parentheses in string interpolation".to_string(),
-                        Some(self.current_span.clone()),
-                    ));
+    /// The string made of `segments`, with each interpolation parsed as an
+    /// expression.
+    fn parse_interpolated_string(&mut self, segments: Vec<StringSegment>) -> Result<Expression> {
+        let mut parts = Vec::with_capacity(segments.len());
+        for segment in segments {
+            parts.push(match segment {
+                StringSegment::Text(text) => StringPart::Literal(text),
+                StringSegment::Code(code) => {
+                    let mut parser = Parser::new(Lexer::new(&code));
+                    StringPart::Interpolation(parser.parse_expression()?)
                 }
-                
-                // Parse the expression string
-                // We need to create a temporary parser for this expression
-                let lexer = crate::lexer::Lexer::new(&expr_str);
-                let mut temp_parser = crate::parser::Parser::new(lexer);
-                let expr = temp_parser.parse_expression()?;
-                parts.push(StringPart::Interpolation(expr));
-            } else {
-                current.push(ch);
-            }
-        }
-        
-        // Add any remaining literal part
-        if !current.is_empty() {
-            parts.push(StringPart::Literal(current));
-        }
-        
-        // If we have interpolation parts, create an interpolated string expression
-        if parts.iter().any(|p| matches!(p, StringPart::Interpolation(_))) {
-            Ok(Expression::StringInterpolation { parts })
-        } else {
-            // No interpolation found, return a simple string
-            Ok(Expression::String(input))
+            });
         }
+        Ok(Expression::StringInterpolation { parts })
     }
 }
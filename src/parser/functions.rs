@@ -2,6 +2,7 @@ use super::core::Parser;
 use crate::ast::{Function, TypeParameter};
 use crate::error::{CompileError, Result};
 use crate::lexer::Token;
+use crate::spans::TokenRole;
 
 impl<'a> Parser<'a> {
     pub fn parse_function(&mut self) -> Result<Function> {
@@ -19,16 +20,17 @@ impl<'a> Parser<'a> {
         // Parse generic type parameters if present: <T, U, ...>
         let mut type_params = Vec::new();
         if self.current_token == Token::Operator("<".to_string()) {
+            self.mark_token(TokenRole::TypeArguments);
             self.next_token();
             loop {
                 if let Token::Identifier(gen) = &self.current_token {
-                    type_params.push(TypeParameter {
-                        name: gen.clone(),
-                        constraints: Vec::new(),
-                    });
+                    let name = gen.clone();
                     self.next_token();
+                    let constraints = self.parse_type_parameter_bounds()?;
+                    type_params.push(TypeParameter { name, constraints });
                     
                     if self.current_token == Token::Operator(">".to_string()) {
+                        self.mark_token(TokenRole::TypeArguments);
                         self.next_token();
                         break;
                     } else if self.current_token == Token::Symbol(',') {
@@ -124,10 +126,7 @@ impl<'a> Parser<'a> {
         }
         self.next_token();
         
-        let mut body = vec![];
-        while self.current_token != Token::Symbol('}') && self.current_token != Token::Eof {
-            body.push(self.parse_statement()?);
-        }
+        let body = self.parse_function_body(name.clone())?;
         
         if self.current_token != Token::Symbol('}') {
             return Err(CompileError::SyntaxError(
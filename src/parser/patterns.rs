@@ -2,13 +2,21 @@ use super::core::Parser;
 use crate::ast::{Pattern, Expression};
 use crate::error::{CompileError, Result};
 use crate::lexer::Token;
+use crate::spans::TokenRole;
 
 impl<'a> Parser<'a> {
     pub fn parse_pattern(&mut self) -> Result<Pattern> {
         match &self.current_token {
-            Token::Integer(_) | Token::Float(_) | Token::StringLiteral(_) => {
+            Token::Integer(_)
+            | Token::Float(_)
+            | Token::SuffixedNumber(..)
+            | Token::Char(_)
+            | Token::Byte(_)
+            | Token::StringLiteral(_)
+            | Token::InterpolatedString(_)
+            | Token::RawString(_) => {
                 // Literal pattern
-                let expr = self.parse_expression()?;
+                let expr = self.parse_expression_in_arm()?;
                 Ok(Pattern::Literal(expr))
             }
             Token::Symbol('.') => {
@@ -67,58 +75,13 @@ impl<'a> Parser<'a> {
                 
                 // Check if it's a struct pattern: StructName { field: pattern, ... }
                 if self.current_token == Token::Symbol('{') {
-                    self.next_token();
-                    let mut fields = vec![];
-                    
-                    while self.current_token != Token::Symbol('}') {
-                        if self.current_token == Token::Eof {
-                            return Err(CompileError::SyntaxError(
-                                "Unexpected end of file in struct pattern".to_string(),
-                                Some(self.current_span.clone()),
-                            ));
-                        }
-                        
-                        // Field name
-                        let field_name = if let Token::Identifier(field) = &self.current_token {
-                            field.clone()
-                        } else {
-                            return Err(CompileError::SyntaxError(
-                                "Expected field name in struct pattern".to_string(),
-                                Some(self.current_span.clone()),
-                            ));
-                        };
-                        self.next_token();
-                        
-                        // Colon
-                        if self.current_token != Token::Symbol(':') {
-                            return Err(CompileError::SyntaxError(
-                                "Expected ':' after field name in struct pattern".to_string(),
-                                Some(self.current_span.clone()),
-                            ));
-                        }
-                        self.next_token();
-                        
-                        // Field pattern
-                        let field_pattern = self.parse_pattern()?;
-                        fields.push((field_name, field_pattern));
-                        
-                        // Comma separator
-                        if self.current_token == Token::Symbol(',') {
-                            self.next_token();
-                        } else if self.current_token != Token::Symbol('}') {
-                            return Err(CompileError::SyntaxError(
-                                "Expected ',' or '}' in struct pattern".to_string(),
-                                Some(self.current_span.clone()),
-                            ));
-                        }
-                    }
-                    
-                    self.next_token(); // consume '}'
+                    let fields = self.parse_struct_pattern_fields()?;
                     return Ok(Pattern::Struct { name, fields });
                 }
                 
                 // Check if it's an enum variant pattern: EnumName::Variant(pattern)
                 if self.current_token == Token::Operator("::".to_string()) {
+                    self.mark_token(TokenRole::Path);
                     self.next_token();
                     
                     let variant_name = if let Token::Identifier(variant) = &self.current_token {
@@ -189,11 +152,14 @@ impl<'a> Parser<'a> {
                 if patterns.len() == 1 {
                     Ok(patterns.remove(0))
                 } else {
-                    // Tuple pattern - for now, just use the first pattern
-                    // TODO: Implement proper tuple pattern support
-                    Ok(patterns.remove(0))
+                    Ok(Pattern::Tuple(patterns))
                 }
             }
+            Token::Symbol('{') => {
+                // Struct pattern without a name: { x, y }
+                let fields = self.parse_struct_pattern_fields()?;
+                Ok(Pattern::Struct { name: String::new(), fields })
+            }
             Token::Operator(op) if op == "|" => {
                 // Or pattern: | pattern1 | pattern2
                 self.next_token();
@@ -227,6 +193,55 @@ impl<'a> Parser<'a> {
         }
     }
     
+    /// The `{ field: pattern, ... }` part of a struct pattern. A field
+    /// written alone, as in `{ x, y }`, binds a variable of the same name.
+    fn parse_struct_pattern_fields(&mut self) -> Result<Vec<(String, Pattern)>> {
+        self.next_token(); // consume '{'
+        let mut fields = vec![];
+
+        while self.current_token != Token::Symbol('}') {
+            if self.current_token == Token::Eof {
+                return Err(CompileError::SyntaxError(
+                    "Unexpected end of file in struct pattern".to_string(),
+                    Some(self.current_span.clone()),
+                ));
+            }
+
+            // Field name
+            let field_name = if let Token::Identifier(field) = &self.current_token {
+                field.clone()
+            } else {
+                return Err(CompileError::SyntaxError(
+                    "Expected field name in struct pattern".to_string(),
+                    Some(self.current_span.clone()),
+                ));
+            };
+            self.next_token();
+
+            // Field pattern, or the field's own name
+            let field_pattern = if self.current_token == Token::Symbol(':') {
+                self.next_token();
+                self.parse_pattern()?
+            } else {
+                Pattern::Identifier(field_name.clone())
+            };
+            fields.push((field_name, field_pattern));
+
+            // Comma separator
+            if self.current_token == Token::Symbol(',') {
+                self.next_token();
+            } else if self.current_token != Token::Symbol('}') {
+                return Err(CompileError::SyntaxError(
+                    "Expected ',' or '}' in struct pattern".to_string(),
+                    Some(self.current_span.clone()),
+                ));
+            }
+        }
+
+        self.next_token(); // consume '}'
+        Ok(fields)
+    }
+
     pub fn parse_binding_pattern(&mut self) -> Result<Pattern> {
         // Parse binding pattern: name -> pattern
         let name = if let Token::Identifier(name) = &self.current_token {
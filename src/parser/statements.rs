@@ -1,230 +1,239 @@
 // Loop syntax is simplified - only conditional and infinite loops are supported.
 // Range and iterator loops have been removed in favor of functional iteration.
 use super::core::Parser;
-use crate::ast::{Program, Declaration, Statement, VariableDeclarationType, Expression};
+use crate::ast::{AstType, BinaryOperator, Program, Declaration, Statement, VariableDeclarationType, Expression};
 use crate::error::{CompileError, Result};
 use crate::lexer::{self, Token};
+use crate::spans::{self, TokenRole};
 
 impl<'a> Parser<'a> {
     pub fn parse_program(&mut self) -> Result<Program> {
+        let (program, errors) = self.parse_program_recovering();
+        match errors.into_iter().next() {
+            Some(first) => Err(first),
+            None => Ok(program),
+        }
+    }
+
+    /// Parse the whole program, recovering from syntax errors. A statement
+    /// that fails to parse becomes `Statement::Error` and parsing resumes at
+    /// the next statement; a declaration becomes `Declaration::Error` and
+    /// parsing resumes at the next top-level `name =` line. Returns what
+    /// was parsed together with every error, in source order.
+    pub fn parse_program_recovering(&mut self) -> (Program, Vec<CompileError>) {
         let mut declarations = vec![];
         while self.current_token != Token::Eof {
-            // Parse top-level declarations
-            if let Token::Identifier(_) = &self.current_token {
-                // Could be a function definition: name :: (params) -> returnType { ... } or name = ...
-                if self.peek_token == Token::Operator("::".to_string()) {
-                    // Function with type annotation: name :: (params) -> returnType { ... }
-                    declarations.push(Declaration::Function(self.parse_function()?));
-                } else if self.peek_token == Token::Operator("=".to_string()) || self.peek_token == Token::Operator("<".to_string()) {
-                    // Check if it's a struct, enum, or function definition
-                    let _name = if let Token::Identifier(name) = &self.current_token {
-                        name.clone()
-                    } else {
-                        unreachable!()
-                    };
-                    
-                    // Look ahead to see what type of declaration this is
-                    let saved_position = self.lexer.position;
-                    let saved_read_position = self.lexer.read_position;
-                    let saved_current_char = self.lexer.current_char;
-                    let saved_current_token = self.current_token.clone();
-                    let saved_peek_token = self.peek_token.clone();
-                    
-                    // If generics, need to look ahead to determine struct vs function
-                    if self.peek_token == Token::Operator("<".to_string()) {
-                        // Look ahead to see if it's a struct or a function with generics
-                        let saved_position = self.lexer.position;
-                        let saved_read_position = self.lexer.read_position;
-                        let saved_current_char = self.lexer.current_char;
-                        let saved_current = self.current_token.clone();
-                        let saved_peek = self.peek_token.clone();
-                        let saved_span = self.current_span.clone();
-                        let saved_peek_span = self.peek_span.clone();
-                        
-                        // Skip past the generics to see what follows
-                        self.next_token(); // Move to <
-                        self.next_token(); // Move past <
-                        let mut depth = 1;
-                        while depth > 0 && self.current_token != Token::Eof {
-                            if self.current_token == Token::Operator("<".to_string()) {
-                                depth += 1;
-                            } else if self.current_token == Token::Operator(">".to_string()) {
-                                depth -= 1;
-                            }
-                            if depth > 0 {
-                                self.next_token();
-                            }
+            let start = self.current_span.clone();
+            match self.parse_declaration() {
+                Ok(declaration) => {
+                    if let Some(key) = spans::declaration_key(&declaration) {
+                        let span = self.span_from(&start);
+                        self.spans.record_declaration(key, span);
+                    }
+                    declarations.push(declaration);
+                }
+                Err(error) => {
+                    self.errors.push(error);
+                    self.synchronize_declaration(&start);
+                    declarations.push(Declaration::Error(self.span_from(&start)));
+                }
+            }
+        }
+        (Program { declarations }, self.take_errors())
+    }
+
+    fn parse_declaration(&mut self) -> Result<Declaration> {
+        // Parse top-level declarations
+        if let Token::Identifier(_) = &self.current_token {
+            // Could be a function definition: name :: (params) -> returnType { ... } or name = ...
+            if self.peek_token == Token::Operator("::".to_string()) {
+                // Function with type annotation: name :: (params) -> returnType { ... }
+                Ok(Declaration::Function(self.parse_function()?))
+            } else if self.peek_token == Token::Operator("=".to_string()) || self.peek_token == Token::Operator("<".to_string()) {
+                // Check if it's a struct, enum, or function definition
+                let _name = if let Token::Identifier(name) = &self.current_token {
+                    name.clone()
+                } else {
+                    unreachable!()
+                };
+                
+                // Look ahead to see what type of declaration this is
+                let checkpoint = self.checkpoint();
+                
+                // If generics, need to look ahead to determine struct vs function
+                if self.peek_token == Token::Operator("<".to_string()) {
+                    // Look ahead to see if it's a struct or a function with generics
+                    // Skip past the generics to see what follows
+                    self.next_token(); // Move to <
+                    self.next_token(); // Move past <
+                    let mut depth = 1;
+                    while depth > 0 && self.current_token != Token::Eof {
+                        if self.current_token == Token::Operator("<".to_string()) {
+                            depth += 1;
+                        } else if self.current_token == Token::Operator(">".to_string()) {
+                            depth -= 1;
                         }
-                        
-                        if depth == 0 {
-                            self.next_token(); // Move past >
-                            
-                            // Check what comes after the generics
-                            let is_struct = self.current_token == Token::Operator("=".to_string()) 
-                                && self.peek_token == Token::Symbol('{');
-                            let is_enum = self.current_token == Token::Operator("=".to_string())
-                                && self.peek_token == Token::Symbol('|');
-                            let is_function = self.current_token == Token::Operator("=".to_string()) 
-                                && self.peek_token == Token::Symbol('(');
-                            let is_behavior = self.current_token == Token::Operator("=".to_string()) 
-                                && self.peek_token == Token::Keyword(lexer::Keyword::Behavior);
-                            
-                            // Restore lexer state
-                            self.lexer.position = saved_position;
-                            self.lexer.read_position = saved_read_position;
-                            self.lexer.current_char = saved_current_char;
-                            self.current_token = saved_current;
-                            self.peek_token = saved_peek;
-                            self.current_span = saved_span;
-                            self.peek_span = saved_peek_span;
-                            
-                            if is_behavior {
-                                declarations.push(Declaration::Behavior(self.parse_behavior()?));
-                            } else if is_enum {
-                                declarations.push(Declaration::Enum(self.parse_enum()?));
-                            } else if is_function {
-                                declarations.push(Declaration::Function(self.parse_function()?));
-                            } else if is_struct {
-                                declarations.push(Declaration::Struct(self.parse_struct()?));
-                            } else {
-                                // Default to struct for backward compatibility
-                                declarations.push(Declaration::Struct(self.parse_struct()?));
-                            }
-                        } else {
-                            // Malformed generics, restore and try to parse as struct
-                            self.lexer.position = saved_position;
-                            self.lexer.read_position = saved_read_position;
-                            self.lexer.current_char = saved_current_char;
-                            self.current_token = saved_current;
-                            self.peek_token = saved_peek;
-                            self.current_span = saved_span;
-                            self.peek_span = saved_peek_span;
-                            declarations.push(Declaration::Struct(self.parse_struct()?));
+                        if depth > 0 {
+                            self.next_token();
                         }
-                    } else {
-                        // Need to look ahead to determine if it's a struct, enum, behavior, or function
-                        self.next_token(); // Move to '='
-                        self.next_token(); // Move past '=' to see what comes after
+                    }
+                    
+                    if depth == 0 {
+                        self.next_token(); // Move past >
+                        
+                        // Check what comes after the generics
+                        let is_struct = self.current_token == Token::Operator("=".to_string()) 
+                            && self.peek_token == Token::Symbol('{');
+                        let is_enum = self.current_token == Token::Operator("=".to_string())
+                            && self.peek_token == Token::Symbol('|');
+                        let is_function = self.current_token == Token::Operator("=".to_string()) 
+                            && self.peek_token == Token::Symbol('(');
+                        let is_behavior = self.current_token == Token::Operator("=".to_string()) 
+                            && self.peek_token == Token::Keyword(lexer::Keyword::Behavior);
+                        let is_impl = self.current_token == Token::Symbol('.')
+                            && self.peek_token == Token::Keyword(lexer::Keyword::Impl);
                         
-                        // Check what comes after '='
-                        let is_struct = matches!(&self.current_token, Token::Symbol('{'));
-                        let is_enum = matches!(&self.current_token, Token::Symbol('|'));
-                        let is_function = matches!(&self.current_token, Token::Symbol('('));
-                        let is_behavior = matches!(&self.current_token, Token::Keyword(lexer::Keyword::Behavior));
-
                         // Restore lexer state
-                        self.lexer.position = saved_position;
-                        self.lexer.read_position = saved_read_position;
-                        self.lexer.current_char = saved_current_char;
-                        self.current_token = saved_current_token;
-                        self.peek_token = saved_peek_token;
-
-                        if is_behavior {
-                            declarations.push(Declaration::Behavior(self.parse_behavior()?));
-                        } else if is_struct {
-                            declarations.push(Declaration::Struct(self.parse_struct()?));
+                        self.restore(checkpoint);
+                        
+                        if is_impl {
+                            // Generic impl block: Type<T>.impl<T> = { ... }
+                            let start = self.current_span.clone();
+                            let AstType::Generic { name, type_args } = self.parse_type()? else {
+                                return Err(CompileError::SyntaxError(
+                                    "Expected a type name before '.impl'".to_string(),
+                                    Some(start),
+                                ));
+                            };
+                            self.next_token(); // consume '.'
+                            self.next_token(); // consume 'impl'
+                            Ok(Declaration::Impl(self.parse_impl_block_from_type(name, type_args)?))
+                        } else if is_behavior {
+                            Ok(Declaration::Behavior(self.parse_behavior()?))
                         } else if is_enum {
-                            declarations.push(Declaration::Enum(self.parse_enum()?));
+                            Ok(Declaration::Enum(self.parse_enum()?))
                         } else if is_function {
-                            declarations.push(Declaration::Function(self.parse_function()?));
+                            Ok(Declaration::Function(self.parse_function()?))
+                        } else if is_struct {
+                            Ok(Declaration::Struct(self.parse_struct()?))
                         } else {
-                            // Try to parse as function (fallback)
-                            declarations.push(Declaration::Function(self.parse_function()?));
+                            // Default to struct for backward compatibility
+                            Ok(Declaration::Struct(self.parse_struct()?))
                         }
-                    }
-                } else if self.peek_token == Token::Symbol('.') {
-                    // Could be an impl block: Type.impl = { ... }
-                    let type_name = if let Token::Identifier(name) = &self.current_token {
-                        name.clone()
                     } else {
-                        unreachable!()
-                    };
-                    
-                    // Save state for potential backtrack
-                    let saved_position = self.lexer.position;
-                    let saved_read_position = self.lexer.read_position;
-                    let saved_current_char = self.lexer.current_char;
-                    let saved_current_token = self.current_token.clone();
-                    let saved_peek_token = self.peek_token.clone();
-                    
-                    self.next_token(); // consume type name
-                    self.next_token(); // consume '.'
+                        // Malformed generics, restore and try to parse as struct
+                        self.restore(checkpoint);
+                        Ok(Declaration::Struct(self.parse_struct()?))
+                    }
+                } else {
+                    // Need to look ahead to determine if it's a struct, enum, behavior, or function
+                    self.next_token(); // Move to '='
+                    self.next_token(); // Move past '=' to see what comes after
                     
-                    if let Token::Keyword(lexer::Keyword::Impl) = self.current_token {
-                        // This is an impl block
-                        self.next_token(); // consume 'impl'
-                        declarations.push(Declaration::Impl(self.parse_impl_block_from_type(type_name)?));
+                    // Check what comes after '='
+                    let is_struct = matches!(&self.current_token, Token::Symbol('{'));
+                    let is_enum = matches!(&self.current_token, Token::Symbol('|'));
+                    let is_function = matches!(&self.current_token, Token::Symbol('('));
+                    let is_behavior = matches!(&self.current_token, Token::Keyword(lexer::Keyword::Behavior));
+
+                    // Restore lexer state
+                    self.restore(checkpoint);
+
+                    if is_behavior {
+                        Ok(Declaration::Behavior(self.parse_behavior()?))
+                    } else if is_struct {
+                        Ok(Declaration::Struct(self.parse_struct()?))
+                    } else if is_enum {
+                        Ok(Declaration::Enum(self.parse_enum()?))
+                    } else if is_function {
+                        Ok(Declaration::Function(self.parse_function()?))
                     } else {
-                        // Not an impl block, restore and error
-                        self.lexer.position = saved_position;
-                        self.lexer.read_position = saved_read_position;
-                        self.lexer.current_char = saved_current_char;
-                        self.current_token = saved_current_token;
-                        self.peek_token = saved_peek_token;
-                        
-                        return Err(CompileError::SyntaxError(
-                            format!("Expected 'impl' after '{}.'", type_name),
-                            Some(self.current_span.clone()),
-                        ));
+                        // Try to parse as function (fallback)
+                        Ok(Declaration::Function(self.parse_function()?))
                     }
-                } else if self.peek_token == Token::Symbol('(') {
-                    // Could be an external function declaration
-                    declarations.push(Declaration::ExternalFunction(self.parse_external_function()?));
+                }
+            } else if self.peek_token == Token::Symbol('.') {
+                // Could be an impl block: Type.impl = { ... }
+                let type_name = if let Token::Identifier(name) = &self.current_token {
+                    name.clone()
                 } else {
+                    unreachable!()
+                };
+                
+                // Save state for potential backtrack
+                let checkpoint = self.checkpoint();
+                
+                self.next_token(); // consume type name
+                self.next_token(); // consume '.'
+                
+                if let Token::Keyword(lexer::Keyword::Impl) = self.current_token {
+                    // This is an impl block
+                    self.next_token(); // consume 'impl'
+                    Ok(Declaration::Impl(self.parse_impl_block_from_type(type_name, Vec::new())?))
+                } else {
+                    // Not an impl block, restore and error
+                    self.restore(checkpoint);
+                    
+                    Err(CompileError::SyntaxError(
+                        format!("Expected 'impl' after '{}.'", type_name),
+                        Some(self.current_span.clone()),
+                    ))
+                }
+            } else if self.peek_token == Token::Symbol('(') {
+                // Could be an external function declaration
+                Ok(Declaration::ExternalFunction(self.parse_external_function()?))
+            } else {
+                Err(CompileError::SyntaxError(
+                    format!("Unexpected token after identifier: {:?}", self.peek_token),
+                    Some(self.current_span.clone()),
+                ))
+            }
+        } else if let Token::Keyword(keyword) = &self.current_token {
+            if matches!(keyword, crate::lexer::Keyword::Type) {
+                // Parse type alias: type Name = Type or type Name<T> = Type<T>
+                Ok(Declaration::TypeAlias(self.parse_type_alias()?))
+            } else if matches!(keyword, crate::lexer::Keyword::Comptime) {
+                // Parse comptime block
+                self.next_token(); // consume 'comptime'
+                if self.current_token != Token::Symbol('{') {
                     return Err(CompileError::SyntaxError(
-                        format!("Unexpected token after identifier: {:?}", self.peek_token),
+                        "Expected '{' after comptime".to_string(),
                         Some(self.current_span.clone()),
                     ));
                 }
-            } else if let Token::Keyword(keyword) = &self.current_token {
-                if matches!(keyword, crate::lexer::Keyword::Type) {
-                    // Parse type alias: type Name = Type or type Name<T> = Type<T>
-                    declarations.push(Declaration::TypeAlias(self.parse_type_alias()?));
-                } else if matches!(keyword, crate::lexer::Keyword::Comptime) {
-                    // Parse comptime block
-                    self.next_token(); // consume 'comptime'
-                    if self.current_token != Token::Symbol('{') {
-                        return Err(CompileError::SyntaxError(
-                            "Expected '{' after comptime".to_string(),
-                            Some(self.current_span.clone()),
-                        ));
-                    }
-                    self.next_token(); // consume '{'
-                    
-                    let mut statements = vec![];
-                    while self.current_token != Token::Symbol('}') && self.current_token != Token::Eof {
-                        statements.push(self.parse_statement()?);
-                    }
-                    
-                    if self.current_token != Token::Symbol('}') {
-                        return Err(CompileError::SyntaxError(
-                            "Expected '}' to close comptime block".to_string(),
-                            Some(self.current_span.clone()),
-                        ));
-                    }
-                    self.next_token(); // consume '}'
-                    
-                    // Add comptime block to declarations
-                    declarations.push(Declaration::ComptimeBlock(statements));
-                } else if matches!(keyword, crate::lexer::Keyword::Extern) {
-                    // Parse external function declaration
-                    self.next_token(); // consume 'extern'
-                    declarations.push(Declaration::ExternalFunction(self.parse_external_function()?));
-                } else {
+                self.next_token(); // consume '{'
+                
+                let mut statements = vec![];
+                while self.current_token != Token::Symbol('}') && self.current_token != Token::Eof {
+                    statements.push(self.parse_statement()?);
+                }
+                
+                if self.current_token != Token::Symbol('}') {
                     return Err(CompileError::SyntaxError(
-                        format!("Unexpected keyword at top level: {:?}", keyword),
+                        "Expected '}' to close comptime block".to_string(),
                         Some(self.current_span.clone()),
                     ));
                 }
+                self.next_token(); // consume '}'
+                
+                // Add comptime block to declarations
+                Ok(Declaration::ComptimeBlock(statements))
+            } else if matches!(keyword, crate::lexer::Keyword::Extern) {
+                // Parse external function declaration
+                self.next_token(); // consume 'extern'
+                Ok(Declaration::ExternalFunction(self.parse_external_function()?))
             } else {
-                return Err(CompileError::SyntaxError(
-                    format!("Unexpected token at top level: {:?}", self.current_token),
+                Err(CompileError::SyntaxError(
+                    format!("Unexpected keyword at top level: {:?}", keyword),
                     Some(self.current_span.clone()),
-                ));
+                ))
             }
+        } else {
+            Err(CompileError::SyntaxError(
+                format!("Unexpected token at top level: {:?}", self.current_token),
+                Some(self.current_span.clone()),
+            ))
         }
-        
-        Ok(Program { declarations })
     }
 
     pub fn parse_statement(&mut self) -> Result<Statement> {
@@ -261,6 +270,8 @@ impl<'a> Parser<'a> {
                                 pointer: lhs,
                                 value,
                             })
+                        } else if let Some((op, tokens)) = self.compound_assignment() {
+                            self.parse_compound_assignment(lhs, op, tokens)
                         } else {
                             // Just an expression statement
                             if self.current_token == Token::Symbol(';') {
@@ -272,6 +283,9 @@ impl<'a> Parser<'a> {
                     _ => {
                         // Not a variable declaration, treat as expression
                         let expr = self.parse_expression()?;
+                        if let Some((op, tokens)) = self.compound_assignment() {
+                            return self.parse_compound_assignment(expr, op, tokens);
+                        }
                         if self.current_token == Token::Symbol(';') {
                             self.next_token();
                         }
@@ -287,7 +301,10 @@ impl<'a> Parser<'a> {
                 }
                 Ok(Statement::Expression(expr))
             }
-            Token::Symbol('(') => {
+            Token::Symbol('(') | Token::Symbol('{') => {
+                if let Some(declaration) = self.parse_destructuring_declaration() {
+                    return declaration;
+                }
                 // Parse parenthesized expression as statement
                 let expr = self.parse_expression()?;
                 if self.current_token == Token::Symbol(';') {
@@ -349,7 +366,7 @@ impl<'a> Parser<'a> {
                 
                 let mut statements = vec![];
                 while self.current_token != Token::Symbol('}') && self.current_token != Token::Eof {
-                    statements.push(self.parse_statement()?);
+                    statements.push(self.parse_body_statement(statements.len()));
                 }
                 
                 if self.current_token != Token::Symbol('}') {
@@ -362,7 +379,16 @@ impl<'a> Parser<'a> {
                 Ok(Statement::ComptimeBlock(statements))
             }
             // Handle literal expressions as valid statements
-            Token::Integer(_) | Token::Float(_) | Token::StringLiteral(_) => {
+            Token::Integer(_)
+            | Token::Float(_)
+            | Token::SuffixedNumber(..)
+            | Token::Char(_)
+            | Token::Byte(_)
+            | Token::StringLiteral(_)
+            | Token::InterpolatedString(_)
+            | Token::RawString(_)
+            | Token::ByteString(_)
+            | Token::Error(_) => {
                 let expr = self.parse_expression()?;
                 if self.current_token == Token::Symbol(';') {
                     self.next_token();
@@ -444,6 +470,35 @@ impl<'a> Parser<'a> {
         })
     }
     
+    /// `(q, r) := value` or `{ x, y } ::= value`. Returns `None`, having
+    /// consumed nothing, when the tokens aren't a pattern followed by `:=`
+    /// or `::=`.
+    fn parse_destructuring_declaration(&mut self) -> Option<Result<Statement>> {
+        let checkpoint = self.checkpoint();
+        let pattern = self.parse_pattern();
+        let is_mutable = match (&pattern, &self.current_token) {
+            (Ok(_), Token::Operator(op)) if op == ":=" => false,
+            (Ok(_), Token::Operator(op)) if op == "::=" => true,
+            _ => {
+                self.restore(checkpoint);
+                return None;
+            }
+        };
+        self.next_token();
+
+        let declaration = self.parse_expression().map(|initializer| {
+            if self.current_token == Token::Symbol(';') {
+                self.next_token();
+            }
+            Statement::DestructuringDeclaration {
+                pattern: pattern.expect("checked above"),
+                initializer,
+                is_mutable,
+            }
+        });
+        Some(declaration)
+    }
+
     fn parse_loop_statement(&mut self) -> Result<Statement> {
         use crate::ast::LoopKind;
         
@@ -489,7 +544,7 @@ impl<'a> Parser<'a> {
         // Parse loop body
         let mut body = vec![];
         while self.current_token != Token::Symbol('}') && self.current_token != Token::Eof {
-            body.push(self.parse_statement()?);
+            body.push(self.parse_body_statement(body.len()));
         }
         
         if self.current_token != Token::Symbol('}') {
@@ -507,6 +562,42 @@ impl<'a> Parser<'a> {
         })
     }
     
+    /// `target op= value`. A variable is assigned `target op value`; a
+    /// field or element gets a compound assignment, so that `target` is
+    /// evaluated once.
+    fn parse_compound_assignment(&mut self, target: Expression, op: BinaryOperator, tokens: usize) -> Result<Statement> {
+        if !matches!(
+            target,
+            Expression::Identifier(_) | Expression::MemberAccess { .. } | Expression::StructField { .. } | Expression::ArrayIndex { .. }
+        ) {
+            return Err(CompileError::SyntaxError(
+                "Invalid left-hand side of compound assignment".to_string(),
+                Some(self.current_span.clone()),
+            ));
+        }
+        if tokens == 2 {
+            self.mark_token(TokenRole::ShiftRight);
+        }
+        for _ in 0..tokens {
+            self.next_token(); // consume the operator
+        }
+        let value = self.parse_expression()?;
+        if self.current_token == Token::Symbol(';') {
+            self.next_token();
+        }
+        match target {
+            Expression::Identifier(name) => Ok(Statement::VariableAssignment {
+                value: Expression::BinaryOp {
+                    left: Box::new(Expression::Identifier(name.clone())),
+                    op,
+                    right: Box::new(value),
+                },
+                name,
+            }),
+            target => Ok(Statement::CompoundAssignment { target, op, value }),
+        }
+    }
+
     fn parse_variable_assignment(&mut self) -> Result<Statement> {
         let name = if let Token::Identifier(name) = &self.current_token {
             name.clone()
@@ -2,6 +2,7 @@ use super::core::Parser;
 use crate::ast::{StructDefinition, StructField, AstType, Function, TypeParameter};
 use crate::error::{CompileError, Result};
 use crate::lexer::Token;
+use crate::spans::TokenRole;
 
 impl<'a> Parser<'a> {
     pub fn parse_struct(&mut self) -> Result<StructDefinition> {
@@ -19,16 +20,17 @@ impl<'a> Parser<'a> {
         // Parse generics if present: <T, U, ...>
         let mut type_params = Vec::new();
         if self.current_token == Token::Operator("<".to_string()) {
+            self.mark_token(TokenRole::TypeArguments);
             self.next_token();
             loop {
                 if let Token::Identifier(gen) = &self.current_token {
-                    type_params.push(TypeParameter {
-                        name: gen.clone(),
-                        constraints: Vec::new(),
-                    });
+                    let name = gen.clone();
                     self.next_token();
+                    let constraints = self.parse_type_parameter_bounds()?;
+                    type_params.push(TypeParameter { name, constraints });
                     
                     if self.current_token == Token::Operator(">".to_string()) {
+                        self.mark_token(TokenRole::TypeArguments);
                         self.next_token();
                         break;
                     } else if self.current_token == Token::Symbol(',') {
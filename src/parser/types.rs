@@ -2,6 +2,7 @@ use super::core::Parser;
 use crate::ast::AstType;
 use crate::error::{CompileError, Result};
 use crate::lexer::Token;
+use crate::spans::TokenRole;
 
 impl<'a> Parser<'a> {
     pub fn parse_type(&mut self) -> Result<AstType> {
@@ -25,9 +26,32 @@ impl<'a> Parser<'a> {
                     "String" | "string" => Ok(AstType::String),
                     "void" => Ok(AstType::Void),
                     "ptr" => Ok(AstType::Pointer(Box::new(AstType::Void))),
+                    "dyn" if matches!(self.current_token, Token::Identifier(_)) => {
+                        // Behavior object: dyn Shape
+                        let Token::Identifier(behavior) = &self.current_token else {
+                            unreachable!()
+                        };
+                        let behavior = behavior.clone();
+                        self.next_token();
+                        Ok(AstType::Dyn(behavior))
+                    }
+                    "Self" if self.current_token == Token::Symbol('.') => {
+                        // Associated type of a behavior: Self.Item
+                        self.next_token();
+                        let Token::Identifier(item) = &self.current_token else {
+                            return Err(CompileError::SyntaxError(
+                                format!("Expected associated type name after 'Self.', got {:?}", self.current_token),
+                                Some(self.current_span.clone()),
+                            ));
+                        };
+                        let name = format!("Self.{}", item);
+                        self.next_token();
+                        Ok(AstType::Generic { name, type_args: vec![] })
+                    }
                     _ => {
                         // Check for generic type instantiation (e.g., List<T>)
                         if self.current_token == Token::Operator("<".to_string()) {
+                            self.mark_token(TokenRole::TypeArguments);
                             self.next_token();
                             let mut type_args = Vec::new();
                             
@@ -35,6 +59,7 @@ impl<'a> Parser<'a> {
                                 type_args.push(self.parse_type()?);
                                 
                                 if self.current_token == Token::Operator(">".to_string()) {
+                                    self.mark_token(TokenRole::TypeArguments);
                                     self.next_token();
                                     break;
                                 } else if self.current_token == Token::Symbol(',') {
@@ -111,34 +136,13 @@ impl<'a> Parser<'a> {
             }
             Token::Symbol('*') => {
                 // Pointer type: *T or function pointer *(params) return_type
+                self.mark_token(TokenRole::Prefix);
                 self.next_token();
                 
                 // Check if it's a function pointer
                 if self.current_token == Token::Symbol('(') {
                     // Function pointer: *(param_types) return_type
-                    self.next_token();
-                    let mut param_types = Vec::new();
-                    
-                    // Parse parameter types
-                    while self.current_token != Token::Symbol(')') {
-                        param_types.push(self.parse_type()?);
-                        
-                        if self.current_token == Token::Symbol(',') {
-                            self.next_token();
-                        } else if self.current_token != Token::Symbol(')') {
-                            return Err(CompileError::SyntaxError(
-                                "Expected ',' or ')' in function pointer parameters".to_string(),
-                                Some(self.current_span.clone()),
-                            ));
-                        }
-                    }
-                    
-                    // Skip ')'
-                    self.next_token();
-                    
-                    // Parse return type
-                    let return_type = self.parse_type()?;
-                    
+                    let (param_types, return_type) = self.parse_signature_type()?;
                     Ok(AstType::FunctionPointer {
                         param_types,
                         return_type: Box::new(return_type),
@@ -151,34 +155,13 @@ impl<'a> Parser<'a> {
             }
             Token::Operator(op) if op == "*" => {
                 // Pointer type: *T (operator version)
+                self.mark_token(TokenRole::Prefix);
                 self.next_token();
                 
                 // Check if it's a function pointer
                 if self.current_token == Token::Symbol('(') {
                     // Function pointer: *(param_types) return_type
-                    self.next_token();
-                    let mut param_types = Vec::new();
-                    
-                    // Parse parameter types
-                    while self.current_token != Token::Symbol(')') {
-                        param_types.push(self.parse_type()?);
-                        
-                        if self.current_token == Token::Symbol(',') {
-                            self.next_token();
-                        } else if self.current_token != Token::Symbol(')') {
-                            return Err(CompileError::SyntaxError(
-                                "Expected ',' or ')' in function pointer parameters".to_string(),
-                                Some(self.current_span.clone()),
-                            ));
-                        }
-                    }
-                    
-                    // Skip ')'
-                    self.next_token();
-                    
-                    // Parse return type
-                    let return_type = self.parse_type()?;
-                    
+                    let (param_types, return_type) = self.parse_signature_type()?;
                     Ok(AstType::FunctionPointer {
                         param_types,
                         return_type: Box::new(return_type),
@@ -189,8 +172,28 @@ impl<'a> Parser<'a> {
                     Ok(AstType::Pointer(Box::new(pointee_type)))
                 }
             }
+            Token::Symbol('(') => {
+                // Function type `(param_types) return_type`, or a tuple type
+                // `(A, B)` when no return type follows on the same line
+                let types = self.parse_parenthesized_types()?;
+                if self.starts_type() && !self.starts_line() {
+                    let return_type = self.parse_type()?;
+                    return Ok(AstType::Closure {
+                        param_types: types,
+                        return_type: Box::new(return_type),
+                    });
+                }
+                if types.len() < 2 {
+                    return Err(CompileError::SyntaxError(
+                        "Expected a return type after function parameter types; tuple types need at least two elements".to_string(),
+                        Some(self.current_span.clone()),
+                    ));
+                }
+                Ok(AstType::Tuple(types))
+            }
             Token::Symbol('&') => {
                 // Reference type: &T
+                self.mark_token(TokenRole::Prefix);
                 self.next_token();
                 let referenced_type = self.parse_type()?;
                 Ok(AstType::Ref(Box::new(referenced_type)))
@@ -222,16 +225,17 @@ impl<'a> Parser<'a> {
         // Parse optional generic type parameters
         let mut type_params = Vec::new();
         if self.current_token == Token::Operator("<".to_string()) {
+            self.mark_token(TokenRole::TypeArguments);
             self.next_token();
             loop {
                 if let Token::Identifier(param_name) = &self.current_token {
-                    type_params.push(TypeParameter {
-                        name: param_name.clone(),
-                        constraints: Vec::new(),
-                    });
+                    let name = param_name.clone();
                     self.next_token();
+                    let constraints = self.parse_type_parameter_bounds()?;
+                    type_params.push(TypeParameter { name, constraints });
                     
                     if self.current_token == Token::Operator(">".to_string()) {
+                        self.mark_token(TokenRole::TypeArguments);
                         self.next_token();
                         break;
                     } else if self.current_token == Token::Symbol(',') {
@@ -269,4 +273,41 @@ impl<'a> Parser<'a> {
             target_type,
         })
     }
+
+    /// The `(param_types) return_type` of a function or function pointer type.
+    fn parse_signature_type(&mut self) -> Result<(Vec<AstType>, AstType)> {
+        let param_types = self.parse_parenthesized_types()?;
+        let return_type = self.parse_type()?;
+        Ok((param_types, return_type))
+    }
+
+    /// A parenthesized, comma-separated list of types.
+    fn parse_parenthesized_types(&mut self) -> Result<Vec<AstType>> {
+        self.next_token(); // consume '('
+        let mut types = Vec::new();
+        while self.current_token != Token::Symbol(')') {
+            types.push(self.parse_type()?);
+
+            if self.current_token == Token::Symbol(',') {
+                self.next_token();
+            } else if self.current_token != Token::Symbol(')') {
+                return Err(CompileError::SyntaxError(
+                    "Expected ',' or ')' in parenthesized types".to_string(),
+                    Some(self.current_span.clone()),
+                ));
+            }
+        }
+        self.next_token(); // consume ')'
+        Ok(types)
+    }
+
+    /// Whether the current token can begin a type.
+    fn starts_type(&self) -> bool {
+        match &self.current_token {
+            Token::Identifier(_) => true,
+            Token::Symbol('(' | '[' | '*' | '&') => true,
+            Token::Operator(op) => op == "*",
+            _ => false,
+        }
+    }
 }
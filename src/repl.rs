@@ -0,0 +1,520 @@
+//! Session state for the interactive REPL.
+//!
+//! Top-level declarations are accumulated across inputs. Bare statements and
+//! expressions are wrapped in a synthetic function together with every
+//! binding entered so far, JIT-compiled and executed. After each evaluation
+//! the values of numeric, boolean and string bindings are captured and the
+//! bindings replaced by literals, so their initializers run only once. Other
+//! bindings are replayed, and their initializers run again.
+
+use crate::ast::{AstType, Declaration, Expression, ExternalFunction, Function, Program, Statement, VariableDeclarationType};
+use crate::compiler::Compiler;
+use crate::error::{CompileError, Result};
+use crate::lexer::{Lexer, Token, TokenWithSpan};
+use crate::parser::Parser;
+use crate::spans;
+use crate::typechecker::TypeChecker;
+use inkwell::context::Context;
+use inkwell::OptimizationLevel;
+use std::cell::RefCell;
+use std::ffi::{c_char, c_void, CStr};
+use std::fmt;
+
+extern "C" {
+    fn fflush(stream: *mut c_void) -> i32;
+}
+
+thread_local! {
+    /// Raw binding values passed to the capture functions by the last evaluation.
+    static CAPTURED: RefCell<Vec<u64>> = const { RefCell::new(Vec::new()) };
+}
+
+extern "C" fn capture_bits(value: u64) {
+    CAPTURED.with(|captured| captured.borrow_mut().push(value));
+}
+
+extern "C" fn capture_f32(value: f32) {
+    capture_bits((value as f64).to_bits());
+}
+
+extern "C" fn capture_f64(value: f64) {
+    capture_bits(value.to_bits());
+}
+
+/// A parsed line (or block) of REPL input.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReplInput {
+    Declarations(Vec<Declaration>),
+    Statements(Vec<Statement>),
+}
+
+/// A value produced by evaluating an expression.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReplValue {
+    Integer(i64),
+    Unsigned(u64),
+    Float(f64),
+    Bool(bool),
+    String(String),
+    /// The expression ran but its value cannot be shown (structs, arrays, ...).
+    Opaque,
+}
+
+impl fmt::Display for ReplValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplValue::Integer(v) => write!(f, "{}", v),
+            ReplValue::Unsigned(v) => write!(f, "{}", v),
+            ReplValue::Float(v) => write!(f, "{}", v),
+            ReplValue::Bool(v) => write!(f, "{}", v),
+            ReplValue::String(v) => write!(f, "{:?}", v),
+            ReplValue::Opaque => write!(f, "<value>"),
+        }
+    }
+}
+
+/// What happened as the result of one REPL input.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReplOutput {
+    /// New or replaced top-level declarations, by name.
+    Defined(Vec<String>),
+    /// A variable binding that will be visible to later inputs.
+    Bound { name: String, type_: AstType },
+    /// An expression value and its type.
+    Value { value: ReplValue, type_: AstType },
+    /// Statements that produced no value.
+    Unit,
+}
+
+/// Accumulated REPL state.
+#[derive(Debug, Default)]
+pub struct ReplSession {
+    declarations: Vec<Declaration>,
+    bindings: Vec<Statement>,
+    eval_count: usize,
+}
+
+impl ReplSession {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Forget all declarations and bindings.
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    pub fn declarations(&self) -> &[Declaration] {
+        &self.declarations
+    }
+
+    /// Parse input as top-level declarations, falling back to statements.
+    pub fn parse_input(input: &str) -> Result<ReplInput> {
+        let mut parser = Parser::new(Lexer::new(input));
+        let declaration_error = match parser.parse_program() {
+            Ok(program) => return Ok(ReplInput::Declarations(program.declarations)),
+            Err(e) => e,
+        };
+
+        let mut parser = Parser::new(Lexer::new(input));
+        let mut statements = Vec::new();
+        while parser.current_token != Token::Eof {
+            let result = parser.parse_statement();
+            // An error recovered from inside a loop or comptime body came first
+            let result = match parser.take_errors().into_iter().next() {
+                Some(e) => Err(e),
+                None => result,
+            };
+            match result {
+                Ok(statement) => statements.push(statement),
+                // Report whichever interpretation got further into the input
+                Err(e) if statements.is_empty() => return Err(pick_error(declaration_error, e)),
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(ReplInput::Statements(statements))
+    }
+
+    /// Evaluate one complete input.
+    pub fn eval(&mut self, context: &Context, input: &str) -> Result<ReplOutput> {
+        match Self::parse_input(input)? {
+            ReplInput::Declarations(declarations) if declarations.is_empty() => Ok(ReplOutput::Unit),
+            ReplInput::Declarations(declarations) => self.define(context, declarations),
+            ReplInput::Statements(statements) => self.run_statements(context, statements),
+        }
+    }
+
+    /// Load every declaration in a source file into the session.
+    pub fn load(&mut self, context: &Context, source: &str) -> Result<ReplOutput> {
+        let mut parser = Parser::new(Lexer::new(source));
+        let program = parser.parse_program()?;
+        self.define(context, program.declarations)
+    }
+
+    /// The type an expression would have in the current session.
+    pub fn type_of(&self, input: &str) -> Result<AstType> {
+        let mut parser = Parser::new(Lexer::new(input));
+        let expr = parser.parse_expression()?;
+        if parser.current_token != Token::Eof {
+            return Err(CompileError::SyntaxError(
+                format!("Unexpected token after expression: {:?}", parser.current_token),
+                Some(parser.current_span.clone()),
+            ));
+        }
+        self.infer_type(&self.bindings, &expr)
+    }
+
+    /// LLVM IR for the accumulated declarations.
+    pub fn ir(&self, context: &Context) -> Result<String> {
+        let program = Program { declarations: self.declarations.clone() };
+        Compiler::new(context).compile_llvm(&program)
+    }
+
+    fn define(&mut self, context: &Context, declarations: Vec<Declaration>) -> Result<ReplOutput> {
+        let mut merged = self.declarations.clone();
+        let mut names = Vec::new();
+        for declaration in declarations {
+            // Impls are keyed by their type and behavior, so redefining one
+            // replaces it
+            let name = spans::declaration_key(&declaration);
+            if let Some(name) = &name {
+                merged.retain(|existing| spans::declaration_key(existing).as_ref() != Some(name));
+                names.push(name.clone());
+            }
+            merged.push(declaration);
+        }
+
+        // Make sure the new declarations compile before committing them
+        let program = Program { declarations: merged.clone() };
+        Compiler::new(context).get_module(&program)?;
+
+        self.declarations = merged;
+        Ok(ReplOutput::Defined(names))
+    }
+
+    fn run_statements(&mut self, context: &Context, mut statements: Vec<Statement>) -> Result<ReplOutput> {
+        let Some(last) = statements.last().cloned() else {
+            return Ok(ReplOutput::Unit);
+        };
+        let tail = match &last {
+            Statement::Expression(expr) => {
+                statements.pop();
+                Some(expr.clone())
+            }
+            _ => None,
+        };
+
+        // A redeclaration takes the place of the binding it replaces, so the
+        // bindings that depend on it still come after their definition
+        let mut body = self.bindings.clone();
+        let mut rest = Vec::new();
+        for statement in statements {
+            if !redeclare(&mut body, &statement) {
+                rest.push(statement);
+            }
+        }
+        body.extend(rest);
+
+        let output_type = match &tail {
+            Some(expr) => self.infer_type(&body, expr)?,
+            None => AstType::Void,
+        };
+
+        // Pass the value of every binding that can be written as a literal
+        // to a capture function, before the tail can return
+        let mut captures = Vec::new();
+        for statement in &body {
+            let Statement::VariableDeclaration { name, type_, .. } = statement else {
+                continue;
+            };
+            let type_ = match type_ {
+                Some(type_) => type_.clone(),
+                None => self.infer_type(&body, &Expression::Identifier(name.clone()))?,
+            };
+            if literal_type(&type_) {
+                captures.push((name.clone(), type_));
+            }
+        }
+        for (slot, (name, _)) in captures.iter().enumerate() {
+            body.push(Statement::Expression(Expression::FunctionCall {
+                name: capture_name(slot),
+                args: vec![Expression::Identifier(name.clone())],
+            }));
+        }
+
+        match &tail {
+            Some(expr) if is_printable(&output_type) => body.push(Statement::Return(expr.clone())),
+            Some(expr) => body.push(Statement::Expression(expr.clone())),
+            None => {}
+        }
+
+        let return_type = if is_printable(&output_type) { output_type.clone() } else { AstType::Void };
+        let capture_types: Vec<AstType> = captures.iter().map(|(_, type_)| type_.clone()).collect();
+        let (value, captured) = self.execute(context, body.clone(), &return_type, &capture_types)?;
+
+        // An early return skips the captures; keep replaying in that case
+        let snapshots: Vec<(String, AstType, ReplValue)> = if captured.len() == captures.len() {
+            captures.into_iter().zip(captured).map(|((name, type_), value)| (name, type_, value)).collect()
+        } else {
+            Vec::new()
+        };
+        self.bindings = body.into_iter().filter_map(|statement| record_binding(statement, &snapshots)).collect();
+
+        match last {
+            Statement::VariableDeclaration { name, .. } => {
+                let type_ = self.infer_type(&self.bindings, &Expression::Identifier(name.clone()))?;
+                Ok(ReplOutput::Bound { name, type_ })
+            }
+            Statement::Expression(_) if output_type != AstType::Void => Ok(ReplOutput::Value {
+                value: value.unwrap_or(ReplValue::Opaque),
+                type_: output_type,
+            }),
+            _ => Ok(ReplOutput::Unit),
+        }
+    }
+
+    fn infer_type(&self, statements: &[Statement], expr: &Expression) -> Result<AstType> {
+        let program = Program { declarations: self.declarations.clone() };
+        TypeChecker::new().infer_type_in_context(&program, statements, expr)
+    }
+
+    /// Run `body` as a function returning `return_type`. Also returns the
+    /// values passed to the capture functions, whose parameter types are
+    /// `captures`.
+    fn execute(
+        &mut self,
+        context: &Context,
+        body: Vec<Statement>,
+        return_type: &AstType,
+        captures: &[AstType],
+    ) -> Result<(Option<ReplValue>, Vec<ReplValue>)> {
+        self.eval_count += 1;
+        let name = format!("__repl_eval_{}", self.eval_count);
+
+        let mut declarations = self.declarations.clone();
+        for (slot, type_) in captures.iter().enumerate() {
+            declarations.push(Declaration::ExternalFunction(ExternalFunction {
+                name: capture_name(slot),
+                args: vec![type_.clone()],
+                return_type: AstType::Void,
+                is_varargs: false,
+            }));
+        }
+        declarations.push(Declaration::Function(Function {
+            name: name.clone(),
+            type_params: vec![],
+            args: vec![],
+            return_type: return_type.clone(),
+            body,
+            is_async: false,
+        }));
+
+        let module = Compiler::new(context).get_module(&Program { declarations })?;
+
+        inkwell::support::load_visible_symbols();
+        let engine = module
+            .create_jit_execution_engine(OptimizationLevel::None)
+            .map_err(|e| CompileError::InternalError(format!("Failed to create execution engine: {}", e), None))?;
+        for (slot, type_) in captures.iter().enumerate() {
+            let function = module
+                .get_function(&capture_name(slot))
+                .ok_or_else(|| CompileError::InternalError(format!("Missing {}", capture_name(slot)), None))?;
+            let capture = match type_ {
+                AstType::F32 => capture_f32 as extern "C" fn(f32) as usize,
+                AstType::F64 => capture_f64 as extern "C" fn(f64) as usize,
+                _ => capture_bits as extern "C" fn(u64) as usize,
+            };
+            engine.add_global_mapping(&function, capture);
+        }
+        CAPTURED.with(|captured| captured.borrow_mut().clear());
+        let address = engine
+            .get_function_address(&name)
+            .map_err(|e| CompileError::InternalError(format!("Failed to get {}: {}", name, e), None))?;
+
+        // SAFETY: `address` is the synthetic function compiled above with no
+        // parameters and an LLVM return type derived from `return_type`.
+        let value = unsafe {
+            match return_type {
+                AstType::Void => {
+                    let f: unsafe extern "C" fn() = std::mem::transmute(address);
+                    f();
+                    None
+                }
+                AstType::Bool => {
+                    let f: unsafe extern "C" fn() -> u64 = std::mem::transmute(address);
+                    Some(ReplValue::Bool(f() & 1 == 1))
+                }
+                AstType::F32 => {
+                    let f: unsafe extern "C" fn() -> f32 = std::mem::transmute(address);
+                    Some(ReplValue::Float(f() as f64))
+                }
+                AstType::F64 => {
+                    let f: unsafe extern "C" fn() -> f64 = std::mem::transmute(address);
+                    Some(ReplValue::Float(f()))
+                }
+                AstType::String | AstType::Pointer(_) => {
+                    let f: unsafe extern "C" fn() -> *const c_char = std::mem::transmute(address);
+                    let ptr = f();
+                    Some(if ptr.is_null() {
+                        ReplValue::Opaque
+                    } else {
+                        ReplValue::String(CStr::from_ptr(ptr).to_string_lossy().into_owned())
+                    })
+                }
+                int_type => {
+                    let f: unsafe extern "C" fn() -> u64 = std::mem::transmute(address);
+                    let bits = int_type.bit_size().unwrap_or(64) as u32;
+                    Some(integer_value(f(), bits, int_type.is_signed_integer()))
+                }
+            }
+        };
+
+        unsafe {
+            fflush(std::ptr::null_mut());
+        }
+
+        // Strings point into the module, so read them while it is loaded
+        let captured = CAPTURED.with(|captured| captured.take());
+        let captured = captured
+            .into_iter()
+            .zip(captures)
+            .map(|(bits, type_)| match type_ {
+                AstType::Bool => ReplValue::Bool(bits & 1 == 1),
+                AstType::F32 | AstType::F64 => ReplValue::Float(f64::from_bits(bits)),
+                // SAFETY: string bindings hold pointers to NUL-terminated strings
+                AstType::String => unsafe {
+                    ReplValue::String(CStr::from_ptr(bits as *const c_char).to_string_lossy().into_owned())
+                },
+                int_type => integer_value(bits, int_type.bit_size().unwrap_or(64) as u32, int_type.is_signed_integer()),
+            })
+            .collect();
+
+        Ok((value, captured))
+    }
+}
+
+/// Whether the input has unclosed `{`, `(` or `[`, or an unterminated
+/// multi-line or raw string, and needs more lines. The input is lexed, so
+/// brackets inside literals and comments don't count.
+pub fn is_incomplete(input: &str) -> bool {
+    let mut lexer = Lexer::new(input);
+    let mut depth: i32 = 0;
+    loop {
+        let TokenWithSpan { token, span } = lexer.next_token_with_span();
+        match token {
+            Token::Symbol('{' | '(' | '[') => depth += 1,
+            Token::Symbol('}' | ')' | ']') => depth -= 1,
+            // Only strings that may span lines run on to the end of input
+            Token::Error(_) if input[span.start..].starts_with("\"\"\"") || input[span.start..].starts_with('r') => {
+                return true;
+            }
+            Token::Eof => return depth > 0,
+            _ => {}
+        }
+    }
+}
+
+fn capture_name(slot: usize) -> String {
+    format!("__repl_capture_{}", slot)
+}
+
+/// Replace the declaration of the same name among `bindings`, dropping its
+/// assignments. Returns false if `statement` doesn't redeclare a binding.
+fn redeclare(bindings: &mut Vec<Statement>, statement: &Statement) -> bool {
+    let Statement::VariableDeclaration { name, .. } = statement else {
+        return false;
+    };
+    let declared = |existing: &Statement| matches!(existing, Statement::VariableDeclaration { name: other, .. } if other == name);
+    let Some(index) = bindings.iter().position(declared) else {
+        return false;
+    };
+    bindings[index] = statement.clone();
+    bindings.retain(|existing| !matches!(existing, Statement::VariableAssignment { name: other, .. } if other == name));
+    true
+}
+
+/// The statement to keep as a binding, if any. Declarations with a
+/// snapshot become literals, and assignments to them are dropped.
+fn record_binding(statement: Statement, snapshots: &[(String, AstType, ReplValue)]) -> Option<Statement> {
+    match statement {
+        Statement::VariableDeclaration { name, type_, initializer, is_mutable, declaration_type } => {
+            let snapshot = snapshots
+                .iter()
+                .find(|(snapshot, ..)| *snapshot == name)
+                .and_then(|(_, type_, value)| Some((type_.clone(), literal(value, type_)?)));
+            Some(match snapshot {
+                Some((type_, literal)) => Statement::VariableDeclaration {
+                    name,
+                    type_: Some(type_),
+                    initializer: Some(literal),
+                    is_mutable,
+                    declaration_type: if is_mutable {
+                        VariableDeclarationType::ExplicitMutable
+                    } else {
+                        VariableDeclarationType::ExplicitImmutable
+                    },
+                },
+                None => Statement::VariableDeclaration { name, type_, initializer, is_mutable, declaration_type },
+            })
+        }
+        Statement::VariableAssignment { ref name, .. } if snapshots.iter().any(|(snapshot, ..)| snapshot == name) => None,
+        Statement::VariableAssignment { .. }
+        | Statement::PointerAssignment { .. }
+        | Statement::CompoundAssignment { .. } => Some(statement),
+        _ => None,
+    }
+}
+
+/// Whether values of this type can be written back as a literal.
+fn literal_type(type_: &AstType) -> bool {
+    type_.is_numeric() || matches!(type_, AstType::Bool | AstType::String)
+}
+
+fn literal(value: &ReplValue, type_: &AstType) -> Option<Expression> {
+    Some(match (value, type_) {
+        (ReplValue::Integer(v), AstType::I8) => Expression::Integer8(*v as i8),
+        (ReplValue::Integer(v), AstType::I16) => Expression::Integer16(*v as i16),
+        (ReplValue::Integer(v), AstType::I32) => Expression::Integer32(*v as i32),
+        (ReplValue::Integer(v), AstType::I64) => Expression::Integer64(*v),
+        (ReplValue::Unsigned(v), AstType::U8) => Expression::Unsigned8(*v as u8),
+        (ReplValue::Unsigned(v), AstType::U16) => Expression::Unsigned16(*v as u16),
+        (ReplValue::Unsigned(v), AstType::U32) => Expression::Unsigned32(*v as u32),
+        (ReplValue::Unsigned(v), AstType::U64) => Expression::Unsigned64(*v),
+        (ReplValue::Float(v), AstType::F32) => Expression::Float32(*v as f32),
+        (ReplValue::Float(v), AstType::F64) => Expression::Float64(*v),
+        (ReplValue::Bool(v), AstType::Bool) => Expression::Boolean(*v),
+        (ReplValue::String(v), AstType::String) => Expression::String(v.clone()),
+        _ => return None,
+    })
+}
+
+fn is_printable(type_: &AstType) -> bool {
+    type_.is_numeric()
+        || matches!(type_, AstType::Bool | AstType::String)
+        || matches!(type_, AstType::Pointer(inner) if **inner == AstType::I8)
+}
+
+fn integer_value(raw: u64, bits: u32, signed: bool) -> ReplValue {
+    if bits >= 64 {
+        return if signed { ReplValue::Integer(raw as i64) } else { ReplValue::Unsigned(raw) };
+    }
+    let masked = raw & ((1u64 << bits) - 1);
+    if signed {
+        let shift = 64 - bits;
+        ReplValue::Integer(((masked << shift) as i64) >> shift)
+    } else {
+        ReplValue::Unsigned(masked)
+    }
+}
+
+fn pick_error(declaration_error: CompileError, statement_error: CompileError) -> CompileError {
+    let offset = |e: &CompileError| match e {
+        CompileError::SyntaxError(_, Some(span)) => span.start,
+        _ => 0,
+    };
+    if offset(&declaration_error) > offset(&statement_error) {
+        declaration_error
+    } else {
+        statement_error
+    }
+}
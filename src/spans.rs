@@ -0,0 +1,394 @@
+//! Source locations for AST nodes.
+//!
+//! AST nodes don't carry spans themselves, so building and comparing trees
+//! stays cheap and location-independent. Instead the parser fills a
+//! `SpanTable` keyed by `NodeId`, and later passes walk the same paths
+//! with a `SpanCursor` so errors they raise can point at real source.
+
+use crate::ast::{Declaration, Expression, LoopKind, Statement, StringPart};
+use crate::error::{CompileError, Span};
+use std::collections::{BTreeMap, HashMap};
+
+/// Identifies a statement or expression by the function that owns it and
+/// its index path from the function's body. `[2, 0]` is the first statement
+/// in the body of the loop that is the function's third statement.
+///
+/// Paths continue through expressions, numbering each node's children in
+/// source order: `[1, 0, 2]` is the second argument of the method call that
+/// makes up the function's second statement. A loop's condition follows its
+/// body statements, an arm's guard and body take two slots after the
+/// scrutinee whether or not it has a guard, and `&` and `*` share the id of
+/// their operand, so a receiver passed by address keeps its id.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct NodeId {
+    pub owner: String,
+    pub path: Vec<usize>,
+}
+
+impl NodeId {
+    pub fn new(owner: impl Into<String>, path: Vec<usize>) -> Self {
+        Self { owner: owner.into(), path }
+    }
+
+    /// The id of child `index` of this node.
+    pub fn child(&self, index: usize) -> NodeId {
+        let mut path = self.path.clone();
+        path.push(index);
+        NodeId::new(self.owner.clone(), path)
+    }
+}
+
+/// A statement or expression reached by `visit_body`.
+#[derive(Debug, Clone, Copy)]
+pub enum Node<'a> {
+    /// A statement of a function, loop or comptime body.
+    Statement(&'a Statement),
+    /// A statement inside a block expression, directly or in the body of a
+    /// loop in one.
+    BlockStatement(&'a Statement),
+    Expression(&'a Expression),
+}
+
+/// Visit the statements of `body`, the body at `path`, and every statement
+/// and expression nested in them in source order, with their paths.
+pub fn visit_body<'a>(body: &'a [Statement], path: &mut Vec<usize>, visit: &mut impl FnMut(Node<'a>, &[usize])) {
+    visit_statements(body, path, false, visit);
+}
+
+/// Visit the children of `statement`, the statement at `path`, and
+/// everything nested in them.
+pub fn visit_statement_children<'a>(statement: &'a Statement, path: &mut Vec<usize>, visit: &mut impl FnMut(Node<'a>, &[usize])) {
+    visit_children(statement, path, false, visit);
+}
+
+/// Visit `expr`, the expression at `path`, and everything nested in it.
+pub fn visit_expression<'a>(expr: &'a Expression, path: &mut Vec<usize>, visit: &mut impl FnMut(Node<'a>, &[usize])) {
+    visit(Node::Expression(expr), path);
+    let child = |index: usize, expr: &'a Expression, path: &mut Vec<usize>, visit: &mut _| {
+        path.push(index);
+        visit_expression(expr, path, visit);
+        path.pop();
+    };
+    match expr {
+        Expression::BinaryOp { left, right, .. } => {
+            child(0, left, path, visit);
+            child(1, right, path, visit);
+        }
+        Expression::UnaryOp { operand: inner, .. }
+        | Expression::StructField { struct_: inner, .. }
+        | Expression::TupleIndex { tuple: inner, .. }
+        | Expression::MemberAccess { object: inner, .. }
+        | Expression::StringLength(inner)
+        | Expression::Comptime(inner)
+        | Expression::Return(inner)
+        | Expression::TypeCast { expr: inner, .. }
+        | Expression::Closure { body: inner, .. } => child(0, inner, path, visit),
+        Expression::AddressOf(inner) | Expression::Dereference(inner) => visit_expression(inner, path, visit),
+        Expression::FunctionCall { args: elements, .. }
+        | Expression::TupleLiteral(elements)
+        | Expression::ArrayLiteral(elements) => {
+            for (index, element) in elements.iter().enumerate() {
+                child(index, element, path, visit);
+            }
+        }
+        Expression::MethodCall { object, args, .. } => {
+            child(0, object, path, visit);
+            for (index, arg) in args.iter().enumerate() {
+                child(index + 1, arg, path, visit);
+            }
+        }
+        Expression::Conditional { scrutinee, arms } => {
+            child(0, scrutinee, path, visit);
+            for (index, arm) in arms.iter().enumerate() {
+                if let Some(guard) = &arm.guard {
+                    child(2 * index + 1, guard, path, visit);
+                }
+                child(2 * index + 2, &arm.body, path, visit);
+            }
+        }
+        Expression::PatternMatch { scrutinee, arms } => {
+            child(0, scrutinee, path, visit);
+            for (index, arm) in arms.iter().enumerate() {
+                if let Some(guard) = &arm.guard {
+                    child(2 * index + 1, guard, path, visit);
+                }
+                child(2 * index + 2, &arm.body, path, visit);
+            }
+        }
+        Expression::PointerOffset { pointer: first, offset: second }
+        | Expression::ArrayIndex { array: first, index: second }
+        | Expression::Range { start: first, end: second, .. } => {
+            child(0, first, path, visit);
+            child(1, second, path, visit);
+        }
+        Expression::StructLiteral { fields, .. } => {
+            for (index, (_, value)) in fields.iter().enumerate() {
+                child(index, value, path, visit);
+            }
+        }
+        Expression::EnumVariant { payload: Some(payload), .. } => child(0, payload, path, visit),
+        Expression::StringInterpolation { parts } => {
+            for (index, part) in parts.iter().enumerate() {
+                if let StringPart::Interpolation(expr) = part {
+                    child(index, expr, path, visit);
+                }
+            }
+        }
+        Expression::Block(statements) => visit_statements(statements, path, true, visit),
+        _ => {}
+    }
+}
+
+fn visit_statements<'a>(body: &'a [Statement], path: &mut Vec<usize>, in_block: bool, visit: &mut impl FnMut(Node<'a>, &[usize])) {
+    for (index, statement) in body.iter().enumerate() {
+        path.push(index);
+        visit(if in_block { Node::BlockStatement(statement) } else { Node::Statement(statement) }, path);
+        visit_children(statement, path, in_block, visit);
+        path.pop();
+    }
+}
+
+fn visit_children<'a>(statement: &'a Statement, path: &mut Vec<usize>, in_block: bool, visit: &mut impl FnMut(Node<'a>, &[usize])) {
+    let child = |index: usize, expr: &'a Expression, path: &mut Vec<usize>, visit: &mut _| {
+        path.push(index);
+        visit_expression(expr, path, visit);
+        path.pop();
+    };
+    match statement {
+        Statement::Expression(expr)
+        | Statement::Return(expr)
+        | Statement::VariableDeclaration { initializer: Some(expr), .. }
+        | Statement::DestructuringDeclaration { initializer: expr, .. }
+        | Statement::VariableAssignment { value: expr, .. } => child(0, expr, path, visit),
+        Statement::PointerAssignment { pointer, value } | Statement::CompoundAssignment { target: pointer, value, .. } => {
+            child(0, pointer, path, visit);
+            child(1, value, path, visit);
+        }
+        Statement::Loop { kind, body, .. } => {
+            if let LoopKind::Condition(condition) = kind {
+                child(body.len(), condition, path, visit);
+            }
+            visit_statements(body, path, in_block, visit);
+        }
+        Statement::ComptimeBlock(statements) => visit_statements(statements, path, in_block, visit),
+        _ => {}
+    }
+}
+
+/// The owner name used for methods in impl blocks, e.g. `Point::area`.
+pub fn method_owner(type_name: &str, method_name: &str) -> String {
+    format!("{}::{}", type_name, method_name)
+}
+
+/// The key a top-level declaration's span is recorded under.
+pub fn declaration_key(declaration: &Declaration) -> Option<String> {
+    match declaration {
+        Declaration::Function(func) => Some(func.name.clone()),
+        Declaration::ExternalFunction(ext) => Some(ext.name.clone()),
+        Declaration::Struct(def) => Some(def.name.clone()),
+        Declaration::Enum(def) => Some(def.name.clone()),
+        Declaration::Behavior(def) => Some(def.name.clone()),
+        Declaration::TypeAlias(alias) => Some(alias.name.clone()),
+        Declaration::Impl(block) => Some(match &block.behavior_name {
+            Some(behavior) => format!("impl {} for {}", behavior, block.target()),
+            None => format!("impl {}", block.target()),
+        }),
+        Declaration::ModuleImport { alias, .. } => Some(alias.clone()),
+        Declaration::ComptimeBlock(_) | Declaration::Error(_) => None,
+    }
+}
+
+/// How the parser read a token that the tokens around it don't settle,
+/// such as `<` opening type arguments rather than comparing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenRole {
+    /// A `<` or `>` around type arguments or type parameters.
+    TypeArguments,
+    /// A prefix operator or pointer type: `-x`, `!done`, `*T`, `&T`.
+    Prefix,
+    /// The `::` between an enum and its variant.
+    Path,
+    /// The `(` of call arguments or the `[` of an index.
+    Postfix,
+    /// The first `>` of `>>` or `>>=`, which are lexed as two tokens.
+    ShiftRight,
+}
+
+/// Spans recorded by the parser for declarations, statements and
+/// identifier tokens, and the roles of ambiguous tokens.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SpanTable {
+    declarations: HashMap<String, Span>,
+    statements: HashMap<NodeId, Span>,
+    /// Identifier tokens by start offset, so re-lexing after lookahead
+    /// doesn't record duplicates.
+    identifiers: BTreeMap<usize, (String, Span)>,
+    token_roles: BTreeMap<usize, TokenRole>,
+    /// Monomorphized function names mapped to the generic they came from.
+    aliases: HashMap<String, String>,
+}
+
+impl SpanTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.declarations.is_empty() && self.statements.is_empty()
+    }
+
+    pub fn record_declaration(&mut self, key: impl Into<String>, span: Span) {
+        self.declarations.insert(key.into(), span);
+    }
+
+    pub fn record_statement(&mut self, id: NodeId, span: Span) {
+        self.statements.insert(id, span);
+    }
+
+    pub fn record_identifier(&mut self, name: &str, span: Span) {
+        self.identifiers.insert(span.start, (name.to_string(), span));
+    }
+
+    pub fn record_token_role(&mut self, start: usize, role: TokenRole) {
+        self.token_roles.insert(start, role);
+    }
+
+    /// Drop the roles of tokens from offset `start` on, which the parser
+    /// is about to read again after backtracking.
+    pub fn forget_token_roles_from(&mut self, start: usize) {
+        self.token_roles.split_off(&start);
+    }
+
+    /// The role of the token starting at offset `start`, if the parser
+    /// recorded one.
+    pub fn token_role(&self, start: usize) -> Option<TokenRole> {
+        self.token_roles.get(&start).copied()
+    }
+
+    /// Record that `instance` was generated from `original`, so statements
+    /// in the instance resolve to the generic function's source.
+    pub fn record_alias(&mut self, instance: impl Into<String>, original: impl Into<String>) {
+        self.aliases.insert(instance.into(), original.into());
+    }
+
+    pub fn declaration(&self, key: &str) -> Option<&Span> {
+        self.declarations
+            .get(key)
+            .or_else(|| self.aliases.get(key).and_then(|original| self.declaration(original)))
+    }
+
+    /// The span of the statement `id`, or of its closest recorded ancestor.
+    pub fn statement(&self, id: &NodeId) -> Option<&Span> {
+        let owner = self.resolve_owner(&id.owner);
+        (1..=id.path.len()).rev().find_map(|len| {
+            self.statements.get(&NodeId::new(owner, id.path[..len].to_vec()))
+        })
+    }
+
+    /// The first occurrence of identifier `name` inside `within`.
+    pub fn identifier_in(&self, name: &str, within: &Span) -> Option<&Span> {
+        self.identifiers
+            .range(within.start..within.end)
+            .find(|(_, (ident, _))| ident == name)
+            .map(|(_, (_, span))| span)
+    }
+
+    /// Best known location for whatever `cursor` is visiting: the current
+    /// statement, falling back to the owning declaration.
+    pub fn locate(&self, cursor: &SpanCursor) -> Option<&Span> {
+        let owner = cursor.owner.as_ref()?;
+        self.statement(&NodeId::new(owner.clone(), cursor.path.clone()))
+            .or_else(|| self.declaration(owner))
+    }
+
+    /// Give `error` a span if it doesn't have one yet. Errors naming an
+    /// identifier are narrowed to where that identifier is used.
+    pub fn attach(&self, cursor: &SpanCursor, error: CompileError) -> CompileError {
+        if error.span().is_some() {
+            return error;
+        }
+        let Some(span) = self.locate(cursor) else {
+            return error;
+        };
+        let span = match &error {
+            CompileError::UndeclaredVariable(name, _) | CompileError::UndeclaredFunction(name, _) => {
+                self.identifier_in(name, span).unwrap_or(span)
+            }
+            _ => span,
+        };
+        error.with_span(span.clone())
+    }
+
+    fn resolve_owner<'a>(&'a self, owner: &'a str) -> &'a str {
+        self.aliases.get(owner).map(String::as_str).unwrap_or(owner)
+    }
+}
+
+/// Tracks which node a pass is visiting, mirroring how the parser numbers
+/// them. Passes `push` the index of each statement and expression they
+/// descend into, so the cursor's path is the id of the node being visited,
+/// and a copy visited in place of a node gets the node's id.
+#[derive(Debug, Clone, Default)]
+pub struct SpanCursor {
+    owner: Option<String>,
+    path: Vec<usize>,
+    suspended: usize,
+}
+
+impl SpanCursor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start visiting the body of `owner`.
+    pub fn enter_owner(&mut self, owner: impl Into<String>) {
+        self.owner = Some(owner.into());
+        self.path.clear();
+        self.suspended = 0;
+    }
+
+    pub fn leave_owner(&mut self) {
+        self.owner = None;
+        self.path.clear();
+        self.suspended = 0;
+    }
+
+    /// Descend into child `index` of the node being visited, or statement
+    /// `index` of the current body.
+    pub fn push(&mut self, index: usize) {
+        if self.suspended == 0 {
+            self.path.push(index);
+        }
+    }
+
+    pub fn pop(&mut self) {
+        if self.suspended == 0 {
+            self.path.pop();
+        }
+    }
+
+    /// Stop numbering statements, e.g. while the parser reads a block
+    /// expression, whose statements are numbered once the statement holding
+    /// it is complete. Errors are then attributed to the enclosing statement.
+    pub fn suspend(&mut self) {
+        self.suspended += 1;
+    }
+
+    pub fn resume(&mut self) {
+        self.suspended = self.suspended.saturating_sub(1);
+    }
+
+    /// The node being visited, unless numbering is suspended.
+    pub fn current(&self) -> Option<NodeId> {
+        match &self.owner {
+            Some(owner) if self.suspended == 0 && !self.path.is_empty() => {
+                Some(NodeId::new(owner.clone(), self.path.clone()))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn owner(&self) -> Option<&str> {
+        self.owner.as_deref()
+    }
+}
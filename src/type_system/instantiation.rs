@@ -1,4 +1,5 @@
 use crate::ast::{AstType, Function, StructDefinition, EnumDefinition, Statement, Expression};
+use crate::mangle;
 use super::{TypeEnvironment, TypeSubstitution};
 
 pub struct TypeInstantiator<'a> {
@@ -22,7 +23,7 @@ impl<'a> TypeInstantiator<'a> {
             substitution.add(param.name.clone(), arg.clone());
         }
         
-        let instantiated_name = generate_instantiated_name(&func.name, &type_args);
+        let instantiated_name = mangle::instantiate(&func.name, &type_args);
         
         let instantiated_args: Vec<(String, AstType)> = func.args
             .iter()
@@ -55,7 +56,7 @@ impl<'a> TypeInstantiator<'a> {
             substitution.add(param.name.clone(), arg.clone());
         }
         
-        let instantiated_name = generate_instantiated_name(&struct_def.name, &type_args);
+        let instantiated_name = mangle::instantiate(&struct_def.name, &type_args);
         
         let instantiated_fields = struct_def.fields
             .iter()
@@ -92,7 +93,7 @@ impl<'a> TypeInstantiator<'a> {
             substitution.add(param.name.clone(), arg.clone());
         }
         
-        let instantiated_name = generate_instantiated_name(&enum_def.name, &type_args);
+        let instantiated_name = mangle::instantiate(&enum_def.name, &type_args);
         
         let instantiated_variants = enum_def.variants
             .iter()
@@ -163,6 +164,13 @@ impl<'a> TypeInstantiator<'a> {
                     declaration_type: declaration_type.clone(),
                 })
             }
+            Statement::DestructuringDeclaration { pattern, initializer, is_mutable } => {
+                Ok(Statement::DestructuringDeclaration {
+                    pattern: pattern.clone(),
+                    initializer: self.instantiate_expression(initializer, substitution),
+                    is_mutable: *is_mutable,
+                })
+            }
             Statement::Expression(expr) => {
                 Ok(Statement::Expression(self.instantiate_expression(expr, substitution)))
             }
@@ -200,6 +208,12 @@ impl<'a> TypeInstantiator<'a> {
                     right: Box::new(self.instantiate_expression(right, substitution)),
                 }
             }
+            Expression::UnaryOp { op, operand } => {
+                Expression::UnaryOp {
+                    op: *op,
+                    operand: Box::new(self.instantiate_expression(operand, substitution)),
+                }
+            }
             Expression::StructLiteral { name, fields } => {
                 Expression::StructLiteral {
                     name: name.clone(),
@@ -215,6 +229,15 @@ impl<'a> TypeInstantiator<'a> {
             Expression::ArrayLiteral(items) => {
                 Expression::ArrayLiteral(items.iter().map(|e| self.instantiate_expression(e, substitution)).collect())
             }
+            Expression::TupleLiteral(items) => {
+                Expression::TupleLiteral(items.iter().map(|e| self.instantiate_expression(e, substitution)).collect())
+            }
+            Expression::TupleIndex { tuple, index } => {
+                Expression::TupleIndex {
+                    tuple: Box::new(self.instantiate_expression(tuple, substitution)),
+                    index: *index,
+                }
+            }
             Expression::Dereference(expr) => {
                 Expression::Dereference(Box::new(self.instantiate_expression(expr, substitution)))
             }
@@ -235,41 +258,3 @@ impl<'a> TypeInstantiator<'a> {
         }
     }
 }
-
-fn generate_instantiated_name(base_name: &str, type_args: &[AstType]) -> String {
-    if type_args.is_empty() {
-        return base_name.to_string();
-    }
-    
-    let type_names: Vec<String> = type_args.iter().map(type_to_string).collect();
-    format!("{}_{}", base_name, type_names.join("_"))
-}
-
-fn type_to_string(ast_type: &AstType) -> String {
-    match ast_type {
-        AstType::I8 => "i8".to_string(),
-        AstType::I16 => "i16".to_string(),
-        AstType::I32 => "i32".to_string(),
-        AstType::I64 => "i64".to_string(),
-        AstType::U8 => "u8".to_string(),
-        AstType::U16 => "u16".to_string(),
-        AstType::U32 => "u32".to_string(),
-        AstType::U64 => "u64".to_string(),
-        AstType::F32 => "f32".to_string(),
-        AstType::F64 => "f64".to_string(),
-        AstType::Bool => "bool".to_string(),
-        AstType::String => "string".to_string(),
-        AstType::Void => "void".to_string(),
-        AstType::Pointer(inner) => format!("ptr_{}", type_to_string(inner)),
-        AstType::Array(inner) => format!("arr_{}", type_to_string(inner)),
-        AstType::Generic { name, type_args } => {
-            if type_args.is_empty() {
-                name.clone()
-            } else {
-                let args: Vec<String> = type_args.iter().map(type_to_string).collect();
-                format!("{}_{}", name, args.join("_"))
-            }
-        }
-        _ => "unknown".to_string(),
-    }
-}
\ No newline at end of file
@@ -74,6 +74,9 @@ impl TypeSubstitution {
                     return_type: Box::new(self.apply(return_type)),
                 }
             }
+            AstType::Tuple(elements) => {
+                AstType::Tuple(elements.iter().map(|t| self.apply(t)).collect())
+            }
             _ => ast_type.clone(),
         }
     }
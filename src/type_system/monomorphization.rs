@@ -1,6 +1,9 @@
-use crate::ast::{Program, Declaration, Expression, AstType, Function};
+use crate::ast::{Program, Declaration, Expression, AstType, Function, ImplBlock};
 use super::{TypeEnvironment, TypeInstantiator};
-use crate::typechecker::TypeChecker;
+use crate::error::{CompileError, Span};
+use crate::mangle;
+use crate::spans::{self, NodeId, SpanCursor, SpanTable};
+use crate::typechecker::{unify, TypeTable};
 use std::collections::{HashMap, HashSet};
 
 pub struct Monomorphizer {
@@ -8,7 +11,15 @@ pub struct Monomorphizer {
     instantiated_functions: HashMap<String, Function>,
     pending_instantiations: Vec<(String, Vec<AstType>)>,
     processed_instantiations: HashSet<(String, Vec<AstType>)>,
-    type_checker: TypeChecker,
+    spans: SpanTable,
+    span_cursor: SpanCursor,
+    // Type arguments the type checker inferred for generic calls
+    types: TypeTable,
+    // Instantiated function name -> generic name and its type arguments
+    instances: HashMap<String, (String, Vec<(String, AstType)>)>,
+    // Methods of generic impl blocks, registered as generic functions under
+    // their symbols -> the owner the type checker recorded them under
+    template_owners: HashMap<String, String>,
 }
 
 impl Monomorphizer {
@@ -18,16 +29,54 @@ impl Monomorphizer {
             instantiated_functions: HashMap::new(),
             pending_instantiations: Vec::new(),
             processed_instantiations: HashSet::new(),
-            type_checker: TypeChecker::new(),
+            spans: SpanTable::new(),
+            span_cursor: SpanCursor::new(),
+            types: TypeTable::new(),
+            instances: HashMap::new(),
+            template_owners: HashMap::new(),
         }
     }
 
-    pub fn monomorphize_program(&mut self, program: &Program) -> Result<Program, String> {
+    /// Take generic call type arguments from the type checker's results
+    /// rather than guessing them from argument shapes.
+    pub fn with_type_table(mut self, types: TypeTable) -> Self {
+        self.types = types;
+        self
+    }
+
+    /// Use the parser's span table. Instantiated functions are recorded as
+    /// aliases of their generic so later passes can still locate them.
+    pub fn with_spans(mut self, spans: SpanTable) -> Self {
+        self.spans = spans;
+        self
+    }
+
+    /// The span table, including aliases for every instantiation made.
+    pub fn spans(&self) -> &SpanTable {
+        &self.spans
+    }
+
+    /// Where the statement being processed when the last error occurred
+    /// came from.
+    pub fn error_span(&self) -> Option<&Span> {
+        self.spans.locate(&self.span_cursor)
+    }
+
+    /// Instantiate every generic used by `program`. The program must
+    /// already have been type checked.
+    pub fn monomorphize_program(&mut self, program: &Program) -> crate::error::Result<Program> {
+        self.instantiate_program(program).map_err(|message| {
+            let error = CompileError::TypeError(message, None);
+            match self.error_span() {
+                Some(span) => error.with_span(span.clone()),
+                None => error,
+            }
+        })
+    }
+
+    fn instantiate_program(&mut self, program: &Program) -> Result<Program, String> {
         let mut declarations = Vec::new();
         
-        // First, type check the program to get type information
-        self.type_checker.check_program(program).map_err(|e| e.to_string())?;
-        
         for decl in &program.declarations {
             match decl {
                 Declaration::Function(func) if !func.type_params.is_empty() => {
@@ -39,6 +88,19 @@ impl Monomorphizer {
                 Declaration::Enum(enum_def) if !enum_def.type_params.is_empty() => {
                     self.env.register_generic_enum(enum_def.clone());
                 }
+                Declaration::Impl(block) if block.is_generic() => {
+                    for method in self.impl_methods(block) {
+                        let symbol = block.method_symbol(&method.name);
+                        self.template_owners.insert(symbol.clone(), spans::method_owner(&block.type_name, &method.name));
+                        self.env.register_generic_function(Function { name: symbol, ..method });
+                    }
+                }
+                Declaration::Impl(block) => {
+                    // Inherited default methods become the block's own, so
+                    // their calls are instantiated and rewritten too
+                    let methods = self.impl_methods(block);
+                    declarations.push(Declaration::Impl(ImplBlock { methods, ..block.clone() }));
+                }
                 _ => declarations.push(decl.clone()),
             }
         }
@@ -59,9 +121,13 @@ impl Monomorphizer {
                 
                 if let Some(func) = self.env.get_generic_function(&name).cloned() {
                     let mut instantiator = TypeInstantiator::new(&mut self.env);
-                    let instantiated = instantiator.instantiate_function(&func, type_args)?;
+                    let instantiated = instantiator.instantiate_function(&func, type_args.clone())?;
                     
-                    self.collect_instantiations_from_function(&instantiated)?;
+                    let owner = self.template_owners.get(&func.name).cloned().unwrap_or_else(|| func.name.clone());
+                    self.spans.record_alias(instantiated.name.clone(), owner.clone());
+                    let params = func.type_params.iter().map(|param| param.name.clone()).zip(type_args).collect();
+                    self.instances.insert(instantiated.name.clone(), (owner, params));
+                    self.collect_instantiations_from_function(&instantiated.name, &instantiated)?;
                     
                     declarations.push(Declaration::Function(instantiated.clone()));
                     self.instantiated_functions.insert(instantiated.name.clone(), instantiated);
@@ -83,18 +149,33 @@ impl Monomorphizer {
         Ok(Program { declarations: transformed_declarations })
     }
 
+    /// The methods of an impl block with the default methods it inherits.
+    fn impl_methods(&self, block: &ImplBlock) -> Vec<Function> {
+        let behavior = block.behavior_name.as_ref().and_then(|name| self.types.behaviors.get_definition(name));
+        block.all_methods(behavior)
+    }
+
     fn collect_instantiations_from_declaration(&mut self, decl: &Declaration) -> Result<(), String> {
         match decl {
-            Declaration::Function(func) => self.collect_instantiations_from_function(func),
+            // Calls inside generic bodies are collected from each instantiation
+            Declaration::Function(func) if !func.type_params.is_empty() => Ok(()),
+            Declaration::Impl(block) if block.is_generic() => Ok(()),
+            Declaration::Function(func) => self.collect_instantiations_from_function(&func.name, func),
+            Declaration::Impl(block) => {
+                for method in self.impl_methods(block) {
+                    self.collect_instantiations_from_function(&spans::method_owner(&block.type_name, &method.name), &method)?;
+                }
+                Ok(())
+            }
             Declaration::Struct(struct_def) => {
                 for method in &struct_def.methods {
-                    self.collect_instantiations_from_function(method)?;
+                    self.collect_instantiations_from_function(&method.name, method)?;
                 }
                 Ok(())
             }
             Declaration::Enum(enum_def) => {
                 for method in &enum_def.methods {
-                    self.collect_instantiations_from_function(method)?;
+                    self.collect_instantiations_from_function(&method.name, method)?;
                 }
                 Ok(())
             }
@@ -102,9 +183,21 @@ impl Monomorphizer {
         }
     }
 
-    fn collect_instantiations_from_function(&mut self, func: &Function) -> Result<(), String> {
-        for stmt in &func.body {
+    /// Collect the instantiations `func` needs, with its statements
+    /// located under `owner`.
+    fn collect_instantiations_from_function(&mut self, owner: &str, func: &Function) -> Result<(), String> {
+        self.span_cursor.enter_owner(owner);
+        self.collect_instantiations_from_body(&func.body)?;
+        self.span_cursor.leave_owner();
+        Ok(())
+    }
+
+    fn collect_instantiations_from_body(&mut self, body: &[crate::ast::Statement]) -> Result<(), String> {
+        // On error the cursor is left on the failing statement for `error_span`
+        for (index, stmt) in body.iter().enumerate() {
+            self.span_cursor.push(index);
             self.collect_instantiations_from_statement(stmt)?;
+            self.span_cursor.pop();
         }
         Ok(())
     }
@@ -112,65 +205,71 @@ impl Monomorphizer {
     fn collect_instantiations_from_statement(&mut self, stmt: &crate::ast::Statement) -> Result<(), String> {
         match stmt {
             crate::ast::Statement::Expression(expr) => {
-                self.collect_instantiations_from_expression(expr)
+                self.collect_from_child(0, expr)
             }
             crate::ast::Statement::Return(expr) => {
-                self.collect_instantiations_from_expression(expr)
+                self.collect_from_child(0, expr)
             }
             crate::ast::Statement::VariableDeclaration { initializer, type_, .. } => {
                 if let Some(init) = initializer {
-                    self.collect_instantiations_from_expression(init)?;
+                    self.collect_from_child(0, init)?;
                 }
                 if let Some(ty) = type_ {
                     self.collect_instantiations_from_type(ty)?;
                 }
                 Ok(())
             }
+            crate::ast::Statement::DestructuringDeclaration { initializer, .. } => {
+                self.collect_from_child(0, initializer)
+            }
             crate::ast::Statement::Loop { kind, body, .. } => {
                 use crate::ast::LoopKind;
                 match kind {
                     LoopKind::Condition(expr) => {
-                        self.collect_instantiations_from_expression(expr)?;
+                        self.collect_from_child(body.len(), expr)?;
                     }
                     LoopKind::Infinite => {}
                 }
-                for stmt in body {
-                    self.collect_instantiations_from_statement(stmt)?;
-                }
-                Ok(())
+                self.collect_instantiations_from_body(body)
             }
             _ => Ok(()),
         }
     }
 
+    /// Collect the instantiations `expr`, child `index` of the node under
+    /// the cursor, needs.
+    fn collect_from_child(&mut self, index: usize, expr: &Expression) -> Result<(), String> {
+        self.span_cursor.push(index);
+        let result = self.collect_instantiations_from_expression(expr);
+        self.span_cursor.pop();
+        result
+    }
+
     fn collect_instantiations_from_expression(&mut self, expr: &Expression) -> Result<(), String> {
         match expr {
             Expression::FunctionCall { name, args } => {
                 // Check if this is a generic function
-                let base_name = extract_base_name(name);
-                if let Some(generic_func) = self.env.get_generic_function(&base_name) {
-                    // Infer type arguments from the call arguments
-                    let type_args = self.infer_type_arguments(&generic_func, args)?;
+                if let Some(generic_func) = self.env.get_generic_function(name) {
+                    let type_args = self.call_type_arguments(generic_func, args)?;
                     if !type_args.is_empty() {
-                        self.pending_instantiations.push((base_name, type_args));
+                        self.pending_instantiations.push((name.clone(), type_args));
                     }
                 }
                 
-                for arg in args {
-                    self.collect_instantiations_from_expression(arg)?;
+                for (index, arg) in args.iter().enumerate() {
+                    self.collect_from_child(index, arg)?;
                 }
                 Ok(())
             }
             Expression::StructLiteral { name, fields } => {
                 // Check if this is a generic struct by checking if it exists in the environment
-                let base_name = extract_base_name(name);
-                if self.env.get_generic_struct(&base_name).is_some() {
+                if self.env.get_generic_struct(name).is_some() {
                     // Infer type arguments from field values
                     let mut type_args = Vec::new();
                     
-                    for (_, field_expr) in fields {
+                    for (index, (_, field_expr)) in fields.iter().enumerate() {
                         // First collect instantiations from the field expression
-                        self.collect_instantiations_from_expression(field_expr)?;
+                        self.collect_from_child(index, field_expr)?;
                         
                         // Then try to infer its type for struct instantiation
                         if let Ok(field_type) = self.infer_expression_type(field_expr) {
@@ -183,36 +282,57 @@ impl Monomorphizer {
                     }
                     
                     if !type_args.is_empty() {
-                        self.pending_instantiations.push((base_name, type_args));
+                        self.pending_instantiations.push((name.clone(), type_args));
                     }
                 } else {
                     // Not a generic struct, just process field expressions
-                    for (_, expr) in fields {
-                        self.collect_instantiations_from_expression(expr)?;
+                    for (index, (_, expr)) in fields.iter().enumerate() {
+                        self.collect_from_child(index, expr)?;
                     }
                 }
                 Ok(())
             }
             Expression::BinaryOp { left, right, .. } => {
-                self.collect_instantiations_from_expression(left)?;
-                self.collect_instantiations_from_expression(right)
+                self.collect_from_child(0, left)?;
+                self.collect_from_child(1, right)
+            }
+            Expression::UnaryOp { operand, .. } => self.collect_from_child(0, operand),
+            Expression::TupleIndex { tuple, .. } => self.collect_from_child(0, tuple),
+            Expression::TupleLiteral(elements) => {
+                for (index, element) in elements.iter().enumerate() {
+                    self.collect_from_child(index, element)?;
+                }
+                Ok(())
+            }
+            Expression::MethodCall { object, args, .. } => {
+                if let Some((function, type_args, _)) = self.resolve_generic_method_call() {
+                    if !type_args.is_empty() {
+                        self.pending_instantiations.push((function, type_args));
+                    }
+                }
+                self.collect_from_child(0, object)?;
+                for (index, arg) in args.iter().enumerate() {
+                    self.collect_from_child(index + 1, arg)?;
+                }
+                Ok(())
             }
             Expression::Conditional { scrutinee, arms } => {
-                self.collect_instantiations_from_expression(scrutinee)?;
-                for arm in arms {
+                self.collect_from_child(0, scrutinee)?;
+                for (index, arm) in arms.iter().enumerate() {
                     if let Some(guard) = &arm.guard {
-                        self.collect_instantiations_from_expression(guard)?;
+                        self.collect_from_child(2 * index + 1, guard)?;
                     }
-                    self.collect_instantiations_from_expression(&arm.body)?;
+                    self.collect_from_child(2 * index + 2, &arm.body)?;
                 }
                 Ok(())
             }
+            // `&` and `*` share the id of their operand
             Expression::Dereference(expr) | Expression::AddressOf(expr) => {
                 self.collect_instantiations_from_expression(expr)
             }
             Expression::ArrayLiteral(items) => {
-                for item in items {
-                    self.collect_instantiations_from_expression(item)?;
+                for (index, item) in items.iter().enumerate() {
+                    self.collect_from_child(index, item)?;
                 }
                 Ok(())
             }
@@ -249,10 +369,59 @@ impl Monomorphizer {
                 }
                 self.collect_instantiations_from_type(return_type)
             }
+            AstType::Tuple(elements) => {
+                for element in elements {
+                    self.collect_instantiations_from_type(element)?;
+                }
+                Ok(())
+            }
             _ => Ok(()),
         }
     }
     
+    /// The type arguments of the call under the cursor, a call to
+    /// `generic_func`. Inside an instantiation the type checker's answer
+    /// for the generic body is specialised with the instantiation's own
+    /// type arguments.
+    fn call_type_arguments(&self, generic_func: &Function, args: &[Expression]) -> Result<Vec<AstType>, String> {
+        if let Some(id) = self.span_cursor.current() {
+            let (id, params) = self.generic_id(id);
+            if let Some(type_args) = self.types.type_arguments(&id) {
+                if type_args.len() == generic_func.type_params.len() {
+                    return Ok(type_args.iter().map(|arg| unify::substitute(arg, params)).collect());
+                }
+            }
+        }
+        self.infer_type_arguments(generic_func, args)
+    }
+
+    /// The function the method call under the cursor calls when that
+    /// depends on generics, with the type arguments to instantiate it with
+    /// (none when it isn't generic) and the call's arguments: a method of a
+    /// generic implementation, or in an instantiation, a method of a bound
+    /// of a type parameter.
+    fn resolve_generic_method_call(&self) -> Option<(String, Vec<AstType>, Vec<Expression>)> {
+        let (id, params) = self.generic_id(self.span_cursor.current()?);
+        if let Some(resolved) = self.types.generic_method_call(&id) {
+            let type_args = resolved.type_args.iter().map(|arg| unify::substitute(arg, params)).collect();
+            return Some((resolved.function.clone(), type_args, resolved.args.clone()));
+        }
+        let bound = self.types.bound_method_call(&id)?;
+        let (_, type_) = params.iter().find(|(param, _)| *param == bound.type_param)?;
+        let (function, type_args) = bound.resolve(type_, &self.types.behaviors)?;
+        Some((function, type_args, bound.args.clone()))
+    }
+
+    /// The id the type checker knows node `id` by: inside an instantiation,
+    /// that of the node in the generic body, with the instantiation's type
+    /// arguments.
+    fn generic_id(&self, id: NodeId) -> (NodeId, &[(String, AstType)]) {
+        match self.instances.get(&id.owner) {
+            Some((generic, params)) => (NodeId::new(generic.clone(), id.path), params.as_slice()),
+            None => (id, &[]),
+        }
+    }
+
     fn infer_type_arguments(&self, generic_func: &Function, args: &[Expression]) -> Result<Vec<AstType>, String> {
         let mut type_args = Vec::new();
         
@@ -318,9 +487,17 @@ impl Monomorphizer {
         for decl in declarations {
             match decl {
                 Declaration::Function(func) => {
-                    let transformed_func = self.transform_function(func)?;
+                    let owner = func.name.clone();
+                    let transformed_func = self.transform_function(owner, func)?;
                     result.push(Declaration::Function(transformed_func));
                 }
+                Declaration::Impl(mut block) => {
+                    block.methods = std::mem::take(&mut block.methods)
+                        .into_iter()
+                        .map(|method| self.transform_function(spans::method_owner(&block.type_name, &method.name), method))
+                        .collect::<Result<_, _>>()?;
+                    result.push(Declaration::Impl(block));
+                }
                 other => result.push(other),
             }
         }
@@ -328,73 +505,112 @@ impl Monomorphizer {
         Ok(result)
     }
     
-    fn transform_function(&mut self, mut func: Function) -> Result<Function, String> {
-        func.body = self.transform_statements(func.body)?;
+    /// Rewrite the calls in `func`, whose statements are located under
+    /// `owner`.
+    fn transform_function(&mut self, owner: String, mut func: Function) -> Result<Function, String> {
+        self.span_cursor.enter_owner(owner);
+        let body = self.transform_statements(&func.body)?;
+        self.span_cursor.leave_owner();
+        func.body = body;
         Ok(func)
     }
     
-    fn transform_statements(&mut self, statements: Vec<crate::ast::Statement>) -> Result<Vec<crate::ast::Statement>, String> {
+    fn transform_statements(&mut self, statements: &[crate::ast::Statement]) -> Result<Vec<crate::ast::Statement>, String> {
         let mut result = Vec::new();
         
-        for stmt in statements {
+        for (index, stmt) in statements.iter().enumerate() {
+            self.span_cursor.push(index);
             result.push(self.transform_statement(stmt)?);
+            self.span_cursor.pop();
         }
         
         Ok(result)
     }
     
-    fn transform_statement(&mut self, stmt: crate::ast::Statement) -> Result<crate::ast::Statement, String> {
+    fn transform_statement(&mut self, stmt: &crate::ast::Statement) -> Result<crate::ast::Statement, String> {
         match stmt {
             crate::ast::Statement::Expression(expr) => {
-                Ok(crate::ast::Statement::Expression(self.transform_expression(expr)?))
+                Ok(crate::ast::Statement::Expression(self.transform_child(0, expr)?))
             }
             crate::ast::Statement::Return(expr) => {
-                Ok(crate::ast::Statement::Return(self.transform_expression(expr)?))
+                Ok(crate::ast::Statement::Return(self.transform_child(0, expr)?))
             }
             crate::ast::Statement::VariableDeclaration { name, type_, initializer, is_mutable, declaration_type } => {
                 let transformed_init = if let Some(init) = initializer {
-                    Some(self.transform_expression(init)?)
+                    Some(self.transform_child(0, init)?)
                 } else {
                     None
                 };
                 Ok(crate::ast::Statement::VariableDeclaration {
-                    name,
-                    type_,
+                    name: name.clone(),
+                    type_: type_.clone(),
                     initializer: transformed_init,
-                    is_mutable,
-                    declaration_type,
+                    is_mutable: *is_mutable,
+                    declaration_type: declaration_type.clone(),
+                })
+            }
+            crate::ast::Statement::DestructuringDeclaration { pattern, initializer, is_mutable } => {
+                Ok(crate::ast::Statement::DestructuringDeclaration {
+                    pattern: pattern.clone(),
+                    initializer: self.transform_child(0, initializer)?,
+                    is_mutable: *is_mutable,
                 })
             }
             crate::ast::Statement::VariableAssignment { name, value } => {
                 Ok(crate::ast::Statement::VariableAssignment {
-                    name,
-                    value: self.transform_expression(value)?,
+                    name: name.clone(),
+                    value: self.transform_child(0, value)?,
+                })
+            }
+            crate::ast::Statement::Loop { kind, label, body } => {
+                use crate::ast::LoopKind;
+                let kind = match kind {
+                    LoopKind::Condition(expr) => LoopKind::Condition(self.transform_child(body.len(), expr)?),
+                    LoopKind::Infinite => LoopKind::Infinite,
+                };
+                Ok(crate::ast::Statement::Loop {
+                    kind,
+                    label: label.clone(),
+                    body: self.transform_statements(body)?,
                 })
             }
-            other => Ok(other),
+            other => Ok(other.clone()),
         }
     }
     
-    fn transform_expression(&mut self, expr: Expression) -> Result<Expression, String> {
+    /// Rewrite `expr`, child `index` of the node under the cursor.
+    fn transform_child(&mut self, index: usize, expr: &Expression) -> Result<Expression, String> {
+        self.span_cursor.push(index);
+        let result = self.transform_expression(expr);
+        self.span_cursor.pop();
+        result
+    }
+
+    fn transform_children(&mut self, elements: &[Expression]) -> Result<Vec<Expression>, String> {
+        elements
+            .iter()
+            .enumerate()
+            .map(|(index, element)| self.transform_child(index, element))
+            .collect()
+    }
+
+    fn transform_expression(&mut self, expr: &Expression) -> Result<Expression, String> {
         match expr {
             Expression::FunctionCall { name, args } => {
-                // Check if this is a call to a generic function that has been monomorphized
-                let base_name = extract_base_name(&name);
+                // Check if this is a call to a generic function that has been
+                // monomorphized, before its arguments are rewritten
+                let type_args = match self.env.get_generic_function(name) {
+                    Some(generic_func) => Some(self.call_type_arguments(generic_func, args)?),
+                    None => None,
+                };
                 
                 // Transform the arguments first
-                let transformed_args: Vec<Expression> = args.into_iter()
-                    .map(|arg| self.transform_expression(arg))
-                    .collect::<Result<Vec<_>, _>>()?;
+                let transformed_args = self.transform_children(args)?;
                 
-                // If this is a generic function, we need to determine which instantiation to use
-                if self.env.get_generic_function(&base_name).is_some() {
-                    // Infer the types of the arguments to determine the instantiation
-                    let arg_types: Vec<AstType> = transformed_args.iter()
-                        .map(|arg| self.infer_expression_type(arg))
-                        .collect::<Result<Vec<_>, _>>()?;
-                    
+                // If this is a generic function, use the instantiation collected for this call
+                if let Some(type_args) = type_args {
                     // Generate the monomorphized name
-                    let instantiated_name = generate_instantiated_name(&base_name, &arg_types);
+                    let instantiated_name = mangle::instantiate(name, &type_args);
                     
                     Ok(Expression::FunctionCall {
                         name: instantiated_name,
@@ -402,36 +618,49 @@ impl Monomorphizer {
                     })
                 } else {
                     Ok(Expression::FunctionCall {
-                        name,
+                        name: name.clone(),
                         args: transformed_args,
                     })
                 }
             }
             Expression::BinaryOp { left, op, right } => {
                 Ok(Expression::BinaryOp {
-                    left: Box::new(self.transform_expression(*left)?),
-                    op,
-                    right: Box::new(self.transform_expression(*right)?),
+                    left: Box::new(self.transform_child(0, left)?),
+                    op: op.clone(),
+                    right: Box::new(self.transform_child(1, right)?),
+                })
+            }
+            Expression::UnaryOp { op, operand } => {
+                Ok(Expression::UnaryOp {
+                    op: *op,
+                    operand: Box::new(self.transform_child(0, operand)?),
+                })
+            }
+            Expression::TupleLiteral(elements) => Ok(Expression::TupleLiteral(self.transform_children(elements)?)),
+            Expression::TupleIndex { tuple, index } => {
+                Ok(Expression::TupleIndex {
+                    tuple: Box::new(self.transform_child(0, tuple)?),
+                    index: *index,
                 })
             }
             Expression::StructLiteral { name, fields } => {
                 // Transform field expressions
-                let transformed_fields: Vec<(String, Expression)> = fields.into_iter()
-                    .map(|(field_name, field_expr)| {
-                        self.transform_expression(field_expr)
-                            .map(|expr| (field_name, expr))
+                let transformed_fields: Vec<(String, Expression)> = fields.iter()
+                    .enumerate()
+                    .map(|(index, (field_name, field_expr))| {
+                        self.transform_child(index, field_expr)
+                            .map(|expr| (field_name.clone(), expr))
                     })
                     .collect::<Result<Vec<_>, _>>()?;
                 
                 // Check if this is a generic struct that needs monomorphization
-                let base_name = extract_base_name(&name);
-                if self.env.get_generic_struct(&base_name).is_some() {
+                if self.env.get_generic_struct(name).is_some() {
                     // Infer types from field values to determine the instantiation
                     // For now, we'll use a simplified approach that looks for specific patterns
                     // This should be enhanced with proper type inference
                     
                     // Try to infer the type from the fields
-                    if let Some(_struct_def) = self.env.get_generic_struct(&base_name).cloned() {
+                    if let Some(_struct_def) = self.env.get_generic_struct(name).cloned() {
                         // Collect type arguments based on field types
                         let mut type_args = Vec::new();
                         
@@ -447,7 +676,7 @@ impl Monomorphizer {
                         }
                         
                         if !type_args.is_empty() {
-                            let instantiated_name = generate_instantiated_name(&base_name, &type_args);
+                            let instantiated_name = mangle::instantiate(name, &type_args);
                             return Ok(Expression::StructLiteral {
                                 name: instantiated_name,
                                 fields: transformed_fields,
@@ -457,62 +686,43 @@ impl Monomorphizer {
                 }
                 
                 Ok(Expression::StructLiteral {
-                    name,
+                    name: name.clone(),
                     fields: transformed_fields,
                 })
             }
             Expression::MemberAccess { object, member } => {
                 Ok(Expression::MemberAccess {
-                    object: Box::new(self.transform_expression(*object)?),
-                    member,
+                    object: Box::new(self.transform_child(0, object)?),
+                    member: member.clone(),
                 })
             }
-            other => Ok(other),
+            Expression::Conditional { scrutinee, arms } => {
+                let scrutinee = Box::new(self.transform_child(0, scrutinee)?);
+                let arms = arms
+                    .iter()
+                    .enumerate()
+                    .map(|(index, arm)| {
+                        Ok(crate::ast::ConditionalArm {
+                            pattern: arm.pattern.clone(),
+                            guard: arm.guard.as_ref().map(|guard| self.transform_child(2 * index + 1, guard)).transpose()?,
+                            body: self.transform_child(2 * index + 2, &arm.body)?,
+                        })
+                    })
+                    .collect::<Result<Vec<_>, String>>()?;
+                Ok(Expression::Conditional { scrutinee, arms })
+            }
+            // Other method calls keep their shape so codegen can look up
+            // what the type checker resolved them to
+            Expression::MethodCall { .. } => {
+                let Some((function, type_args, args)) = self.resolve_generic_method_call() else {
+                    return Ok(expr.clone());
+                };
+                // The arguments are the type checker's copies of the receiver
+                // and the call's arguments, the call's children in order
+                let args = self.transform_children(&args)?;
+                Ok(Expression::FunctionCall { name: mangle::instantiate(&function, &type_args), args })
+            }
+            other => Ok(other.clone()),
         }
     }
 }
-
-fn generate_instantiated_name(base_name: &str, type_args: &[AstType]) -> String {
-    if type_args.is_empty() {
-        return base_name.to_string();
-    }
-    
-    let type_names: Vec<String> = type_args.iter().map(type_to_string).collect();
-    format!("{}_{}", base_name, type_names.join("_"))
-}
-
-fn type_to_string(ast_type: &AstType) -> String {
-    match ast_type {
-        AstType::I8 => "i8".to_string(),
-        AstType::I16 => "i16".to_string(),
-        AstType::I32 => "i32".to_string(),
-        AstType::I64 => "i64".to_string(),
-        AstType::U8 => "u8".to_string(),
-        AstType::U16 => "u16".to_string(),
-        AstType::U32 => "u32".to_string(),
-        AstType::U64 => "u64".to_string(),
-        AstType::F32 => "f32".to_string(),
-        AstType::F64 => "f64".to_string(),
-        AstType::Bool => "bool".to_string(),
-        AstType::String => "string".to_string(),
-        AstType::Void => "void".to_string(),
-        _ => "unknown".to_string(),
-    }
-}
-
-fn extract_generic_struct_types(name: &str) -> Option<Vec<AstType>> {
-    if name.contains('<') && name.contains('>') {
-        // TODO: Parse type arguments from struct construction syntax
-        None
-    } else {
-        None
-    }
-}
-
-fn extract_base_name(name: &str) -> String {
-    if let Some(idx) = name.find('<') {
-        name[..idx].to_string()
-    } else {
-        name.to_string()
-    }
-}
\ No newline at end of file
@@ -1,15 +1,26 @@
-use crate::ast::{BehaviorDefinition, ImplBlock, AstType};
-use crate::error::{CompileError, Result};
+use super::{methods, unify};
+use crate::ast::{self, AssociatedConstant, AstType, BehaviorDefinition, Function, ImplBlock, TypeParameter};
+use crate::error::{CompileError, Result, Span};
 use std::collections::HashMap;
 
+/// How deep the bounds of generic implementations are followed when
+/// deciding whether a type implements a behavior, so implementations whose
+/// bounds refer back to themselves can't recurse forever.
+const MAX_BOUND_DEPTH: usize = 16;
+
 /// Tracks behaviors, implementations, and provides trait resolution
+#[derive(Clone, Debug, Default)]
 pub struct BehaviorResolver {
     /// All defined behaviors/traits
     behaviors: HashMap<String, BehaviorInfo>,
+    /// Behavior definitions, whose default methods implementations inherit
+    definitions: HashMap<String, BehaviorDefinition>,
     /// Maps (type_name, behavior_name) -> implementation
     implementations: HashMap<(String, String), ImplInfo>,
     /// Maps type_name -> inherent methods (impl blocks without behavior)
     inherent_methods: HashMap<String, Vec<MethodInfo>>,
+    /// Generic implementations, in declaration order
+    templates: Vec<ImplTemplate>,
 }
 
 #[derive(Clone, Debug)]
@@ -17,6 +28,8 @@ pub struct BehaviorInfo {
     pub name: String,
     pub type_params: Vec<String>,
     pub methods: Vec<BehaviorMethodInfo>,
+    pub associated_types: Vec<String>,
+    pub constants: Vec<AssociatedConstant>,
 }
 
 #[derive(Clone, Debug)]
@@ -25,6 +38,8 @@ pub struct BehaviorMethodInfo {
     pub param_types: Vec<AstType>,
     pub return_type: AstType,
     pub has_self: bool,
+    /// Whether the behavior gives a default body.
+    pub has_default: bool,
 }
 
 #[derive(Clone, Debug)]
@@ -32,12 +47,58 @@ pub struct ImplInfo {
     pub type_name: String,
     pub behavior_name: String,
     pub type_params: Vec<String>,
+    /// The implementation's own methods and the defaults it inherits
+    pub methods: HashMap<String, MethodInfo>,
+    pub associated_types: HashMap<String, AstType>,
+    pub constants: Vec<AssociatedConstant>,
+}
+
+/// A generic implementation, `Box<T>.impl<T> = { ... }`, or a blanket one,
+/// `T.impl<T: Show> = { Printable: { ... } }`. It applies to every type
+/// matching its target whose type arguments satisfy its bounds, and its
+/// methods are instantiated for each such type they're called on.
+#[derive(Clone, Debug)]
+pub struct ImplTemplate {
+    /// The implementing type, in terms of the type parameters
+    pub target: AstType,
+    pub type_params: Vec<TypeParameter>,
+    pub behavior_name: Option<String>,
+    /// Methods by name; their symbols name the generic functions
     pub methods: HashMap<String, MethodInfo>,
 }
 
+impl ImplTemplate {
+    /// The type arguments that make the target `type_`, ignoring bounds.
+    fn bind(&self, type_: &AstType) -> Option<Vec<AstType>> {
+        let mut bindings = Vec::new();
+        if !bind_type(&self.target, type_, &self.type_params, &mut bindings) {
+            return None;
+        }
+        self.type_params
+            .iter()
+            .map(|param| bindings.iter().find(|(name, _)| *name == param.name).map(|(_, type_)| type_.clone()))
+            .collect()
+    }
+
+    fn is_blanket(&self) -> bool {
+        matches!(&self.target, AstType::Generic { name, type_args } if type_args.is_empty() && self.type_params.iter().any(|param| param.name == *name))
+    }
+
+    /// The name of the type the template implements, for templates that
+    /// aren't blanket implementations.
+    fn base_name(&self) -> Option<&str> {
+        match &self.target {
+            AstType::Generic { name, .. } if !self.is_blanket() => Some(name),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct MethodInfo {
     pub name: String,
+    /// The function the method is compiled to.
+    pub symbol: String,
     pub param_types: Vec<AstType>,
     pub return_type: AstType,
 }
@@ -46,8 +107,10 @@ impl BehaviorResolver {
     pub fn new() -> Self {
         Self {
             behaviors: HashMap::new(),
+            definitions: HashMap::new(),
             implementations: HashMap::new(),
             inherent_methods: HashMap::new(),
+            templates: Vec::new(),
         }
     }
 
@@ -70,6 +133,7 @@ impl BehaviorResolver {
                 param_types: m.params.iter().map(|p| p.type_.clone()).collect(),
                 return_type: m.return_type.clone(),
                 has_self,
+                has_default: m.body.is_some(),
             }
         }).collect();
 
@@ -77,14 +141,21 @@ impl BehaviorResolver {
             name: behavior.name.clone(),
             type_params: behavior.type_params.iter().map(|tp| tp.name.clone()).collect(),
             methods,
+            associated_types: behavior.associated_types.clone(),
+            constants: behavior.constants.clone(),
         };
 
         self.behaviors.insert(behavior.name.clone(), info);
+        self.definitions.insert(behavior.name.clone(), behavior.clone());
         Ok(())
     }
 
-    /// Register an implementation block
-    pub fn register_impl(&mut self, impl_block: &ImplBlock) -> Result<()> {
+    /// Register an implementation block. `span` locates the block for
+    /// errors about it overlapping another.
+    pub fn register_impl(&mut self, impl_block: &ImplBlock, span: Option<&Span>) -> Result<()> {
+        if impl_block.is_generic() || !impl_block.type_args.is_empty() {
+            return self.register_template(impl_block, span);
+        }
         if let Some(behavior_name) = &impl_block.behavior_name {
             // This is a behavior implementation
             let key = (impl_block.type_name.clone(), behavior_name.clone());
@@ -108,9 +179,10 @@ impl BehaviorResolver {
             }
 
             let mut methods = HashMap::new();
-            for method in &impl_block.methods {
+            for method in impl_block.all_methods(self.definitions.get(behavior_name)) {
                 let method_info = MethodInfo {
                     name: method.name.clone(),
+                    symbol: impl_block.method_symbol(&method.name),
                     param_types: method.args.iter().map(|(_, t)| t.clone()).collect(),
                     return_type: method.return_type.clone(),
                 };
@@ -122,33 +194,264 @@ impl BehaviorResolver {
                 behavior_name: behavior_name.clone(),
                 type_params: impl_block.type_params.iter().map(|tp| tp.name.clone()).collect(),
                 methods,
+                associated_types: impl_block.associated_types.iter().cloned().collect(),
+                constants: impl_block.constants.clone(),
             };
 
             self.implementations.insert(key, impl_info);
         } else {
             // This is an inherent impl block
-            let methods: Vec<MethodInfo> = impl_block.methods.iter().map(|method| {
-                MethodInfo {
+            self.register_methods(&impl_block.type_name, &impl_block.methods);
+        }
+
+        Ok(())
+    }
+
+    /// Register a generic implementation as a template. Its type arguments
+    /// must be distinct type parameters, each of which it declares, so
+    /// matching a type binds them all. Overlaps with earlier templates are
+    /// reported here; those with other implementations once all are known,
+    /// by `check_coherence`.
+    fn register_template(&mut self, impl_block: &ImplBlock, span: Option<&Span>) -> Result<()> {
+        let target = impl_block.target();
+        let is_param = |type_: &AstType| match type_ {
+            AstType::Generic { name, type_args } if type_args.is_empty() => {
+                impl_block.type_params.iter().any(|param| param.name == *name)
+            }
+            _ => false,
+        };
+        let blanket = impl_block.type_args.is_empty();
+        if !impl_block.type_args.iter().all(is_param) || (blanket && !is_param(&target)) {
+            return Err(CompileError::TypeError(
+                format!(
+                    "Type arguments of '{}' in an impl block must be the block's type parameters, as in `{}<T>.impl<T>`",
+                    impl_block.type_name, impl_block.type_name
+                ),
+                None,
+            ));
+        }
+        for param in &impl_block.type_params {
+            let uses = if blanket {
+                usize::from(param.name == impl_block.type_name)
+            } else {
+                impl_block.type_args.iter().filter(|arg| arg.to_string() == param.name).count()
+            };
+            if uses != 1 {
+                return Err(CompileError::TypeError(
+                    format!(
+                        "Type parameter '{}' of an impl block must appear exactly once in its type '{}'",
+                        param.name, target
+                    ),
+                    None,
+                ));
+            }
+        }
+        if blanket && impl_block.behavior_name.is_none() {
+            return Err(CompileError::TypeError(
+                format!(
+                    "An impl block for type parameter '{}' must implement a behavior, as in `{}.impl<{}: Bound> = {{ Behavior: {{ ... }} }}`",
+                    impl_block.type_name, impl_block.type_name, impl_block.type_name
+                ),
+                None,
+            ));
+        }
+        let definition = match &impl_block.behavior_name {
+            Some(behavior_name) => Some(self.definitions.get(behavior_name).ok_or_else(|| {
+                CompileError::TypeError(format!("Unknown behavior: {}", behavior_name), None)
+            })?),
+            None => None,
+        };
+
+        let methods = impl_block
+            .all_methods(definition)
+            .into_iter()
+            .map(|method| {
+                let info = MethodInfo {
                     name: method.name.clone(),
+                    symbol: impl_block.method_symbol(&method.name),
                     param_types: method.args.iter().map(|(_, t)| t.clone()).collect(),
                     return_type: method.return_type.clone(),
-                }
-            }).collect();
+                };
+                (method.name, info)
+            })
+            .collect();
+        let template = ImplTemplate {
+            target,
+            type_params: impl_block.type_params.clone(),
+            behavior_name: impl_block.behavior_name.clone(),
+            methods,
+        };
+        for earlier in &self.templates {
+            if let Some(message) = template_overlap(earlier, &template) {
+                return Err(CompileError::TypeError(message, span.cloned()));
+            }
+        }
+        self.templates.push(template);
+        Ok(())
+    }
 
-            self.inherent_methods
-                .entry(impl_block.type_name.clone())
-                .or_insert_with(Vec::new)
-                .extend(methods);
+    /// Report a generic implementation that covers a type some other
+    /// implementation already covers: a behavior implemented for a type
+    /// the template matches, with the template's bounds satisfied, or an
+    /// inherent method of the type the template also defines. `span`
+    /// locates the generic block.
+    pub fn check_coherence(&self, impl_block: &ImplBlock, span: Option<&Span>) -> Result<()> {
+        if !impl_block.is_generic() {
+            return Ok(());
         }
+        let target = impl_block.target();
+        let Some(template) = self.templates.iter().find(|template| {
+            template.target == target && template.behavior_name == impl_block.behavior_name
+        }) else {
+            return Ok(());
+        };
 
+        match &template.behavior_name {
+            Some(behavior_name) => {
+                let mut covered: Vec<&String> = self
+                    .implementations
+                    .keys()
+                    .filter(|(_, behavior)| behavior == behavior_name)
+                    .map(|(type_name, _)| type_name)
+                    .filter(|type_name| {
+                        let type_ = AstType::Generic { name: type_name.to_string(), type_args: vec![] };
+                        template.base_name() == Some(type_name.as_str()) || self.covers(template, &type_, 0).is_some()
+                    })
+                    .collect();
+                covered.sort();
+                if let Some(type_name) = covered.first() {
+                    return Err(CompileError::TypeError(
+                        format!(
+                            "Conflicting implementations of behavior '{}': the one for '{}' also covers '{}', which implements it itself",
+                            behavior_name, template.target, type_name
+                        ),
+                        span.cloned(),
+                    ));
+                }
+            }
+            None => {
+                let inherent = template.base_name().and_then(|name| self.inherent_methods.get(name));
+                let mut shared: Vec<&String> = inherent
+                    .into_iter()
+                    .flatten()
+                    .map(|method| &method.name)
+                    .filter(|name| template.methods.contains_key(*name))
+                    .collect();
+                shared.sort();
+                if let Some(method) = shared.first() {
+                    return Err(CompileError::TypeError(
+                        format!(
+                            "Conflicting implementations of method '{}' for '{}' and '{}'",
+                            method,
+                            template.target,
+                            template.base_name().unwrap_or_default()
+                        ),
+                        span.cloned(),
+                    ));
+                }
+            }
+        }
         Ok(())
     }
 
+    /// Register methods declared on a type outside any behavior: in an
+    /// inherent impl block or in the body of a struct or enum.
+    pub fn register_methods(&mut self, type_name: &str, methods: &[Function]) {
+        let methods = methods.iter().map(|method| {
+            MethodInfo {
+                name: method.name.clone(),
+                symbol: ast::method_symbol(type_name, None, &method.name),
+                param_types: method.args.iter().map(|(_, t)| t.clone()).collect(),
+                return_type: method.return_type.clone(),
+            }
+        });
+
+        self.inherent_methods
+            .entry(type_name.to_string())
+            .or_default()
+            .extend(methods);
+    }
+
+    /// The definition of a behavior, with its default methods
+    pub fn get_definition(&self, behavior_name: &str) -> Option<&BehaviorDefinition> {
+        self.definitions.get(behavior_name)
+    }
+
+    /// Get a behavior definition by name
+    pub fn get_behavior(&self, behavior_name: &str) -> Option<&BehaviorInfo> {
+        self.behaviors.get(behavior_name)
+    }
+
     /// Check if a type implements a behavior
     pub fn type_implements(&self, type_name: &str, behavior_name: &str) -> bool {
         self.implementations.contains_key(&(type_name.to_string(), behavior_name.to_string()))
     }
 
+    /// Whether `type_` implements a behavior, itself or through a generic
+    /// implementation whose bounds it satisfies.
+    pub fn implements(&self, type_: &AstType, behavior_name: &str) -> bool {
+        self.implements_within(type_, behavior_name, 0)
+    }
+
+    fn implements_within(&self, type_: &AstType, behavior_name: &str, depth: usize) -> bool {
+        if self.type_implements(&methods::type_name(type_), behavior_name) {
+            return true;
+        }
+        depth < MAX_BOUND_DEPTH
+            && self.templates.iter().any(|template| {
+                template.behavior_name.as_deref() == Some(behavior_name) && self.covers(template, type_, depth).is_some()
+            })
+    }
+
+    /// The type arguments with which `template` applies to `type_`, if it
+    /// matches and they satisfy its bounds.
+    fn covers(&self, template: &ImplTemplate, type_: &AstType, depth: usize) -> Option<Vec<AstType>> {
+        let type_args = template.bind(type_)?;
+        let satisfied = template.type_params.iter().zip(&type_args).all(|(param, type_arg)| {
+            param.constraints.iter().all(|bound| self.implements_within(type_arg, bound, depth + 1))
+        });
+        satisfied.then_some(type_args)
+    }
+
+    /// The method `method_name` of the generic implementation that applies
+    /// to `type_`, only among implementations of `behavior_name` when one is
+    /// given, with the template's type arguments for `type_`. The method's
+    /// signature is given for `type_`.
+    pub fn resolve_generic_method(
+        &self,
+        type_: &AstType,
+        behavior_name: Option<&str>,
+        method_name: &str,
+    ) -> Option<(MethodInfo, Vec<AstType>)> {
+        self.templates.iter().find_map(|template| {
+            if behavior_name.is_some() && template.behavior_name.as_deref() != behavior_name {
+                return None;
+            }
+            let method = template.methods.get(method_name)?;
+            let type_args = self.covers(template, type_, 0)?;
+            let params: Vec<(String, AstType)> =
+                template.type_params.iter().map(|param| param.name.clone()).zip(type_args.clone()).collect();
+            let method = MethodInfo {
+                param_types: method.param_types.iter().map(|type_| unify::substitute(type_, &params)).collect(),
+                return_type: unify::substitute(&method.return_type, &params),
+                ..method.clone()
+            };
+            Some((method, type_args))
+        })
+    }
+
+    /// The function that calls `method_name` of `behavior_name` on a value
+    /// of `type_`, with the type arguments to instantiate it with when it
+    /// belongs to a generic implementation.
+    pub fn behavior_method(&self, type_: &AstType, behavior_name: &str, method_name: &str) -> Option<(String, Vec<AstType>)> {
+        let type_name = methods::type_name(type_);
+        if self.type_implements(&type_name, behavior_name) {
+            return Some((ast::method_symbol(&type_name, Some(behavior_name), method_name), Vec::new()));
+        }
+        let (method, type_args) = self.resolve_generic_method(type_, Some(behavior_name), method_name)?;
+        Some((method.symbol, type_args))
+    }
+
     /// Get the implementation of a behavior for a type
     pub fn get_impl(&self, type_name: &str, behavior_name: &str) -> Option<&ImplInfo> {
         self.implementations.get(&(type_name.to_string(), behavior_name.to_string()))
@@ -179,14 +482,17 @@ impl BehaviorResolver {
         None
     }
 
-    /// Check if an implementation satisfies a behavior's requirements
+    /// Check that an implementation gives every method, associated type and
+    /// constant its behavior requires, with signatures matching the
+    /// behavior's. Methods and constants with defaults may be left out.
     pub fn verify_impl(&self, impl_block: &ImplBlock) -> Result<()> {
         if let Some(behavior_name) = &impl_block.behavior_name {
-            let behavior = self.behaviors.get(behavior_name)
+            let behavior = self.definitions.get(behavior_name)
                 .ok_or_else(|| CompileError::TypeError(
                     format!("Unknown behavior: {}", behavior_name),
                     None,
                 ))?;
+            let type_name = &impl_block.type_name;
 
             // Check that all required methods are implemented
             let mut missing_methods = Vec::new();
@@ -194,7 +500,7 @@ impl BehaviorResolver {
                 let impl_method = impl_block.methods.iter()
                     .find(|m| m.name == required_method.name);
 
-                if impl_method.is_none() {
+                if impl_method.is_none() && required_method.body.is_none() {
                     missing_methods.push(required_method.name.clone());
                 }
             }
@@ -203,18 +509,106 @@ impl BehaviorResolver {
                 return Err(CompileError::TypeError(
                     format!(
                         "Type '{}' does not implement all methods for behavior '{}'. Missing: {:?}",
-                        impl_block.type_name, behavior_name, missing_methods
+                        type_name, behavior_name, missing_methods
                     ),
                     None,
                 ));
             }
 
-            // TODO: Check method signatures match
+            for associated in &behavior.associated_types {
+                if !impl_block.associated_types.iter().any(|(name, _)| name == associated) {
+                    return Err(CompileError::TypeError(
+                        format!(
+                            "Type '{}' does not give associated type '{}' of behavior '{}'; add `{}: type = ...`",
+                            type_name, associated, behavior_name, associated
+                        ),
+                        None,
+                    ));
+                }
+            }
+            for (name, _) in &impl_block.associated_types {
+                if !behavior.associated_types.contains(name) {
+                    return Err(CompileError::TypeError(
+                        format!("Behavior '{}' has no associated type '{}'", behavior_name, name),
+                        None,
+                    ));
+                }
+            }
+
+            for constant in &behavior.constants {
+                match impl_block.constants.iter().find(|given| given.name == constant.name) {
+                    None if constant.value.is_none() => {
+                        return Err(CompileError::TypeError(
+                            format!(
+                                "Type '{}' does not give constant '{}' of behavior '{}'; add `{}: {} = ...`",
+                                type_name, constant.name, behavior_name, constant.name, constant.type_
+                            ),
+                            None,
+                        ));
+                    }
+                    Some(given) => {
+                        let expected = impl_block.resolve_self(&constant.type_);
+                        if !same_type(&expected, &given.type_) {
+                            return Err(CompileError::TypeError(
+                                format!(
+                                    "Constant '{}' of '{}' has type {}, but behavior '{}' declares it as {}",
+                                    given.name, type_name, given.type_, behavior_name, expected
+                                ),
+                                None,
+                            ));
+                        }
+                    }
+                    None => {}
+                }
+            }
+            for given in &impl_block.constants {
+                if !behavior.constants.iter().any(|constant| constant.name == given.name) {
+                    return Err(CompileError::TypeError(
+                        format!("Behavior '{}' has no constant '{}'", behavior_name, given.name),
+                        None,
+                    ));
+                }
+            }
+
+            for method in &impl_block.methods {
+                let Some(declared) = behavior.methods.iter().find(|declared| declared.name == method.name) else {
+                    return Err(CompileError::TypeError(
+                        format!("Method '{}' of '{}' is not declared by behavior '{}'", method.name, type_name, behavior_name),
+                        None,
+                    ));
+                };
+                verify_method_signature(impl_block, behavior_name, declared, method)?;
+            }
         }
 
         Ok(())
     }
 
+    /// The constant `name` given for `type_name` by one of its behavior
+    /// implementations or, failing that, by a behavior's default.
+    pub fn resolve_constant(&self, type_name: &str, name: &str) -> Option<AssociatedConstant> {
+        let mut implementations: Vec<&ImplInfo> = self
+            .implementations
+            .values()
+            .filter(|implementation| implementation.type_name == type_name)
+            .collect();
+        implementations.sort_by(|a, b| a.behavior_name.cmp(&b.behavior_name));
+        let given = implementations
+            .iter()
+            .find_map(|implementation| implementation.constants.iter().find(|constant| constant.name == name));
+        if let Some(given) = given {
+            return Some(given.clone());
+        }
+        implementations.iter().find_map(|implementation| {
+            let behavior = self.behaviors.get(&implementation.behavior_name)?;
+            behavior
+                .constants
+                .iter()
+                .find(|constant| constant.name == name && constant.value.is_some())
+                .cloned()
+        })
+    }
+
     /// Get all behaviors implemented by a type
     pub fn get_implemented_behaviors(&self, type_name: &str) -> Vec<String> {
         self.implementations
@@ -225,6 +619,108 @@ impl BehaviorResolver {
     }
 }
 
+/// Bind the type parameters in `pattern` so it becomes `type_`.
+fn bind_type(pattern: &AstType, type_: &AstType, params: &[TypeParameter], bindings: &mut Vec<(String, AstType)>) -> bool {
+    let AstType::Generic { name, type_args } = pattern else {
+        return same_type(pattern, type_);
+    };
+    if type_args.is_empty() && params.iter().any(|param| param.name == *name) {
+        if let Some((_, bound)) = bindings.iter().find(|(param, _)| param == name) {
+            return same_type(bound, type_);
+        }
+        bindings.push((name.clone(), type_.clone()));
+        return true;
+    }
+    match type_ {
+        AstType::Generic { name: actual, type_args: actual_args } => {
+            name == actual
+                && type_args.len() == actual_args.len()
+                && type_args.iter().zip(actual_args).all(|(arg, actual)| bind_type(arg, actual, params, bindings))
+        }
+        _ => same_type(pattern, type_),
+    }
+}
+
+/// Why two generic implementations overlap, if a type could match both.
+/// Bounds aren't considered, since some type could satisfy both sets.
+fn template_overlap(earlier: &ImplTemplate, later: &ImplTemplate) -> Option<String> {
+    let targets_overlap = earlier.is_blanket() || later.is_blanket() || earlier.base_name() == later.base_name();
+    if !targets_overlap {
+        return None;
+    }
+    match (&earlier.behavior_name, &later.behavior_name) {
+        (Some(earlier_behavior), Some(later_behavior)) if earlier_behavior == later_behavior => Some(format!(
+            "Conflicting implementations of behavior '{}' for '{}' and '{}': a type could match both",
+            later_behavior, earlier.target, later.target
+        )),
+        (None, None) => {
+            let mut shared: Vec<&String> = later.methods.keys().filter(|name| earlier.methods.contains_key(*name)).collect();
+            shared.sort();
+            shared.first().map(|method| {
+                format!("Conflicting implementations of method '{}' for '{}' and '{}'", method, earlier.target, later.target)
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Check an implementation's method against the behavior's declaration.
+/// The receiver may be taken by value or by pointer either way.
+fn verify_method_signature(
+    impl_block: &ImplBlock,
+    behavior_name: &str,
+    declared: &ast::BehaviorMethod,
+    method: &Function,
+) -> Result<()> {
+    let mismatch = |detail: String| {
+        Err(CompileError::TypeError(
+            format!(
+                "'{}' of '{}' doesn't match its declaration in behavior '{}': {}",
+                method.name, impl_block.type_name, behavior_name, detail
+            ),
+            None,
+        ))
+    };
+    if declared.params.len() != method.args.len() {
+        return mismatch(format!(
+            "it takes {} parameter(s) where the behavior takes {}",
+            method.args.len(),
+            declared.params.len()
+        ));
+    }
+    for (index, (param, (name, type_))) in declared.params.iter().zip(&method.args).enumerate() {
+        let expected = impl_block.resolve_self(&param.type_);
+        let matches = if index == 0 && param.name == "self" {
+            same_type(receiver_value(&expected), receiver_value(&impl_block.resolve_self(type_)))
+        } else {
+            same_type(&expected, &impl_block.resolve_self(type_))
+        };
+        if !matches {
+            return mismatch(format!("parameter '{}' is {} where the behavior has {}", name, type_, expected));
+        }
+    }
+    let expected = impl_block.resolve_self(&declared.return_type);
+    if !same_type(&expected, &impl_block.resolve_self(&method.return_type)) {
+        return mismatch(format!("it returns {} where the behavior returns {}", method.return_type, expected));
+    }
+    Ok(())
+}
+
+/// The type a receiver refers to, whether passed by value or by pointer.
+fn receiver_value(type_: &AstType) -> &AstType {
+    match type_ {
+        AstType::Pointer(inner) => inner,
+        AstType::Generic { name, type_args } if name == "Ptr" && type_args.len() == 1 => &type_args[0],
+        other => other,
+    }
+}
+
+/// Signature types are compared as written, so a struct type and its name
+/// agree.
+fn same_type(expected: &AstType, actual: &AstType) -> bool {
+    expected.to_string() == actual.to_string()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -251,8 +747,11 @@ mod tests {
                         }
                     ],
                     return_type: AstType::String,
+                    body: None,
                 }
             ],
+            associated_types: vec![],
+            constants: vec![],
         };
 
         assert!(resolver.register_behavior(&behavior).is_ok());
@@ -268,6 +767,8 @@ mod tests {
             name: "Display".to_string(),
             type_params: vec![],
             methods: vec![],
+            associated_types: vec![],
+            constants: vec![],
         };
         resolver.register_behavior(&behavior).unwrap();
 
@@ -276,10 +777,13 @@ mod tests {
             type_name: "Point".to_string(),
             behavior_name: Some("Display".to_string()),
             type_params: vec![],
+            type_args: vec![],
             methods: vec![],
+            associated_types: vec![],
+            constants: vec![],
         };
 
-        assert!(resolver.register_impl(&impl_block).is_ok());
+        assert!(resolver.register_impl(&impl_block, None).is_ok());
         assert!(resolver.type_implements("Point", "Display"));
     }
 
@@ -292,6 +796,7 @@ mod tests {
             type_name: "Point".to_string(),
             behavior_name: None,
             type_params: vec![],
+            type_args: vec![],
             methods: vec![
                 Function {
                     name: "distance".to_string(),
@@ -305,9 +810,11 @@ mod tests {
                     type_params: vec![],
                 }
             ],
+            associated_types: vec![],
+            constants: vec![],
         };
 
-        resolver.register_impl(&impl_block).unwrap();
+        resolver.register_impl(&impl_block, None).unwrap();
         
         let method = resolver.resolve_method("Point", "distance");
         assert!(method.is_some());
@@ -0,0 +1,74 @@
+//! Behavior bounds on type parameters.
+//!
+//! `max<T: Ord> = (a: T, b: T) T` may only be called with a `T` that
+//! implements `Ord`. Each call's type arguments are checked once the body
+//! calling it is solved; inside another generic function a type parameter
+//! satisfies a bound when it carries the same bound itself. The body of a
+//! bounded function may call the bounds' methods on its `T` values, which
+//! method-call resolution handles.
+
+use super::{methods, unify, TypeChecker};
+use crate::ast::{AstType, TypeParameter};
+use crate::error::{CompileError, Result};
+
+impl TypeChecker {
+    /// Every bound must name a behavior.
+    pub(super) fn check_type_parameter_bounds(&self, type_params: &[TypeParameter]) -> Result<()> {
+        for param in type_params {
+            for behavior in &param.constraints {
+                if self.behavior_resolver.get_behavior(behavior).is_none() {
+                    return Err(CompileError::TypeError(
+                        format!("Unknown behavior '{}' in the bound on type parameter '{}'", behavior, param.name),
+                        None,
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Require the type arguments of a call to `callee` to implement the
+    /// behaviors its type parameters are bounded by.
+    pub(super) fn check_bounds(&self, callee: &str, type_args: &[(String, AstType)]) -> Result<()> {
+        let Some(signature) = self.functions.get(callee) else {
+            return Ok(());
+        };
+        for (param, (_, type_)) in signature.type_params.iter().zip(type_args) {
+            // Arguments left unsolved are reported by inference
+            if unify::contains_var(type_) {
+                continue;
+            }
+            for behavior in &param.constraints {
+                if let Some(message) = self.unsatisfied_bound(type_, behavior, callee, &param.name) {
+                    return Err(CompileError::TypeError(message, None));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Why `type_`, given for type parameter `param` of `callee`, doesn't
+    /// satisfy the bound `behavior`, naming what's missing.
+    fn unsatisfied_bound(&self, type_: &AstType, behavior: &str, callee: &str, param: &str) -> Option<String> {
+        if let AstType::Generic { name, type_args } = type_ {
+            if let Some(bounds) = self.type_bounds.get(name).filter(|_| type_args.is_empty()) {
+                if bounds.iter().any(|bound| bound == behavior) {
+                    return None;
+                }
+                return Some(format!(
+                    "Type parameter '{}' is not bounded by '{}', which '{}' requires of its type parameter '{}'; add the bound `{}: {}`",
+                    name, behavior, callee, param, name, behavior
+                ));
+            }
+        }
+
+        if self.behavior_resolver.implements(type_, behavior) {
+            return None;
+        }
+        let type_name = methods::type_name(type_);
+        Some(format!(
+            "Type '{}' does not implement behavior '{}', which '{}' requires of its type parameter '{}'; add `{}.impl = {{ {}: {{ ... }} }}`",
+            type_name, behavior, callee, param, type_name, behavior
+        ))
+    }
+}
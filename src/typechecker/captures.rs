@@ -0,0 +1,368 @@
+//! Free-variable analysis for closures.
+//!
+//! A closure captures the variables of the enclosing function that its
+//! body uses. Variables the body only reads are copied into the closure's
+//! environment when it is created. Variables the body assigns to or takes
+//! the address of are captured by pointer, so writes are seen by the
+//! enclosing function and by later calls.
+//!
+//! A pointer is only safe while the variable lives, so closures that
+//! capture by pointer must be local: they can't outlive the function
+//! creating them. `Escapes` works out which closures are.
+
+use crate::ast::{AstType, ConditionalArm, Expression, LoopKind, Pattern, Statement, StringPart};
+use crate::spans::{self, Node};
+use std::collections::HashSet;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaptureMode {
+    /// A copy of the value when the closure is created.
+    Value,
+    /// The address of the enclosing function's variable.
+    Pointer,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Capture {
+    pub name: String,
+    pub mode: CaptureMode,
+}
+
+/// The variables a closure captures, in order of first use. `is_variable`
+/// says whether a name is a variable where the closure is written; other
+/// free names refer to functions or globals and aren't captured.
+pub fn closure_captures(
+    params: &[(String, Option<AstType>)],
+    body: &Expression,
+    is_variable: impl Fn(&str) -> bool,
+) -> Vec<Capture> {
+    let mut walker = Walker {
+        scopes: vec![params.iter().map(|(name, _)| name.clone()).collect()],
+        captures: Vec::new(),
+    };
+    walker.expression(body);
+    walker.captures.retain(|capture| is_variable(&capture.name));
+    walker.captures
+}
+
+/// Which closures of a function body can't outlive it.
+///
+/// A closure is local when it is passed straight to a call, or bound to an
+/// immutable variable that is only called or passed straight to calls.
+/// Anything else may be returned or stored, so it escapes, and so does
+/// everything used inside a closure that escapes. A callee can't keep a
+/// closure passed to it either, as long as each function only calls its
+/// function-valued parameters or passes them on: see `escapes`.
+#[derive(Debug, Clone, Default)]
+pub struct Escapes {
+    closures: Vec<Vec<usize>>,
+    local: HashSet<Vec<usize>>,
+    // Arguments of calls, and operands of `&` and `*`
+    arguments: HashSet<Vec<usize>>,
+    wrapped: HashSet<Vec<usize>>,
+    // Uses of names, and whether each is the callee of a call
+    uses: Vec<(String, Vec<usize>, bool)>,
+}
+
+impl Escapes {
+    /// Analyze the body of a function.
+    pub fn of_body(body: &[Statement]) -> Self {
+        let mut escapes = Escapes::default();
+        let mut bindings = Vec::new();
+        let child = |path: &[usize], index: usize| [path, &[index]].concat();
+        spans::visit_body(body, &mut Vec::new(), &mut |node, path| match node {
+            Node::Expression(Expression::Closure { .. }) => escapes.closures.push(path.to_vec()),
+            Node::Expression(Expression::FunctionCall { name, args }) => {
+                escapes.arguments.extend((0..args.len()).map(|index| child(path, index)));
+                let callee = name.split('.').next().unwrap_or(name);
+                escapes.uses.push((callee.to_string(), path.to_vec(), true));
+            }
+            Node::Expression(Expression::MethodCall { args, .. }) => {
+                escapes.arguments.extend((0..args.len()).map(|index| child(path, index + 1)));
+            }
+            Node::Expression(Expression::AddressOf(_) | Expression::Dereference(_)) => {
+                escapes.wrapped.insert(path.to_vec());
+            }
+            Node::Expression(Expression::Identifier(name)) => escapes.uses.push((name.clone(), path.to_vec(), false)),
+            Node::Statement(Statement::VariableDeclaration {
+                name,
+                initializer: Some(Expression::Closure { .. }),
+                is_mutable: false,
+                ..
+            })
+            | Node::BlockStatement(Statement::VariableDeclaration {
+                name,
+                initializer: Some(Expression::Closure { .. }),
+                is_mutable: false,
+                ..
+            }) => bindings.push((name.clone(), path.to_vec())),
+            _ => {}
+        });
+
+        escapes.local = escapes
+            .closures
+            .iter()
+            .filter(|path| escapes.is_argument(path))
+            .cloned()
+            .collect();
+        escapes.local.extend(bindings.iter().map(|(_, path)| child(path, 0)));
+        // Closures dropped from the set can make others escape in turn
+        loop {
+            let escaping: Vec<Vec<usize>> = bindings
+                .iter()
+                .filter(|(name, path)| escapes.escapes(name, path))
+                .map(|(_, path)| child(path, 0))
+                .filter(|closure| escapes.local.contains(closure))
+                .collect();
+            if escaping.is_empty() {
+                return escapes;
+            }
+            escaping.iter().for_each(|closure| {
+                escapes.local.remove(closure);
+            });
+        }
+    }
+
+    /// Whether the closure at `path` can't outlive the body.
+    pub fn is_local(&self, path: &[usize]) -> bool {
+        self.local.contains(path)
+    }
+
+    /// Whether the function value in variable `name` may outlive the body:
+    /// whether it is used other than by calling it or passing it straight
+    /// to a call, or used in a closure that escapes. `scope` is the path of
+    /// the statement or closure declaring it, empty for parameters of the
+    /// function.
+    pub fn escapes(&self, name: &str, scope: &[usize]) -> bool {
+        self.uses.iter().any(|(used, path, callee)| {
+            used == name
+                && ((!callee && !self.is_argument(path))
+                    || self.closures.iter().any(|closure| {
+                        closure.len() < path.len()
+                            && path.starts_with(closure)
+                            && !scope.starts_with(closure)
+                            && !self.local.contains(closure)
+                    }))
+        })
+    }
+
+    fn is_argument(&self, path: &[usize]) -> bool {
+        self.arguments.contains(path) && !self.wrapped.contains(path)
+    }
+}
+
+struct Walker {
+    // Names bound inside the closure, innermost scope last
+    scopes: Vec<HashSet<String>>,
+    captures: Vec<Capture>,
+}
+
+impl Walker {
+    fn use_name(&mut self, name: &str, mode: CaptureMode) {
+        if self.scopes.iter().any(|scope| scope.contains(name)) {
+            return;
+        }
+        match self.captures.iter_mut().find(|capture| capture.name == name) {
+            Some(capture) if mode == CaptureMode::Pointer => capture.mode = mode,
+            Some(_) => {}
+            None => self.captures.push(Capture { name: name.to_string(), mode }),
+        }
+    }
+
+    fn bind(&mut self, name: &str) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.to_string());
+        }
+    }
+
+    fn scoped(&mut self, names: HashSet<String>, visit: impl FnOnce(&mut Self)) {
+        self.scopes.push(names);
+        visit(self);
+        self.scopes.pop();
+    }
+
+    fn statements(&mut self, statements: &[Statement]) {
+        self.scoped(HashSet::new(), |walker| {
+            statements.iter().for_each(|statement| walker.statement(statement));
+        });
+    }
+
+    fn statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::Expression(expr) | Statement::Return(expr) => self.expression(expr),
+            Statement::VariableDeclaration { name, initializer, .. } => {
+                if let Some(initializer) = initializer {
+                    self.expression(initializer);
+                }
+                self.bind(name);
+            }
+            Statement::DestructuringDeclaration { pattern, initializer, .. } => {
+                self.expression(initializer);
+                let mut names = HashSet::new();
+                pattern_bindings(pattern, &mut names);
+                names.iter().for_each(|name| self.bind(name));
+            }
+            Statement::VariableAssignment { name, value } => {
+                self.expression(value);
+                // `s.x = ...` assigns through the variable `s`
+                let root = name.split('.').next().unwrap_or(name);
+                self.use_name(root, CaptureMode::Pointer);
+            }
+            Statement::PointerAssignment { pointer, value } => {
+                self.expression(value);
+                match assigned_variable(pointer) {
+                    Some(name) => self.use_name(name, CaptureMode::Pointer),
+                    None => self.expression(pointer),
+                }
+            }
+            Statement::CompoundAssignment { target, value, .. } => {
+                // The target is read as well as written
+                self.expression(target);
+                self.expression(value);
+                if let Some(name) = assigned_variable(target) {
+                    self.use_name(name, CaptureMode::Pointer);
+                }
+            }
+            Statement::Loop { kind, body, .. } => {
+                if let LoopKind::Condition(condition) = kind {
+                    self.expression(condition);
+                }
+                self.statements(body);
+            }
+            Statement::ComptimeBlock(statements) => self.statements(statements),
+            Statement::Break { .. }
+            | Statement::Continue { .. }
+            | Statement::ModuleImport { .. }
+            | Statement::Error(_) => {}
+        }
+    }
+
+    fn expression(&mut self, expr: &Expression) {
+        match expr {
+            Expression::Identifier(name) => self.use_name(name, CaptureMode::Value),
+            Expression::FunctionCall { name, args } => {
+                // `object.method(...)` uses `object`
+                let callee = name.split('.').next().unwrap_or(name);
+                self.use_name(callee, CaptureMode::Value);
+                args.iter().for_each(|arg| self.expression(arg));
+            }
+            Expression::MethodCall { object, args, .. } => {
+                self.expression(object);
+                args.iter().for_each(|arg| self.expression(arg));
+            }
+            Expression::AddressOf(inner) => match &**inner {
+                Expression::Identifier(name) => self.use_name(name, CaptureMode::Pointer),
+                inner => self.expression(inner),
+            },
+            Expression::Closure { params, body } => {
+                let names = params.iter().map(|(name, _)| name.clone()).collect();
+                self.scoped(names, |walker| walker.expression(body));
+            }
+            Expression::Block(statements) => self.statements(statements),
+            Expression::Conditional { scrutinee, arms } => {
+                self.expression(scrutinee);
+                for ConditionalArm { pattern, guard, body } in arms {
+                    self.arm(pattern, guard.as_ref(), body);
+                }
+            }
+            Expression::PatternMatch { scrutinee, arms } => {
+                self.expression(scrutinee);
+                for arm in arms {
+                    self.arm(&arm.pattern, arm.guard.as_ref(), &arm.body);
+                }
+            }
+            Expression::BinaryOp { left, right, .. } => {
+                self.expression(left);
+                self.expression(right);
+            }
+            Expression::PointerOffset { pointer: first, offset: second }
+            | Expression::ArrayIndex { array: first, index: second }
+            | Expression::Range { start: first, end: second, .. } => {
+                self.expression(first);
+                self.expression(second);
+            }
+            Expression::Dereference(inner)
+            | Expression::StringLength(inner)
+            | Expression::UnaryOp { operand: inner, .. }
+            | Expression::TupleIndex { tuple: inner, .. }
+            | Expression::Comptime(inner)
+            | Expression::Return(inner)
+            | Expression::StructField { struct_: inner, .. }
+            | Expression::MemberAccess { object: inner, .. }
+            | Expression::TypeCast { expr: inner, .. } => self.expression(inner),
+            Expression::EnumVariant { payload, .. } => {
+                if let Some(payload) = payload {
+                    self.expression(payload);
+                }
+            }
+            Expression::StructLiteral { fields, .. } => {
+                fields.iter().for_each(|(_, value)| self.expression(value));
+            }
+            Expression::ArrayLiteral(elements) | Expression::TupleLiteral(elements) => elements.iter().for_each(|element| self.expression(element)),
+            Expression::StringInterpolation { parts } => {
+                for part in parts {
+                    if let StringPart::Interpolation(expr) = part {
+                        self.expression(expr);
+                    }
+                }
+            }
+            Expression::Integer8(_)
+            | Expression::Integer16(_)
+            | Expression::Integer32(_)
+            | Expression::Integer64(_)
+            | Expression::Unsigned8(_)
+            | Expression::Unsigned16(_)
+            | Expression::Unsigned32(_)
+            | Expression::Unsigned64(_)
+            | Expression::Float32(_)
+            | Expression::Float64(_)
+            | Expression::Boolean(_)
+            | Expression::String(_)
+            | Expression::StdModule(_)
+            | Expression::Module(_) => {}
+        }
+    }
+
+    fn arm(&mut self, pattern: &Pattern, guard: Option<&Expression>, body: &Expression) {
+        let mut names = HashSet::new();
+        pattern_bindings(pattern, &mut names);
+        self.scoped(names, |walker| {
+            if let Some(guard) = guard {
+                walker.expression(guard);
+            }
+            walker.expression(body);
+        });
+    }
+}
+
+/// The variable written by an assignment through `target`, when the
+/// target is a field or element of one: `p.x = ...`, `items[i] = ...`.
+fn assigned_variable(target: &Expression) -> Option<&str> {
+    match target {
+        Expression::Identifier(name) => Some(name),
+        Expression::StructField { struct_: inner, .. }
+        | Expression::MemberAccess { object: inner, .. }
+        | Expression::ArrayIndex { array: inner, .. } => assigned_variable(inner),
+        _ => None,
+    }
+}
+
+fn pattern_bindings(pattern: &Pattern, names: &mut HashSet<String>) {
+    match pattern {
+        Pattern::Identifier(name) => {
+            names.insert(name.clone());
+        }
+        Pattern::Binding { name, pattern } => {
+            names.insert(name.clone());
+            pattern_bindings(pattern, names);
+        }
+        Pattern::Struct { fields, .. } => fields.iter().for_each(|(_, field)| pattern_bindings(field, names)),
+        Pattern::EnumVariant { payload: Some(payload), .. } => pattern_bindings(payload, names),
+        Pattern::Tuple(elements) => elements.iter().for_each(|element| pattern_bindings(element, names)),
+        Pattern::Or(alternatives) => alternatives.iter().for_each(|alternative| pattern_bindings(alternative, names)),
+        Pattern::EnumVariant { payload: None, .. }
+        | Pattern::Literal(_)
+        | Pattern::Wildcard
+        | Pattern::Range { .. } => {}
+    }
+}
@@ -0,0 +1,175 @@
+//! Behavior objects.
+//!
+//! A `dyn Shape` holds a pointer to a value of any type implementing
+//! `Shape` along with that type's vtable for the behavior, so values of
+//! different types can be passed and stored alike. A pointer to a value of
+//! an implementing type converts to one where a `dyn Shape` is expected, as
+//! does a variable of the type, whose address is taken as for method
+//! receivers. Methods called on a behavior object go through the vtable,
+//! whose slots follow the order the behavior declares its methods in.
+//!
+//! Only behaviors whose methods all take `self` by pointer and don't
+//! otherwise mention `Self` can be used this way, since the object has
+//! forgotten which type it holds.
+
+use super::{methods, DynCoercion, DynMethodCall, TypeChecker};
+use crate::ast::{AstType, Expression};
+use crate::error::{CompileError, Result};
+
+impl TypeChecker {
+    /// The type of `expr`, of type `actual`, where a `dyn behavior` is
+    /// expected. Conversions are recorded for codegen.
+    pub(super) fn coerce_to_dyn(&mut self, expr: &Expression, actual: &AstType, behavior: &str) -> Result<AstType> {
+        let actual = self.unifier.resolve(actual);
+        let (value_type, data) = match &actual {
+            // Already an object, or left for inference to report
+            AstType::Dyn(_) | AstType::Var(_) => return Ok(actual),
+            AstType::Pointer(pointee) => (pointee.as_ref().clone(), expr.clone()),
+            value_type if methods::is_place(expr) => (value_type.clone(), Expression::AddressOf(Box::new(expr.clone()))),
+            value_type => {
+                return Err(CompileError::TypeError(
+                    format!(
+                        "A dyn {} points at its value, but this {} is a temporary; bind it to a variable first",
+                        behavior, value_type
+                    ),
+                    None,
+                ));
+            }
+        };
+
+        self.check_object_safe(behavior)?;
+        let type_name = methods::type_name(&value_type);
+        let Some(implementation) = self.behavior_resolver.get_impl(&type_name, behavior) else {
+            return Err(CompileError::TypeError(
+                format!(
+                    "Type '{}' does not implement behavior '{}', so it can't be used as a dyn {}; add `{}.impl = {{ {}: {{ ... }} }}`",
+                    type_name, behavior, behavior, type_name, behavior
+                ),
+                None,
+            ));
+        };
+        let mut by_value: Vec<&String> = implementation
+            .methods
+            .values()
+            .filter(|method| !matches!(method.param_types.first(), Some(AstType::Pointer(_))))
+            .map(|method| &method.name)
+            .collect();
+        by_value.sort();
+        if let Some(method) = by_value.first() {
+            return Err(CompileError::TypeError(
+                format!(
+                    "'{}' of {}'s '{}' implementation must take self by pointer to be called through a dyn {}",
+                    method, type_name, behavior, behavior
+                ),
+                None,
+            ));
+        }
+
+        let coercion = DynCoercion { type_name, behavior: behavior.to_string(), data };
+        if let Some(id) = self.span_cursor.current() {
+            self.dyn_coercions.insert(id, coercion);
+        }
+        Ok(AstType::Dyn(behavior.to_string()))
+    }
+
+    /// `object.method(args)` on a `dyn behavior` value.
+    pub(super) fn infer_dyn_method_call_type(
+        &mut self,
+        object: &Expression,
+        behavior: &str,
+        method: &str,
+        args: &[Expression],
+    ) -> Result<AstType> {
+        self.check_object_safe(behavior)?;
+        let info = self.behavior_resolver.get_behavior(behavior).cloned().ok_or_else(|| unknown_behavior(behavior))?;
+        let Some(slot) = info.methods.iter().position(|declared| declared.name == method) else {
+            return Err(CompileError::TypeError(
+                format!("No method named '{}' for dyn {}; behavior '{}' doesn't declare one", method, behavior, behavior),
+                None,
+            ));
+        };
+        let declared = &info.methods[slot];
+        let param_types = declared.param_types[1..].to_vec();
+        if args.len() != param_types.len() {
+            return Err(CompileError::TypeError(
+                format!("'{}' takes {} argument(s) but {} were given", method, param_types.len(), args.len()),
+                None,
+            ));
+        }
+        for (index, (param_type, arg)) in param_types.iter().zip(args).enumerate() {
+            self.check_argument(index + 1, param_type, arg, || format!("in argument {} of '{}'", index + 1, method))?;
+        }
+
+        let resolved = DynMethodCall {
+            behavior: behavior.to_string(),
+            method: method.to_string(),
+            slot,
+            receiver: object.clone(),
+            args: args.to_vec(),
+            param_types,
+            return_type: declared.return_type.clone(),
+        };
+        if let Some(id) = self.span_cursor.current() {
+            self.dyn_method_calls.insert(id, resolved);
+        }
+        Ok(declared.return_type.clone())
+    }
+
+    /// Whether every method of `behavior` can be called without knowing
+    /// the type behind the object.
+    fn check_object_safe(&self, behavior: &str) -> Result<()> {
+        let info = self.behavior_resolver.get_behavior(behavior).ok_or_else(|| unknown_behavior(behavior))?;
+        if !info.type_params.is_empty() {
+            return Err(CompileError::TypeError(
+                format!("Behavior '{}' has type parameters, so it can't be used as a dyn type", behavior),
+                None,
+            ));
+        }
+        for method in &info.methods {
+            let by_pointer = method.has_self
+                && matches!(method.param_types.first(), Some(AstType::Pointer(pointee)) if is_self(pointee));
+            if !by_pointer {
+                return Err(CompileError::TypeError(
+                    format!(
+                        "'{}' of behavior '{}' doesn't take self by pointer, so it can't be called through a dyn {}",
+                        method.name, behavior, behavior
+                    ),
+                    None,
+                ));
+            }
+            if method.param_types[1..].iter().chain([&method.return_type]).any(mentions_self) {
+                return Err(CompileError::TypeError(
+                    format!(
+                        "'{}' of behavior '{}' uses Self outside its receiver, so it can't be called through a dyn {}",
+                        method.name, behavior, behavior
+                    ),
+                    None,
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn unknown_behavior(behavior: &str) -> CompileError {
+    CompileError::TypeError(format!("Unknown behavior '{}' in dyn {}", behavior, behavior), None)
+}
+
+fn is_self(type_: &AstType) -> bool {
+    matches!(type_, AstType::Generic { name, type_args } if name == "Self" && type_args.is_empty())
+}
+
+fn mentions_self(type_: &AstType) -> bool {
+    match type_ {
+        AstType::Generic { name, type_args } => {
+            is_self(type_) || name.starts_with("Self.") || type_args.iter().any(mentions_self)
+        }
+        AstType::Pointer(inner) | AstType::Array(inner) | AstType::Option(inner) | AstType::Ref(inner) => mentions_self(inner),
+        AstType::Result { ok_type, err_type } => mentions_self(ok_type) || mentions_self(err_type),
+        AstType::Tuple(elements) => elements.iter().any(mentions_self),
+        AstType::Closure { param_types, return_type } | AstType::FunctionPointer { param_types, return_type } => {
+            param_types.iter().any(mentions_self) || mentions_self(return_type)
+        }
+        _ => false,
+    }
+}
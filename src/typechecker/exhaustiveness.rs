@@ -0,0 +1,653 @@
+//! Exhaustiveness and reachability checking for `?` matches.
+//!
+//! Arms are lowered to a pattern matrix over the scrutinee's shape and
+//! checked with the usual usefulness algorithm: an arm is unreachable if
+//! it is not useful after the arms above it, and the match is exhaustive
+//! if a wildcard is not useful after all of them. Guarded arms can be
+//! unreachable but never count towards coverage.
+
+use crate::ast::{AstType, Expression, Pattern};
+use crate::typechecker::{EnumInfo, StructInfo};
+use std::collections::HashMap;
+
+/// Stop listing missing patterns after this many.
+const MAX_MISSING: usize = 8;
+
+/// The outcome of checking one match.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MatchReport {
+    /// Patterns no arm covers, in Zen syntax. Only reported for matches
+    /// over bools, enums, `Option` and `Result`.
+    pub missing: Vec<String>,
+    pub unreachable: Vec<UnreachableArm>,
+}
+
+/// An arm, or one alternative of an or-pattern, that can never match.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnreachableArm {
+    /// Zero-based arm index.
+    pub arm: usize,
+    pub pattern: String,
+    /// Whether only this alternative of the arm's or-pattern is dead.
+    pub alternative: bool,
+}
+
+/// Check the arms of a match on a value of type `scrutinee`. Each arm is
+/// given with whether it has a guard.
+pub fn check_match(
+    scrutinee: &AstType,
+    arms: &[(&Pattern, bool)],
+    enums: &HashMap<String, EnumInfo>,
+    structs: &HashMap<String, StructInfo>,
+) -> MatchReport {
+    let analyzer = Analyzer { enums, structs };
+    let shape = match analyzer.shape_of(scrutinee) {
+        Shape::Opaque => analyzer.shape_from_patterns(arms.iter().map(|(pattern, _)| *pattern)),
+        shape => shape,
+    };
+
+    let mut report = MatchReport::default();
+    let mut rows: Vec<Vec<Pat>> = Vec::new();
+    for (arm, (pattern, guarded)) in arms.iter().enumerate() {
+        let pat = analyzer.lower(pattern, &shape);
+        let shapes = [shape.clone()];
+        match (&pat, pattern) {
+            (Pat::Or(alternatives), Pattern::Or(sources)) => {
+                // Earlier alternatives of the same arm shadow later ones
+                let mut seen = rows.clone();
+                let mut dead = Vec::new();
+                for (alternative, source) in alternatives.iter().zip(sources) {
+                    let row = vec![alternative.clone()];
+                    if !analyzer.is_useful(&seen, &row, &shapes) {
+                        dead.push(describe(source));
+                    }
+                    seen.push(row);
+                }
+                if dead.len() == alternatives.len() {
+                    report.unreachable.push(UnreachableArm { arm, pattern: describe(pattern), alternative: false });
+                } else {
+                    report.unreachable.extend(dead.into_iter().map(|pattern| UnreachableArm {
+                        arm,
+                        pattern,
+                        alternative: true,
+                    }));
+                }
+            }
+            _ => {
+                if !analyzer.is_useful(&rows, &[pat.clone()], &shapes) {
+                    report.unreachable.push(UnreachableArm { arm, pattern: describe(pattern), alternative: false });
+                }
+            }
+        }
+        if !guarded {
+            rows.push(vec![pat]);
+        }
+    }
+
+    if matches!(shape, Shape::Bool | Shape::Enum { .. }) {
+        let missing = analyzer.missing(&rows, &[shape]);
+        report.missing = missing.into_iter().filter_map(|mut witness| witness.pop()).collect();
+    }
+    report
+}
+
+/// Render a pattern in Zen syntax for messages.
+pub fn describe(pattern: &Pattern) -> String {
+    match pattern {
+        Pattern::Wildcard => "_".to_string(),
+        Pattern::Identifier(name) => name.clone(),
+        Pattern::Literal(expr) => describe_expression(expr),
+        Pattern::Range { start, end, inclusive } => format!(
+            "{}{}{}",
+            describe_expression(start),
+            if *inclusive { "..=" } else { ".." },
+            describe_expression(end)
+        ),
+        Pattern::EnumVariant { enum_name, variant, payload } => {
+            let prefix = if enum_name.is_empty() { ".".to_string() } else { format!("{}::", enum_name) };
+            match payload {
+                Some(payload) => format!("{}{}({})", prefix, variant, describe(payload)),
+                None => format!("{}{}", prefix, variant),
+            }
+        }
+        Pattern::Struct { name, fields } => {
+            let fields: Vec<String> = fields
+                .iter()
+                .map(|(field, pattern)| format!("{}: {}", field, describe(pattern)))
+                .collect();
+            format!("{} {{ {} }}", name, fields.join(", "))
+        }
+        Pattern::Tuple(elements) => format!("({})", elements.iter().map(describe).collect::<Vec<_>>().join(", ")),
+        Pattern::Or(patterns) => patterns.iter().map(describe).collect::<Vec<_>>().join(" | "),
+        Pattern::Binding { name, pattern } => format!("{} -> {}", name, describe(pattern)),
+    }
+}
+
+fn describe_expression(expr: &Expression) -> String {
+    match expr {
+        Expression::Range { start, end, inclusive } => format!(
+            "{}{}{}",
+            describe_expression(start),
+            if *inclusive { "..=" } else { ".." },
+            describe_expression(end)
+        ),
+        Expression::String(value) => format!("{:?}", value),
+        Expression::Boolean(value) => value.to_string(),
+        Expression::Identifier(name) => name.clone(),
+        other => integer_value(other)
+            .map(|value| value.to_string())
+            .unwrap_or_else(|| "<literal>".to_string()),
+    }
+}
+
+fn integer_value(expr: &Expression) -> Option<i128> {
+    match expr {
+        Expression::Integer8(v) => Some(*v as i128),
+        Expression::Integer16(v) => Some(*v as i128),
+        Expression::Integer32(v) => Some(*v as i128),
+        Expression::Integer64(v) => Some(*v as i128),
+        Expression::Unsigned8(v) => Some(*v as i128),
+        Expression::Unsigned16(v) => Some(*v as i128),
+        Expression::Unsigned32(v) => Some(*v as i128),
+        Expression::Unsigned64(v) => Some(*v as i128),
+        _ => None,
+    }
+}
+
+/// What patterns can distinguish about a value's type. Payload and field
+/// types are kept as `AstType`s so recursive enums don't expand forever.
+#[derive(Debug, Clone)]
+enum Shape {
+    Bool,
+    Int { min: i128, max: i128 },
+    Enum { variants: Vec<(String, Option<AstType>)> },
+    Struct { name: String, fields: Vec<(String, AstType)> },
+    Tuple(Vec<AstType>),
+    Opaque,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Ctor {
+    Bool(bool),
+    Variant(usize),
+    Struct,
+    /// Inclusive integer range.
+    Range(i128, i128),
+    /// A literal or pattern the analysis can't reason about; only equal
+    /// to itself.
+    Opaque(String),
+}
+
+#[derive(Debug, Clone)]
+enum Pat {
+    Wild,
+    Ctor(Ctor, Vec<Pat>),
+    Or(Vec<Pat>),
+}
+
+struct Analyzer<'a> {
+    enums: &'a HashMap<String, EnumInfo>,
+    structs: &'a HashMap<String, StructInfo>,
+}
+
+impl Analyzer<'_> {
+    fn shape_of(&self, type_: &AstType) -> Shape {
+        match type_ {
+            AstType::Bool => Shape::Bool,
+            AstType::I8 => int_shape(8, true),
+            AstType::I16 => int_shape(16, true),
+            AstType::I32 => int_shape(32, true),
+            AstType::I64 => int_shape(64, true),
+            AstType::U8 => int_shape(8, false),
+            AstType::U16 => int_shape(16, false),
+            AstType::U32 => int_shape(32, false),
+            AstType::U64 => int_shape(64, false),
+            AstType::Option(inner) => option_shape(inner),
+            AstType::Result { ok_type, err_type } => result_shape(ok_type, err_type),
+            AstType::Enum { variants, .. } => Shape::Enum {
+                variants: variants.iter().map(|v| (v.name.clone(), v.payload.clone())).collect(),
+            },
+            AstType::Generic { name, type_args } => match (name.as_str(), type_args.as_slice()) {
+                ("Option", [inner]) => option_shape(inner),
+                ("Result", [ok_type, err_type]) => result_shape(ok_type, err_type),
+                (_, []) => self.named_shape(name),
+                _ => Shape::Opaque,
+            },
+            AstType::Struct { name, .. } => self.named_shape(name),
+            AstType::Tuple(elements) => Shape::Tuple(elements.clone()),
+            _ => Shape::Opaque,
+        }
+    }
+
+    fn named_shape(&self, name: &str) -> Shape {
+        if let Some(info) = self.enums.get(name) {
+            Shape::Enum { variants: info.variants.clone() }
+        } else if let Some(info) = self.structs.get(name) {
+            Shape::Struct { name: name.to_string(), fields: info.fields.clone() }
+        } else {
+            Shape::Opaque
+        }
+    }
+
+    /// Work out what is being matched from the patterns when the
+    /// scrutinee's type is unknown, e.g. `.Some(x)` implies an `Option`.
+    fn shape_from_patterns<'p>(&self, patterns: impl Iterator<Item = &'p Pattern>) -> Shape {
+        for pattern in patterns {
+            match pattern {
+                Pattern::Binding { pattern, .. } => {
+                    let shape = self.shape_from_patterns(std::iter::once(pattern.as_ref()));
+                    if !matches!(shape, Shape::Opaque) {
+                        return shape;
+                    }
+                }
+                Pattern::Or(alternatives) => {
+                    let shape = self.shape_from_patterns(alternatives.iter());
+                    if !matches!(shape, Shape::Opaque) {
+                        return shape;
+                    }
+                }
+                Pattern::Literal(Expression::Boolean(_)) => return Shape::Bool,
+                Pattern::EnumVariant { enum_name, variant, .. } => {
+                    let unknown = AstType::Void;
+                    match (enum_name.as_str(), variant.as_str()) {
+                        ("Option", _) | ("", "Some" | "None") => return option_shape(&unknown),
+                        ("Result", _) | ("", "Ok" | "Err") => return result_shape(&unknown, &unknown),
+                        ("", variant) => {
+                            // Only trust a variant name that a single enum declares
+                            let mut owners = self
+                                .enums
+                                .values()
+                                .filter(|info| info.variants.iter().any(|(name, _)| name == variant));
+                            if let (Some(info), None) = (owners.next(), owners.next()) {
+                                return Shape::Enum { variants: info.variants.clone() };
+                            }
+                        }
+                        (name, _) => return self.named_shape(name),
+                    }
+                }
+                _ => {}
+            }
+        }
+        Shape::Opaque
+    }
+
+    fn lower(&self, pattern: &Pattern, shape: &Shape) -> Pat {
+        match pattern {
+            Pattern::Wildcard | Pattern::Identifier(_) => Pat::Wild,
+            Pattern::Binding { pattern, .. } => self.lower(pattern, shape),
+            Pattern::Or(alternatives) => {
+                Pat::Or(alternatives.iter().map(|alternative| self.lower(alternative, shape)).collect())
+            }
+            Pattern::Literal(Expression::Boolean(value)) => Pat::Ctor(Ctor::Bool(*value), vec![]),
+            Pattern::Literal(Expression::Range { start, end, inclusive }) => range_pat(start, end, *inclusive, pattern),
+            Pattern::Range { start, end, inclusive } => range_pat(start, end, *inclusive, pattern),
+            Pattern::Literal(expr) => match integer_value(expr) {
+                Some(value) => Pat::Ctor(Ctor::Range(value, value), vec![]),
+                None => opaque(pattern),
+            },
+            Pattern::EnumVariant { variant, payload, .. } => {
+                let Shape::Enum { variants } = shape else {
+                    return opaque(pattern);
+                };
+                let Some(index) = variants.iter().position(|(name, _)| name == variant) else {
+                    return opaque(pattern);
+                };
+                let fields = match &variants[index].1 {
+                    Some(payload_type) => {
+                        let payload_shape = self.shape_of(payload_type);
+                        vec![payload.as_ref().map_or(Pat::Wild, |payload| self.lower(payload, &payload_shape))]
+                    }
+                    None => vec![],
+                };
+                Pat::Ctor(Ctor::Variant(index), fields)
+            }
+            Pattern::Struct { name, fields } => {
+                let Shape::Struct { name: struct_name, fields: declared } = shape else {
+                    return opaque(pattern);
+                };
+                if name != struct_name {
+                    return opaque(pattern);
+                }
+                let lowered = declared
+                    .iter()
+                    .map(|(field, field_type)| match fields.iter().find(|(name, _)| name == field) {
+                        Some((_, pattern)) => self.lower(pattern, &self.shape_of(field_type)),
+                        None => Pat::Wild,
+                    })
+                    .collect();
+                Pat::Ctor(Ctor::Struct, lowered)
+            }
+            Pattern::Tuple(elements) => match shape {
+                Shape::Tuple(types) if types.len() == elements.len() => Pat::Ctor(
+                    Ctor::Struct,
+                    elements
+                        .iter()
+                        .zip(types)
+                        .map(|(element, element_type)| self.lower(element, &self.shape_of(element_type)))
+                        .collect(),
+                ),
+                _ => opaque(pattern),
+            },
+        }
+    }
+
+    /// Shapes of the fields a constructor carries.
+    fn fields_of(&self, ctor: &Ctor, shape: &Shape) -> Vec<Shape> {
+        match (ctor, shape) {
+            (Ctor::Variant(index), Shape::Enum { variants }) => variants[*index]
+                .1
+                .iter()
+                .map(|payload| self.shape_of(payload))
+                .collect(),
+            (Ctor::Struct, Shape::Struct { fields, .. }) => {
+                fields.iter().map(|(_, field_type)| self.shape_of(field_type)).collect()
+            }
+            (Ctor::Struct, Shape::Tuple(elements)) => elements.iter().map(|element| self.shape_of(element)).collect(),
+            _ => vec![],
+        }
+    }
+
+    /// Whether a row starting with `v` would match some value that none
+    /// of `rows` match.
+    fn is_useful(&self, rows: &[Vec<Pat>], v: &[Pat], shapes: &[Shape]) -> bool {
+        let Some((head, rest)) = v.split_first() else {
+            return rows.is_empty();
+        };
+        let shape = &shapes[0];
+        match head {
+            Pat::Or(alternatives) => alternatives.iter().any(|alternative| {
+                let mut row = vec![alternative.clone()];
+                row.extend_from_slice(rest);
+                self.is_useful(rows, &row, shapes)
+            }),
+            Pat::Ctor(Ctor::Range(low, high), _) => split_range(*low, *high, &head_ranges(rows))
+                .into_iter()
+                .any(|(low, high)| self.is_useful_with(rows, &Ctor::Range(low, high), &[], rest, shapes)),
+            Pat::Ctor(ctor, fields) => self.is_useful_with(rows, ctor, fields, rest, shapes),
+            Pat::Wild => match self.complete_ctors(rows, shape) {
+                Some(ctors) => ctors.iter().any(|ctor| {
+                    let fields = vec![Pat::Wild; self.fields_of(ctor, shape).len()];
+                    self.is_useful_with(rows, ctor, &fields, rest, shapes)
+                }),
+                None => self.is_useful(&default_rows(rows), rest, &shapes[1..]),
+            },
+        }
+    }
+
+    fn is_useful_with(&self, rows: &[Vec<Pat>], ctor: &Ctor, fields: &[Pat], rest: &[Pat], shapes: &[Shape]) -> bool {
+        let mut sub_shapes = self.fields_of(ctor, &shapes[0]);
+        sub_shapes.extend_from_slice(&shapes[1..]);
+        let mut row = fields.to_vec();
+        row.extend_from_slice(rest);
+        self.is_useful(&specialize(rows, ctor, fields.len()), &row, &sub_shapes)
+    }
+
+    /// Values no row matches, one pattern per column.
+    fn missing(&self, rows: &[Vec<Pat>], shapes: &[Shape]) -> Vec<Vec<String>> {
+        let Some((shape, rest_shapes)) = shapes.split_first() else {
+            return if rows.is_empty() { vec![vec![]] } else { vec![] };
+        };
+
+        let mut witnesses = Vec::new();
+        match self.complete_ctors(rows, shape) {
+            Some(ctors) => {
+                for ctor in ctors {
+                    let arity = self.fields_of(&ctor, shape).len();
+                    let mut sub_shapes = self.fields_of(&ctor, shape);
+                    sub_shapes.extend_from_slice(rest_shapes);
+                    for witness in self.missing(&specialize(rows, &ctor, arity), &sub_shapes) {
+                        let (fields, rest) = witness.split_at(arity);
+                        let mut row = vec![self.format_ctor(&ctor, shape, fields)];
+                        row.extend_from_slice(rest);
+                        witnesses.push(row);
+                    }
+                    if witnesses.len() >= MAX_MISSING {
+                        break;
+                    }
+                }
+            }
+            None => {
+                let rest = self.missing(&default_rows(rows), rest_shapes);
+                if rest.is_empty() {
+                    return witnesses;
+                }
+                // Name the constructors no arm mentions, unless none are
+                // mentioned at all
+                let present = head_ctors(rows);
+                let heads: Vec<String> = match shape {
+                    Shape::Bool | Shape::Enum { .. } if !present.is_empty() => self
+                        .all_ctors(shape)
+                        .into_iter()
+                        .filter(|ctor| !present.contains(ctor))
+                        .map(|ctor| {
+                            let fields = vec!["_".to_string(); self.fields_of(&ctor, shape).len()];
+                            self.format_ctor(&ctor, shape, &fields)
+                        })
+                        .collect(),
+                    _ => vec!["_".to_string()],
+                };
+                for head in heads {
+                    for witness in &rest {
+                        let mut row = vec![head.clone()];
+                        row.extend_from_slice(witness);
+                        witnesses.push(row);
+                    }
+                }
+            }
+        }
+        witnesses.truncate(MAX_MISSING);
+        witnesses
+    }
+
+    fn all_ctors(&self, shape: &Shape) -> Vec<Ctor> {
+        match shape {
+            Shape::Bool => vec![Ctor::Bool(true), Ctor::Bool(false)],
+            Shape::Enum { variants } => (0..variants.len()).map(Ctor::Variant).collect(),
+            Shape::Struct { .. } | Shape::Tuple(_) => vec![Ctor::Struct],
+            Shape::Int { min, max } => vec![Ctor::Range(*min, *max)],
+            Shape::Opaque => vec![],
+        }
+    }
+
+    /// The constructors to split the first column on if the rows mention
+    /// every value of `shape`; `None` if some value is only matched by
+    /// wildcards.
+    fn complete_ctors(&self, rows: &[Vec<Pat>], shape: &Shape) -> Option<Vec<Ctor>> {
+        let present = head_ctors(rows);
+        match shape {
+            Shape::Bool | Shape::Enum { .. } => {
+                let all = self.all_ctors(shape);
+                all.iter().all(|ctor| present.contains(ctor)).then_some(all)
+            }
+            Shape::Struct { .. } | Shape::Tuple(_) => Some(vec![Ctor::Struct]),
+            Shape::Int { min, max } => {
+                let ranges = head_ranges(rows);
+                let segments = split_range(*min, *max, &ranges);
+                let covered = segments
+                    .iter()
+                    .all(|(low, high)| ranges.iter().any(|(a, b)| a <= low && high <= b));
+                covered.then(|| segments.into_iter().map(|(low, high)| Ctor::Range(low, high)).collect())
+            }
+            Shape::Opaque => None,
+        }
+    }
+
+    fn format_ctor(&self, ctor: &Ctor, shape: &Shape, fields: &[String]) -> String {
+        match (ctor, shape) {
+            (Ctor::Bool(value), _) => value.to_string(),
+            (Ctor::Variant(index), Shape::Enum { variants }) => match fields {
+                [payload] => format!(".{}({})", variants[*index].0, payload),
+                _ => format!(".{}", variants[*index].0),
+            },
+            (Ctor::Struct, Shape::Struct { name, fields: declared }) => {
+                let fields: Vec<String> = declared
+                    .iter()
+                    .zip(fields)
+                    .map(|((field, _), pattern)| format!("{}: {}", field, pattern))
+                    .collect();
+                format!("{} {{ {} }}", name, fields.join(", "))
+            }
+            (Ctor::Struct, Shape::Tuple(_)) => format!("({})", fields.join(", ")),
+            (Ctor::Range(low, high), _) if low == high => low.to_string(),
+            (Ctor::Range(low, high), _) => format!("{}..={}", low, high),
+            _ => "_".to_string(),
+        }
+    }
+}
+
+fn int_shape(bits: u32, signed: bool) -> Shape {
+    if signed {
+        Shape::Int { min: -(1i128 << (bits - 1)), max: (1i128 << (bits - 1)) - 1 }
+    } else {
+        Shape::Int { min: 0, max: (1i128 << bits) - 1 }
+    }
+}
+
+fn option_shape(inner: &AstType) -> Shape {
+    Shape::Enum {
+        variants: vec![("Some".to_string(), Some(inner.clone())), ("None".to_string(), None)],
+    }
+}
+
+fn result_shape(ok_type: &AstType, err_type: &AstType) -> Shape {
+    Shape::Enum {
+        variants: vec![
+            ("Ok".to_string(), Some(ok_type.clone())),
+            ("Err".to_string(), Some(err_type.clone())),
+        ],
+    }
+}
+
+fn opaque(pattern: &Pattern) -> Pat {
+    Pat::Ctor(Ctor::Opaque(describe(pattern)), vec![])
+}
+
+fn range_pat(start: &Expression, end: &Expression, inclusive: bool, pattern: &Pattern) -> Pat {
+    match (integer_value(start), integer_value(end)) {
+        (Some(low), Some(high)) => {
+            let high = if inclusive { high } else { high - 1 };
+            if low > high {
+                // An empty range matches nothing
+                opaque(pattern)
+            } else {
+                Pat::Ctor(Ctor::Range(low, high), vec![])
+            }
+        }
+        _ => opaque(pattern),
+    }
+}
+
+/// Rows whose first pattern could match constructor `ctor`, with that
+/// pattern replaced by its `arity` fields. Or-patterns are expanded.
+fn specialize(rows: &[Vec<Pat>], ctor: &Ctor, arity: usize) -> Vec<Vec<Pat>> {
+    let mut result = Vec::new();
+    for row in rows {
+        let (head, rest) = row.split_first().expect("rows are never empty here");
+        match head {
+            Pat::Wild => {
+                let mut new_row = vec![Pat::Wild; arity];
+                new_row.extend_from_slice(rest);
+                result.push(new_row);
+            }
+            Pat::Or(alternatives) => {
+                let expanded: Vec<Vec<Pat>> = alternatives
+                    .iter()
+                    .map(|alternative| {
+                        let mut new_row = vec![alternative.clone()];
+                        new_row.extend_from_slice(rest);
+                        new_row
+                    })
+                    .collect();
+                result.extend(specialize(&expanded, ctor, arity));
+            }
+            Pat::Ctor(head_ctor, fields) if covers(head_ctor, ctor) => {
+                let mut new_row = fields.clone();
+                new_row.extend_from_slice(rest);
+                result.push(new_row);
+            }
+            Pat::Ctor(..) => {}
+        }
+    }
+    result
+}
+
+/// Rows that match anything in the first column, without that column.
+fn default_rows(rows: &[Vec<Pat>]) -> Vec<Vec<Pat>> {
+    let mut result = Vec::new();
+    for row in rows {
+        let (head, rest) = row.split_first().expect("rows are never empty here");
+        match head {
+            Pat::Wild => result.push(rest.to_vec()),
+            Pat::Or(alternatives) => {
+                let expanded: Vec<Vec<Pat>> = alternatives
+                    .iter()
+                    .map(|alternative| {
+                        let mut new_row = vec![alternative.clone()];
+                        new_row.extend_from_slice(rest);
+                        new_row
+                    })
+                    .collect();
+                result.extend(default_rows(&expanded));
+            }
+            Pat::Ctor(..) => {}
+        }
+    }
+    result
+}
+
+/// Whether a pattern headed by `pattern` matches everything `ctor` does.
+/// Ranges are split beforehand so they either contain or miss each other.
+fn covers(pattern: &Ctor, ctor: &Ctor) -> bool {
+    match (pattern, ctor) {
+        (Ctor::Range(a, b), Ctor::Range(low, high)) => a <= low && high <= b,
+        _ => pattern == ctor,
+    }
+}
+
+fn head_ctors(rows: &[Vec<Pat>]) -> Vec<Ctor> {
+    fn collect(pat: &Pat, out: &mut Vec<Ctor>) {
+        match pat {
+            Pat::Ctor(ctor, _) if !out.contains(ctor) => out.push(ctor.clone()),
+            Pat::Or(alternatives) => alternatives.iter().for_each(|alternative| collect(alternative, out)),
+            _ => {}
+        }
+    }
+    let mut ctors = Vec::new();
+    for row in rows {
+        if let Some(head) = row.first() {
+            collect(head, &mut ctors);
+        }
+    }
+    ctors
+}
+
+fn head_ranges(rows: &[Vec<Pat>]) -> Vec<(i128, i128)> {
+    head_ctors(rows)
+        .into_iter()
+        .filter_map(|ctor| match ctor {
+            Ctor::Range(low, high) => Some((low, high)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Cut `low..=high` at the boundaries of `ranges`, so every piece lies
+/// either entirely inside or entirely outside each range.
+fn split_range(low: i128, high: i128, ranges: &[(i128, i128)]) -> Vec<(i128, i128)> {
+    let mut cuts: Vec<i128> = ranges
+        .iter()
+        .flat_map(|(a, b)| [*a, b + 1])
+        .filter(|cut| low < *cut && *cut <= high)
+        .collect();
+    cuts.sort_unstable();
+    cuts.dedup();
+
+    let mut pieces = Vec::new();
+    let mut start = low;
+    for cut in cuts {
+        pieces.push((start, cut - 1));
+        start = cut;
+    }
+    pieces.push((start, high));
+    pieces
+}
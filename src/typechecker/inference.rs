@@ -1,19 +1,43 @@
-use crate::ast::{AstType, BinaryOperator, Expression};
+use crate::ast::{AstType, BinaryOperator, Expression, UnaryOperator};
 use crate::error::{CompileError, Result};
 use crate::typechecker::{TypeChecker, StructInfo};
 use std::collections::HashMap;
 
 /// Infer the type of a binary operation
 pub fn infer_binary_op_type(
-    checker: &TypeChecker,
+    checker: &mut TypeChecker,
     left: &Expression,
     op: &BinaryOperator,
     right: &Expression,
 ) -> Result<AstType> {
-    let left_type = checker.infer_expression_type(left)?;
-    let right_type = checker.infer_expression_type(right)?;
+    let left_type = checker.infer_child_type(0, left)?;
+    let right_type = checker.infer_child_type(1, right)?;
+    infer_operation_type(checker, op, left_type, right_type)
+}
+
+/// Infer the type of applying `op` to operands of the given types, the
+/// operands of the node under the cursor, and record them for codegen.
+pub fn infer_operation_type(
+    checker: &mut TypeChecker,
+    op: &BinaryOperator,
+    left_type: AstType,
+    right_type: AstType,
+) -> Result<AstType> {
+    let (left_type, right_type) = solve_operand_types(checker, op, left_type, right_type)?;
+    checker.record_operand_types(left_type.clone(), right_type.clone());
 
     match op {
+        // Neither side is known yet; both must end up the same type
+        BinaryOperator::Add
+        | BinaryOperator::Subtract
+        | BinaryOperator::Multiply
+        | BinaryOperator::Divide
+        | BinaryOperator::Modulo
+        | BinaryOperator::BitAnd
+        | BinaryOperator::BitOr
+        | BinaryOperator::BitXor
+        | BinaryOperator::ShiftLeft
+        | BinaryOperator::ShiftRight if matches!(left_type, AstType::Var(_)) => Ok(left_type),
         BinaryOperator::Add
         | BinaryOperator::Subtract
         | BinaryOperator::Multiply
@@ -30,6 +54,27 @@ pub fn infer_binary_op_type(
                 ), None))
             }
         }
+        BinaryOperator::BitAnd | BinaryOperator::BitOr | BinaryOperator::BitXor => {
+            if left_type.is_integer() && right_type.is_integer() {
+                promote_numeric_types(&left_type, &right_type)
+            } else {
+                Err(CompileError::TypeError(format!(
+                    "Bitwise operators require integer operands, got {:?} and {:?}",
+                    left_type, right_type
+                ), None))
+            }
+        }
+        BinaryOperator::ShiftLeft | BinaryOperator::ShiftRight => {
+            // The result has the type of the value shifted
+            if left_type.is_integer() && right_type.is_integer() {
+                Ok(left_type)
+            } else {
+                Err(CompileError::TypeError(format!(
+                    "Shifts require integer operands, got {:?} and {:?}",
+                    left_type, right_type
+                ), None))
+            }
+        }
         BinaryOperator::Equals
         | BinaryOperator::NotEquals
         | BinaryOperator::LessThan
@@ -71,6 +116,50 @@ pub fn infer_binary_op_type(
     }
 }
 
+/// Infer the type of a unary operation
+pub fn infer_unary_op_type(checker: &mut TypeChecker, op: UnaryOperator, operand: &Expression) -> Result<AstType> {
+    let operand_type = checker.infer_child_type(0, operand)?;
+    match op {
+        UnaryOperator::Not if matches!(operand_type, AstType::Var(_)) => {
+            checker.constrain(&AstType::Bool, &operand_type, || "in operand of Not".to_string())?;
+            Ok(AstType::Bool)
+        }
+        // Not known yet; it's whatever the operand turns out to be
+        _ if matches!(operand_type, AstType::Var(_)) => Ok(operand_type),
+        UnaryOperator::Not if matches!(operand_type, AstType::Bool) => Ok(AstType::Bool),
+        UnaryOperator::Negate if operand_type.is_numeric() => Ok(operand_type),
+        UnaryOperator::BitNot if operand_type.is_integer() => Ok(operand_type),
+        _ => Err(CompileError::TypeError(format!(
+            "Cannot apply {:?} to type {:?}",
+            op, operand_type
+        ), None)),
+    }
+}
+
+/// Solve an operand whose type is still a bare type variable from the
+/// operator and the other operand.
+fn solve_operand_types(
+    checker: &mut TypeChecker,
+    op: &BinaryOperator,
+    left_type: AstType,
+    right_type: AstType,
+) -> Result<(AstType, AstType)> {
+    if !matches!(left_type, AstType::Var(_)) && !matches!(right_type, AstType::Var(_)) {
+        return Ok((left_type, right_type));
+    }
+
+    let operand_type = match op {
+        BinaryOperator::And | BinaryOperator::Or => AstType::Bool,
+        BinaryOperator::StringConcat => AstType::String,
+        _ if matches!(left_type, AstType::Var(_)) => right_type.clone(),
+        _ => left_type.clone(),
+    };
+    let context = || format!("in operands of {:?}", op);
+    checker.constrain(&operand_type, &left_type, context)?;
+    checker.constrain(&operand_type, &right_type, context)?;
+    Ok((checker.unifier.resolve(&left_type), checker.unifier.resolve(&right_type)))
+}
+
 /// Infer the type of a member access expression
 pub fn infer_member_type(
     object_type: &AstType,
@@ -0,0 +1,403 @@
+//! Method-call resolution.
+//!
+//! `value.method(args)` calls a method declared for the value's type, in an
+//! impl block or in the body of its struct or enum, and otherwise a function
+//! whose first parameter takes the value. The receiver is passed by address
+//! when that parameter is a pointer to its type, and dereferenced when the
+//! receiver is a pointer. `Type.function(args)` calls one of the type's
+//! functions with only the arguments given, and `Type.NAME` is a constant
+//! associated with the type by one of its behavior implementations. Inside
+//! an impl block, `Self` names the implementing type.
+//!
+//! Each call is rewritten into the plain function call it resolves to and
+//! recorded in the type table, which codegen compiles in its place. Inside
+//! a generic function, a value whose type is a type parameter has the
+//! methods of the parameter's bounds; those calls are recorded apart and
+//! resolved by the monomorphizer for each instantiation. A method of a
+//! generic implementation applies when the receiver's type matches the
+//! implementation's and satisfies its bounds, after the type's own methods;
+//! the call is recorded with the implementation's type arguments for the
+//! monomorphizer to instantiate. Calls on a `dyn` behavior object go
+//! through its vtable instead.
+
+use super::{BoundMethodCall, GenericMethodCall, TypeChecker};
+use crate::ast::{AstType, Expression};
+use crate::error::{CompileError, Result};
+
+/// A function a method call can resolve to.
+struct Candidate {
+    function: String,
+    params: Vec<AstType>,
+    return_type: AstType,
+    is_generic: bool,
+    /// The type parameter and behavior of a method declared by a bound.
+    bound: Option<(String, String)>,
+    /// The type arguments of the generic implementation the method belongs
+    /// to, if it does.
+    type_args: Option<Vec<AstType>>,
+}
+
+/// How the receiver is passed as the function's first argument.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Receiver {
+    Value,
+    Reference,
+    Dereference,
+}
+
+impl TypeChecker {
+    pub(super) fn infer_method_call_type(
+        &mut self,
+        call: &Expression,
+        object: &Expression,
+        method: &str,
+        args: &[Expression],
+    ) -> Result<AstType> {
+        if let Expression::Identifier(name) = object {
+            if self.get_variable_type(name).is_err() {
+                let path = self.resolve_self_path(name)?;
+                return self.infer_path_call_type(&path, method, args);
+            }
+        }
+
+        let receiver_type = self.infer_child_type(0, object)?;
+        let receiver_type = self.unifier.resolve(&receiver_type);
+        if let AstType::Generic { name, .. } = &receiver_type {
+            // Module values from `@std` keep their qualified calls
+            if name.starts_with("StdModule::") {
+                if let Some(name) = call.qualified_call_name() {
+                    return self.infer_function_call_type(&name, args, 1);
+                }
+            }
+        }
+        if matches!(receiver_type, AstType::Var(_)) {
+            return Err(CompileError::TypeError(
+                format!("The type of the value '{}' is called on must be known here; add a type annotation", method),
+                None,
+            ));
+        }
+
+        if let AstType::Dyn(behavior) = &receiver_type {
+            return self.infer_dyn_method_call_type(object, behavior, method, args);
+        }
+
+        let (candidate, receiver) = self.find_method(&receiver_type, method)?;
+        if candidate.is_generic {
+            return Err(CompileError::TypeError(
+                format!("Generic function '{}' can't be called with method syntax yet; call it as {}(...)", method, method),
+                None,
+            ));
+        }
+        if args.len() + 1 != candidate.params.len() {
+            return Err(CompileError::TypeError(
+                format!("'{}' takes {} argument(s) but {} were given", method, candidate.params.len() - 1, args.len()),
+                None,
+            ));
+        }
+
+        let receiver = match receiver {
+            Receiver::Value => object.clone(),
+            Receiver::Reference if is_place(object) => Expression::AddressOf(Box::new(object.clone())),
+            Receiver::Reference => {
+                return Err(CompileError::TypeError(
+                    format!("'{}' takes its receiver by pointer, but this {} is a temporary; bind it to a variable first", method, receiver_type),
+                    None,
+                ));
+            }
+            Receiver::Dereference => Expression::Dereference(Box::new(object.clone())),
+        };
+        for (index, (param_type, arg)) in candidate.params[1..].iter().zip(args).enumerate() {
+            self.check_argument(index + 1, param_type, arg, || format!("in argument {} of '{}'", index + 1, method))?;
+        }
+
+        let mut resolved_args = vec![receiver];
+        resolved_args.extend_from_slice(args);
+        match (candidate.bound, candidate.type_args) {
+            (Some((type_param, behavior)), _) => {
+                let resolved = BoundMethodCall {
+                    type_param,
+                    behavior,
+                    method: candidate.function,
+                    args: resolved_args,
+                };
+                self.record_bound_method_call(resolved);
+            }
+            (None, Some(type_args)) => {
+                let resolved = GenericMethodCall {
+                    function: candidate.function,
+                    type_args,
+                    args: resolved_args,
+                };
+                self.record_generic_method_call(resolved);
+            }
+            (None, None) => {
+                self.record_method_call(Expression::FunctionCall { name: candidate.function, args: resolved_args })
+            }
+        }
+        Ok(candidate.return_type)
+    }
+
+    /// `Type.function(args)`, or a call qualified by a module or enum name.
+    fn infer_path_call_type(&mut self, path: &str, function: &str, args: &[Expression]) -> Result<AstType> {
+        let Some(method) = self.behavior_resolver.resolve_method(path, function) else {
+            if self.structs.contains_key(path) {
+                return Err(CompileError::TypeError(
+                    format!("Type '{}' has no function named '{}'", path, function),
+                    None,
+                ));
+            }
+            return self.infer_function_call_type(&format!("{}.{}", path, function), args, 1);
+        };
+
+        if args.len() != method.param_types.len() {
+            return Err(CompileError::TypeError(
+                format!("'{}.{}' takes {} argument(s) but {} were given", path, function, method.param_types.len(), args.len()),
+                None,
+            ));
+        }
+        for (index, (param_type, arg)) in method.param_types.iter().zip(args).enumerate() {
+            let param_type = self_type(param_type, path);
+            self.check_argument(index + 1, &param_type, arg, || {
+                format!("in argument {} of '{}.{}'", index + 1, path, function)
+            })?;
+        }
+        self.record_method_call(Expression::FunctionCall { name: method.symbol, args: args.to_vec() });
+        Ok(method.return_type)
+    }
+
+    /// The type of `Type.NAME` when it names an associated constant.
+    pub(super) fn infer_associated_constant_type(
+        &mut self,
+        path: &str,
+        name: &str,
+    ) -> Result<Option<AstType>> {
+        if self.get_variable_type(path).is_ok() {
+            return Ok(None);
+        }
+        let type_name = self.resolve_self_path(path)?;
+        let Some(constant) = self.behavior_resolver.resolve_constant(&type_name, name) else {
+            if path == "Self" {
+                return Err(CompileError::TypeError(
+                    format!("No associated constant named '{}' for '{}'", name, type_name),
+                    None,
+                ));
+            }
+            return Ok(None);
+        };
+        let type_ = constant.type_.clone();
+        if let Some(id) = self.span_cursor.current() {
+            self.associated_constants.insert(id, constant);
+        }
+        Ok(Some(type_))
+    }
+
+    /// The type a path names: `Self` is the type of the impl being checked.
+    fn resolve_self_path(&self, path: &str) -> Result<String> {
+        if path != "Self" {
+            return Ok(path.to_string());
+        }
+        self.self_type.clone().ok_or_else(|| {
+            CompileError::TypeError("'Self' can only be used in the methods of an impl block".to_string(), None)
+        })
+    }
+
+    /// The function `receiver.method(...)` calls and how the receiver is
+    /// passed to it. Methods of the receiver's type come before functions
+    /// that merely accept it.
+    fn find_method(&self, receiver_type: &AstType, method: &str) -> Result<(Candidate, Receiver)> {
+        let value_type = match receiver_type {
+            AstType::Pointer(pointee) => pointee,
+            receiver_type => receiver_type,
+        };
+        let type_name = type_name(value_type);
+        if let AstType::Generic { name, type_args } = value_type {
+            if type_args.is_empty() && self.type_bounds.contains_key(name) {
+                return self.find_bound_method(receiver_type, name, method);
+            }
+        }
+
+        let resolved = match self.behavior_resolver.resolve_method(&type_name, method) {
+            Some(info) => Some((info, None)),
+            None => self
+                .behavior_resolver
+                .resolve_generic_method(value_type, None, method)
+                .map(|(info, type_args)| (info, Some(type_args))),
+        };
+        if let Some((info, type_args)) = resolved {
+            let params: Vec<AstType> = info.param_types.iter().map(|param| self_type(param, &type_name)).collect();
+            let receiver = params.first().and_then(|first| receiver_passing(receiver_type, first));
+            let Some(receiver) = receiver else {
+                return Err(CompileError::TypeError(
+                    format!(
+                        "'{}' is an associated function of '{}', not a method; call it as {}.{}(...)",
+                        method, type_name, type_name, method
+                    ),
+                    None,
+                ));
+            };
+            let candidate = Candidate {
+                function: info.symbol,
+                params,
+                return_type: info.return_type,
+                is_generic: false,
+                bound: None,
+                type_args,
+            };
+            return Ok((candidate, receiver));
+        }
+
+        if let Some(signature) = self.functions.get(method) {
+            let first = signature.params.first().map(|(_, type_)| type_);
+            if let Some(receiver) = first.and_then(|first| receiver_passing(receiver_type, first)) {
+                let candidate = Candidate {
+                    function: method.to_string(),
+                    params: signature.params.iter().map(|(_, type_)| type_.clone()).collect(),
+                    return_type: signature.return_type.clone(),
+                    is_generic: !signature.type_params.is_empty(),
+                    bound: None,
+                    type_args: None,
+                };
+                return Ok((candidate, receiver));
+            }
+        }
+
+        Err(CompileError::TypeError(
+            format!("No method named '{}' for type '{}'", method, receiver_type),
+            None,
+        ))
+    }
+
+    /// The method `receiver.method(...)` calls when the receiver's type is
+    /// type parameter `param`: the one declared by a behavior it's bounded
+    /// by.
+    fn find_bound_method(&self, receiver_type: &AstType, param: &str, method: &str) -> Result<(Candidate, Receiver)> {
+        let bounds = &self.type_bounds[param];
+        let mut declaring = bounds.iter().filter_map(|behavior| {
+            let info = self.behavior_resolver.get_behavior(behavior)?;
+            let declared = info.methods.iter().find(|declared| declared.name == method)?;
+            Some((behavior, declared))
+        });
+        let Some((behavior, declared)) = declaring.next() else {
+            let hint = if bounds.is_empty() {
+                format!("add a bound such as `{}: Behavior` for a behavior that declares it", param)
+            } else {
+                format!("none of its bounds ({}) declare one", bounds.join(", "))
+            };
+            return Err(CompileError::TypeError(
+                format!("No method named '{}' for type parameter '{}'; {}", method, param, hint),
+                None,
+            ));
+        };
+        if let Some((other, _)) = declaring.next() {
+            return Err(CompileError::TypeError(
+                format!("Method '{}' of type parameter '{}' is declared by both '{}' and '{}'", method, param, behavior, other),
+                None,
+            ));
+        }
+
+        let params: Vec<AstType> = declared.param_types.iter().map(|type_| self_type(type_, param)).collect();
+        let receiver = if declared.has_self {
+            params.first().and_then(|first| receiver_passing(receiver_type, first))
+        } else {
+            None
+        };
+        let Some(receiver) = receiver else {
+            return Err(CompileError::TypeError(
+                format!("'{}' of behavior '{}' is not a method, so it can't be called on a '{}' value", method, behavior, param),
+                None,
+            ));
+        };
+        let candidate = Candidate {
+            function: method.to_string(),
+            params,
+            return_type: self_type(&declared.return_type, param),
+            is_generic: false,
+            bound: Some((param.to_string(), behavior.clone())),
+            type_args: None,
+        };
+        Ok((candidate, receiver))
+    }
+
+    fn record_bound_method_call(&mut self, resolved: BoundMethodCall) {
+        if let Some(id) = self.span_cursor.current() {
+            self.bound_method_calls.insert(id, resolved);
+        }
+    }
+
+    fn record_generic_method_call(&mut self, resolved: GenericMethodCall) {
+        if let Some(id) = self.span_cursor.current() {
+            self.generic_method_calls.insert(id, resolved);
+        }
+    }
+
+    fn record_method_call(&mut self, resolved: Expression) {
+        if let Some(id) = self.span_cursor.current() {
+            self.method_calls.insert(id, resolved);
+        }
+    }
+}
+
+/// How a receiver of type `receiver` is passed to a first parameter of
+/// type `param`, if it can be.
+fn receiver_passing(receiver: &AstType, param: &AstType) -> Option<Receiver> {
+    if same_type(receiver, param) {
+        return Some(Receiver::Value);
+    }
+    match (receiver, param) {
+        (receiver, AstType::Pointer(pointee)) if same_type(receiver, pointee) => Some(Receiver::Reference),
+        (AstType::Pointer(pointee), param) if same_type(pointee, param) => Some(Receiver::Dereference),
+        _ => None,
+    }
+}
+
+/// Struct and enum types are written by name in signatures, so they're
+/// compared by name.
+fn same_type(a: &AstType, b: &AstType) -> bool {
+    if let (AstType::Pointer(a), AstType::Pointer(b)) = (a, b) {
+        return same_type(a, b);
+    }
+    match (nominal_name(a), nominal_name(b)) {
+        (Some(a), Some(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+fn nominal_name(type_: &AstType) -> Option<&str> {
+    match type_ {
+        AstType::Struct { name, .. } | AstType::Enum { name, .. } => Some(name),
+        AstType::Generic { name, type_args } if type_args.is_empty() => Some(name),
+        _ => None,
+    }
+}
+
+/// The name impl blocks for `type_` are declared under.
+pub(super) fn type_name(type_: &AstType) -> String {
+    match nominal_name(type_) {
+        Some(name) => name.to_string(),
+        None => type_.to_string(),
+    }
+}
+
+/// `Self` in a method signature stands for the type the method is on.
+fn self_type(type_: &AstType, type_name: &str) -> AstType {
+    match type_ {
+        AstType::Generic { name, type_args } if name == "Self" && type_args.is_empty() => AstType::Generic {
+            name: type_name.to_string(),
+            type_args: vec![],
+        },
+        AstType::Pointer(inner) => AstType::Pointer(Box::new(self_type(inner, type_name))),
+        other => other.clone(),
+    }
+}
+
+/// Whether `expr` names storage whose address can be taken.
+pub(super) fn is_place(expr: &Expression) -> bool {
+    match expr {
+        Expression::Identifier(_) => true,
+        Expression::StructField { struct_: inner, .. }
+        | Expression::MemberAccess { object: inner, .. }
+        | Expression::ArrayIndex { array: inner, .. } => is_place(inner),
+        Expression::Dereference(_) => true,
+        _ => false,
+    }
+}
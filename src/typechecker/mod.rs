@@ -2,16 +2,29 @@ pub mod types;
 pub mod inference;
 pub mod validation;
 pub mod behaviors;
+pub mod captures;
+mod methods;
+mod bounds;
+mod dynamic;
+pub mod table;
+pub mod unify;
+pub mod exhaustiveness;
 
-use crate::ast::{Program, Declaration, Statement, Expression, AstType, Function};
-use crate::error::{CompileError, Result};
+use crate::ast::{AssociatedConstant, Program, Declaration, Statement, Expression, AstType, Function, ImplBlock, Pattern, TypeParameter};
+use crate::diagnostics::Diagnostic;
+use crate::error::{CompileError, ImmutableTarget, Result, Span};
+use crate::spans::{self, NodeId, SpanCursor, SpanTable};
 use crate::stdlib::StdNamespace;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use behaviors::BehaviorResolver;
+use captures::{CaptureMode, Escapes};
+use unify::{Unifier, UnifyError};
+
+pub use table::{BoundMethodCall, DynCoercion, DynMethodCall, GenericMethodCall, TypeTable};
 
 pub struct TypeChecker {
     // Symbol table for tracking variable types
-    scopes: Vec<HashMap<String, AstType>>,
+    scopes: Vec<HashMap<String, Variable>>,
     // Function signatures
     functions: HashMap<String, FunctionSignature>,
     // Struct definitions
@@ -22,10 +35,74 @@ pub struct TypeChecker {
     behavior_resolver: BehaviorResolver,
     // Standard library namespace
     std_namespace: StdNamespace,
+    // Source locations from the parser, used to place errors
+    spans: SpanTable,
+    span_cursor: SpanCursor,
+    // Types of declared variables, keyed by declaring statement
+    bindings: HashMap<NodeId, AstType>,
+    // Type arguments of generic calls, keyed by the call
+    instantiations: HashMap<NodeId, Vec<AstType>>,
+    // The function calls method calls resolved to, keyed by the call
+    method_calls: HashMap<NodeId, Expression>,
+    // Method calls on bounded type parameters, keyed likewise
+    bound_method_calls: HashMap<NodeId, BoundMethodCall>,
+    // Method calls resolved to methods of generic implementations, keyed
+    // likewise
+    generic_method_calls: HashMap<NodeId, GenericMethodCall>,
+    // Conversions to behavior objects and method calls on them, keyed
+    // likewise
+    dyn_coercions: HashMap<NodeId, DynCoercion>,
+    dyn_method_calls: HashMap<NodeId, DynMethodCall>,
+    // Associated constants accessed as `Type.NAME`, keyed likewise
+    associated_constants: HashMap<NodeId, AssociatedConstant>,
+    // Operand types of binary operations, keyed by the operation
+    operand_types: HashMap<NodeId, (AstType, AstType)>,
+    // Closures that can't outlive the function creating them, and how the
+    // closures of the function being checked are used
+    local_closures: HashSet<NodeId>,
+    escapes: Escapes,
+    // The type `Self` stands for in the impl being checked
+    self_type: Option<String>,
+    // Behaviors bounding each type parameter of the function being checked
+    type_bounds: HashMap<String, Vec<String>>,
+    // Type variables of the body being checked
+    unifier: Unifier,
+    // Bindings and generic calls of the current body whose types are
+    // solved once the whole body has been seen
+    pending_bindings: Vec<(String, NodeId)>,
+    pending_calls: Vec<PendingCall>,
+    pending_bounds: Vec<PendingBounds>,
+    pending_operands: Vec<NodeId>,
+    // Declared return type of the function being checked
+    return_type: Option<AstType>,
+    // Trailing expression statement that produces the function's result
+    result_site: Option<NodeId>,
+    // Problems worth reporting that don't stop compilation
+    warnings: Vec<Diagnostic>,
+}
+
+/// The pattern, guard and body of a conditional or match arm.
+type Arm<'a> = (&'a Pattern, Option<&'a Expression>, &'a Expression);
+
+/// A call to a generic function whose type arguments are still being
+/// inferred.
+struct PendingCall {
+    id: NodeId,
+    callee: String,
+    type_args: Vec<(String, AstType)>,
+}
+
+/// The type arguments of a call to a generic function with bounded type
+/// parameters, checked against the bounds once they are solved.
+struct PendingBounds {
+    site: Option<NodeId>,
+    callee: String,
+    type_args: Vec<(String, AstType)>,
 }
 
 #[derive(Clone, Debug)]
 pub struct FunctionSignature {
+    pub type_params: Vec<TypeParameter>,
     pub params: Vec<(String, AstType)>,
     pub return_type: AstType,
     pub is_external: bool,
@@ -34,10 +111,22 @@ pub struct FunctionSignature {
 #[derive(Clone, Debug)]
 pub struct StructInfo {
     pub fields: Vec<(String, AstType)>,
+    /// Fields declared with `::`, which may be assigned after construction.
+    pub mutable_fields: Vec<String>,
+}
+
+/// A variable in scope: its type, whether it may be reassigned, and where
+/// it was declared.
+#[derive(Clone, Debug)]
+struct Variable {
+    type_: AstType,
+    target: Option<ImmutableTarget>,
+    declared: Option<Span>,
 }
 
 #[derive(Clone, Debug)]
 pub struct EnumInfo {
+    pub type_params: Vec<String>,
     pub variants: Vec<(String, Option<AstType>)>,
 }
 
@@ -50,27 +139,118 @@ impl TypeChecker {
             enums: HashMap::new(),
             behavior_resolver: BehaviorResolver::new(),
             std_namespace: StdNamespace::new(),
+            spans: SpanTable::new(),
+            span_cursor: SpanCursor::new(),
+            bindings: HashMap::new(),
+            instantiations: HashMap::new(),
+            method_calls: HashMap::new(),
+            bound_method_calls: HashMap::new(),
+            generic_method_calls: HashMap::new(),
+            dyn_coercions: HashMap::new(),
+            dyn_method_calls: HashMap::new(),
+            associated_constants: HashMap::new(),
+            operand_types: HashMap::new(),
+            local_closures: HashSet::new(),
+            escapes: Escapes::default(),
+            self_type: None,
+            type_bounds: HashMap::new(),
+            unifier: Unifier::new(),
+            pending_bindings: Vec::new(),
+            pending_calls: Vec::new(),
+            pending_bounds: Vec::new(),
+            pending_operands: Vec::new(),
+            return_type: None,
+            result_site: None,
+            warnings: Vec::new(),
         }
     }
 
+    /// Use the parser's span table so errors point at source locations.
+    pub fn with_spans(mut self, spans: SpanTable) -> Self {
+        self.spans = spans;
+        self
+    }
+
     pub fn check_program(&mut self, program: &Program) -> Result<()> {
+        match self.check_program_collecting(program).into_iter().next() {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+
+    /// Type check a program, continuing past declarations that fail so
+    /// every error in the program is reported.
+    pub fn check_program_collecting(&mut self, program: &Program) -> Vec<CompileError> {
+        let mut errors = Vec::new();
+
         // First pass: collect all type definitions and function signatures
         for declaration in &program.declarations {
-            self.collect_declaration_types(declaration)?;
+            if let Err(error) = self.collect_declaration_types(declaration) {
+                errors.push(self.locate_in_declaration(declaration, error));
+            }
         }
 
         // Second pass: type check function bodies
         for declaration in &program.declarations {
-            self.check_declaration(declaration)?;
+            if let Err(error) = self.check_declaration(declaration) {
+                errors.push(self.locate_in_declaration(declaration, error));
+            }
         }
 
-        Ok(())
+        errors
+    }
+
+    /// Warnings found while checking, such as unreachable match arms.
+    pub fn warnings(&self) -> &[Diagnostic] {
+        &self.warnings
+    }
+
+    /// The types resolved while checking, for codegen to consume.
+    pub fn into_type_table(self) -> TypeTable {
+        TypeTable {
+            functions: self.functions,
+            structs: self.structs,
+            enums: self.enums,
+            bindings: self.bindings,
+            instantiations: self.instantiations,
+            method_calls: self.method_calls,
+            bound_method_calls: self.bound_method_calls,
+            generic_method_calls: self.generic_method_calls,
+            dyn_coercions: self.dyn_coercions,
+            dyn_method_calls: self.dyn_method_calls,
+            associated_constants: self.associated_constants,
+            operand_types: self.operand_types,
+            local_closures: self.local_closures,
+            behaviors: self.behavior_resolver,
+        }
+    }
+
+    /// Infer the type `expr` would have if it followed `statements` in a
+    /// function body inside `program`. The REPL uses this to type bare
+    /// expressions before wrapping them in a synthetic function.
+    pub fn infer_type_in_context(
+        &mut self,
+        program: &Program,
+        statements: &[Statement],
+        expr: &Expression,
+    ) -> Result<AstType> {
+        self.check_program(program)?;
+
+        self.enter_scope();
+        let result = statements
+            .iter()
+            .try_for_each(|statement| self.check_statement(statement))
+            .and_then(|_| self.infer_expression_type(expr));
+        self.exit_scope();
+
+        result.map(|type_| self.unifier.resolve(&type_))
     }
 
     fn collect_declaration_types(&mut self, declaration: &Declaration) -> Result<()> {
         match declaration {
             Declaration::Function(func) => {
                 let signature = FunctionSignature {
+                    type_params: func.type_params.clone(),
                     params: func.args.clone(),
                     return_type: func.return_type.clone(),
                     is_external: false,
@@ -83,6 +263,7 @@ impl TypeChecker {
                     (format!("arg{}", i), t.clone())
                 }).collect();
                 let signature = FunctionSignature {
+                    type_params: vec![],
                     params,
                     return_type: ext_func.return_type.clone(),
                     is_external: true,
@@ -94,10 +275,16 @@ impl TypeChecker {
                 let fields = struct_def.fields.iter().map(|f| {
                     (f.name.clone(), f.type_.clone())
                 }).collect();
+                let mutable_fields = struct_def.fields.iter()
+                    .filter(|f| f.is_mutable)
+                    .map(|f| f.name.clone())
+                    .collect();
                 let info = StructInfo {
                     fields,
+                    mutable_fields,
                 };
                 self.structs.insert(struct_def.name.clone(), info);
+                self.behavior_resolver.register_methods(&struct_def.name, &struct_def.methods);
             }
             Declaration::Enum(enum_def) => {
                 // Convert EnumVariant to (String, Option<AstType>)
@@ -105,15 +292,18 @@ impl TypeChecker {
                     (v.name.clone(), v.payload.clone())
                 }).collect();
                 let info = EnumInfo {
+                    type_params: enum_def.type_params.iter().map(|param| param.name.clone()).collect(),
                     variants,
                 };
                 self.enums.insert(enum_def.name.clone(), info);
+                self.behavior_resolver.register_methods(&enum_def.name, &enum_def.methods);
             }
             Declaration::Behavior(behavior_def) => {
                 self.behavior_resolver.register_behavior(behavior_def)?;
             }
             Declaration::Impl(impl_block) => {
-                self.behavior_resolver.register_impl(impl_block)?;
+                let span = self.declaration_span(declaration);
+                self.behavior_resolver.register_impl(impl_block, span.as_ref())?;
             }
             _ => {}
         }
@@ -123,21 +313,40 @@ impl TypeChecker {
     fn check_declaration(&mut self, declaration: &Declaration) -> Result<()> {
         match declaration {
             Declaration::Function(func) => {
-                self.check_function(func)?;
+                self.check_function(&func.name, func)?;
             }
             Declaration::ComptimeBlock(statements) => {
+                self.begin_inference(None);
                 self.enter_scope();
-                for statement in statements {
-                    self.check_statement(statement)?;
-                }
+                let result = statements
+                    .iter()
+                    .try_for_each(|statement| self.check_statement(statement));
                 self.exit_scope();
+                result?;
+                self.finish_inference()?;
+            }
+            Declaration::Behavior(behavior_def) => {
+                self.check_constants(&behavior_def.constants)?;
             }
             Declaration::Impl(impl_block) => {
                 // Verify that the implementation satisfies the behavior
                 self.behavior_resolver.verify_impl(impl_block)?;
-                // Type check each method in the impl block
-                for method in &impl_block.methods {
-                    self.check_function(method)?;
+                let span = self.declaration_span(declaration);
+                self.behavior_resolver.check_coherence(impl_block, span.as_ref())?;
+                self.check_constants(&impl_block.constants)?;
+                self.self_type = Some(impl_block.type_name.clone());
+                let result = self.check_impl_methods(impl_block);
+                self.self_type = None;
+                result?;
+            }
+            Declaration::Struct(struct_def) => {
+                for method in &struct_def.methods {
+                    self.check_function(&spans::method_owner(&struct_def.name, &method.name), method)?;
+                }
+            }
+            Declaration::Enum(enum_def) => {
+                for method in &enum_def.methods {
+                    self.check_function(&spans::method_owner(&enum_def.name, &method.name), method)?;
                 }
             }
             _ => {}
@@ -145,37 +354,269 @@ impl TypeChecker {
         Ok(())
     }
 
-    fn check_function(&mut self, function: &Function) -> Result<()> {
+    /// Type check each method in an impl block, including the default
+    /// methods it inherits, which are checked for the implementing type.
+    fn check_impl_methods(&mut self, impl_block: &ImplBlock) -> Result<()> {
+        let behavior = impl_block
+            .behavior_name
+            .as_ref()
+            .and_then(|name| self.behavior_resolver.get_definition(name))
+            .cloned();
+        for method in impl_block.all_methods(behavior.as_ref()) {
+            let owner = spans::method_owner(&impl_block.type_name, &method.name);
+            if let Some(behavior) = &behavior {
+                if !impl_block.methods.iter().any(|own| own.name == method.name) {
+                    // Errors in an inherited body point at the behavior's source
+                    self.spans.record_alias(owner.clone(), spans::method_owner(&behavior.name, &method.name));
+                }
+            }
+            self.check_function(&owner, &method)?;
+        }
+        Ok(())
+    }
+
+    /// The values of associated constants must have their declared types.
+    fn check_constants(&mut self, constants: &[AssociatedConstant]) -> Result<()> {
+        for constant in constants {
+            let Some(value) = &constant.value else {
+                continue;
+            };
+            self.begin_inference(None);
+            let value_type = self.infer_expression_type_expecting(value, Some(&constant.type_))?;
+            self.require(&constant.type_, &value_type, || format!("in the value of constant '{}'", constant.name))?;
+            self.finish_inference()?;
+        }
+        Ok(())
+    }
+
+    fn check_function(&mut self, owner: &str, function: &Function) -> Result<()> {
+        let depth = self.scopes.len();
         self.enter_scope();
+        self.span_cursor.enter_owner(owner);
+        self.type_bounds = function
+            .type_params
+            .iter()
+            .map(|param| (param.name.clone(), param.constraints.clone()))
+            .collect();
+        self.begin_inference(Some(function.return_type.clone()));
+        self.escapes = Escapes::of_body(&function.body);
+        if let (Some(Statement::Expression(_)), false) = (function.body.last(), function.return_type == AstType::Void) {
+            self.result_site = Some(NodeId::new(owner, vec![function.body.len() - 1]));
+        }
+        // Always drop the function's scopes (including any left open by an
+        // error in a nested block) so they don't leak into the next function
+        let result = self
+            .check_type_parameter_bounds(&function.type_params)
+            .and_then(|_| self.check_function_body(function))
+            .and_then(|_| self.finish_inference());
+        self.span_cursor.leave_owner();
+        self.escapes = Escapes::default();
+        self.type_bounds.clear();
+        self.scopes.truncate(depth);
+        result
+    }
+
+    fn begin_inference(&mut self, return_type: Option<AstType>) {
+        self.unifier.reset();
+        self.pending_bindings.clear();
+        self.pending_calls.clear();
+        self.pending_bounds.clear();
+        self.pending_operands.clear();
+        self.return_type = return_type;
+        self.result_site = None;
+    }
+
+    /// Substitute the solved types into the body's bindings and generic
+    /// calls, reporting any the body leaves undetermined.
+    fn finish_inference(&mut self) -> Result<()> {
+        for pending in std::mem::take(&mut self.pending_calls) {
+            let mut type_args = Vec::new();
+            for (param, type_) in &pending.type_args {
+                let type_ = self.unifier.resolve(type_);
+                if unify::contains_var(&type_) {
+                    let error = CompileError::TypeError(
+                        format!(
+                            "Cannot infer type argument '{}' of '{}'; annotate the type of the value it produces",
+                            param, pending.callee
+                        ),
+                        None,
+                    );
+                    return Err(self.locate_statement(&pending.id, error));
+                }
+                type_args.push(type_);
+            }
+            self.instantiations.insert(pending.id, type_args);
+        }
+
+        for (name, site) in std::mem::take(&mut self.pending_bindings) {
+            let Some(type_) = self.bindings.get(&site) else { continue };
+            let type_ = self.unifier.resolve(type_);
+            if unify::contains_var(&type_) {
+                let error = CompileError::TypeError(
+                    format!(
+                        "Cannot infer the type of '{}': only known to be {}; add a type annotation",
+                        name, type_
+                    ),
+                    None,
+                );
+                return Err(self.locate_statement(&site, error));
+            }
+            self.bindings.insert(site, type_);
+        }
+
+        for id in std::mem::take(&mut self.pending_operands) {
+            if let Some((left, right)) = self.operand_types.get(&id) {
+                let solved = (self.unifier.resolve(left), self.unifier.resolve(right));
+                self.operand_types.insert(id, solved);
+            }
+        }
+
+        for pending in std::mem::take(&mut self.pending_bounds) {
+            let type_args: Vec<(String, AstType)> = pending
+                .type_args
+                .iter()
+                .map(|(param, type_)| (param.clone(), self.unifier.resolve(type_)))
+                .collect();
+            if let Err(error) = self.check_bounds(&pending.callee, &type_args) {
+                return Err(match &pending.site {
+                    Some(site) => self.locate_statement(site, error),
+                    None => error,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn locate_statement(&self, site: &NodeId, error: CompileError) -> CompileError {
+        match self.spans.statement(site) {
+            Some(span) => error.with_span(span.clone()),
+            None => error,
+        }
+    }
+
+    /// Require `actual` to fit where `expected` is wanted, solving type
+    /// variables. Only types that involve inference are checked; fully
+    /// known types keep the callers' existing compatibility rules.
+    fn constrain(&mut self, expected: &AstType, actual: &AstType, context: impl FnOnce() -> String) -> Result<()> {
+        if !unify::contains_var(expected) && !unify::contains_var(actual) {
+            return Ok(());
+        }
+        self.require(expected, actual, context)
+    }
 
+    /// Require `actual` to fit where `expected` is wanted, whether or not
+    /// either involves inference.
+    fn require(&mut self, expected: &AstType, actual: &AstType, context: impl FnOnce() -> String) -> Result<()> {
+        match self.unifier.unify(expected, actual) {
+            Ok(()) => Ok(()),
+            Err(UnifyError::Mismatch(..)) => Err(CompileError::TypeError(
+                format!(
+                    "Type mismatch {}: expected {}, found {}",
+                    context(),
+                    self.unifier.resolve(expected),
+                    self.unifier.resolve(actual)
+                ),
+                None,
+            )),
+            Err(UnifyError::Infinite(type_)) => Err(CompileError::TypeError(
+                format!("Type mismatch {}: {} would have to contain itself", context(), type_),
+                None,
+            )),
+        }
+    }
+
+    fn check_function_body(&mut self, function: &Function) -> Result<()> {
         // Add function parameters to scope
+        let declared = self
+            .span_cursor
+            .owner()
+            .and_then(|owner| self.spans.declaration(owner))
+            .cloned();
         for (param_name, param_type) in &function.args {
-            self.declare_variable(param_name, param_type.clone())?;
+            let declared = declared
+                .as_ref()
+                .and_then(|span| self.spans.identifier_in(param_name, span))
+                .cloned();
+            self.check_parameter_kept(param_name, param_type, &[], declared.clone())?;
+            let variable = Variable {
+                type_: param_type.clone(),
+                target: Some(ImmutableTarget::Parameter),
+                declared,
+            };
+            self.declare_variable(param_name, variable)?;
         }
 
         // Check function body
-        for statement in &function.body {
-            self.check_statement(statement)?;
-        }
+        self.check_body(&function.body)
+    }
 
-        self.exit_scope();
+    /// Check the statements of a function, loop or comptime body, giving
+    /// errors the span of the statement they came from.
+    fn check_body(&mut self, body: &[Statement]) -> Result<()> {
+        for (index, statement) in body.iter().enumerate() {
+            self.span_cursor.push(index);
+            let result = self.check_statement(statement);
+            let result = result.map_err(|error| self.spans.attach(&self.span_cursor, error));
+            self.span_cursor.pop();
+            result?;
+        }
         Ok(())
     }
 
+    /// Check `statement`, statement `index` of a block expression, giving
+    /// errors its span.
+    fn check_block_statement(&mut self, index: usize, statement: &Statement) -> Result<()> {
+        self.in_child(index, |this| {
+            this.check_statement(statement)
+                .map_err(|error| this.spans.attach(&this.span_cursor, error))
+        })
+    }
+
+    /// Run `check` with the cursor on child `index` of the node being
+    /// checked.
+    fn in_child<T>(&mut self, index: usize, check: impl FnOnce(&mut Self) -> T) -> T {
+        self.span_cursor.push(index);
+        let result = check(self);
+        self.span_cursor.pop();
+        result
+    }
+
+    fn infer_child_type(&mut self, index: usize, expr: &Expression) -> Result<AstType> {
+        self.in_child(index, |this| this.infer_expression_type(expr))
+    }
+
+    fn declaration_span(&self, declaration: &Declaration) -> Option<Span> {
+        spans::declaration_key(declaration).and_then(|key| self.spans.declaration(&key)).cloned()
+    }
+
+    fn locate_in_declaration(&self, declaration: &Declaration, error: CompileError) -> CompileError {
+        match self.declaration_span(declaration) {
+            Some(span) if error.span().is_none() => error.with_span(span),
+            _ => error,
+        }
+    }
+
     fn check_statement(&mut self, statement: &Statement) -> Result<()> {
         match statement {
             Statement::VariableDeclaration {
                 name,
                 type_,
                 initializer,
+                is_mutable,
                 ..
             } => {
                 if let Some(init_expr) = initializer {
-                    let inferred_type = self.infer_expression_type(init_expr)?;
+                    let inferred_type =
+                        self.in_child(0, |this| this.infer_expression_type_expecting(init_expr, type_.as_ref()))?;
                     
                     if let Some(declared_type) = type_ {
-                        // Check that the initializer type matches the declared type
-                        if !self.types_compatible(declared_type, &inferred_type) {
+                        // Check that the initializer type matches the declared type,
+                        // which also fills in anything the initializer left open
+                        if self.unifier.is_partial(&inferred_type) {
+                            self.constrain(declared_type, &inferred_type, || {
+                                format!("in the initializer of '{}'", name)
+                            })?;
+                        } else if self.unifier.unify(declared_type, &inferred_type).is_err() {
                             return Err(CompileError::TypeError(
                                 format!(
                                     "Type mismatch: variable '{}' declared as {:?} but initialized with {:?}",
@@ -184,13 +625,16 @@ impl TypeChecker {
                                 None
                             ));
                         }
-                        self.declare_variable(name, declared_type.clone())?;
+                        self.declare_binding(name, declared_type.clone(), *is_mutable)?;
                     } else {
-                        // Inferred type from initializer
-                        self.declare_variable(name, inferred_type)?;
+                        // Inferred type from initializer, solved at the end of the body
+                        if let Some(site) = self.span_cursor.current() {
+                            self.pending_bindings.push((name.clone(), site));
+                        }
+                        self.declare_binding(name, inferred_type, *is_mutable)?;
                     }
                 } else if let Some(declared_type) = type_ {
-                    self.declare_variable(name, declared_type.clone())?;
+                    self.declare_binding(name, declared_type.clone(), *is_mutable)?;
                 } else {
                     return Err(CompileError::TypeError(
                         format!("Cannot infer type for variable '{}' without initializer", name),
@@ -198,11 +642,24 @@ impl TypeChecker {
                     ));
                 }
             }
+            Statement::DestructuringDeclaration { pattern, initializer, is_mutable } => {
+                let value_type = self.infer_child_type(0, initializer)?;
+                self.declare_pattern(pattern, &value_type, *is_mutable)?;
+                // The statement's binding is the whole value; codegen takes
+                // the types of the parts from it
+                if let Some(site) = self.span_cursor.current() {
+                    self.bindings.insert(site.clone(), value_type);
+                    self.pending_bindings.push((exhaustiveness::describe(pattern), site));
+                }
+            }
             Statement::VariableAssignment { name, value } => {
                 let var_type = self.get_variable_type(name)?;
-                let value_type = self.infer_expression_type(value)?;
+                self.check_assignable(name)?;
+                let value_type = self.infer_child_type(0, value)?;
                 
-                if !self.types_compatible(&var_type, &value_type) {
+                if self.unifier.is_partial(&var_type) || self.unifier.is_partial(&value_type) {
+                    self.constrain(&var_type, &value_type, || format!("in assignment to '{}'", name))?;
+                } else if !self.types_compatible(&var_type, &value_type) {
                     return Err(CompileError::TypeError(
                         format!(
                             "Type mismatch: cannot assign {:?} to variable '{}' of type {:?}",
@@ -213,11 +670,19 @@ impl TypeChecker {
                 }
             }
             Statement::Return(expr) => {
-                let _return_type = self.infer_expression_type(expr)?;
-                // TODO: Check against function return type
+                let expected = self.return_type.clone();
+                let value_type = self.in_child(0, |this| this.infer_expression_type_expecting(expr, expected.as_ref()))?;
+                if let Some(return_type) = self.return_type.clone() {
+                    self.require(&return_type, &value_type, || "in return value".to_string())?;
+                }
             }
             Statement::Expression(expr) => {
-                self.infer_expression_type(expr)?;
+                let value_type = self.infer_child_type(0, expr)?;
+                if self.result_site.is_some() && self.span_cursor.current() == self.result_site {
+                    if let Some(return_type) = self.return_type.clone() {
+                        self.constrain(&return_type, &value_type, || "in the function's result".to_string())?;
+                    }
+                }
             }
             Statement::Loop { kind, body, .. } => {
                 use crate::ast::LoopKind;
@@ -230,7 +695,7 @@ impl TypeChecker {
                     }
                     LoopKind::Condition(expr) => {
                         // Type check the condition
-                        let cond_type = self.infer_expression_type(expr)?;
+                        let cond_type = self.infer_child_type(body.len(), expr)?;
                         // Condition should be boolean or integer (truthy)
                         if !matches!(cond_type, AstType::Bool | AstType::I32 | AstType::I64) {
                             return Err(CompileError::TypeError(
@@ -242,31 +707,39 @@ impl TypeChecker {
                 }
                 
                 // Check loop body with the variable in scope
-                for stmt in body {
-                    self.check_statement(stmt)?;
-                }
+                self.check_body(body)?;
                 self.exit_scope();
             }
             Statement::ComptimeBlock(statements) => {
                 self.enter_scope();
-                for stmt in statements {
-                    self.check_statement(stmt)?;
-                }
+                self.check_body(statements)?;
                 self.exit_scope();
             }
             Statement::PointerAssignment { pointer, value } => {
                 // For array indexing like arr[i] = value
                 // The pointer expression should be a pointer type
-                let _pointer_type = self.infer_expression_type(pointer)?;
-                let _value_type = self.infer_expression_type(value)?;
-                // TODO: Type check that value is compatible with the pointed-to type
+                let target_type = self.infer_child_type(0, pointer)?;
+                if let Expression::MemberAccess { object, member } = pointer {
+                    self.in_child(0, |this| this.check_field_assignable(object, member))?;
+                }
+                let value_type = self.infer_child_type(1, value)?;
+                self.require(&target_type, &value_type, || "in assignment".to_string())?;
+            }
+            Statement::CompoundAssignment { target, op, value } => {
+                let target_type = self.infer_child_type(0, target)?;
+                if let Expression::MemberAccess { object, member } = target {
+                    self.in_child(0, |this| this.check_field_assignable(object, member))?;
+                }
+                let value_type = self.infer_child_type(1, value)?;
+                let result_type = inference::infer_operation_type(self, op, target_type.clone(), value_type)?;
+                self.require(&target_type, &result_type, || "in compound assignment".to_string())?;
             }
             _ => {}
         }
         Ok(())
     }
 
-    fn infer_expression_type(&self, expr: &Expression) -> Result<AstType> {
+    fn infer_expression_type(&mut self, expr: &Expression) -> Result<AstType> {
         match expr {
             Expression::Integer32(_) => Ok(AstType::I32),
             Expression::Integer64(_) => Ok(AstType::I64),
@@ -284,34 +757,30 @@ impl TypeChecker {
                     })
                 } else {
                     // Otherwise check if it's a variable
-                    self.get_variable_type(name)
+                    match self.get_variable_type(name) {
+                        Ok(type_) => Ok(self.unifier.resolve(&type_)),
+                        // `None` on its own leaves the payload type to its uses
+                        Err(_) if name == "None" => Ok(AstType::Option(Box::new(self.unifier.fresh()))),
+                        Err(error) => Err(error),
+                    }
                 }
             }
             Expression::BinaryOp { left, op, right } => {
                 inference::infer_binary_op_type(self, left, op, right)
             }
-            Expression::FunctionCall { name, .. } => {
-                // First check if it's a known function
-                if let Some(sig) = self.functions.get(name) {
-                    Ok(sig.return_type.clone())
-                } else {
-                    // Check if it's a variable holding a function pointer
-                    match self.get_variable_type(name) {
-                        Ok(AstType::FunctionPointer { return_type, .. }) => {
-                            Ok(*return_type)
-                        }
-                        Ok(_) => {
-                            Err(CompileError::TypeError(format!("'{}' is not a function", name), None))
-                        }
-                        Err(_) => {
-                            Err(CompileError::TypeError(format!("Unknown function: {}", name), None))
-                        }
-                    }
-                }
+            Expression::UnaryOp { op, operand } => {
+                inference::infer_unary_op_type(self, *op, operand)
+            }
+            Expression::FunctionCall { name, args } => self.infer_function_call_type(name, args, 0),
+            Expression::MethodCall { object, method, args } => {
+                self.infer_method_call_type(expr, object, method, args)
             }
             Expression::MemberAccess { object, member } => {
                 // Check if accessing @std namespace
                 if let Expression::Identifier(name) = &**object {
+                    if let Some(constant_type) = self.infer_associated_constant_type(name, member)? {
+                        return Ok(constant_type);
+                    }
                     if StdNamespace::is_std_reference(name) {
                         // Resolve @std.module access
                         return Ok(AstType::Generic {
@@ -320,16 +789,30 @@ impl TypeChecker {
                         });
                     }
                 }
-                let object_type = self.infer_expression_type(object)?;
+                let object_type = self.infer_child_type(0, object)?;
                 inference::infer_member_type(&object_type, member, &self.structs)
             }
-            Expression::Comptime(inner) => self.infer_expression_type(inner),
+            Expression::Comptime(inner) => self.infer_child_type(0, inner),
             Expression::Range { .. } => Ok(AstType::Range {
                 start_type: Box::new(AstType::I32),
                 end_type: Box::new(AstType::I32),
                 inclusive: false,
             }),
-            Expression::StructLiteral { name, .. } => {
+            Expression::StructLiteral { name, fields } => {
+                let declared_fields = self
+                    .structs
+                    .get(name)
+                    .map(|info| info.fields.clone())
+                    .unwrap_or_default();
+                for (index, (field, value)) in fields.iter().enumerate() {
+                    let value_type = self.infer_child_type(index, value)?;
+                    if let Some((_, field_type)) = declared_fields.iter().find(|(declared, _)| declared == field) {
+                        self.constrain(field_type, &value_type, || {
+                            format!("in field '{}' of '{}'", field, name)
+                        })?;
+                    }
+                }
+
                 // For struct literals, return the struct type
                 // Check if it's a known struct
                 if let Some(struct_def) = self.structs.get(name) {
@@ -361,15 +844,22 @@ impl TypeChecker {
                 })
             }
             Expression::StringInterpolation { .. } => {
-                // String interpolation always returns a string (pointer to char)
-                Ok(AstType::Pointer(Box::new(AstType::I8)))
+                // String interpolation builds a string like a literal
+                Ok(AstType::String)
             }
             Expression::ArrayIndex { array, .. } => {
                 // Array indexing returns the element type
-                let array_type = self.infer_expression_type(array)?;
+                let array_type = self.infer_child_type(0, array)?;
                 match array_type {
                     AstType::Pointer(elem_type) => Ok(*elem_type),
                     AstType::Array(elem_type) => Ok(*elem_type),
+                    AstType::Var(_) => {
+                        let elem_type = self.unifier.fresh();
+                        self.constrain(&array_type, &AstType::Array(Box::new(elem_type.clone())), || {
+                            "in indexing".to_string()
+                        })?;
+                        Ok(elem_type)
+                    }
                     _ => Err(CompileError::TypeError(
                         format!("Cannot index type {:?}", array_type),
                         None
@@ -392,10 +882,10 @@ impl TypeChecker {
             }
             Expression::PointerOffset { pointer, .. } => {
                 // Pointer offset returns the same pointer type
-                self.infer_expression_type(pointer)
+                self.infer_child_type(0, pointer)
             }
             Expression::StructField { struct_, field } => {
-                let struct_type = self.infer_expression_type(struct_)?;
+                let struct_type = self.infer_child_type(0, struct_)?;
                 match struct_type {
                     AstType::Pointer(inner) => {
                         // Handle pointer to struct - automatically dereference
@@ -429,56 +919,625 @@ impl TypeChecker {
             Expression::Unsigned32(_) => Ok(AstType::U32),
             Expression::Unsigned64(_) => Ok(AstType::U64),
             Expression::ArrayLiteral(elements) => {
-                // Infer type from first element
-                if elements.is_empty() {
-                    Ok(AstType::Array(Box::new(AstType::Void)))
-                } else {
-                    let elem_type = self.infer_expression_type(&elements[0])?;
-                    Ok(AstType::Array(Box::new(elem_type)))
+                // Infer type from first element; an empty literal takes
+                // its element type from how the array is used
+                let elem_type = match elements.first() {
+                    Some(first) => self.infer_child_type(0, first)?,
+                    None => self.unifier.fresh(),
+                };
+                for (index, element) in elements.iter().enumerate().skip(1) {
+                    let element_type = self.infer_child_type(index, element)?;
+                    self.constrain(&elem_type, &element_type, || "in array element".to_string())?;
+                }
+                Ok(AstType::Array(Box::new(elem_type)))
+            }
+            Expression::TupleLiteral(elements) => {
+                let element_types = elements
+                    .iter()
+                    .enumerate()
+                    .map(|(index, element)| self.infer_child_type(index, element))
+                    .collect::<Result<Vec<_>>>()?;
+                Ok(AstType::Tuple(element_types))
+            }
+            Expression::TupleIndex { tuple, index } => {
+                let tuple_type = self.infer_child_type(0, tuple)?;
+                match self.unifier.resolve(&tuple_type) {
+                    AstType::Tuple(mut elements) if *index < elements.len() => Ok(elements.swap_remove(*index)),
+                    AstType::Tuple(elements) => Err(CompileError::TypeError(
+                        format!("Tuple index {} is out of range for a tuple of {} elements", index, elements.len()),
+                        None,
+                    )),
+                    AstType::Var(_) => Err(CompileError::TypeError(
+                        format!("Cannot take element {} of a value whose type isn't known yet; add a type annotation", index),
+                        None,
+                    )),
+                    other => Err(CompileError::TypeError(
+                        format!("Cannot take element {} of type {}: only tuples have numbered elements", index, other),
+                        None,
+                    )),
                 }
             }
             Expression::TypeCast { target_type, .. } => {
                 Ok(target_type.clone())
             }
-            Expression::Conditional { arms, .. } => {
-                // Return type of first arm's body
-                if arms.is_empty() {
-                    Ok(AstType::Void)
-                } else {
-                    self.infer_expression_type(&arms[0].body)
-                }
+            Expression::Conditional { scrutinee, arms } => {
+                let patterns: Vec<(&Pattern, bool)> =
+                    arms.iter().map(|arm| (&arm.pattern, arm.guard.is_some())).collect();
+                let scrutinee_type = self.check_match_arms(scrutinee, &patterns)?;
+                let arms: Vec<Arm> = arms.iter().map(|arm| (&arm.pattern, arm.guard.as_ref(), &arm.body)).collect();
+                self.infer_arms_type(&scrutinee_type, &arms)
             }
-            Expression::PatternMatch { arms, .. } => {
-                // Return type of first arm's body
-                if arms.is_empty() {
-                    Ok(AstType::Void)
-                } else {
-                    self.infer_expression_type(&arms[0].body)
-                }
+            Expression::PatternMatch { scrutinee, arms } => {
+                let patterns: Vec<(&Pattern, bool)> =
+                    arms.iter().map(|arm| (&arm.pattern, arm.guard.is_some())).collect();
+                let scrutinee_type = self.check_match_arms(scrutinee, &patterns)?;
+                let arms: Vec<Arm> = arms.iter().map(|arm| (&arm.pattern, arm.guard.as_ref(), &arm.body)).collect();
+                self.infer_arms_type(&scrutinee_type, &arms)
             }
             Expression::Block(statements) => {
-                // Return type of last statement if it's an expression
-                for stmt in statements {
-                    if let Statement::Expression(expr) = stmt {
-                        // This is just a simple approximation - last expression in block
-                        if statements.last() == Some(stmt) {
-                            return self.infer_expression_type(expr);
-                        }
-                    }
-                }
-                Ok(AstType::Void)
+                self.enter_scope();
+                let result = self.infer_block_type(statements, None);
+                self.exit_scope();
+                Ok(result?.unwrap_or(AstType::Void))
             }
             Expression::Return(expr) => {
-                self.infer_expression_type(expr)
+                self.infer_child_type(0, expr)
             }
-            Expression::EnumVariant { .. } => {
-                // TODO: Implement enum variant type inference
-                Ok(AstType::Void)
+            Expression::EnumVariant { enum_name, variant, payload } if enum_name == "Option" || enum_name == "Result" => {
+                let payload_type = payload.as_ref().map(|payload| self.infer_child_type(0, payload)).transpose()?;
+                self.constructor_type(variant, payload_type).ok_or_else(|| {
+                    CompileError::TypeError(format!("'{}::{}' is not a constructor of {}", enum_name, variant, enum_name), None)
+                })
+            }
+            Expression::EnumVariant { enum_name, variant, payload } => {
+                self.enum_variant_type(enum_name, variant, payload.as_deref())
             }
             Expression::StringLength(_) => {
                 Ok(AstType::I64)
             }
-            _ => Ok(AstType::Void), // Default for unhandled cases
+            Expression::Closure { params, body } => self.infer_closure_type(params, body, None),
+        }
+    }
+
+    /// The result type of calling `name` with `args`: a function, a
+    /// variable holding a function value, or a constructor of `Option` or
+    /// `Result`. The arguments are the children of the call from
+    /// `first_arg` on, which follow the module of a qualified call.
+    fn infer_function_call_type(&mut self, name: &str, args: &[Expression], first_arg: usize) -> Result<AstType> {
+        // First check if it's a known function
+        if let Some(sig) = self.functions.get(name).cloned() {
+            self.infer_call_type(name, &sig, args, first_arg)
+        } else {
+            // Check if it's a variable holding a function pointer
+            match self.get_variable_type(name).map(|type_| self.unifier.resolve(&type_)) {
+                Ok(AstType::FunctionPointer { return_type, .. }) => {
+                    Ok(*return_type)
+                }
+                Ok(AstType::Closure { param_types, return_type }) => {
+                    if args.len() != param_types.len() {
+                        return Err(CompileError::TypeError(
+                            format!("'{}' takes {} argument(s) but {} were given", name, param_types.len(), args.len()),
+                            None,
+                        ));
+                    }
+                    for (index, (param_type, arg)) in param_types.iter().zip(args).enumerate() {
+                        let arg_type = self
+                            .in_child(first_arg + index, |this| this.infer_expression_type_expecting(arg, Some(param_type)))?;
+                        self.require(param_type, &arg_type, || format!("in argument {} of '{}'", index + 1, name))?;
+                    }
+                    Ok(*return_type)
+                }
+                Ok(_) => {
+                    Err(CompileError::TypeError(format!("'{}' is not a function", name), None))
+                }
+                Err(_) => {
+                    let variant = name
+                        .strip_prefix("Option.")
+                        .or_else(|| name.strip_prefix("Result."))
+                        .unwrap_or(name);
+                    match args {
+                        [payload] => {
+                            let payload_type = self.infer_child_type(first_arg, payload)?;
+                            self.constructor_type(variant, Some(payload_type))
+                                .ok_or_else(|| CompileError::UndeclaredFunction(name.to_string(), None))
+                        }
+                        _ => Err(CompileError::UndeclaredFunction(name.to_string(), None)),
+                    }
+                }
+            }
+        }
+    }
+
+    /// Like `infer_expression_type`, but a closure takes the types of its
+    /// unannotated parameters from `expected`, the type wanted where it is
+    /// written.
+    fn infer_expression_type_expecting(&mut self, expr: &Expression, expected: Option<&AstType>) -> Result<AstType> {
+        match expr {
+            Expression::Closure { params, body } => self.infer_closure_type(params, body, expected),
+            Expression::ArrayLiteral(elements) => match expected.map(|type_| self.unifier.resolve(type_)) {
+                Some(AstType::Array(element_type)) => {
+                    for (index, element) in elements.iter().enumerate() {
+                        let actual =
+                            self.in_child(index, |this| this.infer_expression_type_expecting(element, Some(&element_type)))?;
+                        self.constrain(&element_type, &actual, || "in array element".to_string())?;
+                    }
+                    Ok(AstType::Array(element_type))
+                }
+                _ => self.infer_expression_type(expr),
+            },
+            _ => match expected.map(|type_| self.unifier.resolve(type_)) {
+                Some(AstType::Dyn(behavior)) => {
+                    let actual = self.infer_expression_type(expr)?;
+                    self.coerce_to_dyn(expr, &actual, &behavior)
+                }
+                _ => self.infer_expression_type(expr),
+            },
+        }
+    }
+
+    /// The function type of a closure. Parameters without an annotation or
+    /// an expected type are left for the body and later calls to determine.
+    fn infer_closure_type(
+        &mut self,
+        params: &[(String, Option<AstType>)],
+        body: &Expression,
+        expected: Option<&AstType>,
+    ) -> Result<AstType> {
+        let (expected_params, expected_return) = match expected.map(|type_| self.unifier.resolve(type_)) {
+            Some(AstType::Closure { param_types, return_type }) => {
+                if param_types.len() != params.len() {
+                    return Err(CompileError::TypeError(
+                        format!(
+                            "Closure takes {} parameter(s) but {} is expected",
+                            params.len(),
+                            AstType::Closure { param_types, return_type }
+                        ),
+                        None,
+                    ));
+                }
+                (Some(param_types), Some(*return_type))
+            }
+            Some(pointer @ AstType::FunctionPointer { .. }) => {
+                let is_variable = |name: &str| self.lookup_variable(name).is_ok();
+                let reason = match captures::closure_captures(params, body, is_variable).first() {
+                    Some(capture) => format!("it captures '{}'", capture.name),
+                    None => "closures carry an environment".to_string(),
+                };
+                return Err(CompileError::TypeError(
+                    format!(
+                        "A closure can't be used as the function pointer {}: {}; take a function type like {} instead",
+                        pointer,
+                        reason,
+                        pointer.to_string().trim_start_matches('*')
+                    ),
+                    None,
+                ));
+            }
+            _ => (None, None),
+        };
+
+        let mut param_types = Vec::with_capacity(params.len());
+        for (index, (name, annotation)) in params.iter().enumerate() {
+            let expected = expected_params.as_ref().map(|types| types[index].clone());
+            let type_ = match (annotation, expected) {
+                (Some(annotated), Some(expected)) => {
+                    self.require(annotated, &expected, || format!("in closure parameter '{}'", name))?;
+                    annotated.clone()
+                }
+                (Some(annotated), None) => annotated.clone(),
+                (None, Some(expected)) => expected,
+                (None, None) => self.unifier.fresh(),
+            };
+            param_types.push(type_);
+        }
+        let return_type = expected_return.unwrap_or_else(|| self.unifier.fresh());
+        self.check_closure_escape(params, &param_types, body)?;
+
+        // The body's `return`s leave the closure, not the function
+        let enclosing_return = self.return_type.replace(return_type.clone());
+        self.enter_scope();
+        let result = self.check_closure_body(params, &param_types, body, &return_type);
+        self.exit_scope();
+        self.return_type = enclosing_return;
+        result?;
+
+        Ok(AstType::Closure {
+            param_types: param_types.iter().map(|type_| self.unifier.resolve(type_)).collect(),
+            return_type: Box::new(self.unifier.resolve(&return_type)),
+        })
+    }
+
+    fn check_closure_body(
+        &mut self,
+        params: &[(String, Option<AstType>)],
+        param_types: &[AstType],
+        body: &Expression,
+        return_type: &AstType,
+    ) -> Result<()> {
+        for ((name, _), type_) in params.iter().zip(param_types) {
+            let variable = Variable {
+                type_: type_.clone(),
+                target: Some(ImmutableTarget::Parameter),
+                declared: None,
+            };
+            self.declare_variable(name, variable)?;
+        }
+
+        let result = self.in_child(0, |this| match body {
+            Expression::Block(statements) => this.infer_block_type(statements, Some(return_type)),
+            body => this.infer_expression_type_expecting(body, Some(return_type)).map(Some),
+        })?;
+        match result {
+            Some(result) => self.require(return_type, &result, || "in the closure's result".to_string()),
+            // Without a trailing value the closure returns void unless a
+            // `return` said otherwise
+            None if self.unifier.is_partial(return_type) => self.require(return_type, &AstType::Void, || {
+                "in the closure's result".to_string()
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Check that a closure capturing variables by pointer doesn't outlive
+    /// them, and remember closures that can't outlive the function creating
+    /// them, whose environment codegen keeps in that function's frame.
+    fn check_closure_escape(&mut self, params: &[(String, Option<AstType>)], param_types: &[AstType], body: &Expression) -> Result<()> {
+        let path = self.span_cursor.current().map(|id| id.path).unwrap_or_default();
+        for ((name, _), type_) in params.iter().zip(param_types) {
+            self.check_parameter_kept(name, type_, &path, None)?;
+        }
+        if let Some(id) = self.span_cursor.current().filter(|id| self.escapes.is_local(&id.path)) {
+            self.local_closures.insert(id);
+            return Ok(());
+        }
+        let is_variable = |name: &str| self.lookup_variable(name).is_ok();
+        match captures::closure_captures(params, body, is_variable)
+            .into_iter()
+            .find(|capture| capture.mode == CaptureMode::Pointer)
+        {
+            Some(capture) => Err(CompileError::TypeError(
+                format!(
+                    "This closure assigns to or takes the address of '{}', so it must not outlive it; only call the closure or pass it straight to a call",
+                    capture.name
+                ),
+                None,
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// Check that parameter `name`, declared by the function or closure at
+    /// `scope`, is only called or passed on when it holds a function value,
+    /// so a closure passed to it can't outlive the call.
+    fn check_parameter_kept(&self, name: &str, type_: &AstType, scope: &[usize], declared: Option<Span>) -> Result<()> {
+        if !matches!(self.unifier.resolve(type_), AstType::Closure { .. }) || !self.escapes.escapes(name, scope) {
+            return Ok(());
+        }
+        Err(CompileError::TypeError(
+            format!(
+                "Parameter '{}' holds a function value, which may live in the caller's frame; it can only be called or passed straight to a call",
+                name
+            ),
+            declared,
+        ))
+    }
+
+    /// Check the statements of a block expression, returning the type of
+    /// its value: its trailing expression, if it has one, inferred as
+    /// `expected` wants.
+    fn infer_block_type(&mut self, statements: &[Statement], expected: Option<&AstType>) -> Result<Option<AstType>> {
+        let (last, rest) = match statements.split_last() {
+            Some((last @ Statement::Expression(_), rest)) => (Some(last), rest),
+            _ => (None, statements),
+        };
+        for (index, statement) in rest.iter().enumerate() {
+            self.check_block_statement(index, statement)?;
+        }
+        let Some(Statement::Expression(value)) = last else {
+            return Ok(None);
+        };
+        self.in_child(rest.len(), |this| {
+            this.in_child(0, |this| this.infer_expression_type_expecting(value, expected))
+                .map_err(|error| this.spans.attach(&this.span_cursor, error))
+        })
+        .map(Some)
+    }
+
+    /// The result type of calling `name`. A generic function gets fresh
+    /// type variables for its type parameters, solved from the arguments
+    /// and from the context the result is used in.
+    fn infer_call_type(
+        &mut self,
+        name: &str,
+        sig: &FunctionSignature,
+        args: &[Expression],
+        first_arg: usize,
+    ) -> Result<AstType> {
+        let type_args: Vec<(String, AstType)> = sig
+            .type_params
+            .iter()
+            .map(|param| (param.name.clone(), self.unifier.fresh()))
+            .collect();
+        if !type_args.is_empty() {
+            self.record_generic_call(name, &type_args)?;
+        }
+        if sig.type_params.iter().any(|param| !param.constraints.is_empty()) {
+            self.pending_bounds.push(PendingBounds {
+                site: self.span_cursor.current(),
+                callee: name.to_string(),
+                type_args: type_args.clone(),
+            });
+        }
+
+        for (index, ((_, param_type), arg)) in sig.params.iter().zip(args).enumerate() {
+            let param_type = unify::substitute(param_type, &type_args);
+            self.check_argument(first_arg + index, &param_type, arg, || format!("in argument {} of '{}'", index + 1, name))?;
+        }
+        Ok(unify::substitute(&sig.return_type, &type_args))
+    }
+
+    /// Check `arg`, child `index` of the call being checked, against its
+    /// parameter's type.
+    fn check_argument(
+        &mut self,
+        index: usize,
+        param_type: &AstType,
+        arg: &Expression,
+        context: impl FnOnce() -> String,
+    ) -> Result<()> {
+        let arg_type = self.in_child(index, |this| this.infer_expression_type_expecting(arg, Some(param_type)))?;
+        // Function values are checked even when fully known, since the
+        // assignment rules only compare what kind of type they are
+        if matches!(param_type, AstType::Closure { .. }) {
+            self.require(param_type, &arg_type, context)
+        } else {
+            self.constrain(param_type, &arg_type, context)
+        }
+    }
+
+    /// Remember the operand types of the operation under the cursor for
+    /// codegen, which picks signed or unsigned instructions by them.
+    pub(super) fn record_operand_types(&mut self, left: AstType, right: AstType) {
+        if let Some(id) = self.span_cursor.current() {
+            self.operand_types.insert(id.clone(), (left, right));
+            self.pending_operands.push(id);
+        }
+    }
+
+    /// Remember the generic call under the cursor so its type arguments
+    /// can be handed to the monomorphizer once the body is solved. A call
+    /// inferred again gets the same type arguments.
+    fn record_generic_call(&mut self, callee: &str, type_args: &[(String, AstType)]) -> Result<()> {
+        let Some(id) = self.span_cursor.current() else {
+            return Ok(());
+        };
+        let existing = self
+            .pending_calls
+            .iter()
+            .find(|pending| pending.id == id)
+            .map(|pending| pending.type_args.clone());
+        match existing {
+            Some(existing) => {
+                for ((_, earlier), (_, later)) in existing.iter().zip(type_args) {
+                    self.constrain(earlier, later, || format!("in the call to '{}'", callee))?;
+                }
+            }
+            None => self.pending_calls.push(PendingCall {
+                id,
+                callee: callee.to_string(),
+                type_args: type_args.to_vec(),
+            }),
+        }
+        Ok(())
+    }
+
+    /// The type of a built-in `Option`/`Result` constructor such as
+    /// `Some(x)` or `Err(e)` with a payload of `payload_type`; the side the
+    /// payload doesn't determine is left to inference.
+    fn constructor_type(&mut self, variant: &str, payload_type: Option<AstType>) -> Option<AstType> {
+        match (variant, payload_type) {
+            ("Some", Some(inner)) => Some(AstType::Option(Box::new(inner))),
+            ("None", None) => Some(AstType::Option(Box::new(self.unifier.fresh()))),
+            ("Ok", Some(ok_type)) => Some(AstType::Result {
+                ok_type: Box::new(ok_type),
+                err_type: Box::new(self.unifier.fresh()),
+            }),
+            ("Err", Some(err_type)) => Some(AstType::Result {
+                ok_type: Box::new(self.unifier.fresh()),
+                err_type: Box::new(err_type),
+            }),
+            _ => None,
+        }
+    }
+
+    /// The type of `enum_name::variant` built from `payload`. The enum's
+    /// type parameters are solved from the payload where it mentions them,
+    /// and are otherwise left for the context to determine.
+    fn enum_variant_type(&mut self, enum_name: &str, variant: &str, payload: Option<&Expression>) -> Result<AstType> {
+        let Some(info) = self.enums.get(enum_name).cloned() else {
+            return Err(CompileError::TypeError(format!("Unknown enum type '{}'", enum_name), None));
+        };
+        let Some((_, declared)) = info.variants.iter().find(|(name, _)| name == variant) else {
+            return Err(CompileError::TypeError(
+                format!("Enum '{}' has no variant '{}'", enum_name, variant),
+                None,
+            ));
+        };
+        let params: Vec<(String, AstType)> =
+            info.type_params.iter().map(|param| (param.clone(), self.unifier.fresh())).collect();
+        match (declared, payload) {
+            (Some(declared), Some(payload)) => {
+                let expected = unify::substitute(declared, &params);
+                let actual = self.in_child(0, |this| this.infer_expression_type_expecting(payload, Some(&expected)))?;
+                self.require(&expected, &actual, || format!("in the payload of '{}::{}'", enum_name, variant))?;
+            }
+            (None, None) => {}
+            (Some(_), None) => {
+                return Err(CompileError::TypeError(
+                    format!("'{}::{}' takes a payload", enum_name, variant),
+                    None,
+                ))
+            }
+            (None, Some(_)) => {
+                return Err(CompileError::TypeError(
+                    format!("'{}::{}' takes no payload", enum_name, variant),
+                    None,
+                ))
+            }
+        }
+        Ok(AstType::Generic {
+            name: enum_name.to_string(),
+            type_args: params.into_iter().map(|(_, type_)| type_).collect(),
+        })
+    }
+
+    /// Require a match to cover every value of its scrutinee, and warn
+    /// about arms earlier arms already cover. Returns the scrutinee's type.
+    fn check_match_arms(&mut self, scrutinee: &Expression, arms: &[(&Pattern, bool)]) -> Result<AstType> {
+        let scrutinee_type = self.infer_child_type(0, scrutinee)?;
+        let scrutinee_type = self.unifier.resolve(&scrutinee_type);
+        let report = exhaustiveness::check_match(&scrutinee_type, arms, &self.enums, &self.structs);
+
+        for dead in report.unreachable {
+            let message = if dead.alternative {
+                format!("Unreachable pattern: `{}` in arm {} is already covered", dead.pattern, dead.arm + 1)
+            } else {
+                format!("Unreachable pattern: arm {} (`{}`) is already covered by earlier arms", dead.arm + 1, dead.pattern)
+            };
+            let mut warning = Diagnostic::warning(message).with_code("W0001");
+            if let Some(span) = self.spans.locate(&self.span_cursor) {
+                warning = warning.with_label(span.clone(), "in this match");
+            }
+            self.warnings.push(warning);
+        }
+
+        if report.missing.is_empty() {
+            Ok(scrutinee_type)
+        } else {
+            Err(CompileError::NonExhaustiveMatch { missing: report.missing, span: None })
+        }
+    }
+
+    /// The type of a conditional or match: that of its first arm, with
+    /// later arms filling in whatever the first leaves unknown. Each arm's
+    /// guard and body are checked with the names its pattern binds in
+    /// scope.
+    fn infer_arms_type(&mut self, scrutinee_type: &AstType, arms: &[Arm]) -> Result<AstType> {
+        let mut first: Option<(AstType, bool)> = None;
+        for (index, (pattern, guard, body)) in arms.iter().enumerate() {
+            self.enter_scope();
+            let result = self.infer_arm_type(index, scrutinee_type, pattern, *guard, body);
+            self.exit_scope();
+            let arm_type = result?;
+            match &first {
+                None => {
+                    let partial = self.unifier.is_partial(&arm_type);
+                    first = Some((arm_type, partial));
+                }
+                Some((first_type, true)) => {
+                    let first_type = first_type.clone();
+                    self.constrain(&first_type, &arm_type, || "between match arms".to_string())?;
+                }
+                Some(_) => {}
+            }
+        }
+        Ok(first.map_or(AstType::Void, |(first_type, _)| self.unifier.resolve(&first_type)))
+    }
+
+    /// The type of arm `index`, whose guard and body follow the scrutinee
+    /// and the arms before it.
+    fn infer_arm_type(
+        &mut self,
+        index: usize,
+        scrutinee_type: &AstType,
+        pattern: &Pattern,
+        guard: Option<&Expression>,
+        body: &Expression,
+    ) -> Result<AstType> {
+        self.declare_pattern_bindings(pattern, scrutinee_type)?;
+        if let Some(guard) = guard {
+            self.infer_child_type(2 * index + 1, guard)?;
+        }
+        self.infer_child_type(2 * index + 2, body)
+    }
+
+    /// Declare the names `pattern` binds in a match arm, typed from
+    /// `type_`, the type of the value it matches, where that is known.
+    fn declare_pattern_bindings(&mut self, pattern: &Pattern, type_: &AstType) -> Result<()> {
+        let type_ = self.unifier.resolve(type_);
+        match pattern {
+            Pattern::Identifier(name) => self.declare_arm_binding(name, type_),
+            Pattern::Binding { name, pattern } => {
+                self.declare_arm_binding(name, type_.clone())?;
+                self.declare_pattern_bindings(pattern, &type_)
+            }
+            Pattern::EnumVariant { variant, payload: Some(payload), .. } => {
+                let payload_type = self
+                    .variant_payload_type(&type_, variant)
+                    .unwrap_or_else(|| self.unifier.fresh());
+                self.declare_pattern_bindings(payload, &payload_type)
+            }
+            Pattern::Tuple(elements) => {
+                for (index, element) in elements.iter().enumerate() {
+                    let element_type = match &type_ {
+                        AstType::Tuple(types) if types.len() == elements.len() => types[index].clone(),
+                        _ => self.unifier.fresh(),
+                    };
+                    self.declare_pattern_bindings(element, &element_type)?;
+                }
+                Ok(())
+            }
+            Pattern::Struct { name, fields } => {
+                let struct_name = match &type_ {
+                    _ if !name.is_empty() => name.clone(),
+                    AstType::Struct { name, .. } | AstType::Generic { name, .. } => name.clone(),
+                    _ => String::new(),
+                };
+                let declared_fields = self.structs.get(&struct_name).map(|info| info.fields.clone()).unwrap_or_default();
+                for (field, field_pattern) in fields {
+                    let field_type = match declared_fields.iter().find(|(declared, _)| declared == field) {
+                        Some((_, field_type)) => field_type.clone(),
+                        None => self.unifier.fresh(),
+                    };
+                    self.declare_pattern_bindings(field_pattern, &field_type)?;
+                }
+                Ok(())
+            }
+            // Every alternative binds the same names
+            Pattern::Or(alternatives) => match alternatives.first() {
+                Some(first) => self.declare_pattern_bindings(first, &type_),
+                None => Ok(()),
+            },
+            _ => Ok(()),
+        }
+    }
+
+    fn declare_arm_binding(&mut self, name: &str, type_: AstType) -> Result<()> {
+        let variable = Variable {
+            type_,
+            target: Some(ImmutableTarget::Variable),
+            declared: None,
+        };
+        self.declare_variable(name, variable)
+    }
+
+    /// The payload type of `variant` in a value of type `type_`, if known.
+    fn variant_payload_type(&self, type_: &AstType, variant: &str) -> Option<AstType> {
+        let payload = |name: &str, type_args: &[AstType]| {
+            let info = self.enums.get(name)?;
+            let payload = info.variants.iter().find(|(declared, _)| declared == variant)?.1.as_ref()?;
+            let params: Vec<(String, AstType)> = info.type_params.iter().cloned().zip(type_args.iter().cloned()).collect();
+            Some(unify::substitute(payload, &params))
+        };
+        match (type_, variant) {
+            (AstType::Option(inner), "Some") => Some((**inner).clone()),
+            (AstType::Result { ok_type, .. }, "Ok") => Some((**ok_type).clone()),
+            (AstType::Result { err_type, .. }, "Err") => Some((**err_type).clone()),
+            (AstType::Enum { variants, .. }, _) => {
+                variants.iter().find(|declared| declared.name == variant).and_then(|declared| declared.payload.clone())
+            }
+            (AstType::Generic { name, type_args }, _) => payload(name, type_args),
+            _ => None,
         }
     }
 
@@ -494,7 +1553,7 @@ impl TypeChecker {
         self.scopes.pop();
     }
 
-    fn declare_variable(&mut self, name: &str, type_: AstType) -> Result<()> {
+    fn declare_variable(&mut self, name: &str, variable: Variable) -> Result<()> {
         if let Some(scope) = self.scopes.last_mut() {
             if scope.contains_key(name) {
                 return Err(CompileError::TypeError(
@@ -502,21 +1561,178 @@ impl TypeChecker {
                     None
                 ));
             }
-            scope.insert(name.to_string(), type_);
+            scope.insert(name.to_string(), variable);
             Ok(())
         } else {
             Err(CompileError::TypeError("No active scope".to_string(), None))
         }
     }
 
-    fn get_variable_type(&self, name: &str) -> Result<AstType> {
+    /// Declare a variable introduced by the current statement and record
+    /// its type in the type table.
+    fn declare_binding(&mut self, name: &str, type_: AstType, is_mutable: bool) -> Result<()> {
+        if let Some(id) = self.span_cursor.current() {
+            self.bindings.insert(id, type_.clone());
+        }
+        let declared = self
+            .spans
+            .locate(&self.span_cursor)
+            .map(|statement| self.spans.identifier_in(name, statement).unwrap_or(statement))
+            .cloned();
+        let variable = Variable {
+            type_,
+            target: (!is_mutable).then_some(ImmutableTarget::Variable),
+            declared,
+        };
+        self.declare_variable(name, variable)
+    }
+
+    /// Declare the variables a destructuring pattern names, each with the
+    /// type of the part of `type_` it binds.
+    fn declare_pattern(&mut self, pattern: &Pattern, type_: &AstType, is_mutable: bool) -> Result<()> {
+        match pattern {
+            Pattern::Wildcard => Ok(()),
+            Pattern::Identifier(name) => self.declare_binding(name, type_.clone(), is_mutable),
+            Pattern::Tuple(elements) => {
+                let element_types = match self.unifier.resolve(type_) {
+                    AstType::Tuple(types) if types.len() == elements.len() => types,
+                    AstType::Var(_) => {
+                        let types: Vec<AstType> = elements.iter().map(|_| self.unifier.fresh()).collect();
+                        self.constrain(type_, &AstType::Tuple(types.clone()), || "in destructuring".to_string())?;
+                        types
+                    }
+                    other => {
+                        return Err(CompileError::TypeError(
+                            format!("Cannot destructure a value of type {} into {} element(s)", other, elements.len()),
+                            None,
+                        ));
+                    }
+                };
+                for (element, element_type) in elements.iter().zip(&element_types) {
+                    self.declare_pattern(element, element_type, is_mutable)?;
+                }
+                Ok(())
+            }
+            Pattern::Struct { name, fields } => {
+                let struct_name = match self.unifier.resolve(type_) {
+                    AstType::Struct { name, .. } => name,
+                    AstType::Generic { name, type_args } if type_args.is_empty() && self.structs.contains_key(&name) => name,
+                    other => {
+                        return Err(CompileError::TypeError(
+                            format!("Cannot destructure a value of type {} as a struct", other),
+                            None,
+                        ));
+                    }
+                };
+                if !name.is_empty() && *name != struct_name {
+                    return Err(CompileError::TypeError(
+                        format!("Cannot destructure a value of type {} as '{}'", struct_name, name),
+                        None,
+                    ));
+                }
+                let declared_fields = self.structs.get(&struct_name).map(|info| info.fields.clone()).unwrap_or_default();
+                for (field, field_pattern) in fields {
+                    let Some((_, field_type)) = declared_fields.iter().find(|(declared, _)| declared == field) else {
+                        return Err(CompileError::TypeError(
+                            format!("Struct '{}' has no field '{}'", struct_name, field),
+                            None,
+                        ));
+                    };
+                    self.declare_pattern(field_pattern, field_type, is_mutable)?;
+                }
+                Ok(())
+            }
+            other => Err(CompileError::TypeError(
+                format!(
+                    "Only names, `_`, tuples and struct fields can be destructured in a declaration, found `{}`",
+                    exhaustiveness::describe(other)
+                ),
+                None,
+            )),
+        }
+    }
+
+    fn lookup_variable(&self, name: &str) -> Result<&Variable> {
         // Search from innermost to outermost scope
-        for scope in self.scopes.iter().rev() {
-            if let Some(type_) = scope.get(name) {
-                return Ok(type_.clone());
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name))
+            .ok_or_else(|| CompileError::UndeclaredVariable(name.to_string(), None))
+    }
+
+    fn get_variable_type(&self, name: &str) -> Result<AstType> {
+        self.lookup_variable(name).map(|variable| variable.type_.clone())
+    }
+
+    /// Reject assigning to a binding declared with `:=` or to a parameter.
+    fn check_assignable(&self, name: &str) -> Result<()> {
+        let variable = self.lookup_variable(name)?;
+        match &variable.target {
+            None => Ok(()),
+            Some(target) => Err(CompileError::AssignToImmutable {
+                name: name.to_string(),
+                target: target.clone(),
+                declared: variable.declared.clone(),
+                span: None,
+            }),
+        }
+    }
+
+    /// Reject `object.member = ...`, the member access under the cursor,
+    /// unless the field is declared with `::` and the struct is reached
+    /// through a mutable binding or a pointer.
+    fn check_field_assignable(&mut self, object: &Expression, member: &str) -> Result<()> {
+        let object_type = self.infer_child_type(0, object)?;
+        let struct_name = match &object_type {
+            AstType::Struct { name, .. } | AstType::Generic { name, .. } => Some(name),
+            AstType::Pointer(inner) => match &**inner {
+                AstType::Struct { name, .. } | AstType::Generic { name, .. } => Some(name),
+                _ => None,
+            },
+            _ => None,
+        };
+        let by_value = !matches!(object_type, AstType::Pointer(_));
+        if let Some((struct_name, info)) = struct_name.and_then(|name| Some((name, self.structs.get(name)?))) {
+            if info.fields.iter().any(|(field, _)| field == member) && !info.mutable_fields.iter().any(|field| field == member) {
+                let declared = self
+                    .spans
+                    .declaration(struct_name)
+                    .map(|declaration| self.spans.identifier_in(member, declaration).unwrap_or(declaration))
+                    .cloned();
+                return Err(CompileError::AssignToImmutable {
+                    name: member.to_string(),
+                    target: ImmutableTarget::Field { struct_name: struct_name.clone() },
+                    declared,
+                    span: None,
+                });
+            }
+        }
+        // Fields of a struct held by value are only as mutable as the
+        // binding holding it; through a pointer the binding doesn't matter
+        match object {
+            Expression::Identifier(name) if by_value && struct_name.is_some() => self.check_assignable(name),
+            Expression::MemberAccess { object, .. } if by_value && struct_name.is_some() => {
+                match self.in_child(0, |this| this.in_child(0, |this| this.root_binding(object))) {
+                    Some(name) => self.check_assignable(&name),
+                    None => Ok(()),
+                }
             }
+            _ => Ok(()),
+        }
+    }
+
+    /// The variable at the root of `expr`, the chain of by-value field
+    /// accesses under the cursor.
+    fn root_binding(&mut self, expr: &Expression) -> Option<String> {
+        match expr {
+            Expression::Identifier(name) => Some(name.clone()),
+            Expression::MemberAccess { object, .. } => match self.infer_child_type(0, object) {
+                Ok(AstType::Struct { .. } | AstType::Generic { .. }) => self.in_child(0, |this| this.root_binding(object)),
+                _ => None,
+            },
+            _ => None,
         }
-        Err(CompileError::TypeError(format!("Undefined variable: {}", name), None))
     }
 }
 
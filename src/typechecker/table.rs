@@ -0,0 +1,182 @@
+use crate::ast::{AssociatedConstant, AstType, Expression};
+use crate::spans::NodeId;
+use crate::typechecker::behaviors::BehaviorResolver;
+use crate::typechecker::{EnumInfo, FunctionSignature, StructInfo};
+use std::collections::{HashMap, HashSet};
+
+/// The types resolved by a successful type check, handed to later passes
+/// so they don't have to re-infer them.
+///
+/// Variable types are keyed by the `NodeId` of their declaring statement,
+/// and everything recorded about an expression by the expression's own
+/// `NodeId`, which later passes find with their own `SpanCursor`. That
+/// covers the type arguments of generic calls and the function calls
+/// method calls resolve to. Method calls on a bounded type parameter are
+/// kept apart, since the function they call depends on the instantiation,
+/// as are calls to methods of generic implementations, which are
+/// instantiated for the receiver's type. So are conversions to behavior
+/// objects, method calls on them, accesses to associated constants and
+/// the operand types of binary operations, and the closures that can't
+/// outlive the function creating them. The behaviors and their
+/// implementations are kept for resolving bound method calls once the type
+/// parameter is known.
+#[derive(Debug, Clone, Default)]
+pub struct TypeTable {
+    pub(crate) functions: HashMap<String, FunctionSignature>,
+    pub(crate) structs: HashMap<String, StructInfo>,
+    pub(crate) enums: HashMap<String, EnumInfo>,
+    pub(crate) bindings: HashMap<NodeId, AstType>,
+    pub(crate) instantiations: HashMap<NodeId, Vec<AstType>>,
+    pub(crate) method_calls: HashMap<NodeId, Expression>,
+    pub(crate) bound_method_calls: HashMap<NodeId, BoundMethodCall>,
+    pub(crate) generic_method_calls: HashMap<NodeId, GenericMethodCall>,
+    pub(crate) dyn_coercions: HashMap<NodeId, DynCoercion>,
+    pub(crate) dyn_method_calls: HashMap<NodeId, DynMethodCall>,
+    pub(crate) associated_constants: HashMap<NodeId, AssociatedConstant>,
+    pub(crate) operand_types: HashMap<NodeId, (AstType, AstType)>,
+    pub(crate) local_closures: HashSet<NodeId>,
+    pub(crate) behaviors: BehaviorResolver,
+}
+
+/// A method call on a value whose type is a type parameter, resolved to
+/// the method of one of the parameter's bounds.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BoundMethodCall {
+    pub type_param: String,
+    pub behavior: String,
+    pub method: String,
+    /// The receiver, referenced or dereferenced as the behavior's method
+    /// expects, followed by the call's arguments.
+    pub args: Vec<Expression>,
+}
+
+impl BoundMethodCall {
+    /// The function this calls once the type parameter is known to be
+    /// `type_`, with the type arguments to instantiate it with when the
+    /// method comes from a generic implementation.
+    pub fn resolve(&self, type_: &AstType, behaviors: &BehaviorResolver) -> Option<(String, Vec<AstType>)> {
+        behaviors.behavior_method(type_, &self.behavior, &self.method)
+    }
+}
+
+/// A method call resolved to a method of a generic implementation, which
+/// is instantiated with the type arguments the receiver's type gives.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GenericMethodCall {
+    /// The generic function the method is compiled from.
+    pub function: String,
+    /// The implementation's type arguments, in the order of its parameters.
+    pub type_args: Vec<AstType>,
+    /// The receiver, referenced or dereferenced as the method expects,
+    /// followed by the call's arguments.
+    pub args: Vec<Expression>,
+}
+
+/// A value converted to a `dyn` behavior object where one is expected.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DynCoercion {
+    /// The type whose vtable for the behavior the object carries.
+    pub type_name: String,
+    pub behavior: String,
+    /// The pointer to the value: the expression itself, or its address when
+    /// it names a variable of the type.
+    pub data: Expression,
+}
+
+/// A method call on a `dyn` behavior object, made through the slot of its
+/// vtable that holds the method.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DynMethodCall {
+    pub behavior: String,
+    pub method: String,
+    /// The method's position among those the behavior declares.
+    pub slot: usize,
+    pub receiver: Expression,
+    pub args: Vec<Expression>,
+    /// The method's parameter types after `self`.
+    pub param_types: Vec<AstType>,
+    pub return_type: AstType,
+}
+
+impl TypeTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn function(&self, name: &str) -> Option<&FunctionSignature> {
+        self.functions.get(name)
+    }
+
+    pub fn struct_info(&self, name: &str) -> Option<&StructInfo> {
+        self.structs.get(name)
+    }
+
+    pub fn enum_info(&self, name: &str) -> Option<&EnumInfo> {
+        self.enums.get(name)
+    }
+
+    /// The type of the variable declared by statement `id`.
+    pub fn binding(&self, id: &NodeId) -> Option<&AstType> {
+        self.bindings.get(id)
+    }
+
+    /// The inferred type arguments of generic call `id`.
+    pub fn type_arguments(&self, id: &NodeId) -> Option<&[AstType]> {
+        self.instantiations.get(id).map(Vec::as_slice)
+    }
+
+    /// The function call that method call `id` resolved to. The receiver,
+    /// referenced or dereferenced as the function expects, is its first
+    /// argument; associated functions called through their type take none.
+    pub fn method_call(&self, id: &NodeId) -> Option<&Expression> {
+        self.method_calls.get(id)
+    }
+
+    /// The bound method that method call `id` of a generic function
+    /// resolved to.
+    pub fn bound_method_call(&self, id: &NodeId) -> Option<&BoundMethodCall> {
+        self.bound_method_calls.get(id)
+    }
+
+    /// The generic implementation's method that method call `id` resolved
+    /// to.
+    pub fn generic_method_call(&self, id: &NodeId) -> Option<&GenericMethodCall> {
+        self.generic_method_calls.get(id)
+    }
+
+    /// How expression `id` is converted to the behavior object expected
+    /// there.
+    pub fn dyn_coercion(&self, id: &NodeId) -> Option<&DynCoercion> {
+        self.dyn_coercions.get(id)
+    }
+
+    /// The vtable call that method call `id` makes on a behavior object.
+    pub fn dyn_method_call(&self, id: &NodeId) -> Option<&DynMethodCall> {
+        self.dyn_method_calls.get(id)
+    }
+
+    /// The associated constant that `Type.NAME` access `id` refers to.
+    pub fn associated_constant(&self, id: &NodeId) -> Option<&AssociatedConstant> {
+        self.associated_constants.get(id)
+    }
+
+    /// The types of the left and right operands of binary operation `id`.
+    pub fn operand_types(&self, id: &NodeId) -> Option<&(AstType, AstType)> {
+        self.operand_types.get(id)
+    }
+
+    /// Whether closure `id` can't outlive the function creating it.
+    pub fn is_local_closure(&self, id: &NodeId) -> bool {
+        self.local_closures.contains(id)
+    }
+
+    /// The declared type of `field` in struct `struct_name`.
+    pub fn field_type(&self, struct_name: &str, field: &str) -> Option<&AstType> {
+        self.structs
+            .get(struct_name)?
+            .fields
+            .iter()
+            .find(|(name, _)| name == field)
+            .map(|(_, type_)| type_)
+    }
+}
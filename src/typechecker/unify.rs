@@ -0,0 +1,281 @@
+use crate::ast::AstType;
+use crate::typechecker::validation;
+
+/// Type variables created while checking one function, and the types they
+/// have been solved to so far.
+///
+/// Variables appear as `AstType::Var` inside ordinary types, so a partially
+/// known type like `Option<_>` can flow through bindings and expressions
+/// until a later use pins it down.
+#[derive(Debug, Default)]
+pub struct Unifier {
+    solutions: Vec<Option<AstType>>,
+}
+
+/// Why two types could not be unified.
+#[derive(Debug, Clone, PartialEq)]
+pub enum UnifyError {
+    /// The innermost pair of types that conflict.
+    Mismatch(AstType, AstType),
+    /// Solving the variable would make a type contain itself.
+    Infinite(AstType),
+}
+
+impl Unifier {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Forget all variables. Inference is local to a function body, so this
+    /// runs before each one.
+    pub fn reset(&mut self) {
+        self.solutions.clear();
+    }
+
+    /// A new, unsolved type variable.
+    pub fn fresh(&mut self) -> AstType {
+        self.solutions.push(None);
+        AstType::Var(self.solutions.len() - 1)
+    }
+
+    /// `type_` with every solved variable replaced by its solution.
+    pub fn resolve(&self, type_: &AstType) -> AstType {
+        map_type(type_, &mut |t| match t {
+            AstType::Var(id) => match self.solutions.get(*id) {
+                Some(Some(solution)) => Some(self.resolve(solution)),
+                _ => None,
+            },
+            AstType::Generic { .. } if normalize(t) != *t => Some(self.resolve(&normalize(t))),
+            _ => None,
+        })
+    }
+
+    /// Whether `type_` still contains unsolved variables.
+    pub fn is_partial(&self, type_: &AstType) -> bool {
+        contains_var(&self.resolve(type_))
+    }
+
+    /// Make `expected` and `actual` the same type, solving variables on
+    /// either side. Fully known types are compared with the usual
+    /// assignment rules, so numeric widening and `T` to `Option<T>` still
+    /// apply.
+    pub fn unify(&mut self, expected: &AstType, actual: &AstType) -> Result<(), UnifyError> {
+        let expected = self.shallow(expected);
+        let actual = self.shallow(actual);
+
+        match (&expected, &actual) {
+            (AstType::Var(a), AstType::Var(b)) if a == b => Ok(()),
+            (AstType::Var(id), other) | (other, AstType::Var(id)) => self.bind(*id, other),
+            _ if !contains_var(&expected) && !contains_var(&actual) => {
+                if known_types_agree(&expected, &actual) {
+                    Ok(())
+                } else {
+                    Err(UnifyError::Mismatch(expected, actual))
+                }
+            }
+            (AstType::Pointer(e), AstType::Pointer(a))
+            | (AstType::Pointer(e), AstType::Array(a))
+            | (AstType::Array(e), AstType::Array(a))
+            | (AstType::Option(e), AstType::Option(a))
+            | (AstType::Ref(e), AstType::Ref(a)) => self.unify(e, a),
+            (
+                AstType::FixedArray { element_type: e, size: expected_size },
+                AstType::FixedArray { element_type: a, size: actual_size },
+            ) if expected_size == actual_size => self.unify(e, a),
+            (
+                AstType::Result { ok_type: expected_ok, err_type: expected_err },
+                AstType::Result { ok_type: actual_ok, err_type: actual_err },
+            ) => {
+                self.unify(expected_ok, actual_ok)?;
+                self.unify(expected_err, actual_err)
+            }
+            (
+                AstType::Range { start_type: expected_start, end_type: expected_end, .. },
+                AstType::Range { start_type: actual_start, end_type: actual_end, .. },
+            ) => {
+                self.unify(expected_start, actual_start)?;
+                self.unify(expected_end, actual_end)
+            }
+            (
+                AstType::Function { args: expected_args, return_type: expected_ret }
+                | AstType::FunctionPointer { param_types: expected_args, return_type: expected_ret },
+                AstType::Function { args: actual_args, return_type: actual_ret }
+                | AstType::FunctionPointer { param_types: actual_args, return_type: actual_ret },
+            ) if expected_args.len() == actual_args.len() => {
+                for (e, a) in expected_args.iter().zip(actual_args) {
+                    self.unify(e, a)?;
+                }
+                self.unify(expected_ret, actual_ret)
+            }
+            (
+                AstType::Closure { param_types: expected_args, return_type: expected_ret },
+                AstType::Closure { param_types: actual_args, return_type: actual_ret }
+                | AstType::Function { args: actual_args, return_type: actual_ret }
+                | AstType::FunctionPointer { param_types: actual_args, return_type: actual_ret },
+            ) if expected_args.len() == actual_args.len() => {
+                for (e, a) in expected_args.iter().zip(actual_args) {
+                    self.unify(e, a)?;
+                }
+                self.unify(expected_ret, actual_ret)
+            }
+            (
+                AstType::Generic { name: expected_name, type_args: expected_args },
+                AstType::Generic { name: actual_name, type_args: actual_args },
+            ) if expected_name == actual_name && expected_args.len() == actual_args.len() => {
+                for (e, a) in expected_args.iter().zip(actual_args) {
+                    self.unify(e, a)?;
+                }
+                Ok(())
+            }
+            (AstType::Tuple(expected_elements), AstType::Tuple(actual_elements))
+                if expected_elements.len() == actual_elements.len() =>
+            {
+                for (e, a) in expected_elements.iter().zip(actual_elements) {
+                    self.unify(e, a)?;
+                }
+                Ok(())
+            }
+            // A plain value where an option is expected is wrapped in `Some`
+            (AstType::Option(e), _) => self.unify(e, &actual),
+            _ => Err(UnifyError::Mismatch(expected, actual)),
+        }
+    }
+
+    /// `type_` with top-level solved variables followed, in normal form.
+    fn shallow(&self, type_: &AstType) -> AstType {
+        match type_ {
+            AstType::Var(id) => match self.solutions.get(*id) {
+                Some(Some(solution)) => self.shallow(solution),
+                _ => type_.clone(),
+            },
+            _ => normalize(type_),
+        }
+    }
+
+    fn bind(&mut self, id: usize, type_: &AstType) -> Result<(), UnifyError> {
+        let type_ = self.resolve(type_);
+        if occurs(id, &type_) {
+            return Err(UnifyError::Infinite(type_));
+        }
+        self.solutions[id] = Some(type_);
+        Ok(())
+    }
+}
+
+/// Replace the type parameters named in `params` by the paired types.
+pub fn substitute(type_: &AstType, params: &[(String, AstType)]) -> AstType {
+    map_type(type_, &mut |t| match t {
+        AstType::Generic { name, type_args } if type_args.is_empty() => params
+            .iter()
+            .find(|(param, _)| param == name)
+            .map(|(_, replacement)| replacement.clone()),
+        _ => None,
+    })
+}
+
+/// Whether `type_` contains any type variable.
+pub fn contains_var(type_: &AstType) -> bool {
+    let mut found = false;
+    map_type(type_, &mut |t| {
+        found |= matches!(t, AstType::Var(_));
+        None
+    });
+    found
+}
+
+/// Spell `Option<T>` and `Result<T, E>` written as generic applications in
+/// annotations the same way as the built-in forms.
+fn normalize(type_: &AstType) -> AstType {
+    match type_ {
+        AstType::Generic { name, type_args } if name == "Option" && type_args.len() == 1 => {
+            AstType::Option(Box::new(type_args[0].clone()))
+        }
+        AstType::Generic { name, type_args } if name == "Result" && type_args.len() == 2 => {
+            AstType::Result {
+                ok_type: Box::new(type_args[0].clone()),
+                err_type: Box::new(type_args[1].clone()),
+            }
+        }
+        _ => type_.clone(),
+    }
+}
+
+fn occurs(id: usize, type_: &AstType) -> bool {
+    let mut found = false;
+    map_type(type_, &mut |t| {
+        found |= *t == AstType::Var(id);
+        None
+    });
+    found
+}
+
+fn known_types_agree(expected: &AstType, actual: &AstType) -> bool {
+    match (expected, actual) {
+        // Named types are written `Point` in annotations but inferred as
+        // `Struct { name: "Point", .. }` from literals
+        (
+            AstType::Generic { name: expected_name, type_args },
+            AstType::Struct { name: actual_name, .. } | AstType::Enum { name: actual_name, .. },
+        )
+        | (
+            AstType::Struct { name: expected_name, .. } | AstType::Enum { name: expected_name, .. },
+            AstType::Generic { name: actual_name, type_args },
+        ) if type_args.is_empty() => expected_name == actual_name,
+        (
+            AstType::Closure { param_types: expected_params, return_type: expected_ret },
+            AstType::Closure { param_types: actual_params, return_type: actual_ret },
+        ) => validation::signatures_compatible(expected_params, expected_ret, actual_params, actual_ret),
+        (AstType::Tuple(expected_elements), AstType::Tuple(actual_elements)) => {
+            expected_elements.len() == actual_elements.len()
+                && expected_elements.iter().zip(actual_elements).all(|(e, a)| known_types_agree(e, a))
+        }
+        (AstType::Dyn(expected_behavior), AstType::Dyn(actual_behavior)) => expected_behavior == actual_behavior,
+        _ => validation::types_compatible(expected, actual),
+    }
+}
+
+/// Rebuild `type_` bottom-up, replacing every node for which `replace`
+/// returns a type.
+fn map_type(type_: &AstType, replace: &mut impl FnMut(&AstType) -> Option<AstType>) -> AstType {
+    if let Some(replacement) = replace(type_) {
+        return replacement;
+    }
+    let mut map = |t: &AstType| Box::new(map_type(t, replace));
+    match type_ {
+        AstType::Pointer(inner) => AstType::Pointer(map(inner)),
+        AstType::Array(inner) => AstType::Array(map(inner)),
+        AstType::Option(inner) => AstType::Option(map(inner)),
+        AstType::Ref(inner) => AstType::Ref(map(inner)),
+        AstType::FixedArray { element_type, size } => AstType::FixedArray {
+            element_type: map(element_type),
+            size: *size,
+        },
+        AstType::Result { ok_type, err_type } => AstType::Result {
+            ok_type: map(ok_type),
+            err_type: map(err_type),
+        },
+        AstType::Range { start_type, end_type, inclusive } => AstType::Range {
+            start_type: map(start_type),
+            end_type: map(end_type),
+            inclusive: *inclusive,
+        },
+        AstType::Function { args, return_type } => AstType::Function {
+            args: args.iter().map(|t| *map(t)).collect(),
+            return_type: map(return_type),
+        },
+        AstType::FunctionPointer { param_types, return_type } => AstType::FunctionPointer {
+            param_types: param_types.iter().map(|t| *map(t)).collect(),
+            return_type: map(return_type),
+        },
+        AstType::Closure { param_types, return_type } => AstType::Closure {
+            param_types: param_types.iter().map(|t| *map(t)).collect(),
+            return_type: map(return_type),
+        },
+        AstType::Generic { name, type_args } => AstType::Generic {
+            name: name.clone(),
+            type_args: type_args.iter().map(|t| *map(t)).collect(),
+        },
+        AstType::Tuple(elements) => AstType::Tuple(elements.iter().map(|t| *map(t)).collect()),
+        other => other.clone(),
+    }
+}
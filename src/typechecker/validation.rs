@@ -77,6 +77,12 @@ pub fn types_compatible(expected: &AstType, actual: &AstType) -> bool {
                 && expected_params.iter().zip(actual_args.iter()).all(|(e, a)| types_compatible(e, a))
                 && types_compatible(expected_ret, actual_ret)
         }
+        // Named functions can be used as function values
+        (
+            AstType::Closure { param_types: expected_params, return_type: expected_ret },
+            AstType::Function { args: actual_params, return_type: actual_ret }
+            | AstType::FunctionPointer { param_types: actual_params, return_type: actual_ret },
+        ) => signatures_compatible(expected_params, expected_ret, actual_params, actual_ret),
         // Void is only compatible with void
         (AstType::Void, AstType::Void) => true,
         // All other combinations are incompatible
@@ -84,6 +90,20 @@ pub fn types_compatible(expected: &AstType, actual: &AstType) -> bool {
     }
 }
 
+/// Whether a function taking `actual_params` and returning `actual_ret`
+/// can be called as one taking `expected_params` and returning
+/// `expected_ret`.
+pub fn signatures_compatible(
+    expected_params: &[AstType],
+    expected_ret: &AstType,
+    actual_params: &[AstType],
+    actual_ret: &AstType,
+) -> bool {
+    expected_params.len() == actual_params.len()
+        && expected_params.iter().zip(actual_params).all(|(e, a)| types_compatible(e, a))
+        && types_compatible(expected_ret, actual_ret)
+}
+
 /// Check if a type can be implicitly converted to another
 pub fn can_implicitly_convert(from: &AstType, to: &AstType) -> bool {
     // Same type needs no conversion
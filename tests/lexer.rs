@@ -1,4 +1,4 @@
-use zen::lexer::{Lexer, Token, Keyword};
+use zen::lexer::{Lexer, Token, Keyword, StringSegment};
 
 #[test]
 fn test_lexer_identifier_integer_eof() {
@@ -144,10 +144,29 @@ fn test_lexer_zen_variable_declarations() {
 fn test_lexer_string_interpolation() {
     let input = "\"Hello $(name), your score is $(score)\"";
     let mut lexer = Lexer::new(input);
-    assert_eq!(lexer.next_token(), Token::StringLiteral("Hello $(name), your score is $(score)".to_string()));
+    assert_eq!(
+        lexer.next_token(),
+        Token::InterpolatedString(vec![
+            StringSegment::Text("Hello ".to_string()),
+            StringSegment::Code("name".to_string()),
+            StringSegment::Text(", your score is ".to_string()),
+            StringSegment::Code("score".to_string()),
+        ])
+    );
     assert_eq!(lexer.next_token(), Token::Eof);
 }
 
+#[test]
+fn test_lexer_escaped_interpolation_is_text() {
+    let mut lexer = Lexer::new(r#""\$(x)" "$(f(a, (b)))" "$(x""#);
+    assert_eq!(lexer.next_token(), Token::StringLiteral("$(x)".to_string()));
+    assert_eq!(
+        lexer.next_token(),
+        Token::InterpolatedString(vec![StringSegment::Code("f(a, (b))".to_string())])
+    );
+    assert!(matches!(lexer.next_token(), Token::Error(_)));
+}
+
 
 
 #[test]
@@ -228,4 +247,147 @@ fn test_lexer_debug_step_by_step() {
     println!("Token 3: {:?}", token3);
     println!("After token 3 - position: {}, read_position: {}, current_char: {:?}", 
              lexer.position, lexer.read_position, lexer.current_char);
-} 
\ No newline at end of file
+} 
+#[test]
+fn test_lexer_multibyte_string_and_comment() {
+    let input = "// héllo wörld\nx := \"naïve ☕ 🦀\" y";
+    let mut lexer = Lexer::new(input);
+    assert_eq!(lexer.next_token(), Token::Identifier("x".to_string()));
+    assert_eq!(lexer.next_token(), Token::Operator(":=".to_string()));
+    assert_eq!(lexer.next_token(), Token::StringLiteral("naïve ☕ 🦀".to_string()));
+    let y = lexer.next_token_with_span();
+    assert_eq!(y.token, Token::Identifier("y".to_string()));
+    assert_eq!(&input[y.span.start..y.span.end], "y");
+    assert_eq!(lexer.next_token(), Token::Eof);
+}
+
+#[test]
+fn test_lexer_spans_are_byte_offsets_with_char_and_utf16_columns() {
+    // 'é' is two bytes and one UTF-16 unit; '🦀' is four bytes and two units
+    let input = "\"é🦀\" name";
+    let mut lexer = Lexer::new(input);
+    let string = lexer.next_token_with_span();
+    assert_eq!((string.span.start, string.span.end), (0, 8));
+
+    let name = lexer.next_token_with_span();
+    assert_eq!((name.span.start, name.span.end), (9, 13));
+    assert_eq!(name.span.line, 1);
+    assert_eq!(name.span.column, 6);
+    assert_eq!(name.span.utf16_column, 7);
+}
+
+#[test]
+fn test_lexer_unicode_identifiers() {
+    let input = "größe λ_2 名前 _x";
+    let mut lexer = Lexer::new(input);
+    assert_eq!(lexer.next_token(), Token::Identifier("größe".to_string()));
+    assert_eq!(lexer.next_token(), Token::Identifier("λ_2".to_string()));
+    assert_eq!(lexer.next_token(), Token::Identifier("名前".to_string()));
+    assert_eq!(lexer.next_token(), Token::Identifier("_x".to_string()));
+    assert_eq!(lexer.next_token(), Token::Eof);
+}
+
+#[test]
+fn test_lexer_unicode_escapes() {
+    let input = r#""\u{48}i \u{1F980} \u{e9}" "\u{110000} \u{zz}""#;
+    let mut lexer = Lexer::new(input);
+    assert_eq!(lexer.next_token(), Token::StringLiteral("Hi 🦀 é".to_string()));
+    // Malformed escapes are kept as written
+    assert_eq!(lexer.next_token(), Token::StringLiteral("\\u{110000} \\u{zz}".to_string()));
+    assert_eq!(lexer.next_token(), Token::Eof);
+}
+
+#[test]
+fn test_lexer_numeric_literal_forms() {
+    use zen::lexer::NumberSuffix;
+    let input = "0x1F 0o777 0b1_0 1_000 2.5e10 6E+2 42u8 3.0f32 1e3f64 0xFFi64 1..3";
+    let mut lexer = Lexer::new(input);
+    assert_eq!(lexer.next_token(), Token::Integer("31".to_string()));
+    assert_eq!(lexer.next_token(), Token::Integer("511".to_string()));
+    assert_eq!(lexer.next_token(), Token::Integer("2".to_string()));
+    assert_eq!(lexer.next_token(), Token::Integer("1000".to_string()));
+    assert_eq!(lexer.next_token(), Token::Float("2.5e10".to_string()));
+    assert_eq!(lexer.next_token(), Token::Float("6E+2".to_string()));
+    assert_eq!(lexer.next_token(), Token::SuffixedNumber("42".to_string(), NumberSuffix::U8));
+    assert_eq!(lexer.next_token(), Token::SuffixedNumber("3.0".to_string(), NumberSuffix::F32));
+    assert_eq!(lexer.next_token(), Token::SuffixedNumber("1e3".to_string(), NumberSuffix::F64));
+    assert_eq!(lexer.next_token(), Token::SuffixedNumber("255".to_string(), NumberSuffix::I64));
+    assert_eq!(lexer.next_token(), Token::Integer("1".to_string()));
+    assert_eq!(lexer.next_token(), Token::Operator("..".to_string()));
+    assert_eq!(lexer.next_token(), Token::Integer("3".to_string()));
+    assert_eq!(lexer.next_token(), Token::Eof);
+}
+
+#[test]
+fn test_lexer_malformed_numbers_keep_their_text() {
+    let input = "0x 12abc 1.5u8";
+    let mut lexer = Lexer::new(input);
+    assert_eq!(lexer.next_token(), Token::Integer("0x".to_string()));
+    assert_eq!(lexer.next_token(), Token::Integer("12abc".to_string()));
+    assert_eq!(lexer.next_token(), Token::Integer("1.5u8".to_string()));
+    assert_eq!(lexer.next_token(), Token::Eof);
+}
+
+#[test]
+fn test_lexer_char_and_byte_literals() {
+    let input = r"'a' '\n' '\'' '\u{1F980}' 'é' b'z' b'\0'";
+    let mut lexer = Lexer::new(input);
+    assert_eq!(lexer.next_token(), Token::Char('a'));
+    assert_eq!(lexer.next_token(), Token::Char('\n'));
+    assert_eq!(lexer.next_token(), Token::Char('\''));
+    assert_eq!(lexer.next_token(), Token::Char('🦀'));
+    assert_eq!(lexer.next_token(), Token::Char('é'));
+    assert_eq!(lexer.next_token(), Token::Byte(b'z'));
+    assert_eq!(lexer.next_token(), Token::Byte(0));
+    assert_eq!(lexer.next_token(), Token::Eof);
+}
+
+#[test]
+fn test_lexer_malformed_char_literals() {
+    let mut lexer = Lexer::new("'ab' '' b'é' 'x");
+    assert!(matches!(lexer.next_token(), Token::Error(message) if message.contains("exactly one character")));
+    assert_eq!(lexer.next_token(), Token::Error("Empty character literal".to_string()));
+    assert!(matches!(lexer.next_token(), Token::Error(message) if message.contains("ASCII")));
+    assert_eq!(lexer.next_token(), Token::Error("Unterminated character literal".to_string()));
+}
+
+#[test]
+fn test_lexer_byte_and_raw_strings() {
+    let input = r###"b"hi\n" r"C:\path\$(x)" r#"say "hi""# r##"a "# b"##"###;
+    let mut lexer = Lexer::new(input);
+    assert_eq!(lexer.next_token(), Token::ByteString(b"hi\n".to_vec()));
+    assert_eq!(lexer.next_token(), Token::RawString(r"C:\path\$(x)".to_string()));
+    assert_eq!(lexer.next_token(), Token::RawString(r#"say "hi""#.to_string()));
+    assert_eq!(lexer.next_token(), Token::RawString(r##"a "# b"##.to_string()));
+    assert_eq!(lexer.next_token(), Token::Eof);
+
+    // Identifiers starting with `r` or `b` are unaffected
+    let mut lexer = Lexer::new("return bar r b");
+    assert_eq!(lexer.next_token(), Token::Keyword(Keyword::Return));
+    assert_eq!(lexer.next_token(), Token::Identifier("bar".to_string()));
+    assert_eq!(lexer.next_token(), Token::Identifier("r".to_string()));
+    assert_eq!(lexer.next_token(), Token::Identifier("b".to_string()));
+}
+
+#[test]
+fn test_lexer_multiline_strings_are_dedented() {
+    let input = "x := \"\"\"\n        {\n          \"key\": \"\\t$(v)\"\n\n        }\n        \"\"\" y";
+    let mut lexer = Lexer::new(input);
+    lexer.next_token();
+    lexer.next_token();
+    assert_eq!(
+        lexer.next_token(),
+        Token::InterpolatedString(vec![
+            StringSegment::Text("{\n  \"key\": \"\t".to_string()),
+            StringSegment::Code("v".to_string()),
+            StringSegment::Text("\"\n\n}".to_string()),
+        ])
+    );
+    let y = lexer.next_token_with_span();
+    assert_eq!(y.token, Token::Identifier("y".to_string()));
+    assert_eq!(y.span.line, 6);
+
+    let mut lexer = Lexer::new("\"\"\"one line\"\"\" \"\"\"unterminated");
+    assert_eq!(lexer.next_token(), Token::StringLiteral("one line".to_string()));
+    assert!(matches!(lexer.next_token(), Token::Error(_)));
+}
@@ -1,6 +1,6 @@
 use zen::lexer::Lexer;
 use zen::parser::Parser;
-use zen::ast::{self, Program, Declaration, Function, Statement, Expression, AstType, VariableDeclarationType, BinaryOperator, Pattern, LoopKind};
+use zen::ast::{self, Program, Declaration, Function, Statement, Expression, AstType, VariableDeclarationType, BinaryOperator, UnaryOperator, Pattern, LoopKind};
 use zen::error::CompileError;
 
 #[test]
@@ -312,12 +312,13 @@ fn test_parse_comptime_block() {
             assert_eq!(type_, &None);
             assert_eq!(*is_mutable, false);
             assert!(matches!(declaration_type, VariableDeclarationType::InferredImmutable));
-            if let Some(Expression::FunctionCall { name, args }) = initializer {
-                assert_eq!(name, "build.import");
+            if let Some(Expression::MethodCall { object, method, args }) = initializer {
+                assert!(matches!(**object, Expression::Identifier(ref name) if name == "build"));
+                assert_eq!(method, "import");
                 assert_eq!(args.len(), 1);
                 assert!(matches!(args[0], Expression::String(ref s) if s == "io"));
             } else {
-                panic!("Expected FunctionCall in io initialization");
+                panic!("Expected MethodCall in io initialization");
             }
         } else {
             panic!("Expected VariableDeclaration for io");
@@ -593,10 +594,9 @@ fn test_parse_variable_declaration_syntax_separately() {
 fn test_parse_literal_expressions() {
     let cases = vec![
         ("42", Expression::Integer32(42)),
-        ("-7", Expression::BinaryOp {
-            left: Box::new(Expression::Integer32(0)),
-            op: BinaryOperator::Subtract,
-            right: Box::new(Expression::Integer32(7)),
+        ("-7", Expression::UnaryOp {
+            op: UnaryOperator::Negate,
+            operand: Box::new(Expression::Integer32(7)),
         }),
         ("3.14", Expression::Float64(3.14)),
         ("\"hello\"", Expression::String("hello".to_string())),
@@ -610,6 +610,89 @@ fn test_parse_literal_expressions() {
     }
 }
 
+#[test]
+fn test_parse_numeric_literal_syntax() {
+    let cases = vec![
+        ("0xFF", Expression::Integer32(255)),
+        ("0o17", Expression::Integer32(15)),
+        ("0b1010_1010", Expression::Integer32(170)),
+        ("1_000_000", Expression::Integer32(1_000_000)),
+        ("1.5e-3", Expression::Float64(1.5e-3)),
+        ("42u8", Expression::Unsigned8(42)),
+        ("0xFFFF_FFFF_FFFF_FFFFu64", Expression::Unsigned64(u64::MAX)),
+        ("-128i8", Expression::Integer8(-128)),
+        ("-9223372036854775808", Expression::Integer64(i64::MIN)),
+        ("7i64", Expression::Integer64(7)),
+        ("3.0f32", Expression::Float32(3.0)),
+        ("2f64", Expression::Float64(2.0)),
+    ];
+    for (input, expected) in cases {
+        let mut parser = Parser::new(Lexer::new(input));
+        let expr = parser.parse_expression().unwrap();
+        assert_eq!(expr, expected, "input: {}", input);
+    }
+}
+
+#[test]
+fn test_parse_methods_called_on_literals() {
+    let abs = |object| Expression::MethodCall { object: Box::new(object), method: "abs".to_string(), args: vec![] };
+    let cases = vec![
+        ("1i32.abs()", abs(Expression::Integer32(1))),
+        ("2.5.abs()", abs(Expression::Float64(2.5))),
+        // The sign applies to the result, not the receiver
+        ("-1i32.abs()", Expression::UnaryOp { op: UnaryOperator::Negate, operand: Box::new(abs(Expression::Integer32(1))) }),
+    ];
+    for (input, expected) in cases {
+        let mut parser = Parser::new(Lexer::new(input));
+        let expr = parser.parse_expression().unwrap();
+        assert_eq!(expr, expected, "input: {}", input);
+    }
+}
+
+#[test]
+fn test_parse_out_of_range_literals_report_spans() {
+    let cases = [
+        ("x := 300u8", "Literal 300u8 is out of range for u8", 6),
+        ("x := -129i8", "Literal -129i8 is out of range for i8", 7),
+        ("x := 1e39f32", "Literal 1e39f32 is out of range for f32", 6),
+        ("x := 9223372036854775808", "out of range for i64", 6),
+        ("x := 0b102", "Invalid numeric literal: 0b102", 6),
+    ];
+    for (input, message, column) in cases {
+        let source = format!("main = () void {{\n    {}\n}}\n", input);
+        let mut parser = Parser::new(Lexer::new(&source));
+        let error = parser.parse_program().unwrap_err();
+        assert!(error.message().contains(message), "{}: {}", input, error.message());
+        let span = error.span().unwrap();
+        assert_eq!((span.line, span.column), (2, column + 4), "input: {}", input);
+    }
+}
+
+#[test]
+fn test_parse_char_byte_and_raw_string_literals() {
+    let cases = vec![
+        ("'A'", Expression::Unsigned32(65)),
+        ("b'A'", Expression::Unsigned8(65)),
+        ("b\"ok\"", Expression::ArrayLiteral(vec![Expression::Unsigned8(b'o'), Expression::Unsigned8(b'k')])),
+        ("r\"$(name)\\n\"", Expression::String("$(name)\\n".to_string())),
+        ("\"\\$(x)\"", Expression::String("$(x)".to_string())),
+    ];
+    for (input, expected) in cases {
+        let mut parser = Parser::new(Lexer::new(input));
+        let expr = parser.parse_expression().unwrap();
+        assert_eq!(expr, expected, "input: {}", input);
+    }
+
+    let mut parser = Parser::new(Lexer::new("\"\"\"\n    Hello $(name)\n    \"\"\""));
+    let expr = parser.parse_expression().unwrap();
+    assert!(matches!(expr, Expression::StringInterpolation { ref parts } if parts.len() == 2), "{:?}", expr);
+
+    let mut parser = Parser::new(Lexer::new("main = () void {\n    c := 'ab'\n}\n"));
+    let error = parser.parse_program().unwrap_err();
+    assert!(error.message().contains("exactly one character"), "{}", error.message());
+    assert_eq!(error.span().unwrap().line, 2);
+}
+
 #[test]
 fn test_parse_binary_expressions() {
     let lexer = Lexer::new("1 + 2 * 3");
@@ -631,10 +714,9 @@ fn test_parse_unary_and_grouped_expressions() {
     let lexer = Lexer::new("-(1 + 2)");
     let mut parser = Parser::new(lexer);
     let expr = parser.parse_expression().unwrap();
-    assert_eq!(expr, Expression::BinaryOp {
-        left: Box::new(Expression::Integer32(0)),
-        op: BinaryOperator::Subtract,
-        right: Box::new(Expression::BinaryOp {
+    assert_eq!(expr, Expression::UnaryOp {
+        op: UnaryOperator::Negate,
+        operand: Box::new(Expression::BinaryOp {
             left: Box::new(Expression::Integer32(1)),
             op: BinaryOperator::Add,
             right: Box::new(Expression::Integer32(2)),
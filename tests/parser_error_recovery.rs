@@ -1,7 +1,22 @@
+use zen::ast::{Declaration, Statement};
 use zen::lexer::Lexer;
 use zen::parser::Parser;
 use zen::error::CompileError;
 
+fn parse_recovering(input: &str) -> (zen::ast::Program, Vec<CompileError>) {
+    Parser::new(Lexer::new(input)).parse_program_recovering()
+}
+
+fn error_lines(errors: &[CompileError]) -> Vec<usize> {
+    errors
+        .iter()
+        .map(|e| match e {
+            CompileError::SyntaxError(_, Some(span)) => span.line,
+            other => panic!("expected a located syntax error, got {:?}", other),
+        })
+        .collect()
+}
+
 #[test]
 fn test_missing_closing_brace() {
     let input = "main = () void {
@@ -167,9 +182,25 @@ fn test_recovery_after_error() {
     let lexer = Lexer::new(input);
     let mut parser = Parser::new(lexer);
     
-    let result = parser.parse_program();
-    // Currently, parser might not recover, but ideally it should
-    assert!(result.is_err());
+    // A bare `return` is an error too, so good_func reports the second one
+    let (program, errors) = parser.parse_program_recovering();
+    assert_eq!(error_lines(&errors), vec![4, 11]);
+    assert_eq!(program.declarations.len(), 2);
+    match &program.declarations[0] {
+        Declaration::Function(f) => {
+            assert_eq!(f.name, "bad_func");
+            assert!(matches!(f.body[0], Statement::Error(_)));
+        }
+        other => panic!("expected bad_func, got {:?}", other),
+    }
+    match &program.declarations[1] {
+        Declaration::Function(f) => {
+            assert_eq!(f.name, "good_func");
+            assert!(matches!(f.body[0], Statement::VariableDeclaration { .. }));
+            assert!(matches!(f.body[1], Statement::Error(_)));
+        }
+        other => panic!("expected good_func, got {:?}", other),
+    }
 }
 
 #[test]
@@ -190,4 +221,119 @@ fn test_helpful_error_for_common_mistake() {
     } else {
         panic!("Expected helpful error for C-style function");
     }
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_reports_every_statement_error() {
+    let input = "main = () void {
+        a := 5 ???
+        b := 1
+        c := (2 + )
+        d := 3
+    }";
+    let (program, errors) = parse_recovering(input);
+    assert_eq!(error_lines(&errors), vec![2, 4]);
+
+    let Declaration::Function(main) = &program.declarations[0] else {
+        panic!("expected main, got {:?}", program.declarations[0]);
+    };
+    assert_eq!(main.body.len(), 4);
+    assert!(matches!(main.body[0], Statement::Error(_)));
+    assert!(matches!(&main.body[1], Statement::VariableDeclaration { name, .. } if name == "b"));
+    assert!(matches!(main.body[2], Statement::Error(_)));
+    assert!(matches!(&main.body[3], Statement::VariableDeclaration { name, .. } if name == "d"));
+}
+
+#[test]
+fn test_reports_errors_across_declarations() {
+    let input = "Point = {
+        x: i32
+        y
+    }
+
+    first = (x: ) void {
+        return
+    }
+
+    second = () i32 {
+        return 2
+    }
+
+    ) stray
+
+    third = () i32 {
+        return 3 ???
+    }";
+    let (program, errors) = parse_recovering(input);
+    assert_eq!(error_lines(&errors), vec![3, 6, 14, 17]);
+
+    let kinds: Vec<_> = program
+        .declarations
+        .iter()
+        .map(|d| match d {
+            Declaration::Function(f) => f.name.as_str(),
+            Declaration::Error(_) => "<error>",
+            _ => "<other>",
+        })
+        .collect();
+    assert_eq!(kinds, vec!["<error>", "<error>", "second", "<error>", "third"]);
+}
+
+#[test]
+fn test_error_nodes_cover_skipped_source() {
+    let input = "main = () i32 {\n    x := 5 ??? 3\n    return 0\n}\n";
+    let (program, errors) = parse_recovering(input);
+    assert_eq!(errors.len(), 1);
+    let Declaration::Function(main) = &program.declarations[0] else {
+        panic!("expected main");
+    };
+    let Statement::Error(span) = &main.body[0] else {
+        panic!("expected an error statement, got {:?}", main.body[0]);
+    };
+    assert_eq!(&input[span.start..span.end], "x := 5 ??? 3");
+}
+
+#[test]
+fn test_error_inside_nested_block_does_not_cascade() {
+    let input = "main = () i32 {
+        x := 1
+        x > 0 ? | true => {
+            y := ) 2
+            z := 3
+        } | false => {}
+        return x
+    }
+
+    after = () i32 {
+        return 1
+    }";
+    let (program, errors) = parse_recovering(input);
+    assert_eq!(error_lines(&errors), vec![4]);
+    assert!(matches!(&program.declarations[1], Declaration::Function(f) if f.name == "after"));
+}
+
+#[test]
+fn test_parse_program_returns_first_recovered_error() {
+    let input = "main = () void {
+        a := 5 ???
+        b := (1 +
+    }";
+    let (_, errors) = parse_recovering(input);
+    assert!(errors.len() >= 2, "{:?}", errors);
+    let first = Parser::new(Lexer::new(input)).parse_program().unwrap_err();
+    assert_eq!(first, errors[0]);
+}
+
+#[test]
+fn test_valid_program_has_no_errors() {
+    let input = "add = (a: i32, b: i32) i32 {
+        return a + b
+    }
+
+    main = () i32 {
+        return add(1, 2)
+    }";
+    let (program, errors) = parse_recovering(input);
+    assert!(errors.is_empty(), "{:?}", errors);
+    assert_eq!(program, Parser::new(Lexer::new(input)).parse_program().unwrap());
+}
@@ -48,12 +48,13 @@ fn test_parse_member_access_with_function_call() {
     
     let expr = parser.parse_expression().unwrap();
     
-    // Should parse as a function call with object.method name
-    if let Expression::FunctionCall { name, args } = expr {
-        assert_eq!(name, "obj.method");
+    // Should parse as a method call on obj
+    if let Expression::MethodCall { object, method, args } = expr {
+        assert!(matches!(*object, Expression::Identifier(ref name) if name == "obj"));
+        assert_eq!(method, "method");
         assert_eq!(args.len(), 0);
     } else {
-        panic!("Expected FunctionCall expression, got {:?}", expr);
+        panic!("Expected MethodCall expression, got {:?}", expr);
     }
 }
 
@@ -65,13 +66,14 @@ fn test_parse_member_access_with_args() {
     
     let expr = parser.parse_expression().unwrap();
     
-    if let Expression::FunctionCall { name, args } = expr {
-        assert_eq!(name, "obj.method");
+    if let Expression::MethodCall { object, method, args } = expr {
+        assert!(matches!(*object, Expression::Identifier(ref name) if name == "obj"));
+        assert_eq!(method, "method");
         assert_eq!(args.len(), 2);
         assert!(matches!(&args[0], Expression::Integer32(1)));
         assert!(matches!(&args[1], Expression::Integer32(2)));
     } else {
-        panic!("Expected FunctionCall expression");
+        panic!("Expected MethodCall expression");
     }
 }
 
@@ -0,0 +1,132 @@
+use inkwell::context::Context;
+use inkwell::OptimizationLevel;
+use zen::ast::{AstType, Declaration, Program};
+use zen::compiler::Compiler;
+use zen::error::CompileError;
+use zen::lexer::Lexer;
+use zen::parser::Parser;
+use zen::spans::SpanTable;
+use zen::typechecker::TypeChecker;
+
+fn parse(source: &str) -> (Program, SpanTable) {
+    let mut parser = Parser::new(Lexer::new(source));
+    let program = parser.parse_program().unwrap();
+    (program, parser.take_spans())
+}
+
+fn errors(source: &str) -> Vec<CompileError> {
+    let (program, spans) = parse(source);
+    TypeChecker::new().with_spans(spans).check_program_collecting(&program)
+}
+
+fn first_error(source: &str) -> CompileError {
+    let errors = errors(source);
+    assert!(!errors.is_empty(), "expected an error");
+    errors[0].clone()
+}
+
+fn run_source(source: &str) -> i32 {
+    let (program, _) = parse(source);
+    let context = Context::create();
+    let compiler = Compiler::new(&context);
+    let module = compiler.get_module(&program).unwrap();
+    zen::jit::run_main(&module, &["prog".to_string()], OptimizationLevel::None).unwrap()
+}
+
+// `skip` has a default body calling the required `next`
+const COUNTER: &str = "Iterator = behavior {\n    Item: type,\n    LIMIT: i32,\n    STEP: i32 = 1,\n    next = (self) Self.Item,\n    skip = (self) Self.Item {\n        self.next()\n        return self.next()\n    }\n}\n\n\
+Counter = {\n    count: i32 ::\n}\n\n";
+
+const COUNTER_IMPL: &str = "Counter.impl = {\n    Iterator: {\n        Item: type = i32,\n        LIMIT: i32 = 100,\n        next = (self: *Counter) i32 {\n            self.count = self.count + Self.STEP\n            return self.count\n        }\n    }\n}\n\n";
+
+#[test]
+fn test_parse_associated_items() {
+    let (program, _) = parse(&format!("{}{}", COUNTER, COUNTER_IMPL));
+    let Declaration::Behavior(behavior) = &program.declarations[0] else {
+        panic!("expected a behavior");
+    };
+    assert_eq!(behavior.associated_types, vec!["Item".to_string()]);
+    assert_eq!(behavior.constants.len(), 2);
+    assert!(behavior.constants[0].value.is_none());
+    assert!(behavior.constants[1].value.is_some());
+    assert!(behavior.methods[0].body.is_none());
+    assert!(behavior.methods[1].body.is_some());
+
+    let Declaration::Impl(block) = &program.declarations[2] else {
+        panic!("expected an impl block");
+    };
+    assert_eq!(block.associated_types, vec![("Item".to_string(), AstType::I32)]);
+    assert_eq!(block.constants[0].name, "LIMIT");
+    assert_eq!(block.methods.len(), 1);
+}
+
+#[test]
+fn test_default_methods_are_inherited() {
+    let source = format!(
+        "{}{}main = () i32 {{\n    c ::= Counter {{ count: 0 }}\n    return c.skip() + Counter.LIMIT + Counter.STEP\n}}\n",
+        COUNTER, COUNTER_IMPL
+    );
+    assert!(errors(&source).is_empty(), "{:?}", errors(&source));
+}
+
+#[test]
+fn test_impls_must_supply_associated_items() {
+    let source = format!(
+        "{}Counter.impl = {{\n    Iterator: {{\n        LIMIT: i32 = 100,\n        next = (self: *Counter) i32 {{\n            return 0\n        }}\n    }}\n}}\n",
+        COUNTER
+    );
+    let message = first_error(&source).to_string();
+    assert!(message.contains("associated type 'Item'"), "{}", message);
+
+    let source = format!(
+        "{}Counter.impl = {{\n    Iterator: {{\n        Item: type = i32,\n        next = (self: *Counter) i32 {{\n            return 0\n        }}\n    }}\n}}\n",
+        COUNTER
+    );
+    let message = first_error(&source).to_string();
+    assert!(message.contains("constant 'LIMIT'"), "{}", message);
+
+    let source = format!(
+        "{}Counter.impl = {{\n    Iterator: {{\n        Item: type = i32,\n        LIMIT: bool = true,\n        next = (self: *Counter) i32 {{\n            return 0\n        }}\n    }}\n}}\n",
+        COUNTER
+    );
+    let message = first_error(&source).to_string();
+    assert!(message.contains("'LIMIT'"), "{}", message);
+}
+
+#[test]
+fn test_impl_signatures_must_match() {
+    let source = format!(
+        "{}Counter.impl = {{\n    Iterator: {{\n        Item: type = i32,\n        LIMIT: i32 = 100,\n        next = (self: *Counter) bool {{\n            return true\n        }}\n    }}\n}}\n",
+        COUNTER
+    );
+    let message = first_error(&source).to_string();
+    assert!(message.contains("'next'"), "{}", message);
+
+    let source = format!(
+        "{}Counter.impl = {{\n    Iterator: {{\n        Item: type = i32,\n        LIMIT: i32 = 100,\n        next = (self: *Counter) i32 {{\n            return 0\n        }},\n        reset = (self: *Counter) void {{\n        }}\n    }}\n}}\n",
+        COUNTER
+    );
+    let message = first_error(&source).to_string();
+    assert!(message.contains("'reset'"), "{}", message);
+}
+
+#[test]
+fn test_unknown_associated_constant() {
+    let source = format!(
+        "{}{}main = () i32 {{\n    return Counter.MISSING\n}}\n",
+        COUNTER, COUNTER_IMPL
+    );
+    assert!(!errors(&source).is_empty());
+}
+
+#[test]
+fn test_run_default_and_overridden_methods() {
+    let source = format!(
+        "{}{}Evens = {{\n    value: i32 ::\n}}\n\n\
+Evens.impl = {{\n    Iterator: {{\n        Item: type = i32,\n        LIMIT: i32 = 10,\n        STEP: i32 = 2,\n        next = (self: *Evens) i32 {{\n            self.value = self.value + Self.STEP\n            return self.value\n        }},\n        skip = (self: *Evens) i32 {{\n            return 1000\n        }}\n    }}\n}}\n\n\
+main = () i32 {{\n    c ::= Counter {{ count: 0 }}\n    e ::= Evens {{ value: 0 }}\n    return c.skip() + e.next() + e.skip() + Counter.LIMIT + Evens.LIMIT\n}}\n",
+        COUNTER, COUNTER_IMPL
+    );
+    // 2 + 2 + 1000 + 100 + 10
+    assert_eq!(run_source(&source), 1114);
+}
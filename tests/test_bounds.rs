@@ -0,0 +1,145 @@
+use inkwell::context::Context;
+use inkwell::OptimizationLevel;
+use zen::ast::{Declaration, Expression, Program, Statement};
+use zen::compiler::Compiler;
+use zen::error::CompileError;
+use zen::lexer::Lexer;
+use zen::mangle::demangle;
+use zen::parser::Parser;
+use zen::spans::SpanTable;
+use zen::type_system::Monomorphizer;
+use zen::typechecker::TypeChecker;
+
+fn parse(source: &str) -> (Program, SpanTable) {
+    let mut parser = Parser::new(Lexer::new(source));
+    let program = parser.parse_program().unwrap();
+    (program, parser.take_spans())
+}
+
+fn errors(source: &str) -> Vec<CompileError> {
+    let (program, spans) = parse(source);
+    TypeChecker::new().with_spans(spans).check_program_collecting(&program)
+}
+
+fn first_error(source: &str) -> CompileError {
+    let errors = errors(source);
+    assert!(!errors.is_empty(), "expected an error");
+    errors[0].clone()
+}
+
+fn run_source(source: &str) -> i32 {
+    let (program, _) = parse(source);
+    let context = Context::create();
+    let compiler = Compiler::new(&context);
+    let module = compiler.get_module(&program).unwrap();
+    zen::jit::run_main(&module, &["prog".to_string()], OptimizationLevel::None).unwrap()
+}
+
+const ORD: &str = "Ord = behavior {\n    cmp = (self: Self, other: Self) i32\n}\n\n\
+i32.impl = {\n    Ord: {\n        cmp = (self: i32, other: i32) i32 {\n            return self - other\n        }\n    }\n}\n\n\
+max<T: Ord> = (a: T, b: T) T {\n    return (a.cmp(b) > 0) ?\n        | true => a\n        | false => b\n}\n\n";
+
+const POINT: &str = "Point = {\n    x: i32,\n    y: i32\n}\n\n";
+
+#[test]
+fn test_parse_bounds() {
+    let (program, _) = parse("pick<T: Ord + Display, U> = (a: T, b: U) T {\n    return a\n}\n");
+    let Declaration::Function(function) = &program.declarations[0] else {
+        panic!("expected a function");
+    };
+    assert_eq!(function.type_params[0].name, "T");
+    assert_eq!(function.type_params[0].constraints, vec!["Ord".to_string(), "Display".to_string()]);
+    assert!(function.type_params[1].constraints.is_empty());
+
+    let (program, _) = parse("Pair<T: Ord> = {\n    first: T,\n    second: T\n}\n");
+    let Declaration::Struct(struct_def) = &program.declarations[0] else {
+        panic!("expected a struct");
+    };
+    assert_eq!(struct_def.type_params[0].constraints, vec!["Ord".to_string()]);
+}
+
+#[test]
+fn test_bounded_call_checks() {
+    let source = format!("{}main = () i32 {{\n    return max(3, 9)\n}}\n", ORD);
+    assert!(errors(&source).is_empty(), "{:?}", errors(&source));
+}
+
+#[test]
+fn test_missing_impl_is_reported_at_the_call() {
+    let source = format!("{}{}main = () i32 {{\n    p := Point {{ x: 1, y: 2 }}\n    q := max(p, p)\n    return 0\n}}\n", ORD, POINT);
+    let error = first_error(&source);
+    let message = error.to_string();
+    assert!(
+        message.contains("Type 'Point' does not implement behavior 'Ord', which 'max' requires of its type parameter 'T'"),
+        "{}",
+        message
+    );
+    assert!(message.contains("add `Point.impl = { Ord: { ... } }`"), "{}", message);
+
+    // The error points at the call's statement, not the declaration of max
+    let line = source.lines().position(|line| line.contains("q := max(p, p)")).unwrap() + 1;
+    assert_eq!(error.span().map(|span| span.line), Some(line));
+}
+
+#[test]
+fn test_type_parameters_satisfy_their_own_bounds() {
+    let source = format!("{}larger<U: Ord> = (a: U, b: U) U {{\n    return max(a, b)\n}}\n", ORD);
+    assert!(errors(&source).is_empty(), "{:?}", errors(&source));
+
+    let source = format!("{}larger<U> = (a: U, b: U) U {{\n    return max(a, b)\n}}\n", ORD);
+    let message = first_error(&source).to_string();
+    assert!(message.contains("Type parameter 'U' is not bounded by 'Ord'"), "{}", message);
+    assert!(message.contains("add the bound `U: Ord`"), "{}", message);
+}
+
+#[test]
+fn test_methods_on_type_parameters_need_a_bound() {
+    let source = "smallest<T> = (a: T, b: T) T {\n    return (a.cmp(b) < 0) ?\n        | true => a\n        | false => b\n}\n";
+    let message = first_error(source).to_string();
+    assert!(message.contains("No method named 'cmp' for type parameter 'T'; add a bound"), "{}", message);
+
+    let source = format!("{}first<T: Ord> = (a: T) T {{\n    return a.show()\n}}\n", ORD);
+    let message = first_error(&source).to_string();
+    assert!(message.contains("none of its bounds (Ord) declare one"), "{}", message);
+}
+
+#[test]
+fn test_unknown_behavior_in_bound() {
+    let message = first_error("same<T: Orderable> = (a: T) T {\n    return a\n}\n").to_string();
+    assert!(message.contains("Unknown behavior 'Orderable' in the bound on type parameter 'T'"), "{}", message);
+}
+
+#[test]
+fn test_bound_method_calls_resolve_per_instantiation() {
+    let source = format!("{}main = () i32 {{\n    return max(3, 9)\n}}\n", ORD);
+    let (program, spans) = parse(&source);
+    let mut checker = TypeChecker::new().with_spans(spans.clone());
+    assert!(checker.check_program_collecting(&program).is_empty());
+
+    let program = Monomorphizer::new()
+        .with_spans(spans)
+        .with_type_table(checker.into_type_table())
+        .monomorphize_program(&program)
+        .unwrap();
+    let instance = program
+        .functions()
+        .find(|function| demangle(&function.name).is_some_and(|symbol| symbol.to_string() == "max<i32>"))
+        .expect("max is instantiated for i32");
+    let Statement::Return(Expression::Conditional { scrutinee, .. }) = &instance.body[0] else {
+        panic!("expected the conditional return");
+    };
+    let Expression::BinaryOp { left, .. } = scrutinee.as_ref() else {
+        panic!("expected the comparison");
+    };
+    let Expression::FunctionCall { name, args } = left.as_ref() else {
+        panic!("expected the method call to be resolved, found {:?}", left);
+    };
+    assert_eq!(demangle(name).unwrap().to_string(), "i32.Ord.cmp");
+    assert_eq!(args.len(), 2);
+}
+
+#[test]
+fn test_run_bounded_generic() {
+    let source = format!("{}main = () i32 {{\n    return max(3, 9) * 10 + max(4, 2)\n}}\n", ORD);
+    assert_eq!(run_source(&source), 94);
+}
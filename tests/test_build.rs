@@ -0,0 +1,85 @@
+use std::path::Path;
+use inkwell::context::Context;
+use inkwell::OptimizationLevel;
+use tempfile::TempDir;
+use zen::build::{self, BuildOptions, EmitKind};
+use zen::compiler::Compiler;
+use zen::lexer::Lexer;
+use zen::parser::Parser;
+
+fn build_source(source: &str, emit: EmitKind, output: &Path) {
+    let lexer = Lexer::new(source);
+    let mut parser = Parser::new(lexer);
+    let program = parser.parse_program().unwrap();
+
+    let context = Context::create();
+    let compiler = Compiler::new(&context);
+    let module = compiler.get_module(&program).unwrap();
+
+    let options = BuildOptions {
+        emit,
+        output: output.to_path_buf(),
+        opt_level: OptimizationLevel::None,
+    };
+    build::build_module(&module, &options).unwrap();
+}
+
+#[test]
+fn test_emit_kind_parsing() {
+    assert_eq!("obj".parse::<EmitKind>().unwrap(), EmitKind::Object);
+    assert_eq!("asm".parse::<EmitKind>().unwrap(), EmitKind::Assembly);
+    assert_eq!("llvm-ir".parse::<EmitKind>().unwrap(), EmitKind::LlvmIr);
+    assert_eq!("llvm-bc".parse::<EmitKind>().unwrap(), EmitKind::LlvmBitcode);
+    assert_eq!("exe".parse::<EmitKind>().unwrap(), EmitKind::Executable);
+    assert_eq!("bin".parse::<EmitKind>().unwrap(), EmitKind::Executable);
+    let error = "wasm".parse::<EmitKind>().unwrap_err().to_string();
+    assert!(error.contains("exe, bin, obj"), "{}", error);
+}
+
+#[test]
+fn test_default_output_paths() {
+    let input = Path::new("examples/hello.zen");
+    assert_eq!(EmitKind::Object.default_output(input), Path::new("hello.o"));
+    assert_eq!(EmitKind::Assembly.default_output(input), Path::new("hello.s"));
+    assert_eq!(EmitKind::LlvmIr.default_output(input), Path::new("hello.ll"));
+    assert_eq!(EmitKind::LlvmBitcode.default_output(input), Path::new("hello.bc"));
+}
+
+#[test]
+fn test_emit_llvm_ir_file() {
+    let dir = TempDir::new().unwrap();
+    let output = dir.path().join("main.ll");
+    build_source("main = () i32 { 42 }", EmitKind::LlvmIr, &output);
+
+    let ir = std::fs::read_to_string(&output).unwrap();
+    assert!(ir.contains("define i32 @main"));
+}
+
+#[test]
+fn test_emit_object_file() {
+    let dir = TempDir::new().unwrap();
+    let output = dir.path().join("main.o");
+    build_source("main = () i32 { 42 }", EmitKind::Object, &output);
+
+    assert!(std::fs::metadata(&output).unwrap().len() > 0);
+}
+
+#[test]
+fn test_build_executable_exit_code() {
+    let dir = TempDir::new().unwrap();
+    let output = dir.path().join("app");
+    build_source("main = () i32 { 7 }", EmitKind::Executable, &output);
+
+    let status = std::process::Command::new(&output).status().unwrap();
+    assert_eq!(status.code(), Some(7));
+}
+
+#[test]
+fn test_build_executable_leaves_neighbouring_objects_alone() {
+    let dir = TempDir::new().unwrap();
+    let object = dir.path().join("app.o");
+    std::fs::write(&object, "not ours").unwrap();
+    build_source("main = () i32 { 7 }", EmitKind::Executable, &dir.path().join("app"));
+
+    assert_eq!(std::fs::read_to_string(&object).unwrap(), "not ours");
+}
@@ -0,0 +1,256 @@
+use inkwell::context::Context;
+use inkwell::OptimizationLevel;
+use zen::ast::{AstType, BinaryOperator, Declaration, Expression, Program, Statement};
+use zen::compiler::Compiler;
+use zen::error::CompileError;
+use zen::lexer::Lexer;
+use zen::parser::Parser;
+use zen::typechecker::captures::{closure_captures, Capture, CaptureMode, Escapes};
+use zen::typechecker::TypeChecker;
+
+fn parse(source: &str) -> Program {
+    Parser::new(Lexer::new(source)).parse_program().unwrap()
+}
+
+fn parse_expression(source: &str) -> Expression {
+    Parser::new(Lexer::new(source)).parse_expression().unwrap()
+}
+
+fn errors(source: &str) -> Vec<CompileError> {
+    let mut parser = Parser::new(Lexer::new(source));
+    let program = parser.parse_program().unwrap();
+    TypeChecker::new().with_spans(parser.take_spans()).check_program_collecting(&program)
+}
+
+fn message(error: &CompileError) -> String {
+    error.to_string()
+}
+
+fn captures(source: &str, variables: &[&str]) -> Vec<Capture> {
+    match parse_expression(source) {
+        Expression::Closure { params, body } => {
+            closure_captures(&params, &body, |name| variables.contains(&name))
+        }
+        other => panic!("expected a closure, got {:?}", other),
+    }
+}
+
+fn capture(name: &str, mode: CaptureMode) -> Capture {
+    Capture { name: name.to_string(), mode }
+}
+
+fn run_source(source: &str) -> i32 {
+    let program = parse(source);
+    let context = Context::create();
+    let compiler = Compiler::new(&context);
+    let module = compiler.get_module(&program).unwrap();
+    zen::jit::run_main(&module, &["prog".to_string()], OptimizationLevel::None).unwrap()
+}
+
+const APPLY: &str = "apply = (f: (i32) i32, x: i32) i32 {\n    return f(x)\n}\n\n";
+
+#[test]
+fn test_parse_single_parameter_closure() {
+    let expected = Expression::Closure {
+        params: vec![("x".to_string(), None)],
+        body: Box::new(Expression::BinaryOp {
+            left: Box::new(Expression::Identifier("x".to_string())),
+            op: BinaryOperator::Add,
+            right: Box::new(Expression::Integer32(1)),
+        }),
+    };
+    assert_eq!(parse_expression("x -> x + 1"), expected);
+}
+
+#[test]
+fn test_parse_typed_parameter_list() {
+    match parse_expression("(a: i32, b) -> { a + b }") {
+        Expression::Closure { params, body } => {
+            assert_eq!(params, vec![("a".to_string(), Some(AstType::I32)), ("b".to_string(), None)]);
+            assert!(matches!(*body, Expression::Block(_)));
+        }
+        other => panic!("expected a closure, got {:?}", other),
+    }
+    assert!(matches!(parse_expression("() -> 1"), Expression::Closure { params, .. } if params.is_empty()));
+}
+
+#[test]
+fn test_parenthesized_expression_is_not_a_closure() {
+    assert!(matches!(parse_expression("(a + b) * 2"), Expression::BinaryOp { .. }));
+}
+
+#[test]
+fn test_parse_function_value_type() {
+    let program = parse(APPLY);
+    let Declaration::Function(function) = &program.declarations[0] else {
+        panic!("expected a function");
+    };
+    let expected = AstType::Closure { param_types: vec![AstType::I32], return_type: Box::new(AstType::I32) };
+    assert_eq!(function.args[0].1, expected);
+    assert_eq!(expected.to_string(), "(i32) i32");
+}
+
+#[test]
+fn test_reads_are_captured_by_value() {
+    let found = captures("x -> x + offset * scale", &["offset", "scale"]);
+    assert_eq!(found, vec![capture("offset", CaptureMode::Value), capture("scale", CaptureMode::Value)]);
+}
+
+#[test]
+fn test_assignments_are_captured_by_pointer() {
+    let found = captures("x -> { total = total + x\n count }", &["total", "count"]);
+    assert_eq!(found, vec![capture("total", CaptureMode::Pointer), capture("count", CaptureMode::Value)]);
+
+    let found = captures("() -> { p.x = 1 }", &["p"]);
+    assert_eq!(found, vec![capture("p", CaptureMode::Pointer)]);
+}
+
+#[test]
+fn test_address_of_is_captured_by_pointer() {
+    let body = Expression::FunctionCall {
+        name: "bump".to_string(),
+        args: vec![Expression::AddressOf(Box::new(Expression::Identifier("counter".to_string())))],
+    };
+    let found = closure_captures(&[], &body, |name| name == "counter");
+    assert_eq!(found, vec![capture("counter", CaptureMode::Pointer)]);
+}
+
+#[test]
+fn test_parameters_and_locals_are_not_captured() {
+    let found = captures("x -> { y := x * 2\n y + z }", &["x", "y", "z"]);
+    assert_eq!(found, vec![capture("z", CaptureMode::Value)]);
+
+    // Functions and other globals are called, not captured
+    let found = captures("x -> helper(x)", &[]);
+    assert!(found.is_empty());
+}
+
+#[test]
+fn test_nested_closure_captures_pass_through() {
+    let found = captures("x -> y -> x + y + base", &["base"]);
+    assert_eq!(found, vec![capture("base", CaptureMode::Value)]);
+}
+
+#[test]
+fn test_closures_only_called_or_passed_on_are_local() {
+    let source = "main = () i32 {\n    offset := 1\n    f := x -> x + offset\n    g := x -> x * 2\n    h := g\n    apply(x -> x, 1)\n    return apply(f, 2) + h(1)\n}\n";
+    let program = parse(source);
+    let Declaration::Function(main) = &program.declarations[0] else {
+        panic!("expected a function");
+    };
+    let escapes = Escapes::of_body(&main.body);
+    assert!(escapes.is_local(&[1, 0]));
+    assert!(!escapes.is_local(&[2, 0]));
+    assert!(escapes.is_local(&[4, 0, 0]));
+    assert!(escapes.escapes("g", &[2]));
+    assert!(!escapes.escapes("f", &[1]));
+}
+
+#[test]
+fn test_escaping_closure_cannot_capture_by_pointer() {
+    let source = "counter = () (i32) i32 {\n    total ::= 0\n    return (n: i32) -> { total = total + n\n total }\n}\n";
+    let errors = errors(source);
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert!(message(&errors[0]).contains("assigns to or takes the address of 'total'"), "{}", message(&errors[0]));
+}
+
+#[test]
+fn test_function_value_parameter_cannot_be_kept() {
+    let source = "keep = (f: (i32) i32) (i32) i32 {\n    return f\n}\n";
+    let errors = errors(source);
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert!(message(&errors[0]).contains("Parameter 'f' holds a function value"), "{}", message(&errors[0]));
+}
+
+#[test]
+fn test_closure_passed_to_function_parameter_checks() {
+    let source = format!(
+        "{}main = () i32 {{\n    offset := 10\n    return apply(x -> x + offset, 5)\n}}\n",
+        APPLY
+    );
+    assert!(errors(&source).is_empty(), "{:?}", errors(&source));
+}
+
+#[test]
+fn test_named_function_is_a_function_value() {
+    let source = format!(
+        "double = (n: i32) i32 {{\n    return n * 2\n}}\n\n{}main = () i32 {{\n    return apply(double, 4)\n}}\n",
+        APPLY
+    );
+    assert!(errors(&source).is_empty(), "{:?}", errors(&source));
+}
+
+#[test]
+fn test_closure_bound_to_variable_and_called() {
+    let source = "main = () i32 {\n    add := (a: i32, b: i32) -> a + b\n    return add(1, 2)\n}\n";
+    assert!(errors(source).is_empty(), "{:?}", errors(source));
+}
+
+#[test]
+fn test_closure_call_arity_is_checked() {
+    let source = "main = () i32 {\n    add := (a: i32, b: i32) -> a + b\n    return add(1)\n}\n";
+    let errors = errors(source);
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert!(message(&errors[0]).contains("'add' takes 2 argument(s) but 1 were given"), "{}", message(&errors[0]));
+}
+
+#[test]
+fn test_closure_result_must_match_parameter_type() {
+    let source = format!("{}main = () i32 {{\n    return apply(x -> x > 1, 5)\n}}\n", APPLY);
+    assert!(!errors(&source).is_empty());
+}
+
+#[test]
+fn test_closure_rejected_as_raw_function_pointer() {
+    let source = "call = (f: *(i32) i32) i32 {\n    return f(1)\n}\n\nmain = () i32 {\n    base := 2\n    return call(x -> x + base)\n}\n";
+    let errors = errors(source);
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert!(message(&errors[0]).contains("it captures 'base'"), "{}", message(&errors[0]));
+}
+
+#[test]
+fn test_run_closure_with_captured_value() {
+    let source = format!(
+        "{}main = () i32 {{\n    offset := 10\n    return apply(x -> x + offset, 5)\n}}\n",
+        APPLY
+    );
+    assert_eq!(run_source(&source), 15);
+}
+
+#[test]
+fn test_run_closure_writing_through_captured_pointer() {
+    let source = "main = () i32 {\n    total ::= 0\n    add := (n: i32) -> { total = total + n }\n    add(3)\n    add(4)\n    return total\n}\n";
+    assert_eq!(run_source(source), 7);
+}
+
+#[test]
+fn test_run_closures_created_in_a_loop() {
+    let source = format!(
+        "{}main = () i32 {{\n    total ::= 0\n    i ::= 0\n    loop i < 5 {{\n        apply(x -> {{ total = total + x\n x }}, i)\n        i = i + 1\n    }}\n    return total\n}}\n",
+        APPLY
+    );
+    assert_eq!(run_source(&source), 10);
+}
+
+#[test]
+fn test_run_named_function_as_function_value() {
+    let source = format!(
+        "double = (n: i32) i32 {{\n    return n * 2\n}}\n\n{}main = () i32 {{\n    return apply(double, 21)\n}}\n",
+        APPLY
+    );
+    assert_eq!(run_source(&source), 42);
+}
+
+#[test]
+fn test_block_body_can_return_early() {
+    let Expression::Closure { body, .. } = parse_expression("() -> { return 1 }") else {
+        panic!("expected a closure");
+    };
+    let Expression::Block(statements) = *body else {
+        panic!("expected a block");
+    };
+    assert!(matches!(statements[0], Statement::Return(_)));
+
+    let source = format!("{}main = () i32 {{\n    return apply(x -> {{ return x * 3 }}, 2)\n}}\n", APPLY);
+    assert!(errors(&source).is_empty(), "{:?}", errors(&source));
+}
@@ -0,0 +1,134 @@
+use std::fs;
+use std::path::Path;
+use zen::cst::{self, NodeKind, TriviaKind};
+use zen::lexer::Token;
+
+fn assert_round_trip(source: &str) {
+    let tokens: String = cst::tokenize(source).iter().map(|t| t.to_string()).collect();
+    assert_eq!(tokens, source, "token stream lost text");
+    assert_eq!(cst::parse(source).to_string(), source, "tree lost text");
+}
+
+fn zen_files(dir: &Path, files: &mut Vec<std::path::PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else { return };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() && !path.ends_with("target") && !path.ends_with(".git") {
+            zen_files(&path, files);
+        } else if path.extension().is_some_and(|ext| ext == "zen") {
+            files.push(path);
+        }
+    }
+}
+
+#[test]
+fn test_round_trips_repo_sources() {
+    let mut files = Vec::new();
+    zen_files(Path::new(env!("CARGO_MANIFEST_DIR")), &mut files);
+    assert!(!files.is_empty());
+    for file in files {
+        let source = fs::read_to_string(&file).unwrap();
+        let tokens: String = cst::tokenize(&source).iter().map(|t| t.to_string()).collect();
+        assert_eq!(tokens, source, "{} did not round-trip", file.display());
+        assert_eq!(cst::parse(&source).to_string(), source, "{} did not round-trip", file.display());
+    }
+}
+
+#[test]
+fn test_round_trips_awkward_input() {
+    for source in [
+        "",
+        "   \n\t\n",
+        "// only a comment",
+        "/* unterminated",
+        "x := \"unterminated",
+        "main = () void {\r\n    x := 1 /* inline */ + 2\r\n}\r\n",
+        "f = (a: i32, b: [i32; 3]) i32 { a } )) }",
+        "héllo := \"wörld ${name}\" // ünïcode\n",
+        "s := r#\"raw \"quoted\"\"#\nc := 'x'\nb := b\"bytes\"\n",
+        "t := \"\"\"\n    multi\n    line\n    \"\"\"\n",
+        "x := 0x_FF_u8 ~ $ 1.5e-3f32\n",
+    ] {
+        assert_round_trip(source);
+    }
+}
+
+#[test]
+fn test_trivia_attachment() {
+    let source = "x := 1 // one\n\n// about y\ny := 2";
+    let tokens = cst::tokenize(source);
+
+    let one = tokens.iter().find(|t| t.text == "1").unwrap();
+    assert!(one.leading.is_empty());
+    assert_eq!(one.trailing.iter().map(|t| t.kind).collect::<Vec<_>>(), vec![TriviaKind::Whitespace, TriviaKind::LineComment]);
+    assert_eq!(one.trailing[1].text, "// one");
+
+    let y = tokens.iter().find(|t| t.text == "y").unwrap();
+    let leading: Vec<_> = y.leading.iter().map(|t| (t.kind, t.text.as_str())).collect();
+    assert_eq!(
+        leading,
+        vec![
+            (TriviaKind::Whitespace, "\n\n"),
+            (TriviaKind::LineComment, "// about y"),
+            (TriviaKind::Whitespace, "\n"),
+        ]
+    );
+    assert_eq!(y.leading[1].start, source.find("// about").unwrap());
+    assert!(y.starts_line());
+
+    let eof = tokens.last().unwrap();
+    assert_eq!(eof.token, Token::Eof);
+    assert!(eof.text.is_empty());
+}
+
+#[test]
+fn test_comments_at_end_of_file_belong_to_eof() {
+    let tokens = cst::tokenize("x := 1\n// trailing note\n");
+    let eof = tokens.last().unwrap();
+    assert_eq!(eof.token, Token::Eof);
+    assert!(eof.leading.iter().any(|t| t.text == "// trailing note"));
+}
+
+#[test]
+fn test_doc_comments() {
+    let source = "//! Geometry helpers\n\n/// A point.\n/// Has two fields.\nPoint: {\n    x: f64,\n    y: f64,\n}\n\n/** Origin. */\norigin = () Point { Point { x: 0.0, y: 0.0 } }\n";
+    let tree = cst::parse(source);
+    let items: Vec<_> = tree.items().collect();
+    assert_eq!(items.len(), 2);
+    assert_eq!(items[0].doc_comments(), vec!["A point.", "Has two fields."]);
+    assert_eq!(items[1].doc_comments(), vec!["Origin."]);
+
+    let first = items[0].first_token().unwrap();
+    assert!(first.leading.iter().any(|t| t.kind == TriviaKind::InnerDocComment));
+    assert!(items[0].source_text().starts_with("Point: {"));
+    assert!(items[0].source_text().ends_with('}'));
+}
+
+#[test]
+fn test_splits_items_and_groups_brackets() {
+    let source = "add = (a: i32, b: i32) i32 {\n    a + b\n}\n\nvalues := [1, 2,\n    3]\n\nmain = () void {\n    x := add(1,\n        2)\n}\n";
+    let tree = cst::parse(source);
+    let items: Vec<_> = tree.items().map(|item| item.first_token().unwrap().text.clone()).collect();
+    assert_eq!(items, vec!["add", "values", "main"]);
+
+    let add = tree.items().next().unwrap();
+    assert_eq!(add.nodes(NodeKind::Parens).count(), 1);
+    let body = add.nodes(NodeKind::Braces).next().unwrap();
+    assert_eq!(body.source_text(), "{\n    a + b\n}");
+}
+
+#[test]
+fn test_continued_lines_stay_in_one_item() {
+    let source = "total := first +\n    second\nnext := 1\n";
+    let tree = cst::parse(source);
+    let items: Vec<_> = tree.items().map(|item| item.source_text()).collect();
+    assert_eq!(items, vec!["total := first +\n    second", "next := 1"]);
+}
+
+#[test]
+fn test_unclosed_groups_close_at_end_of_file() {
+    let source = "main = () void {\n    call(1, [2\n";
+    let tree = cst::parse(source);
+    assert_eq!(tree.items().count(), 1);
+    assert_eq!(tree.to_string(), source);
+}
@@ -0,0 +1,158 @@
+use zen::diagnostics::{ColorChoice, Diagnostic, DiagnosticRenderer, FileDiagnostic, Severity};
+use zen::error::{CompileError, Span};
+use zen::lexer::Lexer;
+use zen::parser::Parser;
+use zen::typechecker::TypeChecker;
+
+fn parse(source: &str) -> zen::ast::Program {
+    let lexer = Lexer::new(source);
+    let mut parser = Parser::new(lexer);
+    parser.parse_program().unwrap()
+}
+
+#[test]
+fn test_render_labeled_snippet() {
+    let source = "main = () i32 {\n    x := 1 +\n}\n";
+    let diagnostic = Diagnostic::error("Expected expression")
+        .with_label(Span { start: 29, end: 30, line: 3, column: 1, utf16_column: 1 }, "found '}'")
+        .with_label(Span { start: 20, end: 26, line: 2, column: 5, utf16_column: 5 }, "in this declaration")
+        .with_note("expressions cannot end with an operator");
+
+    let renderer = DiagnosticRenderer::new("main.zen", source, ColorChoice::Never);
+    let output = renderer.render(&diagnostic);
+
+    assert!(output.contains("Error: Expected expression"));
+    assert!(output.contains("main.zen:3:1"));
+    assert!(output.contains("found '}'"));
+    assert!(output.contains("in this declaration"));
+    assert!(output.contains("expressions cannot end with an operator"));
+    assert!(!output.contains("\x1b["), "plain output should not contain ANSI escapes");
+}
+
+#[test]
+fn test_render_colored_output() {
+    let source = "main = () i32 { y }";
+    let diagnostic = Diagnostic::error("Undeclared variable: 'y'")
+        .with_label(Span { start: 16, end: 17, line: 1, column: 17, utf16_column: 17 }, "not found in this scope");
+
+    let renderer = DiagnosticRenderer::new("main.zen", source, ColorChoice::Always);
+    assert!(renderer.render(&diagnostic).contains("\x1b["));
+}
+
+#[test]
+fn test_render_without_span() {
+    let diagnostic = Diagnostic::from(&CompileError::TypeError("Unknown struct type: Foo".to_string(), None));
+    let renderer = DiagnosticRenderer::new("main.zen", "", ColorChoice::Never);
+    let output = renderer.render(&diagnostic);
+
+    assert!(output.contains("Type error: Unknown struct type: Foo"));
+    assert!(output.contains("main.zen"));
+}
+
+#[test]
+fn test_diagnostic_from_syntax_error_keeps_span() {
+    let source = "main = () i32 { 1 + }";
+    let lexer = Lexer::new(source);
+    let mut parser = Parser::new(lexer);
+    let error = parser.parse_program().unwrap_err();
+
+    let diagnostic = Diagnostic::from(&error);
+    assert_eq!(diagnostic.severity, Severity::Error);
+    assert!(diagnostic.primary_span().is_some());
+}
+
+#[test]
+fn test_render_all_summarizes_error_count() {
+    let renderer = DiagnosticRenderer::new("main.zen", "", ColorChoice::Never);
+    let diagnostics = vec![Diagnostic::error("first"), Diagnostic::error("second")];
+    let output = renderer.render_all(&diagnostics);
+
+    assert!(output.contains("first"));
+    assert!(output.contains("second"));
+    assert!(output.contains("could not compile due to 2 errors"));
+}
+
+#[test]
+fn test_type_checker_collects_multiple_errors() {
+    let program = parse(
+        "first = () i32 { missing_a }\n\
+         second = () i32 { missing_b }\n\
+         third = () i32 { 1 }",
+    );
+
+    let errors = TypeChecker::new().check_program_collecting(&program);
+    assert_eq!(errors.len(), 2);
+    assert!(errors[0].to_string().contains("missing_a"));
+    assert!(errors[1].to_string().contains("missing_b"));
+}
+
+#[test]
+fn test_error_codes_are_stable() {
+    assert_eq!(CompileError::SyntaxError(String::new(), None).code(), "E0001");
+    assert_eq!(CompileError::UndeclaredVariable(String::new(), None).code(), "E0100");
+    assert_eq!(CompileError::TypeError(String::new(), None).code(), "E0201");
+    assert_eq!(CompileError::InternalError(String::new(), None).code(), "E9999");
+}
+
+#[test]
+fn test_json_diagnostic_format() {
+    let error = CompileError::UndeclaredVariable(
+        "y".to_string(),
+        Some(Span { start: 16, end: 17, line: 1, column: 17, utf16_column: 17 }),
+    );
+    let diagnostic = Diagnostic::from(&error);
+    let json = FileDiagnostic::new("main.zen", &diagnostic).to_json();
+    assert!(!json.contains('\n'), "each diagnostic must be a single line");
+
+    let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(value["file"], "main.zen");
+    assert_eq!(value["severity"], "error");
+    assert_eq!(value["code"], "E0100");
+    assert_eq!(value["message"], "Undeclared variable: 'y'");
+    assert_eq!(value["labels"][0]["span"]["start"], 16);
+    assert_eq!(value["labels"][0]["span"]["end"], 17);
+    assert_eq!(value["labels"][0]["span"]["line"], 1);
+    assert_eq!(value["labels"][0]["span"]["column"], 17);
+}
+
+#[test]
+fn test_diagnostic_round_trips_through_json() {
+    let diagnostic = Diagnostic::error("mismatched types")
+        .with_code("E0200")
+        .with_label(Span { start: 4, end: 9, line: 1, column: 5, utf16_column: 5 }, "expected i32")
+        .with_label(Span { start: 0, end: 1, line: 1, column: 1, utf16_column: 1 }, "declared here")
+        .with_help("add a cast");
+
+    let json = serde_json::to_string(&diagnostic).unwrap();
+    let decoded: Diagnostic = serde_json::from_str(&json).unwrap();
+    assert_eq!(decoded, diagnostic);
+}
+
+#[test]
+fn test_lsp_diagnostic_conversion() {
+    let diagnostic = Diagnostic::error("Undeclared variable: 'y'")
+        .with_code("E0100")
+        .with_label(Span { start: 20, end: 21, line: 2, column: 5, utf16_column: 5 }, "not found in this scope");
+
+    let lsp = zen::lsp::to_lsp_diagnostic(None, &diagnostic);
+    assert_eq!(lsp.range.start.line, 1);
+    assert_eq!(lsp.range.start.character, 4);
+    assert_eq!(lsp.range.end.character, 5);
+    assert_eq!(lsp.message, "Undeclared variable: 'y'");
+    assert_eq!(lsp.source.as_deref(), Some("zen"));
+}
+
+#[test]
+fn test_lsp_positions_count_utf16_units() {
+    let mut lexer = Lexer::new("x := \"🦀\" + y");
+    let span = std::iter::from_fn(|| Some(lexer.next_token_with_span()))
+        .find(|token| token.token == zen::lexer::Token::Identifier("y".to_string()))
+        .unwrap()
+        .span;
+    assert_eq!((span.start, span.column, span.utf16_column), (14, 12, 13));
+
+    let diagnostic = Diagnostic::error("Undeclared variable: 'y'").with_label(span, "not found in this scope");
+    let lsp = zen::lsp::to_lsp_diagnostic(None, &diagnostic);
+    assert_eq!(lsp.range.start.character, 12);
+    assert_eq!(lsp.range.end.character, 13);
+}
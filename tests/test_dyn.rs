@@ -0,0 +1,127 @@
+use inkwell::context::Context;
+use inkwell::OptimizationLevel;
+use zen::ast::{AstType, Declaration, Program};
+use zen::compiler::Compiler;
+use zen::error::CompileError;
+use zen::lexer::Lexer;
+use zen::parser::Parser;
+use zen::spans::SpanTable;
+use zen::typechecker::TypeChecker;
+
+fn parse(source: &str) -> (Program, SpanTable) {
+    let mut parser = Parser::new(Lexer::new(source));
+    let program = parser.parse_program().unwrap();
+    (program, parser.take_spans())
+}
+
+fn errors(source: &str) -> Vec<CompileError> {
+    let (program, spans) = parse(source);
+    TypeChecker::new().with_spans(spans).check_program_collecting(&program)
+}
+
+fn first_error(source: &str) -> CompileError {
+    let errors = errors(source);
+    assert!(!errors.is_empty(), "expected an error");
+    errors[0].clone()
+}
+
+fn run_source(source: &str) -> i32 {
+    let (program, _) = parse(source);
+    let context = Context::create();
+    let compiler = Compiler::new(&context);
+    let module = compiler.get_module(&program).unwrap();
+    zen::jit::run_main(&module, &["prog".to_string()], OptimizationLevel::None).unwrap()
+}
+
+// The impls list their methods in a different order than the behavior, so
+// the vtable slots must follow the behavior
+const SHAPES: &str = "Shape = behavior {\n    area = (self) i32,\n    sides = (self) i32\n}\n\n\
+Square = {\n    side: i32\n}\n\n\
+Rect = {\n    width: i32,\n    height: i32\n}\n\n\
+Square.impl = {\n    Shape: {\n        sides = (self: *Square) i32 {\n            return 4\n        },\n        area = (self: *Square) i32 {\n            return self.side * self.side\n        }\n    }\n}\n\n\
+Rect.impl = {\n    Shape: {\n        area = (self: *Rect) i32 {\n            return self.width * self.height\n        },\n        sides = (self: *Rect) i32 {\n            return 40\n        }\n    }\n}\n\n\
+describe = (shape: dyn Shape) i32 {\n    return shape.area() * 100 + shape.sides()\n}\n\n";
+
+#[test]
+fn test_parse_dyn_type() {
+    let (program, _) = parse("describe = (shape: dyn Shape) i32 {\n    return 0\n}\n");
+    let Declaration::Function(function) = &program.declarations[0] else {
+        panic!("expected a function");
+    };
+    assert_eq!(function.args[0].1, AstType::Dyn("Shape".to_string()));
+    assert_eq!(function.args[0].1.to_string(), "dyn Shape");
+}
+
+#[test]
+fn test_pointers_and_variables_convert_to_dyn() {
+    let source = format!(
+        "{}main = () i32 {{\n    s := Square {{ side: 3 }}\n    r := Rect {{ width: 2, height: 5 }}\n    shapes: [dyn Shape] = [s, r]\n    first: dyn Shape = shapes[0]\n    return describe(s) + describe(first)\n}}\n",
+        SHAPES
+    );
+    assert!(errors(&source).is_empty(), "{:?}", errors(&source));
+}
+
+#[test]
+fn test_dyn_requires_an_impl() {
+    let source = format!("{}Circle = {{\n    radius: i32\n}}\n\nmain = () i32 {{\n    c := Circle {{ radius: 2 }}\n    return describe(c)\n}}\n", SHAPES);
+    let message = first_error(&source).to_string();
+    assert!(
+        message.contains("Type 'Circle' does not implement behavior 'Shape', so it can't be used as a dyn Shape"),
+        "{}",
+        message
+    );
+}
+
+#[test]
+fn test_temporaries_do_not_convert_to_dyn() {
+    let source = format!("{}main = () i32 {{\n    return describe(Square {{ side: 2 }})\n}}\n", SHAPES);
+    let message = first_error(&source).to_string();
+    assert!(message.contains("bind it to a variable first"), "{}", message);
+}
+
+#[test]
+fn test_behaviors_must_be_object_safe() {
+    let source = "Ord = behavior {\n    cmp = (self, other: Self) i32\n}\n\nsmallest = (a: dyn Ord) i32 {\n    return 0\n}\n\n\
+i32.impl = {\n    Ord: {\n        cmp = (self: *i32, other: i32) i32 {\n            return 0\n        }\n    }\n}\n\n\
+main = () i32 {\n    n := 1\n    return smallest(n)\n}\n";
+    let message = first_error(source).to_string();
+    assert!(
+        message.contains("'cmp' of behavior 'Ord' uses Self outside its receiver, so it can't be called through a dyn Ord"),
+        "{}",
+        message
+    );
+
+    let source = "Show = behavior {\n    show = (self: Self) i32\n}\n\nshown = (a: dyn Show) i32 {\n    return a.show()\n}\n";
+    let message = first_error(source).to_string();
+    assert!(message.contains("'show' of behavior 'Show' doesn't take self by pointer"), "{}", message);
+}
+
+#[test]
+fn test_dyn_method_calls_check_the_behavior() {
+    let source = format!("{}perimeter = (shape: dyn Shape) i32 {{\n    return shape.perimeter()\n}}\n", SHAPES);
+    let message = first_error(&source).to_string();
+    assert!(message.contains("No method named 'perimeter' for dyn Shape"), "{}", message);
+
+    let source = format!("{}twice = (shape: dyn Shape) i32 {{\n    return shape.area(2)\n}}\n", SHAPES);
+    let message = first_error(&source).to_string();
+    assert!(message.contains("'area' takes 0 argument(s) but 1 were given"), "{}", message);
+}
+
+#[test]
+fn test_run_dyn_calls_through_vtable() {
+    let source = format!(
+        "{}main = () i32 {{\n    s := Square {{ side: 3 }}\n    r := Rect {{ width: 2, height: 5 }}\n    return describe(s) - describe(r)\n}}\n",
+        SHAPES
+    );
+    // (900 + 4) - (1000 + 40)
+    assert_eq!(run_source(&source), -136);
+}
+
+#[test]
+fn test_run_heterogeneous_list() {
+    let source = format!(
+        "{}main = () i32 {{\n    s := Square {{ side: 3 }}\n    r := Rect {{ width: 2, height: 5 }}\n    shapes: [dyn Shape] = [s, r, s]\n    total ::= 0\n    i ::= 0\n    loop i < 3 {{\n        total = total + shapes[i].area()\n        i = i + 1\n    }}\n    return total\n}}\n",
+        SHAPES
+    );
+    assert_eq!(run_source(&source), 28);
+}
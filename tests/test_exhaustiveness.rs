@@ -0,0 +1,104 @@
+use zen::error::CompileError;
+use zen::lexer::Lexer;
+use zen::parser::Parser;
+use zen::typechecker::TypeChecker;
+
+fn check(source: &str) -> (Vec<CompileError>, Vec<String>) {
+    let mut parser = Parser::new(Lexer::new(source));
+    let program = parser.parse_program().unwrap();
+    let mut checker = TypeChecker::new().with_spans(parser.take_spans());
+    let errors = checker.check_program_collecting(&program);
+    let warnings = checker.warnings().iter().map(|w| w.message.clone()).collect();
+    (errors, warnings)
+}
+
+fn missing(source: &str) -> Vec<String> {
+    let (errors, _) = check(source);
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    match &errors[0] {
+        CompileError::NonExhaustiveMatch { missing, .. } => missing.clone(),
+        other => panic!("expected a non-exhaustive match error, got {:?}", other),
+    }
+}
+
+fn warnings(source: &str) -> Vec<String> {
+    let (errors, warnings) = check(source);
+    assert!(errors.is_empty(), "unexpected errors: {:?}", errors);
+    warnings
+}
+
+#[test]
+fn test_option_missing_none() {
+    let source = "main = (value: Option<i32>) i32 {\n    return value ? | .Some(x) => x\n}\n";
+    assert_eq!(missing(source), vec![".None"]);
+}
+
+#[test]
+fn test_bool_missing_false() {
+    let source = "main = (flag: bool) i32 {\n    return flag ? | true => 1\n}\n";
+    assert_eq!(missing(source), vec!["false"]);
+}
+
+#[test]
+fn test_enum_lists_every_missing_variant() {
+    let source = "Color = | Red | Green | Blue\n\n\
+        main = (color: Color) i32 {\n    return color ? | .Green => 1\n}\n";
+    assert_eq!(missing(source), vec![".Red", ".Blue"]);
+}
+
+#[test]
+fn test_result_missing_err() {
+    let source = "main = (loaded: Result<i32, string>) i32 {\n    return loaded ? | .Ok(n) => n\n}\n";
+    assert_eq!(missing(source), vec![".Err(_)"]);
+}
+
+#[test]
+fn test_guarded_arms_do_not_count_towards_coverage() {
+    let source = "main = (value: Option<i32>) i32 {\n    \
+        return value ? | .Some(x) -> x > 0 => x | .None => 0\n}\n";
+    assert_eq!(missing(source), vec![".Some(_)"]);
+}
+
+#[test]
+fn test_exhaustive_matches_pass() {
+    let source = "Color = | Red | Green | Blue\n\n\
+        main = (color: Color, flag: bool, n: i32) i32 {\n    \
+        a := color ? | .Red | .Green => 1 | .Blue => 2\n    \
+        b := flag ? | true => 1 | false => 0\n    \
+        c := n ? | 0 => 0 | _ => 1\n    \
+        return a + b + c\n}\n";
+    assert!(warnings(source).is_empty());
+}
+
+#[test]
+fn test_arm_after_wildcard_is_unreachable() {
+    let source = "main = (n: i32) i32 {\n    return n ? | _ => 0 | 1 => 1\n}\n";
+    assert_eq!(warnings(source), vec!["Unreachable pattern: arm 2 (`1`) is already covered by earlier arms"]);
+}
+
+#[test]
+fn test_shadowed_or_alternative_is_unreachable() {
+    let source = "main = (flag: bool) i32 {\n    return flag ? | true => 1 | false | true => 0\n}\n";
+    assert_eq!(warnings(source), vec!["Unreachable pattern: `true` in arm 2 is already covered"]);
+}
+
+#[test]
+fn test_value_inside_earlier_range_is_unreachable() {
+    let source = "main = (n: i32) i32 {\n    return n ? | 1..=10 => 1 | 5 => 2 | _ => 0\n}\n";
+    assert_eq!(warnings(source), vec!["Unreachable pattern: arm 2 (`5`) is already covered by earlier arms"]);
+}
+
+#[test]
+fn test_guarded_wildcard_does_not_shadow_later_arms() {
+    let source = "main = (n: i32) i32 {\n    return n ? | _ -> n > 3 => 1 | 2 => 2 | _ => 0\n}\n";
+    assert!(warnings(source).is_empty());
+}
+
+#[test]
+fn test_error_code_and_message() {
+    let source = "main = (value: Option<i32>) i32 {\n    return value ? | .None => 0\n}\n";
+    let (errors, _) = check(source);
+    assert_eq!(errors[0].code(), "E0204");
+    assert_eq!(errors[0].message(), "Non-exhaustive match: `.Some(_)` not covered");
+    assert_eq!(errors[0].span().unwrap().line, 2);
+}
@@ -0,0 +1,120 @@
+use zen::error::CompileError;
+use zen::formatter::format_source;
+use zen::lexer::Lexer;
+use zen::parser::Parser;
+
+fn format(source: &str) -> String {
+    let formatted = format_source(source).unwrap();
+    assert_eq!(format_source(&formatted).unwrap(), formatted, "formatting is not idempotent");
+    let parse = |source: &str| Parser::new(Lexer::new(source)).parse_program().unwrap();
+    assert_eq!(parse(&formatted), parse(source), "formatting changed the AST");
+    formatted
+}
+
+#[test]
+fn test_formats_messy_source() {
+    let source = "// header\n\n\nPoint={x:f64,y :f64 ::}\n/// Adds.\nadd=(a:i32,b:i32)i32{\n  return a+b   // sum\n}\nmain = ( ) i32 {\n        count::=0\n    p := Point{ x:1.0,y:2.0 }\n  count = count+add( 1 ,2 )\n    v := count>2?|true=>1\n              |false=>{ 0 }\n   loop count < 10 {\n       count = count + 1 /* inc */\n   }\n\n\n    return -count\n}";
+    let expected = "// header\n\nPoint = { x: f64, y: f64 :: }\n/// Adds.\nadd = (a: i32, b: i32) i32 {\n    return a + b // sum\n}\nmain = () i32 {\n    count ::= 0\n    p := Point { x: 1.0, y: 2.0 }\n    count = count + add(1, 2)\n    v := count > 2 ? | true => 1\n        | false => { 0 }\n    loop count < 10 {\n        count = count + 1 /* inc */\n    }\n\n    return -count\n}\n";
+    assert_eq!(format(source), expected);
+}
+
+#[test]
+fn test_formatted_source_is_unchanged() {
+    let source = "Vec<T> = {\n    data: *T,\n    len: i64 ::,\n}\n\nvec_new<T> = () Vec<T> {\n    return Vec<T> {\n        data: 0,\n        len: 0,\n    }\n}\n";
+    assert_eq!(format(source), source);
+
+    let source = "max<T: Ord + Display> = (a: T, b: T) T {\n    return a\n}\n";
+    assert_eq!(format(source), source);
+    assert_eq!(format("max<T:Ord+Display> =(a:T,b:T)T{\n  return a\n}"), source);
+}
+
+#[test]
+fn test_keeps_comments() {
+    let source = "//! Module docs\n\n// Leading\nmain = () i32 {\n    /* block */ x := 1 // trailing\n    // before return\n    return x\n    // at the end of the block\n}\n// at the end of the file\n";
+    assert_eq!(format(source), source);
+}
+
+#[test]
+fn test_blank_lines_collapse() {
+    let source = "a = () i32 { 1 }\n\n\n\nb = () i32 { 2 }\nmain = () i32 {\n\n    return a()\n\n}\n\n\n";
+    assert_eq!(format(source), "a = () i32 { 1 }\n\nb = () i32 { 2 }\nmain = () i32 {\n    return a()\n}\n");
+}
+
+#[test]
+fn test_generics_and_comparisons() {
+    let source = "main = () i32 {\n    v := vec_new < i32 > ()\n    w := vec_new<i32>()\n    big := a>b\n    small := a<b\n    return 0\n}\n";
+    let formatted = format(source);
+    assert!(formatted.contains("v := vec_new<i32>()"), "{}", formatted);
+    assert!(formatted.contains("w := vec_new<i32>()"), "{}", formatted);
+    assert!(formatted.contains("big := a > b"), "{}", formatted);
+    assert!(formatted.contains("small := a < b"), "{}", formatted);
+}
+
+#[test]
+fn test_ambiguous_tokens_follow_the_parse() {
+    let source = "first = (p: *i32) *i32 {\n    return p\n}\nmain = (a: i32) i32 {\n    b := (a) * 2\n    c := (a) & 1\n    d := a>>1\n    e := Option :: None\n    return b\n}\n";
+    let formatted = format(source);
+    assert!(formatted.contains("first = (p: *i32) *i32 {"), "{}", formatted);
+    assert!(formatted.contains("b := (a) * 2"), "{}", formatted);
+    assert!(formatted.contains("c := (a) & 1"), "{}", formatted);
+    assert!(formatted.contains("d := a >> 1"), "{}", formatted);
+    assert!(formatted.contains("e := Option::None"), "{}", formatted);
+}
+
+#[test]
+fn test_prefix_and_range_operators() {
+    let source = "main = (p: * i32) i32 {\n    x := - 1\n    y := x-1\n    range(0 .. 10)\n    return y\n}\n";
+    assert_eq!(
+        format(source),
+        "main = (p: *i32) i32 {\n    x := -1\n    y := x - 1\n    range(0..10)\n    return y\n}\n"
+    );
+}
+
+#[test]
+fn test_empty_source() {
+    assert_eq!(format(""), "");
+    assert_eq!(format("\n\n  \n"), "");
+}
+
+#[test]
+fn test_rejects_source_that_does_not_parse() {
+    assert!(format_source("main = () i32 {\n    return\n").is_err());
+}
+
+#[test]
+fn test_rejects_characters_the_lexer_skips() {
+    match format_source("main = () i32 {\n    return 7 # 2\n}\n") {
+        Err(CompileError::SyntaxError(message, Some(span))) => {
+            assert_eq!(message, "Unexpected character '#'");
+            assert_eq!((span.line, span.column), (2, 14));
+        }
+        other => panic!("expected a syntax error, got {:?}", other),
+    }
+}
+
+fn zen_files(dir: &std::path::Path, files: &mut Vec<std::path::PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else { return };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() && !path.ends_with("target") && !path.ends_with(".git") {
+            zen_files(&path, files);
+        } else if path.extension().is_some_and(|ext| ext == "zen") {
+            files.push(path);
+        }
+    }
+}
+
+#[test]
+fn test_formats_repo_sources() {
+    let mut files = Vec::new();
+    zen_files(std::path::Path::new(env!("CARGO_MANIFEST_DIR")), &mut files);
+    let mut formatted = 0;
+    for file in files {
+        let source = std::fs::read_to_string(&file).unwrap();
+        if Parser::new(Lexer::new(&source)).parse_program().is_ok() {
+            format(&source);
+            formatted += 1;
+        }
+    }
+    assert!(formatted > 0);
+}
@@ -0,0 +1,197 @@
+use inkwell::context::Context;
+use inkwell::OptimizationLevel;
+use zen::ast::{AstType, Declaration, Expression, Program, Statement};
+use zen::compiler::Compiler;
+use zen::error::CompileError;
+use zen::lexer::Lexer;
+use zen::mangle::demangle;
+use zen::parser::Parser;
+use zen::spans::SpanTable;
+use zen::type_system::Monomorphizer;
+use zen::typechecker::TypeChecker;
+
+fn parse(source: &str) -> (Program, SpanTable) {
+    let mut parser = Parser::new(Lexer::new(source));
+    let program = parser.parse_program().unwrap();
+    (program, parser.take_spans())
+}
+
+fn errors(source: &str) -> Vec<CompileError> {
+    let (program, spans) = parse(source);
+    TypeChecker::new().with_spans(spans).check_program_collecting(&program)
+}
+
+fn first_error(source: &str) -> CompileError {
+    let errors = errors(source);
+    assert!(!errors.is_empty(), "expected an error");
+    errors[0].clone()
+}
+
+fn run_source(source: &str) -> i32 {
+    let (program, _) = parse(source);
+    let context = Context::create();
+    let compiler = Compiler::new(&context);
+    let module = compiler.get_module(&program).unwrap();
+    zen::jit::run_main(&module, &["prog".to_string()], OptimizationLevel::None).unwrap()
+}
+
+const BEHAVIORS: &str = "Show = behavior {\n    show = (self) i32\n}\n\n\
+Printable = behavior {\n    print = (self) i32\n}\n\n\
+i32.impl = {\n    Show: {\n        show = (self: i32) i32 {\n            return self\n        }\n    }\n}\n\n";
+
+// Everything that can be shown can be printed
+const BLANKET: &str = "T.impl<T: Show> = {\n    Printable: {\n        print = (self: T) i32 {\n            return self.show() + 1\n        }\n    }\n}\n\n";
+
+const BOX: &str = "Box<T> = {\n    value: T\n}\n\n\
+Box<T>.impl<T> = {\n    get = (self: Box<T>) T {\n        return self.value\n    }\n}\n\n";
+
+#[test]
+fn test_parse_generic_impl_blocks() {
+    let (program, _) = parse(&format!("{}{}{}", BEHAVIORS, BLANKET, BOX));
+    let Declaration::Impl(blanket) = &program.declarations[3] else {
+        panic!("expected an impl block");
+    };
+    assert_eq!(blanket.type_name, "T");
+    assert!(blanket.is_generic());
+    assert!(blanket.type_args.is_empty());
+    assert_eq!(blanket.type_params[0].constraints, vec!["Show".to_string()]);
+
+    let Declaration::Impl(block) = &program.declarations[5] else {
+        panic!("expected an impl block");
+    };
+    assert_eq!(block.type_name, "Box");
+    assert_eq!(block.type_args, vec![AstType::Generic { name: "T".to_string(), type_args: vec![] }]);
+    assert!(block.behavior_name.is_none());
+}
+
+#[test]
+fn test_blanket_methods_need_the_bound() {
+    let source = format!("{}{}main = () i32 {{\n    x := 41\n    return x.print()\n}}\n", BEHAVIORS, BLANKET);
+    assert!(errors(&source).is_empty(), "{:?}", errors(&source));
+
+    let source = format!("{}{}main = () i32 {{\n    x := true\n    return x.print()\n}}\n", BEHAVIORS, BLANKET);
+    let message = first_error(&source).to_string();
+    assert!(message.contains("No method named"), "{}", message);
+}
+
+#[test]
+fn test_generic_impl_methods_type_check() {
+    let source = format!(
+        "{}read = (b: Box<i32>) i32 {{\n    return b.get()\n}}\n",
+        BOX
+    );
+    assert!(errors(&source).is_empty(), "{:?}", errors(&source));
+
+    let source = format!(
+        "{}read = (b: i32) i32 {{\n    return b.get()\n}}\n",
+        BOX
+    );
+    let message = first_error(&source).to_string();
+    assert!(message.contains("No method named 'get'"), "{}", message);
+}
+
+#[test]
+fn test_blanket_and_concrete_impls_conflict() {
+    // i32 is Show, so the blanket impl already covers it
+    let source = format!(
+        "{}{}i32.impl = {{\n    Printable: {{\n        print = (self: i32) i32 {{\n            return 0\n        }}\n    }}\n}}\n",
+        BEHAVIORS, BLANKET
+    );
+    let message = first_error(&source).to_string();
+    assert!(message.contains("Conflicting implementations of behavior 'Printable'"), "{}", message);
+
+    // bool is not Show, so it may implement Printable itself
+    let source = format!(
+        "{}{}bool.impl = {{\n    Printable: {{\n        print = (self: bool) i32 {{\n            return 0\n        }}\n    }}\n}}\n",
+        BEHAVIORS, BLANKET
+    );
+    assert!(errors(&source).is_empty(), "{:?}", errors(&source));
+}
+
+#[test]
+fn test_overlapping_templates_conflict() {
+    let source = format!(
+        "{}{}U.impl<U: Printable> = {{\n    Printable: {{\n        print = (self: U) i32 {{\n            return 0\n        }}\n    }}\n}}\n",
+        BEHAVIORS, BLANKET
+    );
+    let message = first_error(&source).to_string();
+    assert!(message.contains("a type could match both"), "{}", message);
+
+    let source = format!(
+        "{}Box<T>.impl<T> = {{\n    get = (self: Box<T>) T {{\n        return self.value\n    }}\n}}\n",
+        BOX
+    );
+    let message = first_error(&source).to_string();
+    assert!(message.contains("'get'"), "{}", message);
+}
+
+#[test]
+fn test_impl_conflicts_point_at_the_generic_block() {
+    let line = |source: String| first_error(&source).span().expect("conflict without a span").line;
+
+    // The blanket impl starts on line 17, the overlapping one on line 25
+    let conflict = "i32.impl = {\n    Printable: {\n        print = (self: i32) i32 {\n            return 0\n        }\n    }\n}\n";
+    assert_eq!(line(format!("{}{}{}", BEHAVIORS, BLANKET, conflict)), 17);
+    let overlap = "U.impl<U: Printable> = {\n    Printable: {\n        print = (self: U) i32 {\n            return 0\n        }\n    }\n}\n";
+    assert_eq!(line(format!("{}{}{}", BEHAVIORS, BLANKET, overlap)), 25);
+
+    // An inherent `Box.impl` after the generic one is a separate block
+    let inherent = "Box.impl = {\n    get = (self: Box<i32>) i32 {\n        return 0\n    }\n}\n";
+    assert_eq!(line(format!("{}{}", BOX, inherent)), 5);
+}
+
+#[test]
+fn test_invalid_generic_impl_targets() {
+    // Target arguments must be the block's own parameters
+    let source = format!(
+        "{}Box<i32>.impl<T> = {{\n    other = (self: Box<i32>) i32 {{\n        return 0\n    }}\n}}\n",
+        BOX
+    );
+    assert!(!errors(&source).is_empty());
+
+    // A blanket impl only makes sense for a behavior
+    let source = "T.impl<T> = {\n    get = (self: T) i32 {\n        return 0\n    }\n}\n";
+    assert!(!errors(source).is_empty());
+}
+
+#[test]
+fn test_blanket_methods_are_instantiated_per_type() {
+    let source = format!("{}{}main = () i32 {{\n    x := 41\n    return x.print()\n}}\n", BEHAVIORS, BLANKET);
+    let (program, spans) = parse(&source);
+    let mut checker = TypeChecker::new().with_spans(spans.clone());
+    assert!(checker.check_program_collecting(&program).is_empty());
+
+    let program = Monomorphizer::new()
+        .with_spans(spans)
+        .with_type_table(checker.into_type_table())
+        .monomorphize_program(&program)
+        .unwrap();
+    let main = program.functions().find(|function| function.name == "main").unwrap();
+    let Statement::Return(Expression::FunctionCall { name, args }) = &main.body[1] else {
+        panic!("expected the method call to be resolved, found {:?}", main.body[1]);
+    };
+    assert_eq!(demangle(name).unwrap().to_string(), "T.Printable.print<i32>");
+    assert_eq!(args.len(), 1);
+
+    let instance = program
+        .functions()
+        .find(|function| function.name == *name)
+        .expect("print is instantiated for i32");
+    let Statement::Return(Expression::BinaryOp { left, .. }) = &instance.body[0] else {
+        panic!("expected the addition");
+    };
+    let Expression::FunctionCall { name, .. } = left.as_ref() else {
+        panic!("expected the bound method call to be resolved, found {:?}", left);
+    };
+    assert_eq!(demangle(name).unwrap().to_string(), "i32.Show.show");
+}
+
+#[test]
+fn test_run_blanket_impl() {
+    let source = format!(
+        "{}{}main = () i32 {{\n    x := 41\n    return x.print() + x.show()\n}}\n",
+        BEHAVIORS, BLANKET
+    );
+    // 42 + 41
+    assert_eq!(run_source(&source), 83);
+}
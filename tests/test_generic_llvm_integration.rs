@@ -1,6 +1,8 @@
 use zen::lexer::Lexer;
 use zen::parser::Parser;
 use zen::compiler::Compiler;
+use zen::ast::AstType;
+use zen::mangle;
 use inkwell::context::Context;
 
 #[test]
@@ -24,12 +26,12 @@ fn test_generic_function_monomorphization_and_llvm() {
     let context = Context::create();
     let compiler = Compiler::new(&context);
     
-    // This should monomorphize identity into identity_i32 and identity_f64
+    // This should monomorphize identity into identity<i32> and identity<f64>
     // then compile to LLVM IR
     let llvm_ir = compiler.compile_llvm(&program).expect("Failed to compile to LLVM");
     
     // Check that the LLVM IR contains monomorphized versions
-    assert!(llvm_ir.contains("identity_i32") || llvm_ir.contains("define i32 @identity"));
+    assert!(llvm_ir.contains(&mangle::instantiate("identity", &[AstType::I32])) || llvm_ir.contains("define i32 @identity"));
     assert!(!llvm_ir.contains("identity<T>"));
 }
 
@@ -54,11 +56,11 @@ fn test_generic_struct_monomorphization_and_llvm() {
     let context = Context::create();
     let compiler = Compiler::new(&context);
     
-    // This should monomorphize Box into Box_i32 and Box_f64
+    // This should monomorphize Box into Box<i32> and Box<f64>
     let llvm_ir = compiler.compile_llvm(&program).expect("Failed to compile to LLVM");
     
     // Check that the LLVM IR contains monomorphized struct types
-    assert!(llvm_ir.contains("Box_i32") || llvm_ir.contains("%Box"));
+    assert!(llvm_ir.contains(&mangle::instantiate("Box", &[AstType::I32])) || llvm_ir.contains("%Box"));
     assert!(!llvm_ir.contains("Box<T>"));
 }
 
@@ -86,5 +88,5 @@ fn test_nested_generic_monomorphization() {
     let llvm_ir = compiler.compile_llvm(&program).expect("Failed to compile to LLVM");
     
     // Check that monomorphization happened
-    assert!(llvm_ir.contains("pair_i32_f64") || llvm_ir.contains("define i32 @pair"));
+    assert!(llvm_ir.contains(&mangle::instantiate("pair", &[AstType::I32, AstType::F64])) || llvm_ir.contains("define i32 @pair"));
 }
\ No newline at end of file
@@ -1,4 +1,5 @@
 use zen::ast::{AstType, TypeParameter, Function, StructDefinition, StructField, EnumDefinition, EnumVariant, Statement, Expression};
+use zen::mangle::demangle;
 use zen::type_system::{TypeEnvironment, TypeInstantiator, TypeSubstitution, Monomorphizer};
 
 #[test]
@@ -49,7 +50,7 @@ fn test_generic_function_instantiation() {
     let mut instantiator = TypeInstantiator::new(&mut env);
     let instantiated = instantiator.instantiate_function(&generic_func, vec![AstType::I32]).unwrap();
     
-    assert_eq!(instantiated.name, "identity_i32");
+    assert_eq!(demangle(&instantiated.name).unwrap().to_string(), "identity<i32>");
     assert_eq!(instantiated.type_params.len(), 0);
     assert_eq!(instantiated.args[0].1, AstType::I32);
     assert_eq!(instantiated.return_type, AstType::I32);
@@ -82,7 +83,7 @@ fn test_generic_struct_instantiation() {
     let mut instantiator = TypeInstantiator::new(&mut env);
     let instantiated = instantiator.instantiate_struct(&generic_struct, vec![AstType::String]).unwrap();
     
-    assert_eq!(instantiated.name, "Box_string");
+    assert_eq!(demangle(&instantiated.name).unwrap().to_string(), "Box<string>");
     assert_eq!(instantiated.type_params.len(), 0);
     assert_eq!(instantiated.fields[0].type_, AstType::String);
 }
@@ -150,7 +151,7 @@ fn test_multiple_type_parameters() {
         vec![AstType::I32, AstType::String]
     ).unwrap();
     
-    assert_eq!(instantiated.name, "pair_i32_string");
+    assert_eq!(demangle(&instantiated.name).unwrap().to_string(), "pair<i32, string>");
     assert_eq!(instantiated.args[0].1, AstType::I32);
     assert_eq!(instantiated.args[1].1, AstType::String);
 }
@@ -198,7 +199,7 @@ fn test_generic_enum_instantiation() {
         vec![AstType::I32, AstType::String]
     ).unwrap();
     
-    assert_eq!(instantiated.name, "Result_i32_string");
+    assert_eq!(demangle(&instantiated.name).unwrap().to_string(), "Result<i32, string>");
     assert_eq!(instantiated.variants[0].payload, Some(AstType::I32));
     assert_eq!(instantiated.variants[1].payload, Some(AstType::String));
 }
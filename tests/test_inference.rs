@@ -0,0 +1,197 @@
+use zen::ast::{AstType, Declaration, Expression, Program, Statement};
+use zen::error::CompileError;
+use zen::lexer::Lexer;
+use zen::mangle;
+use zen::parser::Parser;
+use zen::spans::{NodeId, SpanTable};
+use zen::type_system::Monomorphizer;
+use zen::typechecker::unify::Unifier;
+use zen::typechecker::{TypeChecker, TypeTable};
+
+fn parse(source: &str) -> (Program, SpanTable) {
+    let mut parser = Parser::new(Lexer::new(source));
+    let program = parser.parse_program().unwrap();
+    (program, parser.take_spans())
+}
+
+fn infer(source: &str) -> TypeTable {
+    let (program, spans) = parse(source);
+    let mut checker = TypeChecker::new().with_spans(spans);
+    let errors = checker.check_program_collecting(&program);
+    assert!(errors.is_empty(), "unexpected errors: {:?}", errors);
+    checker.into_type_table()
+}
+
+fn errors(source: &str) -> Vec<CompileError> {
+    let (program, spans) = parse(source);
+    TypeChecker::new().with_spans(spans).check_program_collecting(&program)
+}
+
+fn binding(types: &TypeTable, path: Vec<usize>) -> String {
+    types.binding(&NodeId::new("main", path)).unwrap().to_string()
+}
+
+const MAKE: &str = "make<T> = () Option<T> {\n    return None\n}\n\n";
+
+#[test]
+fn test_unifier_solves_variables_through_annotations() {
+    let mut unifier = Unifier::new();
+    let payload = unifier.fresh();
+    let option = AstType::Option(Box::new(payload.clone()));
+    let annotation = AstType::Generic { name: "Option".to_string(), type_args: vec![AstType::I64] };
+
+    assert!(unifier.is_partial(&option));
+    unifier.unify(&annotation, &option).unwrap();
+    assert_eq!(unifier.resolve(&payload), AstType::I64);
+    assert!(unifier.unify(&AstType::I64, &AstType::String).is_err());
+}
+
+#[test]
+fn test_generic_arguments_inferred_from_call_arguments() {
+    let source = "identity<T> = (value: T) T {\n    return value\n}\n\n\
+        main = () i32 {\n    x := identity(42)\n    return x\n}\n";
+    let types = infer(source);
+
+    assert_eq!(binding(&types, vec![0]), "i32");
+    assert_eq!(types.type_arguments(&NodeId::new("main", vec![0, 0])), Some(&[AstType::I32][..]));
+}
+
+#[test]
+fn test_generic_arguments_inferred_from_return_context() {
+    let source = format!("{}main = () i32 {{\n    value: Option<i64> = make()\n    return 0\n}}\n", MAKE);
+    let types = infer(&source);
+
+    assert_eq!(types.type_arguments(&NodeId::new("main", vec![0, 0])), Some(&[AstType::I64][..]));
+}
+
+#[test]
+fn test_identical_generic_calls_are_inferred_separately() {
+    let source = format!("{}main = () i32 {{\n    pair: (Option<i32>, Option<i64>) = (make(), make())\n    return 0\n}}\n", MAKE);
+    let types = infer(&source);
+
+    assert_eq!(types.type_arguments(&NodeId::new("main", vec![0, 0, 0])), Some(&[AstType::I32][..]));
+    assert_eq!(types.type_arguments(&NodeId::new("main", vec![0, 0, 1])), Some(&[AstType::I64][..]));
+
+    let (program, _) = parse(&source);
+    let program = Monomorphizer::new().with_type_table(types).monomorphize_program(&program).unwrap();
+    let main = program.functions().find(|function| function.name == "main").unwrap();
+    let Statement::VariableDeclaration { initializer: Some(Expression::TupleLiteral(calls)), .. } = &main.body[0] else {
+        panic!("expected a tuple declaration");
+    };
+    let names: Vec<&str> = calls
+        .iter()
+        .filter_map(|call| match call {
+            Expression::FunctionCall { name, .. } => Some(name.as_str()),
+            _ => None,
+        })
+        .collect();
+    let expected = [mangle::instantiate("make", &[AstType::I32]), mangle::instantiate("make", &[AstType::I64])];
+    assert_eq!(names, expected);
+}
+
+#[test]
+fn test_empty_array_element_type_comes_from_later_use() {
+    let source = "main = () void {\n    items := []\n    items[0] = 5\n}\n";
+    let types = infer(source);
+
+    assert_eq!(binding(&types, vec![0]), "[i32]");
+}
+
+#[test]
+fn test_none_and_err_payloads_are_inferred() {
+    let source = "main = () void {\n    maybe ::= None\n    maybe = Some(true)\n    \
+        failure: Result<i32, string> = Err(\"bad\")\n    loaded ::= Ok(1)\n    loaded = Err(\"io\")\n}\n";
+    let types = infer(source);
+
+    assert_eq!(binding(&types, vec![0]), "Option<bool>");
+    assert_eq!(binding(&types, vec![3]), "Result<i32, string>");
+}
+
+#[test]
+fn test_unconstrained_binding_reports_cannot_infer() {
+    let source = "main = () void {\n    ok := 1\n    failure := Err(\"bad\")\n}\n";
+    let errors = errors(source);
+
+    assert_eq!(errors.len(), 1);
+    let message = errors[0].message();
+    assert!(message.contains("Cannot infer the type of 'failure'"), "{}", message);
+    assert!(message.contains("Result<_, string>"), "{}", message);
+    assert_eq!(errors[0].span().unwrap().line, 3);
+}
+
+#[test]
+fn test_unconstrained_type_argument_reports_cannot_infer() {
+    let source = format!("{}main = () void {{\n    make()\n}}\n", MAKE);
+    let errors = errors(&source);
+
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].message().contains("Cannot infer type argument 'T' of 'make'"));
+    assert_eq!(errors[0].span().unwrap().line, 6);
+}
+
+#[test]
+fn test_conflicting_uses_are_type_errors() {
+    let source = "same<T> = (first: T, second: T) T {\n    return first\n}\n\n\
+        main = () void {\n    same(1, \"one\")\n}\n";
+    let errors = errors(source);
+
+    assert_eq!(errors.len(), 1);
+    let message = errors[0].message();
+    assert!(message.contains("argument 2 of 'same': expected i32, found string"), "{}", message);
+}
+
+#[test]
+fn test_fully_known_types_are_checked() {
+    let message = errors("main = () i32 {\n    return \"s\"\n}\n")[0].to_string();
+    assert!(message.contains("in return value: expected i32, found string"), "{}", message);
+
+    let source = "Counter = {\n    count: i32 ::\n}\n\nmain = () i32 {\n    c ::= Counter { count: 0 }\n    c.count = true\n    return c.count\n}\n";
+    let message = errors(source)[0].to_string();
+    assert!(message.contains("in assignment: expected i32, found bool"), "{}", message);
+}
+
+const PAINT: &str = "Color =\n    | Red\n    | Green\n\nPaint = {\n    color: Color ::,\n}\n\n\
+    Boxed<T> =\n    | Full(value: T)\n    | Empty\n\n";
+
+#[test]
+fn test_enum_variants_are_typed() {
+    let source = format!(
+        "{}pick = () Color {{\n    return Color::Red\n}}\n\n\
+        wrap = (n: i64) Boxed<i64> {{\n    return Boxed::Full(n)\n}}\n\n\
+        main = () i32 {{\n    p ::= Paint {{ color: Color::Green }}\n    p.color = Color::Red\n    \
+        label := \"$(1)\"\n    return 0\n}}\n",
+        PAINT
+    );
+    let types = infer(&source);
+    assert_eq!(binding(&types, vec![2]), "string");
+
+    let source = format!("{}main = () Boxed<i64> {{\n    return Boxed::Full(true)\n}}\n", PAINT);
+    let message = errors(&source)[0].to_string();
+    assert!(message.contains("expected Boxed<i64>, found Boxed<bool>"), "{}", message);
+
+    let source = format!("{}main = () Color {{\n    return Color::Blue\n}}\n", PAINT);
+    let message = errors(&source)[0].to_string();
+    assert!(message.contains("Enum 'Color' has no variant 'Blue'"), "{}", message);
+}
+
+#[test]
+fn test_monomorphizer_uses_inferred_type_arguments() {
+    let source = format!("{}main = () i32 {{\n    value: Option<i64> = make()\n    return 0\n}}\n", MAKE);
+    let (program, spans) = parse(&source);
+    let mut checker = TypeChecker::new().with_spans(spans);
+    assert!(checker.check_program_collecting(&program).is_empty());
+
+    let program = Monomorphizer::new()
+        .with_type_table(checker.into_type_table())
+        .monomorphize_program(&program)
+        .unwrap();
+    let names: Vec<&str> = program
+        .declarations
+        .iter()
+        .filter_map(|declaration| match declaration {
+            Declaration::Function(function) => Some(function.name.as_str()),
+            _ => None,
+        })
+        .collect();
+    assert!(names.contains(&mangle::instantiate("make", &[AstType::I64]).as_str()), "{:?}", names);
+}
@@ -0,0 +1,64 @@
+use inkwell::context::Context;
+use inkwell::OptimizationLevel;
+use zen::compiler::Compiler;
+use zen::lexer::Lexer;
+use zen::parser::Parser;
+
+fn run_source(source: &str, args: &[&str]) -> i32 {
+    let lexer = Lexer::new(source);
+    let mut parser = Parser::new(lexer);
+    let program = parser.parse_program().unwrap();
+
+    let context = Context::create();
+    let compiler = Compiler::new(&context);
+    let module = compiler.get_module(&program).unwrap();
+
+    let args: Vec<String> = args.iter().map(|s| s.to_string()).collect();
+    zen::jit::run_main(&module, &args, OptimizationLevel::None).unwrap()
+}
+
+#[test]
+fn test_run_returns_exit_code() {
+    assert_eq!(run_source("main = () i32 { 42 }", &["prog"]), 42);
+}
+
+#[test]
+fn test_run_negative_exit_code() {
+    assert_eq!(run_source("main = () i32 { 0 - 1 }", &["prog"]), -1);
+}
+
+#[test]
+fn test_run_sign_extends_narrow_exit_codes() {
+    assert_eq!(run_source("main = () i8 {\n    return 0i8 - 3i8\n}\n", &["prog"]), -3);
+    assert_eq!(run_source("main = () i64 { 0 - 7 }", &["prog"]), -7);
+}
+
+#[test]
+fn test_run_bool_main_exits_with_its_low_bit() {
+    assert_eq!(run_source("main = () bool {\n    return 2 > 1\n}\n", &["prog"]), 1);
+    assert_eq!(run_source("main = () bool {\n    return 1 > 2\n}\n", &["prog"]), 0);
+}
+
+#[test]
+fn test_run_void_main_exits_with_zero() {
+    assert_eq!(run_source("main = () void {\n}\n", &["prog"]), 0);
+}
+
+#[test]
+fn test_run_passes_argc() {
+    let source = "main = (argc: i32, argv: ptr) i32 { argc }";
+    assert_eq!(run_source(source, &["prog", "a", "b"]), 3);
+}
+
+#[test]
+fn test_run_without_main_fails() {
+    let lexer = Lexer::new("helper = () i32 { 1 }");
+    let mut parser = Parser::new(lexer);
+    let program = parser.parse_program().unwrap();
+
+    let context = Context::create();
+    let compiler = Compiler::new(&context);
+    let module = compiler.get_module(&program).unwrap();
+
+    assert!(zen::jit::run_main(&module, &[], OptimizationLevel::None).is_err());
+}
@@ -80,8 +80,8 @@ fn test_struct_with_methods() {
         extern malloc = (size: i64) *void
         
         Point = {
-            x: i32,
-            y: i32,
+            x: i32 ::,
+            y: i32 ::,
         }
         
         point_new = (x: i32, y: i32) *Point {
@@ -273,8 +273,8 @@ fn test_multiple_return_values() {
         extern malloc = (size: i64) *void
         
         DivModResult = {
-            quotient: i32,
-            remainder: i32,
+            quotient: i32 ::,
+            remainder: i32 ::,
         }
         
         divmod = (a: i32, b: i32) *DivModResult {
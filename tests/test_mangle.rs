@@ -0,0 +1,95 @@
+use zen::ast::{self, AstType};
+use zen::mangle::{self, demangle, demangle_text, Symbol};
+
+fn named(name: &str, type_args: Vec<AstType>) -> AstType {
+    AstType::Generic { name: name.to_string(), type_args }
+}
+
+fn round_trip(symbol: Symbol) -> String {
+    let mangled = symbol.mangle();
+    assert_eq!(demangle(&mangled), Some(symbol), "{}", mangled);
+    demangle(&mangled).unwrap().to_string()
+}
+
+#[test]
+fn test_plain_names_are_not_mangled() {
+    assert_eq!(Symbol::name("main").mangle(), "main");
+    assert_eq!(mangle::instantiate("main", &[]), "main");
+    assert_eq!(demangle("main"), None);
+    assert_eq!(demangle("$Zgarbage"), None);
+}
+
+#[test]
+fn test_generic_instances() {
+    assert_eq!(mangle::instantiate("identity", &[AstType::I32]), "$ZN8identityIiE");
+    assert_eq!(round_trip(Symbol::name("pair").with_type_args(vec![AstType::I32, AstType::String])), "pair<i32, string>");
+
+    // Nested generics keep their structure
+    let nested = Symbol::name("pair").with_type_args(vec![
+        named("Box", vec![named("Box", vec![AstType::I32])]),
+        AstType::Pointer(Box::new(AstType::U8)),
+    ]);
+    assert_eq!(round_trip(nested), "pair<Box<Box<i32>>, *u8>");
+
+    let compound = Symbol::name("apply").with_type_args(vec![
+        AstType::Tuple(vec![AstType::Bool, AstType::Array(Box::new(AstType::F64))]),
+        AstType::Closure { param_types: vec![AstType::I64], return_type: Box::new(AstType::Void) },
+        AstType::FixedArray { element_type: Box::new(AstType::I8), size: 12 },
+        AstType::Result { ok_type: Box::new(AstType::I32), err_type: Box::new(AstType::String) },
+        AstType::Dyn("Shape".to_string()),
+    ]);
+    assert_eq!(round_trip(compound), "apply<(bool, [f64]), (i64) void, [i8; 12], Result<i32, string>, dyn Shape>");
+}
+
+#[test]
+fn test_distinct_instances_get_distinct_names() {
+    let names = [
+        mangle::instantiate("pair", &[named("Box", vec![AstType::I32]), AstType::I32]),
+        mangle::instantiate("pair", &[named("Box", vec![AstType::I32, AstType::I32])]),
+        mangle::instantiate("pair", &[named("Point", vec![])]),
+        mangle::instantiate("pair", &[named("Line", vec![])]),
+        mangle::instantiate("pair_i32", &[AstType::I32]),
+        mangle::instantiate("pair", &[AstType::I32, AstType::I32]),
+    ];
+    for (i, name) in names.iter().enumerate() {
+        assert!(!names[i + 1..].contains(name), "{} is generated twice", name);
+    }
+}
+
+#[test]
+fn test_methods_and_behavior_impls() {
+    let inherent = ast::method_symbol("Point", None, "area");
+    assert_eq!(demangle(&inherent).unwrap().to_string(), "Point.area");
+
+    let behavior = ast::method_symbol("i32", Some("Ord"), "cmp");
+    assert_eq!(behavior, "$ZBi3Ord3cmp");
+    assert_eq!(demangle(&behavior).unwrap().to_string(), "i32.Ord.cmp");
+
+    // Methods of generic implementations are instantiated like functions
+    let template = ast::method_symbol("Box", None, "get");
+    assert_eq!(demangle(&mangle::instantiate(&template, &[AstType::F32])).unwrap().to_string(), "Box.get<f32>");
+
+    let vtable = ast::vtable_symbol("Circle", "Drawable");
+    assert_eq!(demangle(&vtable).unwrap().to_string(), "vtable for Circle as dyn Drawable");
+}
+
+#[test]
+fn test_module_paths() {
+    let symbol = Symbol::name("println").in_module("std.io");
+    assert_eq!(symbol.mangle(), "$ZM3stdM2ioN7println");
+    assert_eq!(round_trip(symbol), "std.io.println");
+
+    let generic = Symbol::name("max").in_module("math").with_type_args(vec![AstType::I64]);
+    assert_eq!(round_trip(generic), "math.max<i64>");
+}
+
+#[test]
+fn test_demangle_text() {
+    let text = format!(
+        "  0: {} at main.zen:3\n  1: {}\n  2: main",
+        mangle::instantiate("identity", &[AstType::I32]),
+        ast::method_symbol("Point", Some("Shape"), "area"),
+    );
+    assert_eq!(demangle_text(&text), "  0: identity<i32> at main.zen:3\n  1: Point.Shape.area\n  2: main");
+    assert_eq!(demangle_text("cost $Z and $Zq"), "cost $Z and $Zq");
+}
@@ -0,0 +1,217 @@
+use inkwell::context::Context;
+use inkwell::OptimizationLevel;
+use zen::ast::{self, Expression, Program, Statement};
+use zen::compiler::Compiler;
+use zen::error::CompileError;
+use zen::lexer::Lexer;
+use zen::parser::Parser;
+use zen::spans::NodeId;
+use zen::typechecker::{TypeChecker, TypeTable};
+
+fn parse(source: &str) -> Program {
+    Parser::new(Lexer::new(source)).parse_program().unwrap()
+}
+
+fn parse_expression(source: &str) -> Expression {
+    Parser::new(Lexer::new(source)).parse_expression().unwrap()
+}
+
+fn check(source: &str) -> (Program, TypeTable) {
+    let program = parse(source);
+    let mut checker = TypeChecker::new();
+    let errors = checker.check_program_collecting(&program);
+    assert!(errors.is_empty(), "{:?}", errors);
+    (program, checker.into_type_table())
+}
+
+fn errors(source: &str) -> Vec<CompileError> {
+    let mut parser = Parser::new(Lexer::new(source));
+    let program = parser.parse_program().unwrap();
+    TypeChecker::new().with_spans(parser.take_spans()).check_program_collecting(&program)
+}
+
+fn first_error(source: &str) -> String {
+    let errors = errors(source);
+    assert!(!errors.is_empty(), "expected an error");
+    errors[0].to_string()
+}
+
+fn run_source(source: &str) -> i32 {
+    let program = parse(source);
+    let context = Context::create();
+    let compiler = Compiler::new(&context);
+    let module = compiler.get_module(&program).unwrap();
+    zen::jit::run_main(&module, &["prog".to_string()], OptimizationLevel::None).unwrap()
+}
+
+const POINT: &str = "Point = {\n    x: i32 ::,\n    y: i32\n}\n\nPoint.impl = {\n    new = (x: i32, y: i32) Point {\n        return Point { x: x, y: y }\n    }\n    sum = (self: Point) i32 {\n        return self.x + self.y\n    }\n    scale = (self: *Point, by: i32) void {\n        self.x = self.x * by\n    }\n}\n\n";
+
+#[test]
+fn test_parse_method_call() {
+    let expected = Expression::MethodCall {
+        object: Box::new(Expression::Identifier("p".to_string())),
+        method: "scale".to_string(),
+        args: vec![Expression::Integer32(2)],
+    };
+    assert_eq!(parse_expression("p.scale(2)"), expected);
+}
+
+#[test]
+fn test_parse_chained_method_calls() {
+    let Expression::MethodCall { object, method, .. } = parse_expression("a.first().second(1)") else {
+        panic!("expected a method call");
+    };
+    assert_eq!(method, "second");
+    assert!(matches!(*object, Expression::MethodCall { ref method, .. } if method == "first"));
+}
+
+#[test]
+fn test_qualified_call_name() {
+    assert_eq!(parse_expression("io.print(1)").qualified_call_name(), Some("io.print".to_string()));
+    assert_eq!(parse_expression("a.b.c()").qualified_call_name(), None);
+}
+
+#[test]
+fn test_impl_methods_check() {
+    let source = format!(
+        "{}main = () i32 {{\n    p ::= Point.new(1, 2)\n    p.scale(3)\n    return p.sum()\n}}\n",
+        POINT
+    );
+    assert!(errors(&source).is_empty(), "{:?}", errors(&source));
+}
+
+#[test]
+fn test_method_call_resolves_to_function_call() {
+    let source = format!("{}main = () i32 {{\n    p ::= Point.new(1, 2)\n    p.scale(3)\n    return p.sum()\n}}\n", POINT);
+    let (_, types) = check(&source);
+
+    // Pointer receivers are passed by address
+    let resolved = types.method_call(&NodeId::new("main", vec![1, 0])).unwrap();
+    let expected = Expression::FunctionCall {
+        name: ast::method_symbol("Point", None, "scale"),
+        args: vec![
+            Expression::AddressOf(Box::new(Expression::Identifier("p".to_string()))),
+            Expression::Integer32(3),
+        ],
+    };
+    assert_eq!(resolved, &expected);
+
+    // Associated functions take only the arguments given
+    let resolved = types.method_call(&NodeId::new("main", vec![0, 0])).unwrap();
+    assert!(matches!(resolved, Expression::FunctionCall { name, args } if *name == ast::method_symbol("Point", None, "new") && args.len() == 2));
+}
+
+#[test]
+fn test_identical_calls_in_block_arms_resolve_separately() {
+    let source = format!(
+        "{}total = (p: *Point, q: Point, flag: bool) i32 {{\n    n := flag ? | true => {{\n            x := p\n            x.sum()\n        }}\n        | false => {{\n            x := q\n            x.sum()\n        }}\n    return n\n}}\n",
+        POINT
+    );
+    let (_, types) = check(&source);
+    let x = || Box::new(Expression::Identifier("x".to_string()));
+
+    // The first arm's `x` is a pointer, the second's a value
+    let first = types.method_call(&NodeId::new("total", vec![0, 0, 2, 1, 0])).unwrap();
+    assert!(matches!(first, Expression::FunctionCall { args, .. } if args[..] == [Expression::Dereference(x())]));
+    let second = types.method_call(&NodeId::new("total", vec![0, 0, 4, 1, 0])).unwrap();
+    assert!(matches!(second, Expression::FunctionCall { args, .. } if args[..] == [*x()]));
+}
+
+#[test]
+fn test_nested_calls_are_keyed_by_position() {
+    let source = format!(
+        "{}total = (p: *Point, q: Point) i32 {{\n    p.scale(q.sum())\n    return p.sum() + q.sum()\n}}\n",
+        POINT
+    );
+    let (_, types) = check(&source);
+    let pointer = || Expression::Dereference(Box::new(Expression::Identifier("p".to_string())));
+    let value = || Expression::Identifier("q".to_string());
+
+    // The argument is child 1 of the call, after the receiver
+    let argument = types.method_call(&NodeId::new("total", vec![0, 0, 1])).unwrap();
+    assert!(matches!(argument, Expression::FunctionCall { args, .. } if args[..] == [value()]));
+    let left = types.method_call(&NodeId::new("total", vec![1, 0, 0])).unwrap();
+    assert!(matches!(left, Expression::FunctionCall { args, .. } if args[..] == [pointer()]));
+    let right = types.method_call(&NodeId::new("total", vec![1, 0, 1])).unwrap();
+    assert!(matches!(right, Expression::FunctionCall { args, .. } if args[..] == [value()]));
+}
+
+#[test]
+fn test_closure_parameter_shadowing_receiver_resolves_separately() {
+    let source = format!(
+        "{}apply = (f: (Point) i32, p: Point) i32 {{\n    return f(p)\n}}\n\n\
+        total = (x: *Point) i32 {{\n    return apply((x: Point) -> x.sum(), Point.new(1, 2)) + x.sum()\n}}\n",
+        POINT
+    );
+    let (_, types) = check(&source);
+    let x = || Box::new(Expression::Identifier("x".to_string()));
+
+    let inner = types.method_call(&NodeId::new("total", vec![0, 0, 0, 0, 0])).unwrap();
+    assert!(matches!(inner, Expression::FunctionCall { args, .. } if args[..] == [*x()]));
+    let outer = types.method_call(&NodeId::new("total", vec![0, 0, 1])).unwrap();
+    assert!(matches!(outer, Expression::FunctionCall { args, .. } if args[..] == [Expression::Dereference(x())]));
+}
+
+#[test]
+fn test_pointer_receiver_is_dereferenced() {
+    let source = format!(
+        "{}total = (p: *Point) i32 {{\n    return p.sum()\n}}\n\nmain = () i32 {{\n    return 0\n}}\n",
+        POINT
+    );
+    assert!(errors(&source).is_empty(), "{:?}", errors(&source));
+}
+
+#[test]
+fn test_free_function_called_as_method() {
+    let source = format!(
+        "{}dist = (p: Point, q: Point) i32 {{\n    return q.x - p.x\n}}\n\nmain = () i32 {{\n    a := Point.new(1, 2)\n    b := Point.new(4, 6)\n    return a.dist(b)\n}}\n",
+        POINT
+    );
+    assert!(errors(&source).is_empty(), "{:?}", errors(&source));
+}
+
+#[test]
+fn test_unknown_method_is_reported() {
+    let source = format!("{}main = () i32 {{\n    p := Point.new(1, 2)\n    return p.area()\n}}\n", POINT);
+    let message = first_error(&source);
+    assert!(message.contains("No method named 'area' for type 'Point'"), "{}", message);
+}
+
+#[test]
+fn test_associated_function_needs_type_path() {
+    let source = format!("{}main = () i32 {{\n    p := Point.new(1, 2)\n    q := p.new(3, 4)\n    return 0\n}}\n", POINT);
+    let message = first_error(&source);
+    assert!(message.contains("'new' is an associated function of 'Point', not a method"), "{}", message);
+}
+
+#[test]
+fn test_method_arity_is_checked() {
+    let source = format!("{}main = () i32 {{\n    p ::= Point.new(1, 2)\n    p.scale()\n    return 0\n}}\n", POINT);
+    let message = first_error(&source);
+    assert!(message.contains("'scale' takes 1 argument(s) but 0 were given"), "{}", message);
+}
+
+#[test]
+fn test_pointer_receiver_must_be_a_place() {
+    let source = format!("{}main = () i32 {{\n    Point.new(1, 2).scale(2)\n    return 0\n}}\n", POINT);
+    let message = first_error(&source);
+    assert!(message.contains("is a temporary"), "{}", message);
+}
+
+#[test]
+fn test_run_methods() {
+    let source = format!(
+        "{}main = () i32 {{\n    p ::= Point.new(1, 2)\n    p.scale(3)\n    return p.sum()\n}}\n",
+        POINT
+    );
+    assert_eq!(run_source(&source), 5);
+}
+
+#[test]
+fn test_run_free_function_as_method() {
+    let source = format!(
+        "{}dist = (p: Point, q: Point) i32 {{\n    return q.y - p.y\n}}\n\nmain = () i32 {{\n    a := Point.new(1, 2)\n    b := Point.new(4, 9)\n    return a.dist(b)\n}}\n",
+        POINT
+    );
+    assert_eq!(run_source(&source), 7);
+}
@@ -0,0 +1,233 @@
+use inkwell::context::Context;
+use inkwell::OptimizationLevel;
+use zen::ast::{AstType, BinaryOperator, Expression, Program, Statement, UnaryOperator};
+use zen::compiler::Compiler;
+use zen::error::CompileError;
+use zen::formatter::format_source;
+use zen::lexer::{Lexer, Token};
+use zen::parser::Parser;
+use zen::spans::NodeId;
+use zen::typechecker::TypeChecker;
+
+fn parse(source: &str) -> Program {
+    Parser::new(Lexer::new(source)).parse_program().unwrap()
+}
+
+fn parse_expression(source: &str) -> Expression {
+    Parser::new(Lexer::new(source)).parse_expression().unwrap()
+}
+
+fn errors(source: &str) -> Vec<CompileError> {
+    let mut parser = Parser::new(Lexer::new(source));
+    let program = parser.parse_program().unwrap();
+    TypeChecker::new().with_spans(parser.take_spans()).check_program_collecting(&program)
+}
+
+fn main_body(source: &str) -> Vec<Statement> {
+    let program = parse(source);
+    let main = program.functions().find(|function| function.name == "main").unwrap();
+    main.body.clone()
+}
+
+fn run_source(source: &str) -> i32 {
+    let program = parse(source);
+    let context = Context::create();
+    let compiler = Compiler::new(&context);
+    let module = compiler.get_module(&program).unwrap();
+    zen::jit::run_main(&module, &["prog".to_string()], OptimizationLevel::None).unwrap()
+}
+
+fn ident(name: &str) -> Box<Expression> {
+    Box::new(Expression::Identifier(name.to_string()))
+}
+
+fn binary(left: Box<Expression>, op: BinaryOperator, right: Box<Expression>) -> Box<Expression> {
+    Box::new(Expression::BinaryOp { left, op, right })
+}
+
+#[test]
+fn test_lex_new_operators() {
+    let mut lexer = Lexer::new("% ^ ~ += -= *= /= %= &= |= ^= << <<= >>");
+    let mut tokens = Vec::new();
+    loop {
+        match lexer.next_token() {
+            Token::Eof => break,
+            token => tokens.push(token),
+        }
+    }
+    let operators = ["%", "^", "~", "+=", "-=", "*=", "/=", "%=", "&=", "|=", "^=", "<<", "<<=", ">", ">"];
+    let expected: Vec<Token> = operators.iter().map(|op| Token::Operator(op.to_string())).collect();
+    assert_eq!(tokens, expected);
+}
+
+#[test]
+fn test_parse_unary_operators() {
+    let expected = Expression::UnaryOp { op: UnaryOperator::Not, operand: ident("done") };
+    assert_eq!(parse_expression("!done"), expected);
+
+    let expected = Expression::UnaryOp {
+        op: UnaryOperator::Negate,
+        operand: Box::new(Expression::UnaryOp { op: UnaryOperator::BitNot, operand: ident("x") }),
+    };
+    assert_eq!(parse_expression("-~x"), expected);
+}
+
+#[test]
+fn test_bitwise_precedence() {
+    // a | b ^ c & d << 1 + 2
+    let expected = *binary(
+        ident("a"),
+        BinaryOperator::BitOr,
+        binary(
+            ident("b"),
+            BinaryOperator::BitXor,
+            binary(
+                ident("c"),
+                BinaryOperator::BitAnd,
+                binary(
+                    ident("d"),
+                    BinaryOperator::ShiftLeft,
+                    binary(Box::new(Expression::Integer32(1)), BinaryOperator::Add, Box::new(Expression::Integer32(2))),
+                ),
+            ),
+        ),
+    );
+    assert_eq!(parse_expression("a | b ^ c & d << 1 + 2"), expected);
+
+    // Comparisons bind looser than bitwise operators
+    let expected = *binary(binary(ident("x"), BinaryOperator::BitAnd, ident("m")), BinaryOperator::Equals, ident("m"));
+    assert_eq!(parse_expression("x & m == m"), expected);
+}
+
+#[test]
+fn test_shift_right_needs_adjacent_angles() {
+    let expected = *binary(ident("h"), BinaryOperator::ShiftRight, Box::new(Expression::Integer32(3)));
+    assert_eq!(parse_expression("h >> 3"), expected);
+    assert!(Parser::new(Lexer::new("h > > 3")).parse_expression().is_err());
+}
+
+#[test]
+fn test_nested_type_arguments_still_close() {
+    let program = parse("main = () i32 {\n    x: Option<Option<i32>> = Option.None\n    return 0\n}\n");
+    assert_eq!(program.declarations.len(), 1);
+}
+
+#[test]
+fn test_bit_or_in_match_arm_body_needs_parentheses() {
+    let Expression::Conditional { arms, .. } = parse_expression("x ? | 1 => a | 2 => (b | c)") else {
+        panic!("expected a conditional");
+    };
+    assert_eq!(arms.len(), 2);
+    assert_eq!(arms[0].body, Expression::Identifier("a".to_string()));
+    assert_eq!(arms[1].body, *binary(ident("b"), BinaryOperator::BitOr, ident("c")));
+}
+
+#[test]
+fn test_compound_assignments_desugar() {
+    let body = main_body("main = () void {\n    x ::= 1\n    x += 2 * 3\n    x >>= 1\n    p.count |= 4\n}\n");
+    let expected = Statement::VariableAssignment {
+        name: "x".to_string(),
+        value: *binary(
+            ident("x"),
+            BinaryOperator::Add,
+            binary(Box::new(Expression::Integer32(2)), BinaryOperator::Multiply, Box::new(Expression::Integer32(3))),
+        ),
+    };
+    assert_eq!(body[1], expected);
+    let expected = Statement::VariableAssignment {
+        name: "x".to_string(),
+        value: *binary(ident("x"), BinaryOperator::ShiftRight, Box::new(Expression::Integer32(1))),
+    };
+    assert_eq!(body[2], expected);
+    assert!(matches!(
+        &body[3],
+        Statement::CompoundAssignment { target: Expression::MemberAccess { .. }, op: BinaryOperator::BitOr, .. }
+    ));
+}
+
+const SIDE_EFFECT: &str = "main = () i32 {\n    values ::= [10, 20, 30]\n    calls ::= 0\n    \
+    next := () -> {\n        calls = calls + 1\n        calls\n    }\n    \
+    values[next()] += 5\n    return values[1] * 10 + calls\n}\n";
+
+#[test]
+fn test_compound_assignment_names_its_target_once() {
+    let body = main_body(SIDE_EFFECT);
+    let Statement::CompoundAssignment { target, op: BinaryOperator::Add, value } = &body[3] else {
+        panic!("expected a compound assignment, got {:?}", body[3]);
+    };
+    assert!(matches!(target, Expression::ArrayIndex { index, .. } if matches!(**index, Expression::FunctionCall { .. })));
+    assert_eq!(value, &Expression::Integer32(5));
+    assert!(errors(SIDE_EFFECT).is_empty(), "{:?}", errors(SIDE_EFFECT));
+}
+
+#[test]
+fn test_operators_typecheck() {
+    let source = "main = () i32 {\n    h ::= 5381\n    h = h << 5 ^ h\n    h &= 255\n    flag := !(h > 3)\n    return ~h + -h\n}\n";
+    assert!(errors(source).is_empty(), "{:?}", errors(source));
+}
+
+#[test]
+fn test_operator_type_errors() {
+    let source = "main = () i32 {\n    x := 1.5 & 2.5\n    return 0\n}\n";
+    assert!(errors(source)[0].to_string().contains("Bitwise operators require integer operands"));
+
+    let source = "main = () i32 {\n    x := !3\n    return 0\n}\n";
+    assert!(errors(source)[0].to_string().contains("Cannot apply Not"));
+
+    let source = "main = () i32 {\n    x := 1\n    x += 1\n    return x\n}\n";
+    assert!(!errors(source).is_empty());
+}
+
+#[test]
+fn test_format_keeps_operators_apart() {
+    let formatted = format_source("main = () i32 {\n    x ::= a&b|c>>2\n    x <<= ~x\n    return -x\n}\n").unwrap();
+    assert_eq!(formatted, "main = () i32 {\n    x ::= a & b | c >> 2\n    x <<= ~x\n    return -x\n}\n");
+}
+
+#[test]
+fn test_run_bitwise_and_shifts() {
+    let source = "main = () i32 {\n    x ::= 6\n    x |= 9\n    x ^= 3\n    return (x << 2) + (x >> 1) + (x & 5)\n}\n";
+    // x = 15 ^ 3 = 12, so 48 + 6 + 4
+    assert_eq!(run_source(source), 58);
+}
+
+#[test]
+fn test_run_shift_right_by_signedness() {
+    let source = "main = () i32 {\n    s: i32 = -16\n    u := 4294967280u32\n    a := s >> 2\n    b := u >> 28\n    return a + b as i32\n}\n";
+    // -16 >> 2 is -4 arithmetically; 0xFFFFFFF0 >> 28 is 15 logically
+    assert_eq!(run_source(source), 11);
+}
+
+const REGISTER: &str = "Register = {\n    bits: u32,\n}\n\n\
+    main = () i32 {\n    r := Register { bits: 4294967280u32 }\n    \
+    top := r.bits >> 28\n    return top as i32\n}\n";
+
+#[test]
+fn test_operand_types_are_recorded() {
+    let program = parse(REGISTER);
+    let mut checker = TypeChecker::new();
+    assert!(checker.check_program_collecting(&program).is_empty());
+    let types = checker.into_type_table();
+
+    let shift = types.operand_types(&NodeId::new("main", vec![1, 0]));
+    assert_eq!(shift, Some(&(AstType::U32, AstType::I32)));
+}
+
+#[test]
+fn test_run_shift_right_of_unsigned_field() {
+    // The field's type comes from the checker, so the shift is logical
+    assert_eq!(run_source(REGISTER), 15);
+}
+
+#[test]
+fn test_run_compound_assignment_evaluates_index_once() {
+    // `next()` runs once, so only `values[1]` changes
+    assert_eq!(run_source(SIDE_EFFECT), 251);
+}
+
+#[test]
+fn test_run_unary_operators() {
+    let source = "main = () i32 {\n    x ::= 5\n    x -= 2\n    x *= 4\n    ok := !(x == 12)\n    penalty := ok ? | true => 100 | false => 0\n    return ~x + -x + 40 - penalty\n}\n";
+    // ~12 + -12 + 40 = -13 - 12 + 40
+    assert_eq!(run_source(source), 15);
+}
@@ -0,0 +1,136 @@
+use inkwell::context::Context;
+use zen::ast::{AstType, Declaration, Statement};
+use zen::repl::{self, ReplInput, ReplOutput, ReplSession, ReplValue};
+
+#[test]
+fn test_incomplete_input_detection() {
+    assert!(repl::is_incomplete("add = (a: i32, b: i32) i32 {"));
+    assert!(repl::is_incomplete("foo(1,"));
+    assert!(!repl::is_incomplete("add = (a: i32, b: i32) i32 { a + b }"));
+    assert!(!repl::is_incomplete("s := \"{\""));
+    assert!(!repl::is_incomplete("x := 1 // {"));
+    assert!(!repl::is_incomplete("c := '{'"));
+    assert!(!repl::is_incomplete("s := r\"{\""));
+    assert!(!repl::is_incomplete("s := \"\"\"\n{\n\"\"\""));
+    assert!(repl::is_incomplete("s := \"\"\"\n{"));
+    assert!(repl::is_incomplete("s := r#\"{"));
+}
+
+#[test]
+fn test_parse_input_declarations() {
+    let input = ReplSession::parse_input("add = (a: i32, b: i32) i32 { a + b }").unwrap();
+    match input {
+        ReplInput::Declarations(decls) => {
+            assert_eq!(decls.len(), 1);
+            assert!(matches!(&decls[0], Declaration::Function(f) if f.name == "add"));
+        }
+        other => panic!("Expected declarations, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_parse_input_statements() {
+    let input = ReplSession::parse_input("x := 10").unwrap();
+    match input {
+        ReplInput::Statements(stmts) => {
+            assert_eq!(stmts.len(), 1);
+            assert!(matches!(&stmts[0], Statement::VariableDeclaration { name, .. } if name == "x"));
+        }
+        other => panic!("Expected statements, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_type_of_expression() {
+    let session = ReplSession::new();
+    assert_eq!(session.type_of("1 + 2").unwrap(), AstType::I32);
+    assert_eq!(session.type_of("1 < 2").unwrap(), AstType::Bool);
+    assert!(session.type_of("undefined_name").is_err());
+}
+
+#[test]
+fn test_bindings_persist_across_inputs() {
+    let context = Context::create();
+    let mut session = ReplSession::new();
+
+    let bound = session.eval(&context, "x := 10").unwrap();
+    assert_eq!(bound, ReplOutput::Bound { name: "x".to_string(), type_: AstType::I32 });
+
+    let value = session.eval(&context, "x + 5").unwrap();
+    assert_eq!(value, ReplOutput::Value { value: ReplValue::Integer(15), type_: AstType::I32 });
+}
+
+#[test]
+fn test_declarations_persist_across_inputs() {
+    let context = Context::create();
+    let mut session = ReplSession::new();
+
+    let defined = session.eval(&context, "add = (a: i32, b: i32) i32 { a + b }").unwrap();
+    assert_eq!(defined, ReplOutput::Defined(vec!["add".to_string()]));
+
+    let value = session.eval(&context, "add(2, 3)").unwrap();
+    assert_eq!(value, ReplOutput::Value { value: ReplValue::Integer(5), type_: AstType::I32 });
+    assert_eq!(session.type_of("add(2, 3)").unwrap(), AstType::I32);
+}
+
+#[test]
+fn test_redefining_an_impl_replaces_it() {
+    let context = Context::create();
+    let mut session = ReplSession::new();
+    let impl_returning = |sides: i32| {
+        format!(
+            "Square.impl = {{\n    Sides: {{\n        sides = (self: *Square) i32 {{\n            return {}\n        }}\n    }}\n}}",
+            sides
+        )
+    };
+
+    session.eval(&context, "Sides = behavior {\n    sides = (self) i32\n}").unwrap();
+    session.eval(&context, "Square = {\n    side: i32\n}").unwrap();
+    session.eval(&context, &impl_returning(3)).unwrap();
+    let defined = session.eval(&context, &impl_returning(4)).unwrap();
+    assert_eq!(defined, ReplOutput::Defined(vec!["impl Sides for Square".to_string()]));
+
+    session.eval(&context, "s := Square { side: 1 }").unwrap();
+    let value = session.eval(&context, "s.sides()").unwrap();
+    assert_eq!(value, ReplOutput::Value { value: ReplValue::Integer(4), type_: AstType::I32 });
+}
+
+#[test]
+fn test_reset_forgets_bindings() {
+    let context = Context::create();
+    let mut session = ReplSession::new();
+
+    session.eval(&context, "x := 1").unwrap();
+    session.reset();
+    assert!(session.type_of("x").is_err());
+}
+
+#[test]
+fn test_redeclaration_keeps_dependents() {
+    let context = Context::create();
+    let mut session = ReplSession::new();
+
+    session.eval(&context, "x := 1").unwrap();
+    session.eval(&context, "y := x + 1").unwrap();
+    let bound = session.eval(&context, "x := 5").unwrap();
+    assert_eq!(bound, ReplOutput::Bound { name: "x".to_string(), type_: AstType::I32 });
+
+    // y was computed from the first x and keeps its value
+    let value = session.eval(&context, "x + y").unwrap();
+    assert_eq!(value, ReplOutput::Value { value: ReplValue::Integer(7), type_: AstType::I32 });
+}
+
+#[test]
+fn test_bindings_keep_their_evaluated_values() {
+    let context = Context::create();
+    let mut session = ReplSession::new();
+
+    session.eval(&context, "n ::= 1").unwrap();
+    session.eval(&context, "n = n * 10").unwrap();
+    session.eval(&context, "m := n + 1").unwrap();
+    session.eval(&context, "n = 0").unwrap();
+
+    let value = session.eval(&context, "m").unwrap();
+    assert_eq!(value, ReplOutput::Value { value: ReplValue::Integer(11), type_: AstType::I32 });
+    assert_eq!(session.type_of("n").unwrap(), AstType::I32);
+}
@@ -0,0 +1,125 @@
+use zen::ast::Program;
+use zen::error::CompileError;
+use zen::lexer::Lexer;
+use zen::parser::Parser;
+use zen::spans::{NodeId, SpanTable};
+use zen::typechecker::TypeChecker;
+
+fn parse(source: &str) -> (Program, SpanTable) {
+    let mut parser = Parser::new(Lexer::new(source));
+    let program = parser.parse_program().unwrap();
+    (program, parser.take_spans())
+}
+
+fn check(source: &str) -> Vec<CompileError> {
+    let (program, spans) = parse(source);
+    TypeChecker::new().with_spans(spans).check_program_collecting(&program)
+}
+
+fn text<'a>(source: &'a str, error: &CompileError) -> &'a str {
+    let span = error.span().expect("error should have a span");
+    &source[span.start..span.end]
+}
+
+#[test]
+fn test_statement_spans_are_recorded() {
+    let source = "main = () i32 {\n    x := 1\n    y := x + 2\n    return y\n}\n";
+    let (_, spans) = parse(source);
+
+    let second = spans.statement(&NodeId::new("main", vec![1])).unwrap();
+    assert_eq!(&source[second.start..second.end], "y := x + 2");
+    assert_eq!(second.line, 3);
+    assert_eq!(second.column, 5);
+
+    let function = spans.declaration("main").unwrap();
+    assert_eq!(function.line, 1);
+    assert!(source[function.start..function.end].ends_with('}'));
+}
+
+#[test]
+fn test_loop_body_statements_are_keyed_by_path() {
+    let source = "main = () void {\n    loop {\n        a := 1\n        break\n    }\n}\n";
+    let (_, spans) = parse(source);
+
+    let inner = spans.statement(&NodeId::new("main", vec![0, 1])).unwrap();
+    assert_eq!(&source[inner.start..inner.end], "break");
+    assert_eq!(inner.line, 4);
+}
+
+#[test]
+fn test_block_statements_are_keyed_by_path() {
+    let source = "main = (x: i32) i32 {\n    y := x ? | 1 => {\n        z := 2\n        loop {\n            break\n        }\n        z\n    }\n        | _ => 0\n    return y\n}\n";
+    let (_, spans) = parse(source);
+
+    // The first arm's body of the initializer of the first statement
+    let first = spans.statement(&NodeId::new("main", vec![0, 0, 2, 0])).unwrap();
+    assert_eq!(&source[first.start..first.end], "z := 2");
+    let inner = spans.statement(&NodeId::new("main", vec![0, 0, 2, 1, 0])).unwrap();
+    assert_eq!(&source[inner.start..inner.end], "break");
+    assert_eq!(inner.line, 5);
+}
+
+#[test]
+fn test_type_error_in_closure_points_at_block_statement() {
+    let source = "main = () i32 {\n    f := (n: i32) -> {\n        m: bool = n\n        m\n    }\n    return 0\n}\n";
+    let errors = check(source);
+
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert_eq!(text(source, &errors[0]), "m: bool = n");
+}
+
+#[test]
+fn test_undeclared_variable_points_at_identifier() {
+    let source = "main = () i32 {\n    x := 1\n    return x + missing\n}\n";
+    let errors = check(source);
+
+    assert_eq!(errors.len(), 1);
+    assert_eq!(text(source, &errors[0]), "missing");
+    assert_eq!(errors[0].span().unwrap().line, 3);
+}
+
+#[test]
+fn test_type_error_points_at_statement() {
+    let source = "main = () i32 {\n    ok := 1\n    x: bool = 42\n    return 0\n}\n";
+    let errors = check(source);
+
+    assert_eq!(errors.len(), 1);
+    assert_eq!(text(source, &errors[0]), "x: bool = 42");
+}
+
+#[test]
+fn test_errors_in_each_function_are_located() {
+    let source = "first = () i32 { missing_a }\nsecond = () i32 { missing_b }\n";
+    let errors = check(source);
+
+    assert_eq!(errors.len(), 2);
+    assert_eq!(text(source, &errors[0]), "missing_a");
+    assert_eq!(errors[1].span().unwrap().line, 2);
+}
+
+#[test]
+fn test_declaration_spans_survive_lookahead() {
+    let source = "Point = {\n    x: i32,\n}\n\nidentity<T> = (value: T) T {\n    value\n}\n";
+    let (_, spans) = parse(source);
+
+    let generic = spans.declaration("identity").unwrap();
+    assert_eq!(generic.line, 5);
+    assert_eq!(generic.column, 1);
+}
+
+#[test]
+fn test_instantiated_functions_resolve_to_generic_source() {
+    let source = "identity<T> = (value: T) T {\n    value\n}\n";
+    let (_, mut spans) = parse(source);
+    spans.record_alias("identity_i32", "identity");
+
+    let statement = spans.statement(&NodeId::new("identity_i32", vec![0])).unwrap();
+    assert_eq!(&source[statement.start..statement.end], "value");
+}
+
+#[test]
+fn test_spans_do_not_affect_ast_equality() {
+    let (with_blank_lines, _) = parse("\n\nmain = () i32 {\n\n    return 0\n}\n");
+    let (compact, _) = parse("main = () i32 { return 0 }");
+    assert_eq!(with_blank_lines, compact);
+}
@@ -92,12 +92,13 @@ fn test_build_import() {
         if let Statement::VariableDeclaration { name, initializer, .. } = &statements[1] {
             assert_eq!(name, "io");
             
-            if let Some(Expression::FunctionCall { name, args }) = initializer {
-                assert_eq!(name, "build.import");
+            if let Some(Expression::MethodCall { object, method, args }) = initializer {
+                assert!(matches!(**object, Expression::Identifier(ref name) if name == "build"));
+                assert_eq!(method, "import");
                 assert_eq!(args.len(), 1);
                 assert!(matches!(args[0], Expression::String(ref s) if s == "io"));
             } else {
-                panic!("Expected FunctionCall in io initialization");
+                panic!("Expected MethodCall in io initialization");
             }
         } else {
             panic!("Expected VariableDeclaration for io");
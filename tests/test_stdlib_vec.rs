@@ -72,7 +72,7 @@ comptime {
 
 Vec<T> = {
     data: *T,
-    len: i64,
+    len: i64 ::,
     capacity: i64,
 }
 
@@ -125,9 +125,9 @@ extern free = (ptr: *void) void
 extern memcpy = (dest: *void, src: *void, size: i64) void
 
 Vec<T> = {
-    data: *T,
+    data: *T ::,
     len: i64,  
-    capacity: i64,
+    capacity: i64 ::,
 }
 
 vec_grow<T> = (vec: *Vec<T>, new_capacity: i64) void {
@@ -0,0 +1,177 @@
+use inkwell::context::Context;
+use inkwell::OptimizationLevel;
+use zen::ast::{AstType, Declaration, Expression, Pattern, Program, Statement};
+use zen::compiler::Compiler;
+use zen::error::CompileError;
+use zen::lexer::Lexer;
+use zen::parser::Parser;
+use zen::typechecker::TypeChecker;
+
+fn parse(source: &str) -> Program {
+    Parser::new(Lexer::new(source)).parse_program().unwrap()
+}
+
+fn parse_expression(source: &str) -> Expression {
+    Parser::new(Lexer::new(source)).parse_expression().unwrap()
+}
+
+fn errors(source: &str) -> Vec<CompileError> {
+    let mut parser = Parser::new(Lexer::new(source));
+    let program = parser.parse_program().unwrap();
+    TypeChecker::new().with_spans(parser.take_spans()).check_program_collecting(&program)
+}
+
+fn first_error(source: &str) -> String {
+    let errors = errors(source);
+    assert!(!errors.is_empty(), "expected an error");
+    errors[0].to_string()
+}
+
+fn main_body(source: &str) -> Vec<Statement> {
+    let program = parse(source);
+    let main = program.functions().find(|function| function.name == "main").unwrap();
+    main.body.clone()
+}
+
+fn run_source(source: &str) -> i32 {
+    let program = parse(source);
+    let context = Context::create();
+    let compiler = Compiler::new(&context);
+    let module = compiler.get_module(&program).unwrap();
+    zen::jit::run_main(&module, &["prog".to_string()], OptimizationLevel::None).unwrap()
+}
+
+const DIVMOD: &str = "divmod = (a: i32, b: i32) (i32, i32) {\n    return (a / b, a % b)\n}\n\n";
+
+const POINT: &str = "Point = {\n    x: i32,\n    y: i32\n}\n\n";
+
+#[test]
+fn test_parse_tuple_types() {
+    let program = parse(DIVMOD);
+    let Declaration::Function(function) = &program.declarations[0] else {
+        panic!("expected a function");
+    };
+    assert_eq!(function.return_type, AstType::Tuple(vec![AstType::I32, AstType::I32]));
+    assert_eq!(function.return_type.to_string(), "(i32, i32)");
+
+    // A type after the parentheses still makes a function type
+    let program = parse("apply = (f: (i32) i32, pair: (i32, string)) i32 {\n    return f(pair.0)\n}\n");
+    let Declaration::Function(function) = &program.declarations[0] else {
+        panic!("expected a function");
+    };
+    assert!(matches!(function.args[0].1, AstType::Closure { .. }));
+    assert_eq!(function.args[1].1, AstType::Tuple(vec![AstType::I32, AstType::String]));
+}
+
+#[test]
+fn test_parse_tuple_literals_and_indices() {
+    let expected = Expression::TupleLiteral(vec![Expression::Integer32(1), Expression::Identifier("x".to_string())]);
+    assert_eq!(parse_expression("(1, x)"), expected);
+    assert_eq!(parse_expression("(x)"), Expression::Identifier("x".to_string()));
+
+    // `t.1.0` lexes its indices as the float `1.0`
+    let expected = Expression::TupleIndex {
+        tuple: Box::new(Expression::TupleIndex {
+            tuple: Box::new(Expression::Identifier("t".to_string())),
+            index: 1,
+        }),
+        index: 0,
+    };
+    assert_eq!(parse_expression("t.1.0"), expected);
+}
+
+#[test]
+fn test_parse_destructuring_declarations() {
+    let body = main_body("main = () void {\n    (a) := 1\n    (q, _) := divmod(7, 2)\n    { x, y: py } ::= p\n}\n");
+    let Statement::DestructuringDeclaration { pattern, is_mutable, .. } = &body[1] else {
+        panic!("expected a destructuring declaration");
+    };
+    assert_eq!(pattern, &Pattern::Tuple(vec![Pattern::Identifier("q".to_string()), Pattern::Wildcard]));
+    assert!(!is_mutable);
+
+    let Statement::DestructuringDeclaration { pattern, is_mutable, .. } = &body[2] else {
+        panic!("expected a destructuring declaration");
+    };
+    let expected = Pattern::Struct {
+        name: String::new(),
+        fields: vec![
+            ("x".to_string(), Pattern::Identifier("x".to_string())),
+            ("y".to_string(), Pattern::Identifier("py".to_string())),
+        ],
+    };
+    assert_eq!(pattern, &expected);
+    assert!(is_mutable);
+
+    assert!(matches!(&body[0], Statement::DestructuringDeclaration { pattern: Pattern::Identifier(name), .. } if name == "a"));
+}
+
+#[test]
+fn test_tuples_typecheck() {
+    let source = format!(
+        "{}{}main = () i32 {{\n    (q, r) := divmod(17, 5)\n    pair: (i64, bool) = (1, true)\n    {{ x, y }} := Point {{ x: q, y: r }}\n    nested := ((x, y), pair)\n    return nested.0.1 + q\n}}\n",
+        DIVMOD, POINT
+    );
+    assert!(errors(&source).is_empty(), "{:?}", errors(&source));
+}
+
+#[test]
+fn test_tuple_index_errors() {
+    let source = "main = () i32 {\n    t := (1, 2)\n    return t.2\n}\n";
+    let message = first_error(source);
+    assert!(message.contains("Tuple index 2 is out of range for a tuple of 2 elements"), "{}", message);
+
+    let source = "main = () i32 {\n    n := 3\n    return n.0\n}\n";
+    let message = first_error(source);
+    assert!(message.contains("only tuples have numbered elements"), "{}", message);
+}
+
+#[test]
+fn test_destructuring_errors() {
+    let source = format!("{}main = () i32 {{\n    (a, b, c) := divmod(1, 2)\n    return a\n}}\n", DIVMOD);
+    let message = first_error(&source);
+    assert!(message.contains("Cannot destructure a value of type (i32, i32) into 3 element(s)"), "{}", message);
+
+    let source = format!("{}main = () i32 {{\n    {{ x, z }} := Point {{ x: 1, y: 2 }}\n    return x\n}}\n", POINT);
+    let message = first_error(&source);
+    assert!(message.contains("Struct 'Point' has no field 'z'"), "{}", message);
+
+    let source = "main = () i32 {\n    (1, b) := (1, 2)\n    return b\n}\n";
+    let message = first_error(source);
+    assert!(message.contains("can be destructured in a declaration, found `1`"), "{}", message);
+}
+
+#[test]
+fn test_destructured_bindings_follow_mutability() {
+    let source = "main = () i32 {\n    (a, b) := (1, 2)\n    a = 3\n    return a\n}\n";
+    assert!(matches!(errors(source).first(), Some(CompileError::AssignToImmutable { name, .. }) if name == "a"));
+
+    let source = "main = () i32 {\n    (a, b) ::= (1, 2)\n    a = 3\n    return a + b\n}\n";
+    assert!(errors(source).is_empty(), "{:?}", errors(source));
+}
+
+#[test]
+fn test_run_tuple_return_and_destructuring() {
+    let source = format!("{}main = () i32 {{\n    (q, r) := divmod(17, 5)\n    return q * 10 + r\n}}\n", DIVMOD);
+    assert_eq!(run_source(&source), 32);
+}
+
+#[test]
+fn test_run_struct_destructuring() {
+    let source = format!(
+        "{}main = () i32 {{\n    p := Point {{ x: 4, y: 9 }}\n    {{ x, y: height }} := p\n    return height - x\n}}\n",
+        POINT
+    );
+    assert_eq!(run_source(&source), 5);
+}
+
+#[test]
+fn test_run_tuple_indices_and_widening() {
+    let source = "main = () i32 {\n    pair: (i64, i32) = (40, 2)\n    nested := (pair, (1, 7))\n    return nested.0.0 as i32 + nested.0.1 - nested.1.0\n}\n";
+    assert_eq!(run_source(source), 41);
+}
+
+#[test]
+fn test_run_tuple_patterns_in_match() {
+    let source = "main = () i32 {\n    t := (2, 5)\n    return t ?\n        | (1, _) => 1\n        | (2, 5) => 200\n        | _ => 0\n}\n";
+    assert_eq!(run_source(source), 200);
+}
@@ -0,0 +1,74 @@
+use inkwell::context::Context;
+use zen::ast::{AstType, Program};
+use zen::compiler::Compiler;
+use zen::error::CompileError;
+use zen::lexer::Lexer;
+use zen::parser::Parser;
+use zen::spans::{NodeId, SpanTable};
+use zen::typechecker::TypeChecker;
+
+fn parse(source: &str) -> (Program, SpanTable) {
+    let mut parser = Parser::new(Lexer::new(source));
+    let program = parser.parse_program().unwrap();
+    (program, parser.take_spans())
+}
+
+const POINTS: &str = "Point = {\n    x: i32,\n    y: i32,\n}\n\n\
+    origin = () Point {\n    return Point { x: 0, y: 0 }\n}\n\n\
+    main = () i32 {\n    p := origin()\n    n := 40 + 2\n    return p.x\n}\n";
+
+#[test]
+fn test_type_table_records_inferred_bindings() {
+    let (program, _) = parse(POINTS);
+    let mut checker = TypeChecker::new();
+    assert!(checker.check_program_collecting(&program).is_empty());
+    let types = checker.into_type_table();
+
+    let p = types.binding(&NodeId::new("main", vec![0])).unwrap();
+    assert_eq!(p.to_string(), "Point");
+    assert_eq!(types.binding(&NodeId::new("main", vec![1])), Some(&AstType::I32));
+    assert_eq!(types.field_type("Point", "y"), Some(&AstType::I32));
+    assert_eq!(types.function("origin").map(|sig| sig.return_type.to_string()), Some("Point".to_string()));
+}
+
+#[test]
+fn test_type_table_keys_loop_bindings_by_path() {
+    let (program, _) = parse("main = () void {\n    loop {\n        flag := true\n        break\n    }\n}\n");
+    let mut checker = TypeChecker::new();
+    assert!(checker.check_program_collecting(&program).is_empty());
+    let types = checker.into_type_table();
+
+    assert_eq!(types.binding(&NodeId::new("main", vec![0, 0])), Some(&AstType::Bool));
+}
+
+#[test]
+fn test_type_table_keys_block_bindings_by_path() {
+    let source = "main = (flag: bool) i32 {\n    y := flag ? | true => {\n            z := 2i64\n            1\n        }\n        | false => 0\n    return y\n}\n";
+    let (program, _) = parse(source);
+    let mut checker = TypeChecker::new();
+    assert!(checker.check_program_collecting(&program).is_empty());
+    let types = checker.into_type_table();
+
+    assert_eq!(types.binding(&NodeId::new("main", vec![0, 0, 2, 0])), Some(&AstType::I64));
+}
+
+#[test]
+fn test_check_stage_stops_before_codegen() {
+    let (program, spans) = parse(POINTS);
+    let context = Context::create();
+    let checked = Compiler::new(&context).with_spans(spans).check(&program).unwrap();
+
+    assert!(checked.types.binding(&NodeId::new("main", vec![0])).is_some());
+    assert_eq!(checked.program.declarations.len(), program.declarations.len());
+}
+
+#[test]
+fn test_check_stage_keeps_structured_errors() {
+    let source = "main = () i32 {\n    return missing\n}\n";
+    let (program, spans) = parse(source);
+    let context = Context::create();
+    let errors = Compiler::new(&context).with_spans(spans).check(&program).err().unwrap();
+
+    assert_eq!(errors.len(), 1);
+    assert!(matches!(&errors[0], CompileError::UndeclaredVariable(name, Some(_)) if name == "missing"));
+}